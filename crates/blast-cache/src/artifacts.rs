@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::normalize_name;
+
+/// Directory of the artifact cache below the blast cache directory
+pub const ARTIFACTS_DIR: &str = "artifacts";
+
+/// Store of downloaded distribution files (wheels and sdists)
+///
+/// Files are kept under `<root>/<normalized name>/<version>/<filename>` so
+/// installs can be served without network access.
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    root: PathBuf,
+}
+
+/// A distribution file held in the artifact cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedArtifact {
+    /// Normalized project name
+    pub name: String,
+    /// Release version
+    pub version: String,
+    /// Distribution filename
+    pub filename: String,
+    /// Location of the file in the cache
+    pub path: PathBuf,
+    /// Hex encoded SHA-256 digest of the file
+    pub sha256: String,
+}
+
+impl ArtifactCache {
+    /// Create an artifact cache rooted at `root`
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Get the cache root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Store a distribution file, replacing any previous copy
+    pub async fn store(&self, name: &str, version: &str, filename: &str, data: &[u8]) -> BlastResult<CachedArtifact> {
+        if !valid_filename(filename) {
+            return Err(BlastError::cache(format!("Invalid artifact filename: {}", filename)));
+        }
+
+        let dir = self.release_dir(name, version);
+        fs::create_dir_all(&dir).await?;
+
+        // Write to a temporary file first so readers never see partial artifacts
+        let path = dir.join(filename);
+        let partial = dir.join(format!(".{}.partial", filename));
+        fs::write(&partial, data).await?;
+        fs::rename(&partial, &path).await?;
+
+        Ok(CachedArtifact {
+            name: normalize_name(name),
+            version: version.to_string(),
+            filename: filename.to_string(),
+            path,
+            sha256: hex::encode(Sha256::digest(data)),
+        })
+    }
+
+    /// Get the preferred artifact of a release, wheels before sdists
+    pub async fn get(&self, name: &str, version: &str) -> BlastResult<Option<CachedArtifact>> {
+        let mut artifacts = self.release_artifacts(name, version).await?;
+        artifacts.sort_by_key(|artifact| (!artifact.filename.ends_with(".whl"), artifact.filename.clone()));
+        Ok(artifacts.into_iter().next())
+    }
+
+    /// Get every cached file of a release
+    pub async fn get_all(&self, name: &str, version: &str) -> BlastResult<Vec<CachedArtifact>> {
+        let mut artifacts = self.release_artifacts(name, version).await?;
+        artifacts.sort_by(|a, b| a.filename.cmp(&b.filename));
+        Ok(artifacts)
+    }
+
+    /// Get one file of a release, if it is cached
+    pub async fn get_file(&self, name: &str, version: &str, filename: &str) -> BlastResult<Option<CachedArtifact>> {
+        if !valid_filename(filename) {
+            return Ok(None);
+        }
+        let path = self.release_dir(name, version).join(filename);
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(CachedArtifact {
+            name: normalize_name(name),
+            version: version.to_string(),
+            filename: filename.to_string(),
+            path,
+            sha256: hex::encode(Sha256::digest(&data)),
+        }))
+    }
+
+    /// Check if any artifact of a release is cached
+    pub async fn contains(&self, name: &str, version: &str) -> BlastResult<bool> {
+        Ok(!self.release_artifacts(name, version).await?.is_empty())
+    }
+
+    /// List all cached artifacts
+    pub async fn list(&self) -> BlastResult<Vec<CachedArtifact>> {
+        let mut artifacts = Vec::new();
+        for name in list_dir(&self.root).await? {
+            for version in list_dir(&self.root.join(&name)).await? {
+                artifacts.extend(self.release_artifacts(&name, &version).await?);
+            }
+        }
+        artifacts.sort_by(|a, b| (&a.name, &a.version, &a.filename).cmp(&(&b.name, &b.version, &b.filename)));
+        Ok(artifacts)
+    }
+
+    /// Get the directory holding a release's files
+    fn release_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.root.join(normalize_name(name)).join(version)
+    }
+
+    /// Read all complete artifacts of a release
+    async fn release_artifacts(&self, name: &str, version: &str) -> BlastResult<Vec<CachedArtifact>> {
+        let dir = self.release_dir(name, version);
+        let mut artifacts = Vec::new();
+
+        for filename in list_files(&dir).await? {
+            if filename.starts_with('.') {
+                continue;
+            }
+            let path = dir.join(&filename);
+            let data = fs::read(&path).await?;
+            artifacts.push(CachedArtifact {
+                name: normalize_name(name),
+                version: version.to_string(),
+                filename,
+                path,
+                sha256: hex::encode(Sha256::digest(&data)),
+            });
+        }
+        Ok(artifacts)
+    }
+}
+
+/// Check that a filename names a file directly in a release directory
+fn valid_filename(filename: &str) -> bool {
+    !filename.is_empty() && !filename.contains(['/', '\\']) && !filename.starts_with('.')
+}
+
+/// List subdirectory names, treating a missing directory as empty
+async fn list_dir(dir: &Path) -> BlastResult<Vec<String>> {
+    list_entries(dir, true).await
+}
+
+/// List file names, treating a missing directory as empty
+async fn list_files(dir: &Path) -> BlastResult<Vec<String>> {
+    list_entries(dir, false).await
+}
+
+async fn list_entries(dir: &Path, directories: bool) -> BlastResult<Vec<String>> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() != directories {
+            continue;
+        }
+        if let Some(name) = entry.file_name().to_str() {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
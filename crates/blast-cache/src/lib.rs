@@ -13,6 +13,7 @@ use hex;
 
 use blast_core::error::{BlastError, BlastResult};
 
+pub mod artifacts;
 pub mod compression;
 pub mod storage;
 pub mod memory;
@@ -20,6 +21,7 @@ pub mod layered;
 pub mod lru;
 pub mod index;
 pub mod disk;
+pub mod store;
 
 use std::path::Path;
 use memory::MemoryStorage;
@@ -29,6 +31,8 @@ use lru::LRUCache;
 use index::IndexedStorage;
 
 // Re-export types
+pub use artifacts::{ArtifactCache, CachedArtifact, ARTIFACTS_DIR};
+pub use store::{PackageStore, StoreCollection, STORE_DIR};
 pub use layered::{CacheLayer, LayerType};
 pub use compression::CompressionLevel;
 
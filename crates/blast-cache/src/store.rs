@@ -0,0 +1,277 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use blast_core::environment::package::{unpack_wheel, StoreLock, UnpackedWheel, WheelStore};
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::types::{CacheSettings, LinkMode};
+
+/// Directory of the package store below the blast cache directory
+pub const STORE_DIR: &str = "store";
+
+/// Directory of the unpacked wheels below the store root
+const WHEELS_DIR: &str = "wheels";
+
+/// Directory of the references to unpacked wheels below the store root
+const REFS_DIR: &str = "refs";
+
+/// Directory below the store root naming, for each referenced `.dist-info`
+/// directory, the unpacked wheel its reference is under
+const REFS_BY_DIST_DIR: &str = "refs-by-dist";
+
+/// Lock file below the store root, held shared by installs and
+/// exclusively by garbage collection
+const LOCK_FILE: &str = ".lock";
+
+/// Store of unpacked wheels shared by every environment
+///
+/// A wheel is unpacked once, under `<root>/wheels/<sha256>/` where the key
+/// is the digest of the wheel file, and installs link its files into
+/// site-packages. Each install leaves a reference under
+/// `<root>/refs/<sha256>/` naming the `.dist-info` directory it created,
+/// and `<root>/refs-by-dist/` maps the reference back to the wheel so a
+/// reinstall can drop the old one. References to distributions that have
+/// since been removed no longer count, and [`PackageStore::gc`] frees the
+/// wheels nothing references.
+/// Installs hold a shared lock on the store from unpacking a wheel until
+/// its reference is written, which collection waits for.
+///
+/// Everything here blocks, as installs run on blocking threads.
+#[derive(Debug, Clone)]
+pub struct PackageStore {
+    root: PathBuf,
+    link_modes: Vec<LinkMode>,
+}
+
+/// What a garbage collection of the package store freed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreCollection {
+    /// Digests of the unpacked wheels removed
+    pub removed: Vec<String>,
+    /// Size of the removed files in bytes
+    pub bytes: u64,
+}
+
+impl PackageStore {
+    /// Create a package store rooted at `root`
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            link_modes: CacheSettings::default().link_modes(),
+        }
+    }
+
+    /// Create the package store of a cache directory, linking files as the
+    /// settings choose
+    pub fn from_settings(settings: &CacheSettings) -> Self {
+        Self::new(settings.cache_dir.join(STORE_DIR)).with_link_modes(settings.link_modes())
+    }
+
+    /// Set the ways files are placed into environments, in the order they
+    /// are tried
+    pub fn with_link_modes(mut self, link_modes: Vec<LinkMode>) -> Self {
+        self.link_modes = link_modes;
+        self
+    }
+
+    /// Get the store root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the unpacked copy of a wheel, unpacking it on first use
+    pub fn unpack(&self, wheel: &Path) -> BlastResult<UnpackedWheel> {
+        let sha256 = file_sha256(wheel)?;
+        if let Some(unpacked) = self.get(&sha256) {
+            return Ok(unpacked);
+        }
+
+        // Unpack next to the final location first so installs never link
+        // from a partial wheel
+        let wheels = self.root.join(WHEELS_DIR);
+        let path = wheels.join(&sha256);
+        let partial = wheels.join(format!(".{}.{}.partial", sha256, std::process::id()));
+        remove_dir(&partial)?;
+        fs::create_dir_all(&partial)?;
+        let unpacked = unpack_wheel(wheel, &partial).and_then(|_| match fs::rename(&partial, &path) {
+            // Another install unpacked the same wheel first
+            Err(_) if path.is_dir() => remove_dir(&partial),
+            renamed => renamed.map_err(BlastError::from),
+        });
+        if let Err(e) = unpacked {
+            remove_dir(&partial)?;
+            return Err(e);
+        }
+        Ok(UnpackedWheel { sha256, path })
+    }
+
+    /// Get an unpacked wheel by the digest of its file, if it is stored
+    pub fn get(&self, sha256: &str) -> Option<UnpackedWheel> {
+        let path = self.root.join(WHEELS_DIR).join(sha256);
+        path.is_dir().then(|| UnpackedWheel {
+            sha256: sha256.to_string(),
+            path,
+        })
+    }
+
+    /// List the digests of all unpacked wheels
+    pub fn list(&self) -> BlastResult<Vec<String>> {
+        list_names(&self.root.join(WHEELS_DIR))
+    }
+
+    /// Get the `.dist-info` directories of the installed distributions
+    /// linked from an unpacked wheel
+    pub fn references(&self, sha256: &str) -> BlastResult<Vec<PathBuf>> {
+        Ok(self.read_references(sha256)?
+            .into_iter()
+            .filter_map(|(_, dist_info)| dist_info.is_dir().then_some(dist_info))
+            .collect())
+    }
+
+    /// Remove the unpacked wheels no installed distribution references,
+    /// and the references to distributions that were removed
+    ///
+    /// Waits for running installs to record their references first.
+    pub fn gc(&self) -> BlastResult<StoreCollection> {
+        let _lock = StoreLock::exclusive(&self.root.join(LOCK_FILE))?;
+        let mut collection = StoreCollection::default();
+        for sha256 in self.list()? {
+            let mut referenced = false;
+            for (reference, dist_info) in self.read_references(&sha256)? {
+                if dist_info.is_dir() {
+                    referenced = true;
+                } else {
+                    remove_file(&reference)?;
+                    if let Some(name) = reference.file_name() {
+                        self.remove_index_entry(Path::new(name), &sha256)?;
+                    }
+                }
+            }
+            if referenced {
+                continue;
+            }
+
+            let path = self.root.join(WHEELS_DIR).join(&sha256);
+            collection.bytes += WalkDir::new(&path)
+                .into_iter()
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum::<u64>();
+            remove_dir(&path)?;
+            remove_dir(&self.root.join(REFS_DIR).join(&sha256))?;
+            collection.removed.push(sha256);
+        }
+        Ok(collection)
+    }
+
+    /// Remove the reverse index entry of a reference if it still points at
+    /// the given unpacked wheel
+    fn remove_index_entry(&self, name: &Path, sha256: &str) -> BlastResult<()> {
+        let entry = self.root.join(REFS_BY_DIST_DIR).join(name);
+        match fs::read_to_string(&entry) {
+            Ok(indexed) if indexed == sha256 => remove_file(&entry),
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read the references to an unpacked wheel, as the reference file and
+    /// the `.dist-info` directory it names
+    fn read_references(&self, sha256: &str) -> BlastResult<Vec<(PathBuf, PathBuf)>> {
+        let dir = self.root.join(REFS_DIR).join(sha256);
+        let mut references = Vec::new();
+        for name in list_names(&dir)? {
+            let reference = dir.join(name);
+            match fs::read_to_string(&reference) {
+                Ok(dist_info) => references.push((reference, PathBuf::from(dist_info))),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(references)
+    }
+}
+
+impl WheelStore for PackageStore {
+    fn unpacked(&self, wheel: &Path) -> BlastResult<UnpackedWheel> {
+        self.unpack(wheel)
+    }
+
+    fn link_modes(&self) -> &[LinkMode] {
+        &self.link_modes
+    }
+
+    fn lock_shared(&self) -> BlastResult<StoreLock> {
+        StoreLock::shared(&self.root.join(LOCK_FILE))
+    }
+
+    fn add_reference(&self, unpacked: &UnpackedWheel, dist_info: &Path) -> BlastResult<()> {
+        let dist_info = dist_info.to_string_lossy();
+        let name = &hex::encode(Sha256::digest(dist_info.as_bytes()))[..16];
+
+        // A distribution reinstalled from another wheel drops its old
+        // reference
+        let index = self.root.join(REFS_BY_DIST_DIR);
+        let entry = index.join(name);
+        match fs::read_to_string(&entry) {
+            Ok(previous) if previous != unpacked.sha256 => {
+                remove_file(&self.root.join(REFS_DIR).join(previous).join(name))?;
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let dir = self.root.join(REFS_DIR).join(&unpacked.sha256);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(name), dist_info.as_bytes())?;
+        fs::create_dir_all(&index)?;
+        fs::write(entry, unpacked.sha256.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Get the hex encoded SHA-256 digest of a file
+fn file_sha256(path: &Path) -> BlastResult<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// List entry names, skipping partial ones and treating a missing
+/// directory as empty
+fn list_names(dir: &Path) -> BlastResult<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        if let Some(name) = entry?.file_name().to_str().filter(|name| !name.starts_with('.')) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn remove_file(path: &Path) -> BlastResult<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn remove_dir(path: &Path) -> BlastResult<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -1,7 +1,9 @@
-use blast_core::{error::BlastResult, config::BlastConfig};
+use blast_core::{error::BlastResult, config::BlastConfig, version::Version};
 use blast_daemon::state::StateManagement;
+use blast_resolver::{create_resolver_with_config, normalize_name, Config, Lockfile, LOCKFILE_NAME};
 use tracing::{info, debug};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use crate::commands;
 
 /// Execute the check command
@@ -101,5 +103,77 @@ pub async fn execute(config: &BlastConfig) -> BlastResult<()> {
         Err(e) => info!("Warning: Daemon is not running: {}", e),
     }
 
+    check_yanked(config).await
+}
+
+/// Warn about locked or installed releases that have been yanked since
+async fn check_yanked(config: &BlastConfig) -> BlastResult<()> {
+    // Where each release was found, keyed by normalized name and version
+    let mut releases: BTreeMap<(String, String), Vec<&str>> = BTreeMap::new();
+    let lock_path = config.project_root.join(LOCKFILE_NAME);
+    if lock_path.exists() {
+        for package in Lockfile::read(&lock_path)?.packages {
+            releases.entry((package.name, package.version)).or_default().push("locked");
+        }
+    }
+    for (name, version) in installed_distributions(&config.env_path()) {
+        releases.entry((normalize_name(&name), version)).or_default().push("installed");
+    }
+    if releases.is_empty() {
+        return Ok(());
+    }
+
+    debug!("Checking {} releases for yanked versions", releases.len());
+    let resolver = create_resolver_with_config(Config::from_blast_config(config)?).await?;
+    let mut yanked = 0;
+    for ((name, version), origins) in &releases {
+        let Ok(parsed) = Version::parse(version) else {
+            continue;
+        };
+        match resolver.yanked_release(name, &parsed).await {
+            Ok(Some(release)) => {
+                yanked += 1;
+                println!(
+                    "Warning: {}=={} ({}) has been yanked{}",
+                    name,
+                    version,
+                    origins.join(", "),
+                    release.reason.map(|reason| format!(": {}", reason)).unwrap_or_default()
+                );
+            }
+            Ok(None) => {}
+            Err(e) => debug!("Could not check {}=={} for yanking: {}", name, version, e),
+        }
+    }
+    if yanked == 0 {
+        println!("No yanked packages locked or installed");
+    }
+
     Ok(())
+}
+
+/// List the distributions installed in an environment from their
+/// `.dist-info` directories
+fn installed_distributions(env_path: &Path) -> Vec<(String, String)> {
+    let site_packages: Vec<PathBuf> = std::fs::read_dir(env_path.join("lib"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join("site-packages"))
+        .filter(|path| path.is_dir())
+        .collect();
+
+    let mut distributions = Vec::new();
+    for dir in site_packages {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            // Names in `.dist-info` directories have their dashes escaped,
+            // so the first one separates the version
+            let Some((name, version)) = file_name.strip_suffix(".dist-info").and_then(|stem| stem.split_once('-')) else {
+                continue;
+            };
+            distributions.push((name.to_string(), version.to_string()));
+        }
+    }
+    distributions
 } 
\ No newline at end of file
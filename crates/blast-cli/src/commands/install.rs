@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use blast_cache::PackageStore;
+use blast_core::{
+    config::BlastConfig,
+    environment::package::{
+        DependencyGraph, InstallationProgress, InstallationStep, PackageInstaller, ProgressTracker,
+        SchedulerConfig, SyncPlan,
+    },
+    environment::PackageConfig,
+    error::{BlastError, BlastResult},
+};
+use blast_resolver::{project_package, Config, DistFilename, Lockfile, WheelTags, LOCKFILE_NAME};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{info, debug, warn};
+
+/// Execute the install command, installing exactly what `blast.lock` records
+pub async fn execute(config: &BlastConfig) -> BlastResult<()> {
+    let (path, lockfile) = read_lockfile(config)?;
+    install_locked(config, &lockfile).await?;
+
+    println!("Installed {} packages from {}", lockfile.packages.len(), path.display());
+    Ok(())
+}
+
+/// Read the project's `blast.lock`, warning if it is out of date
+pub(crate) fn read_lockfile(config: &BlastConfig) -> BlastResult<(PathBuf, Lockfile)> {
+    let path = config.project_root.join(LOCKFILE_NAME);
+    if !path.exists() {
+        return Err(BlastError::config(format!(
+            "No {} found in {}, run `blast lock` first",
+            LOCKFILE_NAME,
+            config.project_root.display()
+        )));
+    }
+
+    let lockfile = Lockfile::read(&path)?;
+    if !lockfile.is_up_to_date(&project_package(config)?, &Config::from_blast_config(config)?) {
+        warn!("{} is out of date with blast.toml, run `blast lock` to update it", LOCKFILE_NAME);
+    }
+    Ok((path, lockfile))
+}
+
+/// Install every package of a lockfile, dependencies first
+pub(crate) async fn install_locked(config: &BlastConfig, lockfile: &Lockfile) -> BlastResult<()> {
+    let install = LockedInstall::prepare(config, lockfile).await?;
+    // Each level of the graph is installed concurrently, dependencies first
+    let result = install.installer.install_packages(&dependency_graph(lockfile)).await;
+    install.finish().await?;
+    result
+}
+
+/// Make the environment hold exactly the packages of a lockfile, removing
+/// every other package unless other tools installed it and
+/// `keep_unmanaged` is set
+pub(crate) async fn sync_locked(config: &BlastConfig, lockfile: &Lockfile, keep_unmanaged: bool) -> BlastResult<SyncPlan> {
+    let mut install = LockedInstall::prepare(config, lockfile).await?;
+    install.installer = install.installer.with_keep_unmanaged(keep_unmanaged);
+    let result = install.installer.sync_packages(&dependency_graph(lockfile)).await;
+    install.finish().await?;
+    result
+}
+
+/// Plan syncing the environment to a lockfile without fetching or changing
+/// anything
+pub(crate) fn plan_sync(config: &BlastConfig, lockfile: &Lockfile, keep_unmanaged: bool) -> BlastResult<SyncPlan> {
+    let resolver_config = Config::from_blast_config(config)?;
+    PackageInstaller::new(package_config(config, &resolver_config))
+        .with_keep_unmanaged(keep_unmanaged)
+        .plan_sync(&dependency_graph(lockfile))
+}
+
+/// An installer for a lockfile's packages with their wheels at hand
+struct LockedInstall {
+    installer: PackageInstaller,
+    store: Arc<PackageStore>,
+    bars: JoinHandle<()>,
+}
+
+impl LockedInstall {
+    /// Fetch and build the wheels of a lockfile's packages
+    async fn prepare(config: &BlastConfig, lockfile: &Lockfile) -> BlastResult<Self> {
+        // Everything the lock pins goes into the artifact cache first, and the
+        // installer takes wheels from nowhere else
+        let resolver_config = Config::from_blast_config(config)?;
+        let artifacts = lockfile.fetch_artifacts(&resolver_config.artifact_cache(), &resolver_config.fetch_options()).await?;
+        debug!("{} artifacts ready", artifacts.len());
+
+        // Exactly the locked files are installed, sdists once their PEP 517
+        // backend has built them into wheels
+        let frontend = resolver_config.build_frontend();
+        let mut wheels = Vec::new();
+        for artifact in artifacts {
+            let wheel = if artifact.filename.ends_with(".whl") {
+                artifact.path
+            } else {
+                frontend.build_wheel(&artifact.path).await?.path
+            };
+            wheels.push((artifact.name, wheel));
+        }
+
+        // Local paths and git checkouts are built now, git at the locked commit
+        for package in lockfile.packages.iter().filter(|package| package.is_built_locally()) {
+            if let Some(direct) = &package.direct {
+                info!("Building {} from {}", package.name, direct);
+                let wheel = resolver_config.direct_source(&package.name, direct.clone())
+                    .with_commit(package.commit.clone())
+                    .wheel()
+                    .await?;
+                wheels.push((package.name.clone(), wheel));
+            }
+        }
+
+        // Wheels are unpacked once into the shared store and linked from there
+        let store = Arc::new(PackageStore::from_settings(&config.cache_settings));
+        let tracker = Arc::new(ProgressTracker::new());
+        let bars = tokio::spawn(show_progress(tracker.subscribe()));
+        let installer = wheels.into_iter().fold(
+            PackageInstaller::new(package_config(config, &resolver_config))
+                .with_scheduler_config(&SchedulerConfig::default())
+                .with_progress(tracker)
+                .with_store(store.clone())
+                .with_wheel_ranking(wheel_ranking(&resolver_config)),
+            |installer, (name, wheel)| installer.with_wheel(&name, wheel),
+        );
+        Ok(Self { installer, store, bars })
+    }
+
+    /// Wait for the progress bars, then free the unpacked wheels nothing
+    /// uses any more
+    async fn finish(self) -> BlastResult<()> {
+        let Self { installer, store, bars } = self;
+        drop(installer);
+        let _ = bars.await;
+
+        // Distributions removed since earlier installs may have been the last
+        // users of their unpacked wheels
+        let collection = tokio::task::spawn_blocking(move || store.gc())
+            .await
+            .map_err(|e| BlastError::cache(format!("Failed to clean the package store: {}", e)))??;
+        if !collection.removed.is_empty() {
+            debug!("Freed {} unpacked wheels ({} bytes) from the package store", collection.removed.len(), collection.bytes);
+        }
+        Ok(())
+    }
+}
+
+/// Get the installer configuration for the project's environment
+fn package_config(config: &BlastConfig, resolver_config: &Config) -> PackageConfig {
+    PackageConfig {
+        python_version: config.python_version.to_string(),
+        env_path: config.env_path(),
+        require_hashes: false,
+        cache_dir: resolver_config.cache_dir.clone(),
+        offline: true,
+        ..PackageConfig::default()
+    }
+}
+
+/// Rank cached wheels by the tags the environment's interpreter supports,
+/// rejecting the rest
+fn wheel_ranking(resolver_config: &Config) -> impl Fn(&str) -> Option<usize> + Send + Sync + 'static {
+    let tags = WheelTags::new(&resolver_config.python);
+    move |filename| DistFilename::parse(filename).ok().and_then(|filename| tags.rank(&filename))
+}
+
+/// Build the dependency graph of a lockfile's packages
+fn dependency_graph(lockfile: &Lockfile) -> DependencyGraph {
+    let mut graph = DependencyGraph::new();
+    for package in &lockfile.packages {
+        graph.add_package(&package.name, package.version.clone());
+    }
+    for package in &lockfile.packages {
+        for dependency in &package.dependencies {
+            graph.add_dependency(&package.name, dependency);
+        }
+    }
+    graph
+}
+
+/// Show a bar per package until the installer drops its tracker
+async fn show_progress(mut updates: broadcast::Receiver<InstallationProgress>) {
+    let bars = MultiProgress::new();
+    let style = ProgressStyle::default_bar()
+        .template("{spinner:.green} {prefix:>20} [{bar:30.cyan/blue}] {msg}")
+        .unwrap_or_else(|_| ProgressStyle::default_bar());
+    let mut packages: HashMap<String, ProgressBar> = HashMap::new();
+
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let bar = packages.entry(update.operation_id.clone()).or_insert_with(|| {
+            let bar = bars.add(ProgressBar::new(100));
+            bar.set_style(style.clone());
+            bar.set_prefix(update.package_name.clone());
+            bar
+        });
+        bar.set_position((update.progress * 100.0) as u64);
+        match update.step {
+            InstallationStep::Complete => bar.finish_with_message("installed"),
+            InstallationStep::Failed => bar.abandon_with_message(update.error.unwrap_or_default()),
+            _ => bar.set_message(update.current_action),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use blast_core::{error::BlastResult, config::BlastConfig};
+use blast_resolver::{create_resolver_with_config, project_package, Config, Lockfile, ResolutionMode, LOCKFILE_NAME};
+use chrono::{DateTime, Utc};
+use tracing::{info, debug};
+
+/// Execute the lock command
+///
+/// Settings that are not given explicitly are taken from an existing
+/// lockfile, so relocking reproduces the same kind of result, and git
+/// dependencies keep the commits it pins.
+pub async fn execute(
+    resolution: Option<ResolutionMode>,
+    exclude_newer: Option<DateTime<Utc>>,
+    config: &BlastConfig,
+) -> BlastResult<()> {
+    let path = config.project_root.join(LOCKFILE_NAME);
+    let previous = if path.exists() { Some(Lockfile::read(&path)?) } else { None };
+
+    let resolver_config = Config {
+        resolution: resolution
+            .or_else(|| previous.as_ref().map(|lockfile| lockfile.resolution))
+            .unwrap_or_default(),
+        exclude_newer: exclude_newer.or_else(|| previous.as_ref().and_then(|lockfile| lockfile.exclude_newer)),
+        ..Config::from_blast_config(config)?
+    };
+    // Git dependencies stay at their locked commit until their reference changes
+    let resolver_config = match &previous {
+        Some(lockfile) => resolver_config.with_locked_commits(lockfile),
+        None => resolver_config,
+    };
+    debug!("Resolving project dependencies ({} resolution)", resolver_config.resolution);
+    if let Some(cutoff) = resolver_config.exclude_newer {
+        info!("Ignoring files uploaded after {}", cutoff.to_rfc3339());
+    }
+
+    let resolver = create_resolver_with_config(resolver_config).await?;
+    let project = project_package(config)?;
+    let result = resolver.resolve_project(&project).await?;
+    let lockfile = resolver.lock_resolution(&project, &result).await?;
+
+    lockfile.write(&path)?;
+    info!("Wrote {}", path.display());
+    println!("Locked {} packages in {}", lockfile.packages.len(), path.display());
+    for applied in &result.applied {
+        println!("  {}", applied);
+    }
+
+    Ok(())
+}
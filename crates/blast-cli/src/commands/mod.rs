@@ -7,6 +7,9 @@ mod load;
 mod clean;
 mod list;
 mod check;
+mod lock;
+mod install;
+mod sync;
 
 use std::path::PathBuf;
 use blast_core::{
@@ -23,6 +26,9 @@ pub use load::execute as execute_load;
 pub use clean::execute as execute_clean;
 pub use list::execute as execute_list;
 pub use check::execute as execute_check;
+pub use lock::execute as execute_lock;
+pub use install::execute as execute_install;
+pub use sync::execute as execute_sync;
 
 /// Get a configured daemon instance with proper paths
 pub(crate) async fn get_daemon(config: &BlastConfig, env_name: Option<&str>) -> BlastResult<Daemon> {
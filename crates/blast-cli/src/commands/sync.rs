@@ -0,0 +1,56 @@
+use blast_core::{
+    config::{BlastConfig, GroupSelection},
+    error::BlastResult,
+};
+use blast_resolver::applicable_requirements;
+use tracing::debug;
+
+use super::install::{plan_sync, read_lockfile, sync_locked};
+
+/// Execute the sync command, making the environment hold exactly the locked
+/// packages needed by the main dependencies and the selected dependency
+/// groups
+pub async fn execute(
+    groups: Vec<String>,
+    no_dev: bool,
+    dry_run: bool,
+    keep_unmanaged: bool,
+    config: &BlastConfig,
+) -> BlastResult<()> {
+    let (path, lockfile) = read_lockfile(config)?;
+    let selection = GroupSelection {
+        groups,
+        dev: !no_dev,
+    };
+    let requirements = applicable_requirements(config, config.requirements(&selection)?)?;
+    debug!("Syncing {} requirements with {:?}", requirements.len(), selection);
+
+    let lockfile = lockfile.subset(requirements.iter().map(|spec| spec.name.as_str()))?;
+    if dry_run {
+        let plan = plan_sync(config, &lockfile, keep_unmanaged)?;
+        for action in &plan.actions {
+            println!("Would {}", action);
+        }
+        for name in &plan.unmanaged {
+            println!("Would keep {} (not installed by blast)", name);
+        }
+        println!(
+            "{} changes, {} packages up to date",
+            plan.actions.len(),
+            plan.unchanged.len()
+        );
+        return Ok(());
+    }
+
+    let plan = sync_locked(config, &lockfile, keep_unmanaged).await?;
+    for action in &plan.actions {
+        debug!("Synced: {}", action);
+    }
+    println!(
+        "Synced {} packages from {} ({} changes)",
+        lockfile.packages.len(),
+        path.display(),
+        plan.actions.len()
+    );
+    Ok(())
+}
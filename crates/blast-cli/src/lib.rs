@@ -2,11 +2,13 @@
 
 use std::path::PathBuf;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
 use once_cell::sync::OnceCell;
 
 use blast_core::config::BlastConfig;
 use blast_core::python::PythonVersion;
+use blast_resolver::ResolutionMode;
 
 mod commands;
 pub mod output;
@@ -71,6 +73,11 @@ pub struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Resolve and install only from cached metadata, cached artifacts
+    /// and local sources
+    #[arg(long, global = true)]
+    offline: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -123,8 +130,44 @@ pub enum Commands {
     /// List all environments
     List,
 
-    /// Check environment status
+    /// Check environment status and flag yanked packages
     Check,
+
+    /// Resolve the project's dependencies and write blast.lock
+    Lock {
+        /// Version preference: highest, lowest or lowest-direct
+        /// (defaults to the one recorded in blast.lock)
+        #[arg(long, value_name = "MODE")]
+        resolution: Option<ResolutionMode>,
+
+        /// Ignore files uploaded after this RFC 3339 timestamp
+        /// (defaults to the one recorded in blast.lock)
+        #[arg(long, value_name = "TIMESTAMP")]
+        exclude_newer: Option<DateTime<Utc>>,
+    },
+
+    /// Install exactly the packages pinned in blast.lock
+    Install,
+
+    /// Make the environment hold exactly the locked packages of the main
+    /// dependencies and chosen dependency groups
+    Sync {
+        /// Dependency group to install as well (repeatable)
+        #[arg(long = "group", value_name = "GROUP")]
+        groups: Vec<String>,
+
+        /// Leave out dev_dependencies and the `dev` group
+        #[arg(long)]
+        no_dev: bool,
+
+        /// Report what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Keep packages other tools installed that the lock does not have
+        #[arg(long)]
+        keep_unmanaged: bool,
+    },
 }
 
 /// Run the CLI application
@@ -165,7 +208,7 @@ pub async fn run() -> Result<()> {
     }
 
     // Load or create config
-    let config = if let Some(path) = cli.config {
+    let mut config = if let Some(path) = cli.config {
         BlastConfig::from_file(path)?
     } else {
         let current_dir = std::env::current_dir()?;
@@ -176,6 +219,7 @@ pub async fn run() -> Result<()> {
             current_dir,
         )
     };
+    config.offline |= cli.offline;
 
     // Execute command
     match cli.command {
@@ -207,6 +251,15 @@ pub async fn run() -> Result<()> {
         Commands::Check => {
             commands::execute_check(&config).await?;
         }
+        Commands::Lock { resolution, exclude_newer } => {
+            commands::execute_lock(resolution, exclude_newer, &config).await?;
+        }
+        Commands::Install => {
+            commands::execute_install(&config).await?;
+        }
+        Commands::Sync { groups, no_dev, dry_run, keep_unmanaged } => {
+            commands::execute_sync(groups, no_dev, dry_run, keep_unmanaged, &config).await?;
+        }
     }
 
     Ok(())
@@ -1,7 +1,9 @@
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{BlastError, BlastResult};
+use crate::package::normalize_name;
 use crate::python::PythonVersion;
 use crate::types::{CacheSettings, UpdateStrategy};
 
@@ -26,6 +28,20 @@ pub struct BlastConfig {
     pub dependencies: DependenciesConfig,
     /// Development dependencies configuration
     pub dev_dependencies: Option<DependenciesConfig>,
+    /// Resolve and install without network access
+    #[serde(default)]
+    pub offline: bool,
+    /// Version limits that apply whenever a package is required, without
+    /// requiring it themselves (pip `-c` semantics)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<DependencySpec>,
+    /// Requirements that replace whatever dependencies declare for a package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<DependencySpec>,
+    /// Named sets of extra requirements (PEP 735 `[dependency-groups]`),
+    /// locked together with the main dependencies
+    #[serde(default, rename = "dependency-groups", skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependency_groups: BTreeMap<String, Vec<DependencyGroupEntry>>,
 }
 
 impl BlastConfig {
@@ -46,6 +62,10 @@ impl BlastConfig {
             env_dir: PathBuf::from(".venv"),
             dependencies: DependenciesConfig::default(),
             dev_dependencies: None,
+            offline: false,
+            constraints: Vec::new(),
+            overrides: Vec::new(),
+            dependency_groups: BTreeMap::new(),
         }
     }
 
@@ -102,6 +122,80 @@ impl BlastConfig {
         &self.project_root
     }
 
+    /// Get the requirements of a dependency group, with included groups
+    /// expanded
+    pub fn dependency_group(&self, name: &str) -> BlastResult<Vec<DependencySpec>> {
+        let mut specs = Vec::new();
+        self.expand_group(name, &mut Vec::new(), &mut specs)?;
+        Ok(specs)
+    }
+
+    fn expand_group(&self, name: &str, stack: &mut Vec<String>, specs: &mut Vec<DependencySpec>) -> BlastResult<()> {
+        let normalized = normalize_name(name);
+        let mut matching = self.dependency_groups.iter().filter(|(group, _)| normalize_name(group) == normalized);
+        let (group, entries) = matching.next()
+            .ok_or_else(|| BlastError::config(format!("Unknown dependency group '{}'", name)))?;
+        if let Some((duplicate, _)) = matching.next() {
+            return Err(BlastError::config(format!(
+                "Dependency groups '{}' and '{}' have the same normalized name",
+                group, duplicate
+            )));
+        }
+        if stack.contains(&normalized) {
+            return Err(BlastError::config(format!(
+                "Dependency group '{}' includes itself: {} -> {}",
+                group,
+                stack.join(" -> "),
+                normalized
+            )));
+        }
+
+        stack.push(normalized);
+        for entry in entries {
+            match entry {
+                DependencyGroupEntry::Requirement(requirement) => specs.push(DependencySpec::parse(requirement)?),
+                DependencyGroupEntry::Include { include_group } => self.expand_group(include_group, stack, specs)?,
+            }
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    /// Get the requirements of the main dependencies and the selected groups
+    pub fn requirements(&self, selection: &GroupSelection) -> BlastResult<Vec<DependencySpec>> {
+        let mut specs = self.dependencies.packages.clone();
+        let mut groups: Vec<&str> = selection.groups.iter().map(String::as_str).collect();
+        if selection.dev {
+            specs.extend(self.dev_dependencies.iter().flat_map(|deps| deps.packages.iter().cloned()));
+            if self.has_dependency_group(DEV_GROUP) {
+                groups.push(DEV_GROUP);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for group in groups {
+            if seen.insert(normalize_name(group)) {
+                specs.extend(self.dependency_group(group)?);
+            }
+        }
+        Ok(specs)
+    }
+
+    /// Get every requirement of the project, from the main dependencies, the
+    /// development dependencies and all dependency groups
+    pub fn all_requirements(&self) -> BlastResult<Vec<DependencySpec>> {
+        self.requirements(&GroupSelection {
+            groups: self.dependency_groups.keys().cloned().collect(),
+            dev: true,
+        })
+    }
+
+    /// Check if a dependency group is defined
+    pub fn has_dependency_group(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.dependency_groups.keys().any(|group| normalize_name(group) == name)
+    }
+
     /// Convert config to TOML string
     pub fn to_toml(&self) -> BlastResult<String> {
         toml::to_string(self).map_err(|e| BlastError::Config(format!("Failed to serialize config: {}", e)))
@@ -113,6 +207,41 @@ impl BlastConfig {
     }
 }
 
+/// Name of the dependency group installed along with `dev_dependencies`
+pub const DEV_GROUP: &str = "dev";
+
+/// An entry of a dependency group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DependencyGroupEntry {
+    /// A requirement string, e.g. `pytest>=8`
+    Requirement(String),
+    /// The requirements of another group (`{include-group = "lint"}`)
+    Include {
+        /// Name of the included group
+        #[serde(rename = "include-group")]
+        include_group: String,
+    },
+}
+
+/// Dependency groups to install along with the main dependencies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupSelection {
+    /// Named dependency groups
+    pub groups: Vec<String>,
+    /// Include `dev_dependencies` and the `dev` group
+    pub dev: bool,
+}
+
+impl Default for GroupSelection {
+    fn default() -> Self {
+        Self {
+            groups: Vec::new(),
+            dev: true,
+        }
+    }
+}
+
 /// Configuration for dependencies
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DependenciesConfig {
@@ -120,8 +249,16 @@ pub struct DependenciesConfig {
     pub packages: Vec<DependencySpec>,
     /// Additional package indexes
     pub package_index: Option<Vec<String>>,
+    /// Local directories of wheels and sdists (relative to project root)
+    pub find_links: Option<Vec<PathBuf>>,
     /// Allow pre-releases
     pub allow_prereleases: bool,
+    /// Only install wheels, never build source distributions
+    #[serde(default)]
+    pub no_build: bool,
+    /// Install locked files that have no recorded hash
+    #[serde(default)]
+    pub allow_unhashed: bool,
 }
 
 /// Specification for a package dependency
@@ -130,9 +267,166 @@ pub struct DependencySpec {
     /// Package name
     pub name: String,
     /// Version constraint
+    #[serde(default = "any_version")]
     pub version: String,
     /// Optional extras
     pub extras: Option<Vec<String>>,
     /// Optional package index
     pub index: Option<String>,
-} 
\ No newline at end of file
+    /// Path, archive URL or git repository to take the package from
+    /// instead of an index
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<DependencySource>,
+    /// PEP 508 environment marker, such as `sys_platform == 'win32'`,
+    /// limiting the environments the requirement applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+impl DependencySpec {
+    /// Parse a requirement string like `requests[socks]>=2.31` or a PEP 508
+    /// direct reference like `mylib @ git+https://host/mylib.git@v1.0`
+    ///
+    /// An environment marker after `;` is kept for the resolver to evaluate.
+    /// After a URL, the `;` has to follow whitespace, as URLs may contain it.
+    pub fn parse(requirement: &str) -> BlastResult<Self> {
+        let requirement = requirement.trim();
+        let is_url = requirement.split(';').next().is_some_and(|head| head.contains('@'));
+        let separator = if is_url {
+            requirement.find(" ;").map(|i| i + 1)
+        } else {
+            requirement.find(';')
+        };
+        let (requirement, marker) = match separator {
+            Some(i) => {
+                let marker = requirement[i + 1..].trim();
+                if marker.is_empty() {
+                    return Err(BlastError::config(format!("Empty marker in requirement '{}'", requirement)));
+                }
+                (requirement[..i].trim(), Some(marker.to_string()))
+            }
+            None => (requirement, None),
+        };
+
+        let name_end = requirement
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(requirement.len());
+        let (name, mut rest) = requirement.split_at(name_end);
+        if name.is_empty() {
+            return Err(BlastError::config(format!("Invalid requirement '{}'", requirement)));
+        }
+
+        let mut extras = None;
+        if let Some(after) = rest.trim_start().strip_prefix('[') {
+            let (list, after) = after.split_once(']')
+                .ok_or_else(|| BlastError::config(format!("Unclosed extras in requirement '{}'", requirement)))?;
+            extras = Some(list.split(',').map(|extra| extra.trim().to_string()).filter(|extra| !extra.is_empty()).collect());
+            rest = after;
+        }
+
+        let (version, source) = match rest.trim().strip_prefix('@') {
+            Some(url) => ("*", Some(DependencySource::from_url(url.trim())?)),
+            None => (rest.trim(), None),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            version: if version.is_empty() { "*".to_string() } else { version.to_string() },
+            extras,
+            index: None,
+            source,
+            marker,
+        })
+    }
+}
+
+fn any_version() -> String {
+    "*".to_string()
+}
+
+/// Where a dependency comes from when it is not looked up in an index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DependencySource {
+    /// A git repository, at a revision, tag or branch, or its default branch
+    Git {
+        /// Repository URL, without the `git+` prefix
+        git: String,
+        /// Commit or other revision
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+        /// Tag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+        /// Branch
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        branch: Option<String>,
+    },
+    /// A project directory, wheel or sdist (relative to project root)
+    Path {
+        /// Location of the project or file
+        path: PathBuf,
+        /// Install a project directory so its sources are imported in place
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        editable: bool,
+    },
+    /// A wheel or sdist archive to download
+    Url {
+        /// Archive URL
+        url: String,
+    },
+}
+
+impl DependencySource {
+    /// Parse the URL of a PEP 508 direct reference: `git+<url>[@<rev>]`,
+    /// a `file:` URL or an archive URL
+    pub fn from_url(url: &str) -> BlastResult<Self> {
+        if let Some(repository) = url.strip_prefix("git+") {
+            // A revision follows the last `@` of the path, not the one of
+            // any credentials before the host
+            let host = repository.find("://").map_or(0, |i| i + 3);
+            let path = repository[host..].find('/').map_or(repository.len(), |i| host + i);
+            let (git, rev) = match repository[path..].rfind('@') {
+                Some(i) => (&repository[..path + i], Some(repository[path + i + 1..].to_string())),
+                None => (repository, None),
+            };
+            return Ok(Self::Git {
+                git: git.to_string(),
+                rev,
+                tag: None,
+                branch: None,
+            });
+        }
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(Self::Path {
+                path: PathBuf::from(path),
+                editable: false,
+            });
+        }
+        if url.starts_with("https://") || url.starts_with("http://") {
+            return Ok(Self::Url { url: url.to_string() });
+        }
+        Err(BlastError::config(format!("Unsupported direct reference URL: {}", url)))
+    }
+
+    /// Get the git revision to check out: the revision, tag or branch
+    /// asked for, or `HEAD`
+    pub fn git_reference(&self) -> Option<String> {
+        match self {
+            Self::Git { rev: Some(rev), .. } => Some(rev.clone()),
+            Self::Git { tag: Some(tag), .. } => Some(format!("refs/tags/{}", tag)),
+            Self::Git { branch: Some(branch), .. } => Some(format!("refs/heads/{}", branch)),
+            Self::Git { .. } => Some("HEAD".to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Git { git, .. } => write!(f, "git+{}", git),
+            Self::Path { path, .. } => write!(f, "{}", path.display()),
+            Self::Url { url } => write!(f, "{}", url),
+        }
+    }
+}
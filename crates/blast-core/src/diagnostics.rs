@@ -154,6 +154,22 @@ impl Diagnostic {
     }
 }
 
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(details) = &self.details {
+            write!(f, "\n\n{}", details)?;
+        }
+        if !self.suggestions.is_empty() {
+            writeln!(f)?;
+        }
+        for suggestion in &self.suggestions {
+            write!(f, "\nhelp: {}", suggestion.description)?;
+        }
+        Ok(())
+    }
+}
+
 /// Collection of diagnostics with query capabilities
 #[derive(Debug, Default)]
 pub struct DiagnosticCollection {
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
 use petgraph::algo::toposort;
 use petgraph::visit::Dfs;
 use tokio::sync::broadcast;
@@ -326,6 +327,42 @@ impl DependencyGraph {
         }
     }
 
+    /// Group packages into levels to install one after another, each level
+    /// depending only on the levels before it
+    ///
+    /// Packages in a dependency cycle, or depending on one, share a last
+    /// level.
+    pub fn installation_levels(&self) -> Vec<Vec<&DependencyNode>> {
+        // Dependencies of each package that are not in a level yet
+        let mut pending: HashMap<NodeIndex, usize> = self.graph.node_indices()
+            .map(|idx| (idx, self.graph.neighbors(idx).count()))
+            .collect();
+        let mut levels = Vec::new();
+        while !pending.is_empty() {
+            let mut level: Vec<NodeIndex> = pending.iter()
+                .filter(|(_, &count)| count == 0)
+                .map(|(&idx, _)| idx)
+                .collect();
+            if level.is_empty() {
+                level = pending.keys().copied().collect();
+            }
+            level.sort_by(|a, b| self.graph[*a].name.cmp(&self.graph[*b].name));
+
+            for idx in &level {
+                pending.remove(idx);
+            }
+            for idx in &level {
+                for dependent in self.graph.neighbors_directed(*idx, Direction::Incoming) {
+                    if let Some(count) = pending.get_mut(&dependent) {
+                        *count -= 1;
+                    }
+                }
+            }
+            levels.push(level.into_iter().map(|idx| &self.graph[idx]).collect());
+        }
+        levels
+    }
+
     /// Prune unused dependencies
     pub fn prune_unused(&mut self) {
         let mut used = HashSet::new();
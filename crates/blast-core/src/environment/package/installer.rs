@@ -1,197 +1,512 @@
-use std::path::Path;
-use tokio::process::Command;
-use crate::error::BlastResult;
-use super::{PackageConfig, DependencyGraph};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use futures::stream::{self, TryStreamExt};
+use tracing::debug;
+use crate::error::{BlastError, BlastResult};
+use crate::package::normalize_name;
+use crate::version::Version as Pep440Version;
+use super::progress::InstallationStep as ProgressStep;
+use super::{
+    PackageConfig, DependencyGraph, ProgressTracker, SchedulerConfig, WheelInstaller, WheelStore,
+    InstalledDistribution, INSTALLER_NAME,
+};
+
+/// Directory of the artifact cache below the cache directory
+const ARTIFACTS_DIR: &str = "artifacts";
+
+/// Rank of a wheel filename for the target environment, lower is better,
+/// or `None` if the wheel cannot be installed there
+pub type WheelRanking = Arc<dyn Fn(&str) -> Option<usize> + Send + Sync>;
 
 /// Package installer implementation
+///
+/// Graphs are installed level by level, dependencies first, with the
+/// packages of a level unpacked concurrently.
 pub struct PackageInstaller {
     /// Configuration
     config: PackageConfig,
+    /// Most packages installed at once
+    max_concurrent_ops: usize,
+    /// Per-package progress of graph installs
+    progress: Arc<ProgressTracker>,
+    /// Wheels chosen for packages, installed instead of the artifact
+    /// cache's, by normalized name
+    wheels: HashMap<String, PathBuf>,
+    /// Store of unpacked wheels files are linked from, if any
+    store: Option<Arc<dyn WheelStore>>,
+    /// Leave packages installed by other tools when syncing
+    keep_unmanaged: bool,
+    /// Choice among the cached wheels of a package, if any
+    wheel_ranking: Option<WheelRanking>,
 }
 
 impl PackageInstaller {
     /// Create new package installer
     pub fn new(config: PackageConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            max_concurrent_ops: SchedulerConfig::default().max_concurrent_ops,
+            progress: Arc::new(ProgressTracker::new()),
+            wheels: HashMap::new(),
+            store: None,
+            keep_unmanaged: false,
+            wheel_ranking: None,
+        }
+    }
+
+    /// Limit concurrent installs as the scheduler does
+    pub fn with_scheduler_config(mut self, scheduler: &SchedulerConfig) -> Self {
+        self.max_concurrent_ops = scheduler.max_concurrent_ops.max(1);
+        self
+    }
+
+    /// Report progress to a shared tracker
+    pub fn with_progress(mut self, progress: Arc<ProgressTracker>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Install a package from exactly this wheel, such as the file a
+    /// lockfile pins or one built locally, rather than any cached one
+    pub fn with_wheel(mut self, name: &str, wheel: impl Into<PathBuf>) -> Self {
+        self.wheels.insert(normalize_name(name), wheel.into());
+        self
+    }
+
+    /// Link installed files from a store of unpacked wheels
+    pub fn with_store(mut self, store: Arc<dyn WheelStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Leave packages that other tools installed in place when syncing,
+    /// even if the graph does not have them
+    pub fn with_keep_unmanaged(mut self, keep_unmanaged: bool) -> Self {
+        self.keep_unmanaged = keep_unmanaged;
+        self
+    }
+
+    /// Install only the cached wheels a ranking accepts, preferring the best
+    /// ranked
+    ///
+    /// Without a ranking any cached wheel of the version is installed.
+    pub fn with_wheel_ranking(mut self, ranking: impl Fn(&str) -> Option<usize> + Send + Sync + 'static) -> Self {
+        self.wheel_ranking = Some(Arc::new(ranking));
+        self
+    }
+
+    /// Get the tracker progress is reported to
+    pub fn progress(&self) -> &Arc<ProgressTracker> {
+        &self.progress
     }
 
     /// Install packages from dependency graph
     pub async fn install_packages(&self, graph: &DependencyGraph) -> BlastResult<()> {
-        // Create installation plan
         let plan = self.create_installation_plan(graph);
-        
-        // Execute each step in plan
-        for step in plan {
-            match step {
-                InstallationStep::Install { name, version } => {
-                    self.install_package(&name, &version).await?;
-                }
-                InstallationStep::Update { name, from: _, to } => {
-                    self.update_package(&name, &to).await?;
-                }
-                InstallationStep::Remove { name } => {
-                    self.uninstall_package(&name).await?;
-                }
-            }
-        }
-        
-        Ok(())
+        self.check_cached_wheels(&plan)?;
+        self.execute_plan(plan).await
     }
 
     /// Update packages from dependency graph
     pub async fn update_packages(&self, graph: &DependencyGraph) -> BlastResult<()> {
-        // Create update plan
         let plan = self.create_update_plan(graph);
-        
-        // Execute each step in plan
-        for step in plan {
-            match step {
-                InstallationStep::Install { name, version } => {
-                    self.install_package(&name, &version).await?;
-                }
-                InstallationStep::Update { name, from: _, to } => {
-                    self.update_package(&name, &to).await?;
-                }
-                InstallationStep::Remove { name } => {
-                    self.uninstall_package(&name).await?;
-                }
+        self.check_cached_wheels(&plan)?;
+        self.execute_plan(plan).await
+    }
+
+    /// Plan making the environment hold exactly the packages of a graph,
+    /// without changing anything
+    pub fn plan_sync(&self, graph: &DependencyGraph) -> BlastResult<SyncPlan> {
+        Ok(self.create_sync_plan(graph)?.0)
+    }
+
+    /// Make the environment hold exactly the packages of a graph, at their
+    /// versions, and return what was changed
+    ///
+    /// Unwanted packages are removed first, then missing and outdated ones
+    /// installed level by level. If a step fails after others changed the
+    /// environment, the error lists the changes already made.
+    pub async fn sync_packages(&self, graph: &DependencyGraph) -> BlastResult<SyncPlan> {
+        let (plan, steps) = self.create_sync_plan(graph)?;
+        self.check_cached_wheels(&steps)?;
+        let done = Mutex::new(HashSet::new());
+        if let Err(e) = self.execute_levels(steps, &done).await {
+            let done = done.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let applied: Vec<String> = plan.actions.iter()
+                .filter(|action| done.contains(action.name()))
+                .map(|action| action.to_string())
+                .collect();
+            if applied.is_empty() {
+                return Err(e);
             }
+            return Err(BlastError::package(format!(
+                "Sync stopped after {} of {} changes, the environment is partly synced (applied: {}): {}",
+                applied.len(),
+                plan.actions.len(),
+                applied.join(", "),
+                e
+            )));
         }
-        
-        Ok(())
+        Ok(plan)
     }
 
-    /// Install single package
-    pub async fn install_package(&self, name: &str, version: &str) -> BlastResult<()> {
-        // Prepare pip command
-        let mut cmd = self.create_pip_command();
-        
-        cmd.arg("install")
-            .arg("--no-deps") // Dependencies handled separately
-            .arg("--no-cache-dir") // Use our own caching
-            .arg(format!("{}=={}", name, version));
-        
-        // Add index URL if specified
-        if !self.config.index_url.is_empty() {
-            cmd.arg("--index-url").arg(&self.config.index_url);
-        }
-        
-        // Add extra index URLs
-        for url in &self.config.extra_index_urls {
-            cmd.arg("--extra-index-url").arg(url);
-        }
-        
-        // Add trusted hosts
-        for host in &self.config.trusted_hosts {
-            cmd.arg("--trusted-host").arg(host);
+    /// Run a plan level by level, each level's steps concurrently
+    async fn execute_plan(&self, plan: Vec<Vec<InstallationStep>>) -> BlastResult<()> {
+        self.execute_levels(plan, &Mutex::default()).await
+    }
+
+    /// Run a plan's levels, collecting the packages whose steps completed
+    async fn execute_levels(&self, plan: Vec<Vec<InstallationStep>>, done: &Mutex<HashSet<String>>) -> BlastResult<()> {
+        for level in plan {
+            stream::iter(level.into_iter().map(Ok))
+                .try_for_each_concurrent(self.max_concurrent_ops, |step| async move {
+                    let name = step.name().to_string();
+                    self.execute_step(step).await?;
+                    done.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(name);
+                    Ok::<_, BlastError>(())
+                })
+                .await?;
         }
-        
-        // Execute command
-        let output = cmd.output().await?;
-        
-        if !output.status.success() {
-            return Err(crate::error::BlastError::package(format!(
-                "Failed to install package {}: {}",
-                name,
-                String::from_utf8_lossy(&output.stderr)
-            )));
+        Ok(())
+    }
+
+    /// Run a step of a plan, reporting its progress
+    async fn execute_step(&self, step: InstallationStep) -> BlastResult<()> {
+        let id = self.progress.start_operation(step.name().to_string()).await;
+        let result = self.run_step(&id, &step).await;
+        match &result {
+            Ok(()) => self.progress.complete_operation(&id).await,
+            Err(e) => self.progress.fail_operation(&id, e.to_string()).await,
+        };
+        result
+    }
+
+    async fn run_step(&self, id: &str, step: &InstallationStep) -> BlastResult<()> {
+        match step {
+            InstallationStep::Install { name, version } => {
+                let wheel = self.fetch_wheel(id, name, version).await?;
+                self.report(id, ProgressStep::Installing, 0.5, format!("Unpacking {}", file_name(&wheel))).await;
+                self.install_wheel(&wheel).await
+            }
+            InstallationStep::Update { name, from, to } => {
+                let wheel = self.fetch_wheel(id, name, to).await?;
+                self.report(id, ProgressStep::Installing, 0.3, format!("Removing {} {}", name, from)).await;
+                self.uninstall_package(name).await?;
+                self.report(id, ProgressStep::Installing, 0.6, format!("Unpacking {}", file_name(&wheel))).await;
+                self.install_wheel(&wheel).await.map_err(|e| {
+                    BlastError::package(format!("{} {} was removed, but {} could not be installed: {}", name, from, to, e))
+                })
+            }
+            InstallationStep::Remove { name } => {
+                self.report(id, ProgressStep::Installing, 0.5, format!("Removing {}", name)).await;
+                self.uninstall_package(name).await
+            }
         }
-        
+    }
+
+    /// Get the wheel of a step from the artifact cache
+    async fn fetch_wheel(&self, id: &str, name: &str, version: &str) -> BlastResult<PathBuf> {
+        self.report(id, ProgressStep::Downloading, 0.0, format!("Fetching {}=={}", name, version)).await;
+        self.cached_wheel(name, version)
+    }
+
+    async fn report(&self, id: &str, step: ProgressStep, progress: f32, action: String) {
+        self.progress.update_operation(id, step, progress, action).await;
+    }
+
+    /// Install single package from its wheel in the artifact cache
+    pub async fn install_package(&self, name: &str, version: &str) -> BlastResult<()> {
+        let wheel = self.cached_wheel(name, version)?;
+        self.install_wheel(&wheel).await
+    }
+
+    /// Install a package from a wheel file, such as one built from a local
+    /// path or git checkout
+    pub async fn install_wheel(&self, wheel: &Path) -> BlastResult<()> {
+        let installer = self.wheel_installer();
+        let path = wheel.to_path_buf();
+        let installed = tokio::task::spawn_blocking(move || installer.install(&path))
+            .await
+            .map_err(|e| BlastError::package(format!("Failed to install {}: {}", wheel.display(), e)))??;
+        debug!("Installed {} {} into {}", installed.name, installed.version, self.config.env_path.display());
         Ok(())
     }
 
-    /// Update single package
+    /// Update single package, replacing whatever version is installed
     pub async fn update_package(&self, name: &str, to: &str) -> BlastResult<()> {
-        // First uninstall old version
+        // Check the new version is at hand before removing the old one
+        let wheel = self.cached_wheel(name, to)?;
         self.uninstall_package(name).await?;
-        
-        // Then install new version
-        self.install_package(name, to).await?;
-        
-        Ok(())
+        self.install_wheel(&wheel).await
     }
 
-    /// Uninstall single package
+    /// Uninstall single package, using the files its `RECORD` lists
     pub async fn uninstall_package(&self, name: &str) -> BlastResult<()> {
-        // Prepare pip command
-        let mut cmd = self.create_pip_command();
-        
-        cmd.arg("uninstall")
-            .arg("--yes") // Don't ask for confirmation
-            .arg(name);
-        
-        // Execute command
-        let output = cmd.output().await?;
-        
-        if !output.status.success() {
-            return Err(crate::error::BlastError::package(format!(
-                "Failed to uninstall package {}: {}",
-                name,
-                String::from_utf8_lossy(&output.stderr)
-            )));
+        let installer = self.wheel_installer();
+        let package = name.to_string();
+        let removed = tokio::task::spawn_blocking(move || installer.uninstall(&package))
+            .await
+            .map_err(|e| BlastError::package(format!("Failed to uninstall package {}: {}", name, e)))??;
+        match removed {
+            Some(removed) => debug!("Uninstalled {} {}", removed.name, removed.version),
+            None => debug!("{} is not installed", name),
         }
-        
         Ok(())
     }
 
-    /// Create pip command with proper environment
-    fn create_pip_command(&self) -> Command {
-        let mut cmd = Command::new(self.get_pip_path());
-        
-        // Set environment variables
-        cmd.env("PYTHONPATH", &self.config.env_path)
-            .env("PIP_DISABLE_PIP_VERSION_CHECK", "1")
-            .env("PIP_NO_WARN_SCRIPT_LOCATION", "1");
-        
-        if self.config.require_hashes {
-            cmd.env("PIP_REQUIRE_HASHES", "1");
+    /// Get the installer for the environment's directories
+    fn wheel_installer(&self) -> WheelInstaller {
+        let installer = WheelInstaller::for_env(&self.config.env_path, &self.config.python_version);
+        match &self.store {
+            Some(store) => installer.with_store(store.clone()),
+            None => installer,
         }
-        
-        cmd
     }
 
-    /// Get pip executable path
-    fn get_pip_path(&self) -> &Path {
-        // TODO: Make this configurable and platform-specific
-        Path::new("/usr/local/bin/pip")
+    /// Get the artifact cache directory holding a release's files
+    fn artifact_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.config.cache_dir
+            .join(ARTIFACTS_DIR)
+            .join(normalize_name(name))
+            .join(version)
     }
 
-    /// Create installation plan from dependency graph
-    fn create_installation_plan(&self, graph: &DependencyGraph) -> Vec<InstallationStep> {
-        let mut plan = Vec::new();
-        
-        // Add installation steps in dependency order
-        for node in graph.nodes() {
-            plan.push(InstallationStep::Install {
-                name: node.name.clone(),
-                version: node.version.clone(),
-            });
+    /// Find the wheel of a release, the one given for the package or else
+    /// the best in the artifact cache
+    fn cached_wheel(&self, name: &str, version: &str) -> BlastResult<PathBuf> {
+        if let Some(wheel) = self.wheels.get(&normalize_name(name)) {
+            return Ok(wheel.clone());
         }
-        
-        plan
-    }
-
-    /// Create update plan from dependency graph
-    fn create_update_plan(&self, graph: &DependencyGraph) -> Vec<InstallationStep> {
-        let mut plan = Vec::new();
-        
-        // Add update steps in dependency order
-        for node in graph.nodes() {
-            if let Some(current_version) = node.current_version.as_ref() {
-                plan.push(InstallationStep::Update {
-                    name: node.name.clone(),
-                    from: current_version.clone(),
-                    to: node.version.clone(),
-                });
-            } else {
-                plan.push(InstallationStep::Install {
+        let dir = self.artifact_dir(name, version);
+        let mut wheels: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map(|entries| entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect())
+            .unwrap_or_default();
+        wheels.retain(|path| path.extension().is_some_and(|extension| extension == "whl"));
+        let found = wheels.len();
+
+        let mut ranked: Vec<(usize, PathBuf)> = wheels
+            .into_iter()
+            .filter_map(|path| {
+                let filename = path.file_name()?.to_string_lossy().into_owned();
+                let rank = match &self.wheel_ranking {
+                    Some(ranking) => ranking(&filename)?,
+                    None => 0,
+                };
+                Some((rank, path))
+            })
+            .collect();
+        ranked.sort();
+        match ranked.into_iter().next() {
+            Some((_, wheel)) => Ok(wheel),
+            None if found > 0 => Err(BlastError::package(format!(
+                "Cannot install {}=={}: none of the {} cached wheels supports this environment ({})",
+                name,
+                version,
+                found,
+                dir.display()
+            ))),
+            None => Err(BlastError::package(format!(
+                "Cannot install {}=={}: no wheel in the artifact cache ({})",
+                name,
+                version,
+                dir.display()
+            ))),
+        }
+    }
+
+    fn check_cached_wheels(&self, plan: &[Vec<InstallationStep>]) -> BlastResult<()> {
+        let missing: Vec<String> = plan.iter()
+            .flatten()
+            .filter_map(|step| match step {
+                InstallationStep::Install { name, version }
+                | InstallationStep::Update { name, to: version, .. } => Some((name, version)),
+                InstallationStep::Remove { .. } => None,
+            })
+            .filter(|(name, version)| self.cached_wheel(name, version).is_err())
+            .map(|(name, version)| format!("{}=={}", name, version))
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(BlastError::package(format!(
+            "Cannot install, no compatible wheel in the artifact cache for: {}",
+            missing.join(", ")
+        )))
+    }
+
+    /// Create installation plan from dependency graph, in levels of
+    /// packages whose dependencies are all in earlier levels
+    fn create_installation_plan(&self, graph: &DependencyGraph) -> Vec<Vec<InstallationStep>> {
+        graph.installation_levels()
+            .into_iter()
+            .map(|level| level.into_iter()
+                .map(|node| InstallationStep::Install {
                     name: node.name.clone(),
                     version: node.version.clone(),
-                });
+                })
+                .collect())
+            .collect()
+    }
+
+    /// Create update plan from dependency graph, in the same levels
+    fn create_update_plan(&self, graph: &DependencyGraph) -> Vec<Vec<InstallationStep>> {
+        graph.installation_levels()
+            .into_iter()
+            .map(|level| level.into_iter()
+                .map(|node| match &node.current_version {
+                    Some(current_version) => InstallationStep::Update {
+                        name: node.name.clone(),
+                        from: current_version.clone(),
+                        to: node.version.clone(),
+                    },
+                    None => InstallationStep::Install {
+                        name: node.name.clone(),
+                        version: node.version.clone(),
+                    },
+                })
+                .collect())
+            .collect()
+    }
+
+    /// Compare what is installed with a graph, giving the sync plan and the
+    /// steps carrying it out
+    fn create_sync_plan(&self, graph: &DependencyGraph) -> BlastResult<(SyncPlan, Vec<Vec<InstallationStep>>)> {
+        let installed: HashMap<String, InstalledDistribution> = self.wheel_installer()
+            .installed()?
+            .into_iter()
+            .map(|distribution| (normalize_name(&distribution.name), distribution))
+            .collect();
+        let wanted: HashSet<String> = graph.nodes().iter().map(|node| normalize_name(&node.name)).collect();
+
+        let mut plan = SyncPlan::default();
+        let mut removals = Vec::new();
+        let mut unwanted: Vec<&InstalledDistribution> = installed.iter()
+            .filter(|(name, _)| !wanted.contains(*name))
+            .map(|(_, distribution)| distribution)
+            .collect();
+        unwanted.sort_by_key(|distribution| normalize_name(&distribution.name));
+        for distribution in unwanted {
+            if self.keep_unmanaged && !installed_by_blast(distribution) {
+                plan.unmanaged.push(distribution.name.clone());
+                continue;
             }
+            plan.actions.push(SyncAction::Remove {
+                name: distribution.name.clone(),
+                version: distribution.version.clone(),
+            });
+            removals.push(InstallationStep::Remove {
+                name: distribution.name.clone(),
+            });
+        }
+
+        let mut steps = vec![removals];
+        for level in graph.installation_levels() {
+            let mut level_steps = Vec::new();
+            for node in level {
+                let (name, version) = (node.name.clone(), node.version.clone());
+                let Some(current) = installed.get(&normalize_name(&name)) else {
+                    plan.actions.push(SyncAction::Install { name: name.clone(), version: version.clone() });
+                    level_steps.push(InstallationStep::Install { name, version });
+                    continue;
+                };
+                let from = current.version.clone();
+                let action = match compare_versions(&from, &version) {
+                    Some(Ordering::Equal) => {
+                        plan.unchanged.push(name);
+                        continue;
+                    }
+                    Some(Ordering::Less) => SyncAction::Upgrade { name: name.clone(), from: from.clone(), to: version.clone() },
+                    Some(Ordering::Greater) => SyncAction::Downgrade { name: name.clone(), from: from.clone(), to: version.clone() },
+                    None => SyncAction::Reinstall { name: name.clone(), from: from.clone(), to: version.clone() },
+                };
+                plan.actions.push(action);
+                level_steps.push(InstallationStep::Update { name, from, to: version });
+            }
+            steps.push(level_steps);
+        }
+        steps.retain(|level| !level.is_empty());
+        Ok((plan, steps))
+    }
+}
+
+/// Change a sync makes to one package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAction {
+    /// Install a missing package
+    Install {
+        name: String,
+        version: String,
+    },
+    /// Replace an older installed version
+    Upgrade {
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// Replace a newer installed version
+    Downgrade {
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// Replace an installed version that cannot be ordered against the
+    /// wanted one, as either is not a valid version
+    Reinstall {
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// Remove a package that is not wanted
+    Remove {
+        name: String,
+        version: String,
+    },
+}
+
+impl SyncAction {
+    /// Get the package the action is about
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Install { name, .. }
+            | Self::Upgrade { name, .. }
+            | Self::Downgrade { name, .. }
+            | Self::Reinstall { name, .. }
+            | Self::Remove { name, .. } => name,
         }
-        
-        plan
+    }
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Install { name, version } => write!(f, "install {} {}", name, version),
+            Self::Upgrade { name, from, to } => write!(f, "upgrade {} {} -> {}", name, from, to),
+            Self::Downgrade { name, from, to } => write!(f, "downgrade {} {} -> {}", name, from, to),
+            Self::Reinstall { name, from, to } => write!(f, "reinstall {} {} -> {}", name, from, to),
+            Self::Remove { name, version } => write!(f, "remove {} {}", name, version),
+        }
+    }
+}
+
+/// What syncing an environment to a package set changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// Changes, removals first and then installs in dependency order
+    pub actions: Vec<SyncAction>,
+    /// Packages already installed at the wanted version
+    pub unchanged: Vec<String>,
+    /// Packages other tools installed that are kept although not wanted
+    pub unmanaged: Vec<String>,
+}
+
+impl SyncPlan {
+    /// Check if the environment already matches
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
     }
 }
 
@@ -206,13 +521,40 @@ enum InstallationStep {
     /// Update existing package
     Update {
         name: String,
-        #[allow(dead_code)]
         from: String,
         to: String,
     },
     /// Remove package
-    #[allow(dead_code)]
     Remove {
         name: String,
     },
+}
+
+impl InstallationStep {
+    /// Get the package the step is about
+    fn name(&self) -> &str {
+        match self {
+            Self::Install { name, .. } | Self::Update { name, .. } | Self::Remove { name } => name,
+        }
+    }
+}
+
+/// Check if blast installed a distribution, going by its `INSTALLER` file
+fn installed_by_blast(distribution: &InstalledDistribution) -> bool {
+    std::fs::read_to_string(distribution.dist_info.join("INSTALLER"))
+        .is_ok_and(|installer| installer.trim() == INSTALLER_NAME)
+}
+
+/// Compare versions by PEP 440, or `None` if either is not a valid
+/// version and they differ
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    match (Pep440Version::parse(a), Pep440Version::parse(b)) {
+        (Ok(a), Ok(b)) => Some(a.cmp(&b)),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
 } 
\ No newline at end of file
@@ -16,14 +16,18 @@ mod state;
 mod graph;
 mod progress;
 mod scheduler;
+mod wheel;
 
 pub use resolver::DependencyResolver;
-pub use installer::PackageInstaller;
+pub use installer::{PackageInstaller, SyncAction, SyncPlan, WheelRanking};
 pub use interceptor::PipInterceptor;
 pub use state::{PackageState, PackageInfo};
 pub use graph::{DependencyGraph, DependencyNode};
 pub use progress::{ProgressTracker, InstallationProgress, InstallationStep};
 pub use scheduler::{OperationScheduler, SchedulerConfig, OperationPriority, OperationType, OperationStatus, QueueStatistics};
+pub use wheel::{
+    unpack_wheel, InstallScheme, InstalledDistribution, StoreLock, UnpackedWheel, WheelInstaller, WheelStore, INSTALLER_NAME,
+};
 
 /// Package version information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +97,8 @@ pub struct PackageConfig {
     pub cache_dir: PathBuf,
     /// Cache TTL in seconds
     pub cache_ttl: u64,
+    /// Install only from the artifact cache, never from an index
+    pub offline: bool,
 }
 
 impl Default for PackageConfig {
@@ -107,6 +113,7 @@ impl Default for PackageConfig {
             allow_prereleases: false,
             cache_dir: PathBuf::from("/var/lib/blast/cache"),
             cache_ttl: 86400, // 24 hours
+            offline: false,
         }
     }
 }
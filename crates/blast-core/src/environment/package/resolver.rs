@@ -1,9 +1,8 @@
 use std::collections::{HashMap, HashSet};
-use semver::{Version as SemVersion, VersionReq};
 use crate::error::BlastResult;
 use super::{PackageConfig, Version, Dependency, DependencyGraph, PackageState, PackageInfo};
 use std::path::Path;
-use crate::version::VersionConstraint;
+use crate::version::{Version as Pep440Version, VersionConstraint};
 use tokio::sync::RwLock;
 use std::sync::Arc;
 
@@ -12,7 +11,7 @@ use std::sync::Arc;
 pub struct VersionConflict {
     pub package: String,
     pub required_versions: Vec<Version>,
-    pub requiring_packages: HashMap<String, VersionReq>,
+    pub requiring_packages: HashMap<String, VersionConstraint>,
     pub resolution_strategy: ConflictResolutionStrategy,
 }
 
@@ -121,16 +120,15 @@ impl DependencyResolver {
     fn find_latest_satisfying_version(
         &self,
         package: &str,
-        constraints: &HashMap<String, VersionReq>,
+        constraints: &HashMap<String, VersionConstraint>,
     ) -> BlastResult<Version> {
         let versions = self.get_available_versions(package)?;
         
         versions.into_iter()
             .rev() // Latest first
             .find(|version| {
-                constraints.values().all(|req| {
-                    req.matches(&SemVersion::parse(&version.version).unwrap())
-                })
+                Pep440Version::parse(&version.version)
+                    .map_or(false, |v| constraints.values().all(|req| req.matches(&v)))
             })
             .ok_or_else(|| {
                 crate::error::BlastError::resolution(
@@ -144,15 +142,14 @@ impl DependencyResolver {
     fn find_minimum_satisfying_version(
         &self,
         package: &str,
-        constraints: &HashMap<String, VersionReq>,
+        constraints: &HashMap<String, VersionConstraint>,
     ) -> BlastResult<Version> {
         let versions = self.get_available_versions(package)?;
         
         versions.into_iter()
             .find(|version| {
-                constraints.values().all(|req| {
-                    req.matches(&SemVersion::parse(&version.version).unwrap())
-                })
+                Pep440Version::parse(&version.version)
+                    .map_or(false, |v| constraints.values().all(|req| req.matches(&v)))
             })
             .ok_or_else(|| {
                 crate::error::BlastError::resolution(
@@ -166,7 +163,7 @@ impl DependencyResolver {
     fn find_maximum_satisfying_version(
         &self,
         package: &str,
-        constraints: &HashMap<String, VersionReq>,
+        constraints: &HashMap<String, VersionConstraint>,
     ) -> BlastResult<Version> {
         let versions = self.get_available_versions(package)?;
         
@@ -176,7 +173,7 @@ impl DependencyResolver {
         for version in versions {
             let satisfaction_count = constraints.values()
                 .filter(|req| {
-                    req.matches(&SemVersion::parse(&version.version).unwrap())
+                    Pep440Version::parse(&version.version).map_or(false, |v| req.matches(&v))
                 })
                 .count();
             
@@ -247,7 +244,7 @@ impl DependencyResolver {
             }
 
             // Get compatible version
-            let version_req = VersionReq::parse(&dep.version_constraint)
+            let version_req = VersionConstraint::parse(&dep.version_constraint)
                 .map_err(|e| crate::error::BlastError::resolution(e.to_string()))?;
 
             let dep_version = self.find_compatible_version(&dep.name, &version_req)?;
@@ -302,11 +299,11 @@ impl DependencyResolver {
             for dep in deps {
                 if let Some(installed) = state.get_package(&dep.name) {
                     // Parse version constraint
-                    let version_req = VersionReq::parse(&dep.version_constraint)
+                    let version_req = VersionConstraint::parse(&dep.version_constraint)
                         .map_err(|e| crate::error::BlastError::resolution(e.to_string()))?;
                     
                     // Check if installed version satisfies constraint
-                    let version = SemVersion::parse(&installed.version.version)
+                    let version = Pep440Version::parse(&installed.version.version)
                         .map_err(|e| crate::error::BlastError::resolution(e.to_string()))?;
                     
                     if !version_req.matches(&version) {
@@ -347,14 +344,14 @@ impl DependencyResolver {
     }
 
     /// Find compatible version
-    fn find_compatible_version(&self, name: &str, req: &VersionReq) -> BlastResult<Version> {
+    fn find_compatible_version(&self, name: &str, req: &VersionConstraint) -> BlastResult<Version> {
         if let Some(versions) = self.cache.get(name) {
             // Find latest compatible version
             for version in versions.iter().rev() {
-                let semver = SemVersion::parse(&version.version)
+                let parsed = Pep440Version::parse(&version.version)
                     .map_err(|e| crate::error::BlastError::resolution(e.to_string()))?;
 
-                if req.matches(&semver) {
+                if req.matches(&parsed) {
                     if let Some(ref requires) = version.python_requires {
                         if self.is_python_compatible(requires) {
                             return Ok(version.clone());
@@ -374,9 +371,9 @@ impl DependencyResolver {
     /// Check Python version compatibility
     fn is_python_compatible(&self, requires: &str) -> bool {
         // Parse Python version requirement
-        if let Ok(req) = VersionReq::parse(requires) {
+        if let Ok(req) = VersionConstraint::parse(requires) {
             // Parse current Python version
-            if let Ok(version) = SemVersion::parse(&self.config.python_version) {
+            if let Ok(version) = Pep440Version::parse(&self.config.python_version) {
                 return req.matches(&version);
             }
         }
@@ -395,9 +392,9 @@ impl DependencyResolver {
         
         if let Some(versions) = self.cache.get(name) {
             for version in versions {
-                let semver = SemVersion::parse(&version.version)
+                let parsed = Pep440Version::parse(&version.version)
                     .map_err(|e| crate::error::BlastError::resolution(e.to_string()))?;
-                if required.matches(&crate::version::Version::parse(&semver.to_string())?) && version.version != installed.version {
+                if required.matches(&parsed) && version.version != installed.version {
                     compatible.push(version.clone());
                 }
             }
@@ -0,0 +1,891 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tracing::debug;
+use crate::error::{BlastError, BlastResult};
+use crate::package::normalize_name;
+use crate::types::LinkMode;
+
+/// Name written to the `INSTALLER` file of installed distributions
+pub const INSTALLER_NAME: &str = "blast";
+
+/// Longest shebang line that every kernel reads whole
+const MAX_SHEBANG: usize = 127;
+
+/// Directories the files of a wheel are installed into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallScheme {
+    /// Pure Python modules
+    pub purelib: PathBuf,
+    /// Platform specific modules
+    pub platlib: PathBuf,
+    /// Executables
+    pub scripts: PathBuf,
+    /// Data files, relative to the environment root
+    pub data: PathBuf,
+    /// C headers, in a directory per distribution
+    pub headers: PathBuf,
+    /// Root of the environment; nothing outside it is ever removed
+    pub prefix: PathBuf,
+}
+
+impl InstallScheme {
+    /// Get the scheme of a virtual environment
+    pub fn for_env(env_path: &Path, python_version: &str) -> Self {
+        let site_packages = site_packages(env_path, python_version);
+        let (scripts, headers) = if cfg!(windows) {
+            (env_path.join("Scripts"), env_path.join("Include").join("site"))
+        } else {
+            let python = format!("python{}", major_minor(python_version));
+            (env_path.join("bin"), env_path.join("include").join("site").join(python))
+        };
+        Self {
+            purelib: site_packages.clone(),
+            platlib: site_packages,
+            scripts,
+            data: env_path.to_path_buf(),
+            headers,
+            prefix: env_path.to_path_buf(),
+        }
+    }
+
+    /// Get the directory of a `.data` subdirectory of a wheel
+    fn directory(&self, key: &str, distribution: &str) -> Option<PathBuf> {
+        match key {
+            "purelib" => Some(self.purelib.clone()),
+            "platlib" => Some(self.platlib.clone()),
+            "scripts" => Some(self.scripts.clone()),
+            "data" => Some(self.data.clone()),
+            "headers" => Some(self.headers.join(distribution)),
+            _ => None,
+        }
+    }
+
+    /// Check if a directory is one of the scheme's own, which are kept when
+    /// they become empty
+    fn is_root(&self, dir: &Path) -> bool {
+        [&self.purelib, &self.platlib, &self.scripts, &self.data, &self.headers, &self.prefix]
+            .iter()
+            .any(|root| normalize_path(root) == dir)
+    }
+}
+
+/// Store of unpacked wheels that installs link files from instead of
+/// unpacking every wheel again
+///
+/// Methods block and are called from the installing thread.
+pub trait WheelStore: std::fmt::Debug + Send + Sync {
+    /// Get the unpacked copy of a wheel, unpacking it first if the store
+    /// has none
+    fn unpacked(&self, wheel: &Path) -> BlastResult<UnpackedWheel>;
+
+    /// Get the ways files are placed into environments, in the order they
+    /// are tried
+    fn link_modes(&self) -> &[LinkMode];
+
+    /// Record that an installed distribution was linked from an unpacked
+    /// wheel
+    fn add_reference(&self, unpacked: &UnpackedWheel, dist_info: &Path) -> BlastResult<()>;
+
+    /// Keep unpacked wheels from being collected until the lock is dropped
+    ///
+    /// Installs hold it from unpacking a wheel until its reference is
+    /// recorded, as the wheel is unreferenced in between.
+    fn lock_shared(&self) -> BlastResult<StoreLock>;
+}
+
+/// Advisory lock on a lock file, released when dropped
+///
+/// Any number of shared holders exclude an exclusive one, as installs
+/// exclude a garbage collection of the store they link from.
+#[derive(Debug)]
+pub struct StoreLock {
+    _file: fs::File,
+}
+
+impl StoreLock {
+    /// Take the lock shared, waiting while it is held exclusively
+    pub fn shared(path: &Path) -> BlastResult<Self> {
+        let file = Self::open(path)?;
+        file.lock_shared()?;
+        Ok(Self { _file: file })
+    }
+
+    /// Take the lock exclusively, waiting while anyone else holds it
+    pub fn exclusive(path: &Path) -> BlastResult<Self> {
+        let file = Self::open(path)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+
+    fn open(path: &Path) -> BlastResult<fs::File> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::OpenOptions::new().create(true).truncate(false).write(true).open(path)?)
+    }
+}
+
+/// A wheel unpacked by [`unpack_wheel`] into a store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnpackedWheel {
+    /// Hex encoded SHA-256 digest of the wheel file
+    pub sha256: String,
+    /// Directory holding the wheel's files
+    pub path: PathBuf,
+}
+
+/// A distribution installed from a wheel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledDistribution {
+    /// Distribution name as the wheel spells it
+    pub name: String,
+    /// Installed version
+    pub version: String,
+    /// The `.dist-info` directory written for it
+    pub dist_info: PathBuf,
+}
+
+/// Installer unpacking wheels into an environment per the wheel spec, and
+/// removing them again
+///
+/// Files go to purelib or platlib, `.data` subdirectories to their scheme
+/// directory, and `RECORD` is rewritten to list everything installed so
+/// the distribution can be removed again. Scripts with a `#!python`
+/// shebang and the launchers generated for console and GUI entry points
+/// run the environment's interpreter.
+#[derive(Debug, Clone)]
+pub struct WheelInstaller {
+    scheme: InstallScheme,
+    /// Interpreter scripts are run with
+    python: PathBuf,
+    /// Store files are linked from, if any
+    store: Option<Arc<dyn WheelStore>>,
+}
+
+impl WheelInstaller {
+    /// Create an installer for a scheme and interpreter
+    pub fn new(scheme: InstallScheme, python: impl Into<PathBuf>) -> Self {
+        Self {
+            scheme,
+            python: python.into(),
+            store: None,
+        }
+    }
+
+    /// Link files from a store of unpacked wheels instead of unpacking
+    /// each wheel into the environment
+    pub fn with_store(mut self, store: Arc<dyn WheelStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Create an installer for a virtual environment
+    pub fn for_env(env_path: &Path, python_version: &str) -> Self {
+        let python = if cfg!(windows) {
+            env_path.join("Scripts").join("python.exe")
+        } else {
+            env_path.join("bin").join("python")
+        };
+        Self::new(InstallScheme::for_env(env_path, python_version), python)
+    }
+
+    /// Get the directories files are installed into
+    pub fn scheme(&self) -> &InstallScheme {
+        &self.scheme
+    }
+
+    /// Install a wheel file, replacing files it shares with what is installed
+    pub fn install(&self, wheel: &Path) -> BlastResult<InstalledDistribution> {
+        let Some(store) = &self.store else {
+            return self.install_files(wheel, WheelFiles::archive(wheel)?);
+        };
+        let _lock = store.lock_shared()?;
+        let unpacked = store.unpacked(wheel)?;
+        let files = WheelFiles::unpacked(&unpacked.path, store.link_modes())?;
+        let installed = self.install_files(wheel, files)?;
+        store.add_reference(&unpacked, &installed.dist_info)?;
+        Ok(installed)
+    }
+
+    fn install_files(&self, wheel: &Path, mut files: WheelFiles) -> BlastResult<InstalledDistribution> {
+        let names = files.names();
+        let dist_info = find_dist_info(&names, wheel)?;
+        let (name, version) = dist_info.trim_end_matches(".dist-info").split_once('-')
+            .ok_or_else(|| invalid(wheel, format!("bad metadata directory {}", dist_info)))?;
+        let (name, version) = (name.to_string(), version.to_string());
+        if let Some(filename) = wheel.file_name().and_then(|name| name.to_str()) {
+            let expected = filename.split('-').next().unwrap_or_default();
+            if normalize_name(expected) != normalize_name(&name) {
+                return Err(invalid(wheel, format!("contains {} instead of {}", name, expected)));
+            }
+        }
+
+        let wheel_info = files.read(&format!("{}/WHEEL", dist_info))
+            .ok_or_else(|| invalid(wheel, format!("{}/WHEEL is missing", dist_info)))??;
+        let wheel_info = parse_headers(&wheel_info);
+        let wheel_version = wheel_info.get("wheel-version").map(String::as_str).unwrap_or("1.0");
+        if wheel_version.split('.').next() != Some("1") {
+            return Err(invalid(wheel, format!("unsupported Wheel-Version {}", wheel_version)));
+        }
+        let root = match wheel_info.get("root-is-purelib").map(String::as_str) {
+            Some("true") => &self.scheme.purelib,
+            _ => &self.scheme.platlib,
+        };
+
+        let record_path = format!("{}/RECORD", dist_info);
+        let expected: HashMap<String, String> = match files.read(&record_path) {
+            Some(record) => parse_record(&record?),
+            None => HashMap::new(),
+        };
+        // Entry points are checked before anything is written
+        let scripts = match files.read(&format!("{}/entry_points.txt", dist_info)) {
+            Some(entry_points) => console_scripts(&entry_points?)?,
+            None => Vec::new(),
+        };
+        let data_dir = format!("{}.data/", dist_info.trim_end_matches(".dist-info"));
+
+        let mut record = Vec::new();
+        for path in &names {
+            let relative = safe_path(path).ok_or_else(|| invalid(wheel, format!("unsafe path {}", path)))?;
+            // RECORD is written anew and its signatures no longer apply
+            if *path == record_path || *path == format!("{}.jws", record_path) || *path == format!("{}.p7s", record_path) {
+                continue;
+            }
+
+            let (target, is_script) = match path.strip_prefix(&data_dir) {
+                Some(rest) => {
+                    let (key, rest) = rest.split_once('/')
+                        .ok_or_else(|| invalid(wheel, format!("unexpected file {}", path)))?;
+                    let directory = self.scheme.directory(key, &name)
+                        .ok_or_else(|| invalid(wheel, format!("unknown data directory {}", key)))?;
+                    (directory.join(rest), key == "scripts")
+                }
+                None => (root.join(relative), false),
+            };
+            let hash = expected.get(path).filter(|hash| !hash.is_empty()).map(String::as_str);
+            let placed = if is_script {
+                let data = self.rewrite_shebang(files.read_bytes(path, hash, wheel)?);
+                write_file(&target, &data, true)?;
+                RecordEntry::new(&target, &data)
+            } else {
+                files.place(path, hash, &target, wheel)?
+            };
+            record.push(placed);
+        }
+
+        for (script, module, attr) in scripts {
+            let target = self.scheme.scripts.join(&script);
+            let data = self.launcher(&module, &attr).into_bytes();
+            write_file(&target, &data, true)?;
+            record.push(RecordEntry::new(&target, &data));
+        }
+
+        let dist_info_dir = root.join(&dist_info);
+        let installer = dist_info_dir.join("INSTALLER");
+        let data = format!("{}\n", INSTALLER_NAME).into_bytes();
+        write_file(&installer, &data, false)?;
+        record.push(RecordEntry::new(&installer, &data));
+
+        let mut content = String::new();
+        for entry in &record {
+            content.push_str(&entry.line(root));
+        }
+        content.push_str(&format!("{},,\n", csv_field(&record_path)));
+        write_file(&dist_info_dir.join("RECORD"), content.as_bytes(), false)?;
+
+        debug!("Installed {} {} ({} files)", name, version, record.len() + 1);
+        Ok(InstalledDistribution {
+            name,
+            version,
+            dist_info: dist_info_dir,
+        })
+    }
+
+    /// Point a `#!python` or `#!pythonw` shebang at the environment's
+    /// interpreter, keeping any arguments
+    fn rewrite_shebang(&self, data: Vec<u8>) -> Vec<u8> {
+        let Some(rest) = data.strip_prefix(b"#!python") else {
+            return data;
+        };
+        let rest = rest.strip_prefix(b"w").unwrap_or(rest);
+        let end = rest.iter().position(|&b| b == b'\n').map_or(rest.len(), |i| i + 1);
+        // Anything but arguments after `#!python` is some other shebang
+        if end > 0 && !matches!(rest[0], b' ' | b'\t' | b'\r' | b'\n') {
+            return data;
+        }
+        let arguments = String::from_utf8_lossy(&rest[..end]).trim().to_string();
+        let mut script = self.shebang(&arguments).into_bytes();
+        script.extend_from_slice(&rest[end..]);
+        script
+    }
+
+    /// Get the shebang running a script with the environment's interpreter
+    ///
+    /// Interpreter paths that are too long or contain spaces go through
+    /// `/bin/sh`, with a line that is a no-op string in Python.
+    fn shebang(&self, arguments: &str) -> String {
+        let python = self.python.display().to_string();
+        let line = match arguments {
+            "" => format!("#!{}", python),
+            _ => format!("#!{} {}", python, arguments),
+        };
+        if line.len() <= MAX_SHEBANG && !python.contains(' ') {
+            return format!("{}\n", line);
+        }
+        format!("#!/bin/sh\n'''exec' \"{}\" {}\"$0\" \"$@\"\n' '''\n", python, match arguments {
+            "" => String::new(),
+            _ => format!("{} ", arguments),
+        })
+    }
+
+    /// Get a launcher script calling an entry point
+    fn launcher(&self, module: &str, attr: &str) -> String {
+        let import = attr.split('.').next().unwrap_or(attr);
+        format!(
+            "{}# -*- coding: utf-8 -*-\n\
+             import re\n\
+             import sys\n\
+             from {} import {}\n\
+             if __name__ == \"__main__\":\n    \
+             sys.argv[0] = re.sub(r\"(-script\\.pyw|\\.exe)?$\", \"\", sys.argv[0])\n    \
+             sys.exit({}())\n",
+            self.shebang(""),
+            module,
+            import,
+            attr
+        )
+    }
+}
+
+impl WheelInstaller {
+    /// List the distributions installed in the scheme's site-packages,
+    /// sorted by name
+    pub fn installed(&self) -> BlastResult<Vec<InstalledDistribution>> {
+        let mut roots = vec![&self.scheme.purelib];
+        if self.scheme.platlib != self.scheme.purelib {
+            roots.push(&self.scheme.platlib);
+        }
+        let mut installed = Vec::new();
+        for root in roots {
+            let Ok(entries) = fs::read_dir(root) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                let distribution = path.file_name()
+                    .and_then(|file| file.to_str())
+                    .and_then(|file| file.strip_suffix(".dist-info"))
+                    .and_then(|stem| stem.split_once('-'))
+                    .map(|(name, version)| (name.to_string(), version.to_string()));
+                if let Some((name, version)) = distribution.filter(|_| path.is_dir()) {
+                    installed.push(InstalledDistribution {
+                        name,
+                        version,
+                        dist_info: path,
+                    });
+                }
+            }
+        }
+        installed.sort_by_key(|distribution| normalize_name(&distribution.name));
+        Ok(installed)
+    }
+
+    /// Find the `.dist-info` directory of an installed distribution
+    pub fn find_installed(&self, name: &str) -> BlastResult<Option<PathBuf>> {
+        let name = normalize_name(name);
+        Ok(self.installed()?
+            .into_iter()
+            .find(|distribution| normalize_name(&distribution.name) == name)
+            .map(|distribution| distribution.dist_info))
+    }
+
+    /// Remove an installed distribution, returning what was removed or
+    /// `None` if it is not installed
+    ///
+    /// Every file in its `RECORD` goes, with the bytecode compiled from it,
+    /// the scripts generated for its entry points and the directories left
+    /// empty. Nothing is removed if any listed file lies outside the
+    /// environment.
+    pub fn uninstall(&self, name: &str) -> BlastResult<Option<InstalledDistribution>> {
+        let Some(dist_info) = self.find_installed(name)? else {
+            return Ok(None);
+        };
+        let dir_name = dist_info.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let (name, version) = dir_name.trim_end_matches(".dist-info").split_once('-').unwrap_or((&dir_name, ""));
+        let root = dist_info.parent().unwrap_or(&dist_info);
+
+        let record = dist_info.join("RECORD");
+        if !record.is_file() {
+            return Err(BlastError::package(format!(
+                "Cannot uninstall {}: {} has no RECORD",
+                name,
+                dist_info.display()
+            )));
+        }
+        let mut files: BTreeSet<PathBuf> = parse_record(&fs::read_to_string(&record)?)
+            .into_keys()
+            .map(|path| normalize_path(&root.join(path)))
+            .collect();
+        if let Ok(entry_points) = fs::read_to_string(dist_info.join("entry_points.txt")) {
+            for (script, _, _) in console_scripts(&entry_points)? {
+                for file in [script.clone(), format!("{}.exe", script), format!("{}-script.py", script)] {
+                    files.insert(normalize_path(&self.scheme.scripts.join(file)));
+                }
+            }
+        }
+
+        let prefix = normalize_path(&self.scheme.prefix);
+        let outside: Vec<String> = files.iter()
+            .filter(|file| !file.starts_with(&prefix))
+            .map(|file| file.display().to_string())
+            .collect();
+        if !outside.is_empty() {
+            return Err(BlastError::package(format!(
+                "Refusing to uninstall {}: RECORD lists files outside {}: {}",
+                name,
+                prefix.display(),
+                outside.join(", ")
+            )));
+        }
+
+        let mut dirs = BTreeSet::new();
+        for file in &files {
+            remove_file(file)?;
+            if let Some(parent) = file.parent() {
+                dirs.insert(parent.to_path_buf());
+            }
+            // Bytecode compiled from a module, e.g. `__pycache__/six.cpython-311.pyc`
+            if file.extension().is_some_and(|extension| extension == "py") {
+                let (Some(parent), Some(stem)) = (file.parent(), file.file_stem()) else {
+                    continue;
+                };
+                let cache = parent.join("__pycache__");
+                let module = format!("{}.", stem.to_string_lossy());
+                for entry in fs::read_dir(&cache).into_iter().flatten() {
+                    let path = entry?.path();
+                    let compiled = path.file_name()
+                        .and_then(|file| file.to_str())
+                        .is_some_and(|file| file.starts_with(&module) && file.ends_with(".pyc"));
+                    if compiled {
+                        remove_file(&path)?;
+                    }
+                }
+                dirs.insert(cache);
+            }
+        }
+        if dist_info.is_dir() {
+            fs::remove_dir_all(&dist_info)?;
+        }
+
+        // Deepest first, so parents are empty by the time they are checked
+        for dir in dirs.iter().rev() {
+            let mut dir = dir.as_path();
+            while dir.starts_with(&prefix) && !self.scheme.is_root(dir) && fs::remove_dir(dir).is_ok() {
+                let Some(parent) = dir.parent() else {
+                    break;
+                };
+                dir = parent;
+            }
+        }
+
+        debug!("Uninstalled {} {} ({} files)", name, version, files.len());
+        Ok(Some(InstalledDistribution {
+            name: name.to_string(),
+            version: version.to_string(),
+            dist_info,
+        }))
+    }
+}
+
+/// Files of a wheel, read from its archive or linked from an unpacked copy
+enum WheelFiles<'a> {
+    Archive(zip::ZipArchive<fs::File>),
+    Unpacked {
+        dir: PathBuf,
+        link_modes: &'a [LinkMode],
+    },
+}
+
+impl<'a> WheelFiles<'a> {
+    fn archive(wheel: &Path) -> BlastResult<Self> {
+        let file = fs::File::open(wheel)?;
+        Ok(Self::Archive(zip::ZipArchive::new(file).map_err(|e| invalid(wheel, e))?))
+    }
+
+    fn unpacked(dir: &Path, link_modes: &'a [LinkMode]) -> BlastResult<Self> {
+        if !dir.is_dir() {
+            return Err(BlastError::package(format!("Unpacked wheel {} is missing", dir.display())));
+        }
+        Ok(Self::Unpacked {
+            dir: dir.to_path_buf(),
+            link_modes,
+        })
+    }
+
+    /// Get the paths of all files, `/` separated as in the archive
+    fn names(&self) -> Vec<String> {
+        match self {
+            Self::Archive(archive) => archive.file_names()
+                .filter(|name| !name.ends_with('/'))
+                .map(str::to_string)
+                .collect(),
+            Self::Unpacked { dir, .. } => {
+                let mut names = Vec::new();
+                list_files(dir, dir, &mut names);
+                names.sort();
+                names
+            }
+        }
+    }
+
+    /// Read a text file of the wheel, if it has one
+    fn read(&mut self, name: &str) -> Option<BlastResult<String>> {
+        match self {
+            Self::Archive(archive) => {
+                let mut entry = archive.by_name(name).ok()?;
+                let mut content = String::new();
+                Some(entry.read_to_string(&mut content).map(|_| content).map_err(BlastError::from))
+            }
+            Self::Unpacked { dir, .. } => {
+                let path = dir.join(name);
+                path.is_file().then(|| fs::read_to_string(path).map_err(BlastError::from))
+            }
+        }
+    }
+
+    /// Read a file, checking it against its `RECORD` hash
+    fn read_bytes(&mut self, name: &str, hash: Option<&str>, wheel: &Path) -> BlastResult<Vec<u8>> {
+        let data = match self {
+            Self::Archive(archive) => {
+                let mut entry = archive.by_name(name).map_err(|e| invalid(wheel, e))?;
+                let mut data = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut data)?;
+                data
+            }
+            Self::Unpacked { dir, .. } => fs::read(dir.join(name))?,
+        };
+        check_hash(name, hash, &data, wheel)?;
+        Ok(data)
+    }
+
+    /// Put a file at its installed location
+    ///
+    /// Unpacked files were checked when they were unpacked, and are linked
+    /// with the hash from `RECORD` kept.
+    fn place(&mut self, name: &str, hash: Option<&str>, target: &Path, wheel: &Path) -> BlastResult<RecordEntry> {
+        let (dir, link_modes) = match self {
+            Self::Archive(archive) => {
+                let executable = archive.by_name(name).ok()
+                    .and_then(|entry| entry.unix_mode())
+                    .is_some_and(|mode| mode & 0o111 != 0);
+                let data = self.read_bytes(name, hash, wheel)?;
+                write_file(target, &data, executable)?;
+                return Ok(RecordEntry::new(target, &data));
+            }
+            Self::Unpacked { dir, link_modes } => (dir, *link_modes),
+        };
+        let source = dir.join(name);
+        link_file(&source, target, link_modes)?;
+        match hash {
+            Some(hash) => Ok(RecordEntry {
+                path: target.to_path_buf(),
+                hash: hash.to_string(),
+                size: fs::metadata(&source)?.len() as usize,
+            }),
+            None => Ok(RecordEntry::new(target, &fs::read(&source)?)),
+        }
+    }
+}
+
+/// Unpack a wheel as it is into a directory, checking every file against
+/// its `RECORD` hash, for installs to link from later
+pub fn unpack_wheel(wheel: &Path, dest: &Path) -> BlastResult<()> {
+    let mut files = WheelFiles::archive(wheel)?;
+    let names = files.names();
+    let dist_info = find_dist_info(&names, wheel)?;
+    let expected: HashMap<String, String> = match files.read(&format!("{}/RECORD", dist_info)) {
+        Some(record) => parse_record(&record?),
+        None => HashMap::new(),
+    };
+
+    for name in &names {
+        let relative = safe_path(name).ok_or_else(|| invalid(wheel, format!("unsafe path {}", name)))?;
+        let hash = expected.get(name).filter(|hash| !hash.is_empty()).map(String::as_str);
+        files.place(name, hash, &dest.join(relative), wheel)?;
+    }
+    Ok(())
+}
+
+/// An installed file as listed in `RECORD`
+struct RecordEntry {
+    path: PathBuf,
+    hash: String,
+    size: usize,
+}
+
+impl RecordEntry {
+    fn new(path: &Path, data: &[u8]) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            hash: format!("sha256={}", URL_SAFE_NO_PAD.encode(Sha256::digest(data))),
+            size: data.len(),
+        }
+    }
+
+    /// Format the entry with its path relative to the install root
+    fn line(&self, root: &Path) -> String {
+        format!("{},{},{}\n", csv_field(&relative_path(&self.path, root)), self.hash, self.size)
+    }
+}
+
+/// Get the site-packages directory of a virtual environment
+pub fn site_packages(env_path: &Path, python_version: &str) -> PathBuf {
+    if cfg!(windows) {
+        return env_path.join("Lib").join("site-packages");
+    }
+    env_path.join("lib")
+        .join(format!("python{}", major_minor(python_version)))
+        .join("site-packages")
+}
+
+/// Get the `X.Y` part of a Python version
+fn major_minor(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/// Find the single top-level `.dist-info` directory of a wheel
+fn find_dist_info(names: &[String], wheel: &Path) -> BlastResult<String> {
+    let mut found: Vec<&str> = names.iter()
+        .filter_map(|name| name.split_once('/').map(|(dir, _)| dir))
+        .filter(|dir| dir.ends_with(".dist-info"))
+        .collect();
+    found.sort_unstable();
+    found.dedup();
+    match found.as_slice() {
+        [dist_info] => Ok(dist_info.to_string()),
+        [] => Err(invalid(wheel, "no .dist-info directory")),
+        _ => Err(invalid(wheel, format!("several .dist-info directories: {}", found.join(", ")))),
+    }
+}
+
+/// Parse `Key: value` lines, with keys lowercased
+fn parse_headers(content: &str) -> HashMap<String, String> {
+    content.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect()
+}
+
+/// Parse `RECORD` into hashes by path
+///
+/// Hashes and sizes never contain commas, so the path is everything before
+/// the last two fields.
+fn parse_record(content: &str) -> HashMap<String, String> {
+    content.lines()
+        .filter_map(|line| {
+            let mut fields = line.rsplitn(3, ',');
+            let _size = fields.next()?;
+            let hash = fields.next()?;
+            let path = fields.next()?;
+            let path = match path.strip_prefix('"').and_then(|path| path.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\"\"", "\""),
+                None => path.to_string(),
+            };
+            Some((path, hash.to_string()))
+        })
+        .collect()
+}
+
+/// Check data against a `RECORD` hash, or `None` if its algorithm is
+/// unknown or one the wheel spec forbids, such as md5 and sha1
+fn hash_matches(hash: &str, data: &[u8]) -> Option<bool> {
+    let (algorithm, expected) = hash.split_once('=')?;
+    let digest = match algorithm {
+        "sha256" => Sha256::digest(data).to_vec(),
+        "sha384" => Sha384::digest(data).to_vec(),
+        "sha512" => Sha512::digest(data).to_vec(),
+        _ => return None,
+    };
+    Some(URL_SAFE_NO_PAD.encode(digest) == expected.trim_end_matches('='))
+}
+
+/// Get the scripts declared in `entry_points.txt`, as name, module and
+/// attribute
+fn console_scripts(content: &str) -> BlastResult<Vec<(String, String, String)>> {
+    let mut scripts = Vec::new();
+    let mut section = "";
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.trim();
+            continue;
+        }
+        if section != "console_scripts" && section != "gui_scripts" {
+            continue;
+        }
+        let (name, value) = line.split_once('=')
+            .ok_or_else(|| BlastError::package(format!("Invalid entry point: {}", line)))?;
+        // Extras only matter to whoever installs the dependencies
+        let value = value.split('[').next().unwrap_or(value).trim();
+        let (module, attr) = value.split_once(':')
+            .filter(|(module, attr)| !module.trim().is_empty() && !attr.trim().is_empty())
+            .ok_or_else(|| BlastError::package(format!("Invalid entry point: {}", line)))?;
+        // Scripts are written to the scripts directory under their name
+        let name = name.trim();
+        if !is_file_name(name) {
+            return Err(BlastError::package(format!("Invalid entry point: unsafe script name {}", name)));
+        }
+        scripts.push((name.to_string(), module.trim().to_string(), attr.trim().to_string()));
+    }
+    Ok(scripts)
+}
+
+/// Fail if a file of a wheel does not match its `RECORD` hash
+fn check_hash(name: &str, hash: Option<&str>, data: &[u8], wheel: &Path) -> BlastResult<()> {
+    let Some(hash) = hash else {
+        return Ok(());
+    };
+    match hash_matches(hash, data) {
+        Some(true) => Ok(()),
+        Some(false) => Err(invalid(wheel, format!("{} does not match the hash in RECORD", name))),
+        None => Err(invalid(wheel, format!("{} has an unsupported hash in RECORD: {}", name, hash))),
+    }
+}
+
+/// Check a path from a wheel stays inside the directory it is unpacked to
+fn safe_path(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| path.to_path_buf())
+}
+
+/// Check a name is a single plain path component, with no separators
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+/// Write a file, replacing rather than truncating what is there since the
+/// old file may be linked elsewhere
+fn write_file(path: &Path, data: &[u8], executable: bool) -> BlastResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if path.symlink_metadata().is_ok() {
+        fs::remove_file(path)?;
+    }
+    fs::write(path, data)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
+
+/// Place a stored file at a target by the first link mode that works,
+/// replacing what is there
+fn link_file(source: &Path, target: &Path, link_modes: &[LinkMode]) -> BlastResult<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    remove_file(target)?;
+
+    let mut error = None;
+    for mode in link_modes {
+        let linked = match mode {
+            // Clones get the permissions of a new file, not the source's
+            LinkMode::Reflink => reflink_copy::reflink(source, target)
+                .and_then(|_| fs::set_permissions(target, fs::metadata(source)?.permissions())),
+            LinkMode::Hardlink => fs::hard_link(source, target),
+            LinkMode::Copy => fs::copy(source, target).map(|_| ()),
+        };
+        match linked {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                debug!("Cannot {:?} {} to {}: {}", mode, source.display(), target.display(), e);
+                remove_file(target)?;
+                error = Some(e);
+            }
+        }
+    }
+    Err(BlastError::package(format!(
+        "Failed to link {} to {}: {}",
+        source.display(),
+        target.display(),
+        error.map_or_else(|| "no link mode configured".to_string(), |e| e.to_string())
+    )))
+}
+
+/// Collect the paths of the files below a directory, relative to `root` and
+/// `/` separated
+fn list_files(root: &Path, dir: &Path, names: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(root, &path, names);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let parts: Vec<_> = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect();
+            names.push(parts.join("/"));
+        }
+    }
+}
+
+/// Remove a file, if it is still there
+fn remove_file(path: &Path) -> BlastResult<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve `.` and `..` in a path without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Get a path relative to a base directory, as `RECORD` lists it
+fn relative_path(path: &Path, base: &Path) -> String {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let parents = std::iter::repeat_n("..".to_string(), base.len() - common);
+    let rest = path[common..].iter().map(|component| component.as_os_str().to_string_lossy().into_owned());
+    parents.chain(rest).collect::<Vec<_>>().join("/")
+}
+
+/// Quote a `RECORD` field if it needs it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn invalid(wheel: &Path, reason: impl std::fmt::Display) -> BlastError {
+    BlastError::package(format!("Invalid wheel {}: {}", wheel.display(), reason))
+}
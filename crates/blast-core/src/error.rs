@@ -3,6 +3,8 @@ use std::io;
 use glob::PatternError;
 use thiserror::Error;
 
+use crate::diagnostics::Diagnostic;
+
 /// Custom result type for Blast operations
 pub type BlastResult<T> = Result<T, BlastError>;
 
@@ -27,6 +29,9 @@ pub enum BlastError {
     #[error("Resolution error: {0}")]
     Resolution(String),
 
+    #[error("Resolution error: {0}")]
+    Conflict(Box<Diagnostic>),
+
     #[error("Configuration error: {0}")]
     Config(String),
 
@@ -108,6 +113,19 @@ impl BlastError {
         BlastError::Resolution(msg.into())
     }
 
+    /// Create a resolution error explained by a diagnostic
+    pub fn conflict(diagnostic: Diagnostic) -> Self {
+        BlastError::Conflict(Box::new(diagnostic))
+    }
+
+    /// Get the diagnostic explaining this error, if any
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            BlastError::Conflict(diagnostic) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
     /// Create a new configuration error
     pub fn config<S: Into<String>>(msg: S) -> Self {
         BlastError::Config(msg.into())
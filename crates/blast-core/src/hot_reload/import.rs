@@ -1,5 +1,24 @@
+use async_trait::async_trait;
 use regex::Regex;
 
+use crate::error::BlastResult;
+
+/// Maps imported modules to the distributions providing them
+///
+/// `import yaml` needs `PyYAML`, so the auto-installer asks this rather than
+/// installing the module name.
+#[async_trait]
+pub trait ImportResolver: Send + Sync {
+    /// Get the distribution to install for an imported module
+    ///
+    /// Fails if several distributions provide the module, rather than
+    /// guessing which one is meant.
+    async fn distribution_for(&self, import_name: &str) -> BlastResult<String>;
+
+    /// Remember that installing `distribution` provided an imported module
+    async fn learn_import(&self, import_name: &str, distribution: &str) -> BlastResult<()>;
+}
+
 /// Represents a Python import statement
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImportStatement {
@@ -32,7 +51,10 @@ impl ImportStatement {
         }
     }
 
-    /// Get the root package name that needs to be installed
+    /// Get the top-level module imported
+    ///
+    /// This names the distribution to install only when the two match; see
+    /// [`ImportResolver`].
     pub fn get_package_name(&self) -> String {
         if self.is_from {
             // For 'from' imports, use the first part of the path
@@ -1,16 +1,15 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::collections::HashMap;
 use tokio::sync::RwLock;
 use notify::{Watcher, RecursiveMode, Event};
+use tracing::warn;
 use crate::error::{BlastResult, BlastError};
-use crate::version::VersionConstraint;
-use crate::metadata::PackageMetadata;
 use super::{
     HotReloadConfig,
     HotReloadUpdate,
     HotReloadUpdateType,
     HotReloadUpdateStatus,
+    ImportResolver,
     ImportStatement,
 };
 
@@ -24,16 +23,20 @@ pub struct HotReloadManager {
     updates: Arc<RwLock<Vec<HotReloadUpdate>>>,
     /// Watched paths
     watched_paths: Vec<PathBuf>,
+    /// Distributions providing imported modules
+    imports: Arc<dyn ImportResolver>,
 }
 
 impl HotReloadManager {
-    /// Create new hot reload manager
-    pub fn new(config: HotReloadConfig) -> Self {
+    /// Create new hot reload manager, installing for imports the
+    /// distributions `imports` maps them to
+    pub fn new(config: HotReloadConfig, imports: Arc<dyn ImportResolver>) -> Self {
         Self {
             config,
             watcher: None,
             updates: Arc::new(RwLock::new(Vec::new())),
             watched_paths: Vec::new(),
+            imports,
         }
     }
 
@@ -61,6 +64,7 @@ impl HotReloadManager {
 
         // Handle file system events
         let updates = self.updates.clone();
+        let resolver = self.imports.clone();
         tokio::spawn(async move {
             while let Some(event) = rx.recv().await {
                 match event.kind {
@@ -72,30 +76,20 @@ impl HotReloadManager {
                                     if let Ok(content) = tokio::fs::read_to_string(path).await {
                                         let imports = Self::extract_imports(&content);
                                         for import in imports {
-                                            let pkg_name = import.get_package_name();
-                                            let mut deps = HashMap::new();
-                                            deps.insert(pkg_name.clone(), VersionConstraint::any());
-                                            
-                                            let metadata = PackageMetadata::new(
-                                                pkg_name.clone(),
-                                                "*".to_string(),
-                                                deps,
-                                                VersionConstraint::any(),
-                                            );
-                                            
-                                            if let Ok(package) = crate::package::Package::new(
-                                                pkg_name,
-                                                "*".to_string(),
-                                                metadata,
-                                                VersionConstraint::any(),
-                                            ) {
-                                                let update = HotReloadUpdate {
-                                                    timestamp: tokio::time::Instant::now(),
-                                                    update_type: HotReloadUpdateType::Package(package),
-                                                    status: HotReloadUpdateStatus::Pending,
-                                                };
-                                                updates.write().await.push(update);
-                                            }
+                                            let module = import.get_package_name();
+                                            let distribution = match resolver.distribution_for(&module).await {
+                                                Ok(distribution) => distribution,
+                                                Err(e) => {
+                                                    warn!("Not installing anything for {}: {}", module, e);
+                                                    continue;
+                                                }
+                                            };
+                                            let update = HotReloadUpdate {
+                                                timestamp: tokio::time::Instant::now(),
+                                                update_type: HotReloadUpdateType::Import { module, distribution },
+                                                status: HotReloadUpdateStatus::Pending,
+                                            };
+                                            updates.write().await.push(update);
                                         }
                                     }
                                 }
@@ -125,10 +119,20 @@ impl HotReloadManager {
     }
 
     /// Update status for a specific update
+    ///
+    /// A completed install for an import is remembered, so the module maps
+    /// to the distribution that provided it from then on.
     pub async fn update_status(&self, timestamp: tokio::time::Instant, status: HotReloadUpdateStatus) {
         let mut updates = self.updates.write().await;
         if let Some(update) = updates.iter_mut().find(|u| u.timestamp == timestamp) {
             update.status = status;
+            if let (HotReloadUpdateStatus::Completed, HotReloadUpdateType::Import { module, distribution }) =
+                (&update.status, &update.update_type)
+            {
+                if let Err(e) = self.imports.learn_import(module, distribution).await {
+                    warn!("Failed to remember that {} provides {}: {}", distribution, module, e);
+                }
+            }
         }
     }
 
@@ -6,6 +6,13 @@ use tokio::time::Instant;
 pub enum HotReloadUpdateType {
     /// Package update
     Package(Package),
+    /// Install of the distribution providing an imported module
+    Import {
+        /// Top-level module imported
+        module: String,
+        /// Distribution providing it
+        distribution: String,
+    },
     /// Environment variable update
     EnvVar(String, String),
     /// Python version update
@@ -39,7 +39,7 @@ pub use crate::error::{BlastError, BlastResult};
 pub use crate::package::{Package, PackageId};
 pub use crate::version::{Version, VersionConstraint};
 pub use crate::python::{PythonEnvironment, PythonVersion};
-pub use crate::types::{CacheSettings, UpdateStrategy};
+pub use crate::types::{CacheSettings, LinkMode, UpdateStrategy};
 pub use crate::version_control::{VersionManager, VersionPolicy, UpgradeStrategy};
 pub use crate::version_history::{VersionHistory, VersionEvent, VersionImpact, VersionChangeAnalysis};
 pub use crate::state::{EnvironmentState, StateCheckpoint, StateDiff, StateVerification};
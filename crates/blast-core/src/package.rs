@@ -6,6 +6,24 @@ use crate::error::BlastResult;
 use crate::version::{Version, VersionConstraint};
 use crate::metadata::{PackageMetadata, BuildMetadata, DistributionMetadata};
 
+/// Normalize a project name as described by PEP 503
+///
+/// Runs of `-`, `_` and `.` collapse into a single `-` and the result is
+/// lowercased, so `Foo.Bar`, `foo__bar` and `foo-bar` all compare equal.
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
 /// Unique identifier for a package
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PackageId {
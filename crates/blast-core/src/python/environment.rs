@@ -3,6 +3,7 @@ use tokio::process::Command;
 use crate::error::BlastResult;
 use crate::package::Package;
 use crate::environment::Environment;
+use crate::environment::package::WheelInstaller;
 use super::PythonVersion;
 
 /// Python environment implementation
@@ -94,17 +95,10 @@ impl Environment for PythonEnvironment {
     }
 
     async fn uninstall_package(&self, name: String) -> BlastResult<()> {
-        let output = Command::new(self.inner.path.join("bin").join("pip"))
-            .args(&["uninstall", "-y", &name])
-            .output()
-            .await?;
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(crate::error::BlastError::Environment(
-                format!("Failed to uninstall package {}", name)
-            ))
-        }
+        let installer = WheelInstaller::for_env(&self.inner.path, &self.version_string);
+        tokio::task::spawn_blocking(move || installer.uninstall(&name).map(|_| ()))
+            .await
+            .map_err(|e| crate::error::BlastError::Environment(format!("Failed to uninstall package: {}", e)))?
     }
 
     async fn update_package(&self, name: String, version: String) -> BlastResult<()> {
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use crate::error::{BlastError, BlastResult};
+use crate::version::Version;
 
 /// Python version
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -72,6 +73,16 @@ impl PythonVersion {
         self.patch
     }
 
+    /// Get this interpreter version as a PEP 440 version, e.g. for
+    /// matching against `requires_python`
+    pub fn to_version(&self) -> Version {
+        Version::new([
+            u64::from(self.major),
+            u64::from(self.minor),
+            u64::from(self.patch.unwrap_or(0)),
+        ])
+    }
+
     /// Check if this version is compatible with another version
     pub fn is_compatible_with(&self, other: &PythonVersion) -> bool {
         // Major version must match exactly
@@ -312,9 +312,9 @@ impl UpgradeOperation {
     }
 
     pub fn validate_versions(&self, package: &Package) -> bool {
-        let current_constraint = VersionConstraint::parse(&format!("={}", self.from_version))
+        let current_constraint = VersionConstraint::parse(&format!("=={}", self.from_version))
             .unwrap_or_else(|_| VersionConstraint::any());
-        let target_constraint = VersionConstraint::parse(&format!("={}", self.to_version))
+        let target_constraint = VersionConstraint::parse(&format!("=={}", self.to_version))
             .unwrap_or_else(|_| VersionConstraint::any());
         
         current_constraint.matches(package.version()) || target_constraint.matches(package.version())
@@ -737,7 +737,7 @@ impl SyncManager {
                     }
 
                     // Check for major version changes
-                    if from_version.as_semver().major != to_version.as_semver().major {
+                    if from_version.major() != to_version.major() {
                         issues.push(ValidationIssue {
                             severity: IssueSeverity::Warning,
                             description: format!(
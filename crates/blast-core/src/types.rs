@@ -35,9 +35,10 @@ pub struct CacheSettings {
     /// Time to keep unused packages in cache
     #[serde(with = "duration_serde")]
     pub ttl: Duration,
-    /// Whether to use hardlinks when possible
+    /// Whether to hardlink files from the package store when possible
     pub use_hardlinks: bool,
-    /// Whether to use copy-on-write when possible
+    /// Whether to reflink (copy-on-write) files from the package store when
+    /// possible
     pub use_cow: bool,
 }
 
@@ -53,6 +54,42 @@ impl Default for CacheSettings {
     }
 }
 
+impl CacheSettings {
+    /// Get the ways to place files from the package store into an
+    /// environment, in the order they are tried
+    ///
+    /// Reflinks share blocks on filesystems that support them, hardlinks need
+    /// the store on the same filesystem, and copying always works.
+    pub fn link_modes(&self) -> Vec<LinkMode> {
+        let mut modes = Vec::new();
+        if self.use_cow {
+            modes.push(LinkMode::Reflink);
+        }
+        if self.use_hardlinks {
+            modes.push(LinkMode::Hardlink);
+        }
+        modes.push(LinkMode::Copy);
+        modes
+    }
+}
+
+/// Way of placing a file from the package store into an environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// Copy-on-write clone
+    Reflink,
+    /// Hard link to the stored file
+    ///
+    /// The installed file and the stored one are the same inode, so editing
+    /// an installed file in place, rather than replacing it, changes the
+    /// store and every other environment linked to it. Installs replace
+    /// files before writing, but other tools may not.
+    Hardlink,
+    /// Full copy
+    Copy,
+}
+
 /// Get the default cache directory
 fn default_cache_dir() -> PathBuf {
     dirs::cache_dir()
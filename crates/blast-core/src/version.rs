@@ -1,31 +1,268 @@
 use std::cmp::Ordering;
 use std::fmt;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
-use serde::{Deserialize, Serialize};
-use semver::{Version as SemVer, VersionReq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::{BlastError, BlastResult};
 
+/// Pre-release phase of a version
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PreReleaseKind {
+    /// Alpha release (`a`, `alpha`)
+    Alpha,
+    /// Beta release (`b`, `beta`)
+    Beta,
+    /// Release candidate (`rc`, `c`, `pre`, `preview`)
+    ReleaseCandidate,
+}
+
+impl fmt::Display for PreReleaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Alpha => write!(f, "a"),
+            Self::Beta => write!(f, "b"),
+            Self::ReleaseCandidate => write!(f, "rc"),
+        }
+    }
+}
+
+/// Pre-release segment of a version, e.g. `rc1`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct PreRelease {
+    /// Pre-release phase
+    pub kind: PreReleaseKind,
+    /// Pre-release number
+    pub number: u64,
+}
+
+impl PreRelease {
+    /// Create a new pre-release segment
+    pub fn new(kind: PreReleaseKind, number: u64) -> Self {
+        Self { kind, number }
+    }
+}
+
+impl fmt::Display for PreRelease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind, self.number)
+    }
+}
+
+/// Segment of a local version label, e.g. `ubuntu` or `1` in `+ubuntu.1`
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum LocalSegment {
+    /// Numeric segment, compared numerically
+    Number(u64),
+    /// Alphanumeric segment, compared lexicographically
+    String(String),
+}
+
+impl Ord for LocalSegment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Numeric segments always sort after alphanumeric ones
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Number(_), Self::String(_)) => Ordering::Greater,
+            (Self::String(_), Self::Number(_)) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for LocalSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for LocalSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
 /// Package version following PEP 440
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
-pub struct Version(SemVer);
+///
+/// Versions are normalized on parse, so `1.0-RC.1` and `1.0rc1` are the same
+/// version, and compare equal to each other regardless of trailing zeros in
+/// the release segment (`1.0 == 1.0.0`).
+#[derive(Debug, Clone)]
+pub struct Version {
+    epoch: u64,
+    release: Vec<u64>,
+    pre: Option<PreRelease>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: Vec<LocalSegment>,
+}
 
 impl Version {
-    /// Create a new version
-    pub fn new(version: SemVer) -> Self {
-        Self(version)
+    /// Create a new final release version from its release segment
+    pub fn new(release: impl IntoIterator<Item = u64>) -> Self {
+        let mut release: Vec<u64> = release.into_iter().collect();
+        if release.is_empty() {
+            release.push(0);
+        }
+        Self {
+            epoch: 0,
+            release,
+            pre: None,
+            post: None,
+            dev: None,
+            local: Vec::new(),
+        }
     }
 
     /// Parse a version string
     pub fn parse(version: &str) -> BlastResult<Self> {
-        Ok(Self(SemVer::parse(version).map_err(|e| {
-            BlastError::version(format!("Invalid version '{}': {}", version, e))
-        })?))
+        VersionParser::new(version).parse().ok_or_else(|| {
+            BlastError::version(format!("Invalid version '{}': not a valid PEP 440 version", version))
+        })
+    }
+
+    /// Set the epoch
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Set the pre-release segment
+    pub fn with_pre(mut self, pre: Option<PreRelease>) -> Self {
+        self.pre = pre;
+        self
+    }
+
+    /// Set the post-release number
+    pub fn with_post(mut self, post: Option<u64>) -> Self {
+        self.post = post;
+        self
+    }
+
+    /// Set the development release number
+    pub fn with_dev(mut self, dev: Option<u64>) -> Self {
+        self.dev = dev;
+        self
+    }
+
+    /// Set the local version label
+    pub fn with_local(mut self, local: Vec<LocalSegment>) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Get the epoch
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Get the release segment
+    pub fn release(&self) -> &[u64] {
+        &self.release
+    }
+
+    /// Get the pre-release segment
+    pub fn pre(&self) -> Option<PreRelease> {
+        self.pre
+    }
+
+    /// Get the post-release number
+    pub fn post(&self) -> Option<u64> {
+        self.post
+    }
+
+    /// Get the development release number
+    pub fn dev(&self) -> Option<u64> {
+        self.dev
+    }
+
+    /// Get the local version label
+    pub fn local(&self) -> &[LocalSegment] {
+        &self.local
+    }
+
+    /// Get the major release number
+    pub fn major(&self) -> u64 {
+        self.release_part(0)
+    }
+
+    /// Get the minor release number
+    pub fn minor(&self) -> u64 {
+        self.release_part(1)
+    }
+
+    /// Get the micro release number
+    pub fn micro(&self) -> u64 {
+        self.release_part(2)
+    }
+
+    /// Get a release segment part, treating missing parts as zero
+    pub fn release_part(&self, index: usize) -> u64 {
+        self.release.get(index).copied().unwrap_or(0)
+    }
+
+    /// Check if this is a pre-release (including development releases)
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Check if this is a post-release
+    pub fn is_postrelease(&self) -> bool {
+        self.post.is_some()
     }
 
-    /// Get the underlying semver version
-    pub fn as_semver(&self) -> &SemVer {
-        &self.0
+    /// Check if this is a development release
+    pub fn is_devrelease(&self) -> bool {
+        self.dev.is_some()
+    }
+
+    /// Check if this version has a local version label
+    pub fn is_local(&self) -> bool {
+        !self.local.is_empty()
+    }
+
+    /// Get the public version, i.e. this version without its local label
+    pub fn public(&self) -> Self {
+        Self {
+            local: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Get the base version, i.e. the epoch and release segment only
+    pub fn base(&self) -> Self {
+        Self::new(self.release.iter().copied()).with_epoch(self.epoch)
+    }
+
+    /// Release segment without trailing zeros, used for comparison
+    fn trimmed_release(&self) -> &[u64] {
+        let len = self.release.iter().rposition(|&n| n != 0).map_or(0, |i| i + 1);
+        &self.release[..len]
+    }
+}
+
+/// Sort key for the pre-release position of a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PhaseKey {
+    /// Development release of a final version (`1.0.dev0` < `1.0a0`)
+    Dev,
+    /// Pre-release
+    Pre(PreRelease),
+    /// Final or post release
+    Final,
+}
+
+impl Version {
+    fn phase_key(&self) -> PhaseKey {
+        match self.pre {
+            Some(pre) => PhaseKey::Pre(pre),
+            None if self.post.is_none() && self.dev.is_some() => PhaseKey::Dev,
+            None => PhaseKey::Final,
+        }
     }
 }
 
@@ -39,7 +276,45 @@ impl FromStr for Version {
 
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some(pre) = self.pre {
+            write!(f, "{}", pre)?;
+        }
+        if let Some(post) = self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        if !self.local.is_empty() {
+            let local: Vec<String> = self.local.iter().map(|s| s.to_string()).collect();
+            write!(f, "+{}", local.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must agree with `Ord`, so trailing zeros are not hashed
+        self.epoch.hash(state);
+        self.trimmed_release().hash(state);
+        self.pre.hash(state);
+        self.post.hash(state);
+        self.dev.hash(state);
+        self.local.hash(state);
     }
 }
 
@@ -51,13 +326,439 @@ impl PartialOrd for Version {
 
 impl Ord for Version {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+        self.epoch.cmp(&other.epoch)
+            .then_with(|| self.trimmed_release().cmp(other.trimmed_release()))
+            .then_with(|| self.phase_key().cmp(&other.phase_key()))
+            .then_with(|| self.post.cmp(&other.post))
+            // A missing dev segment sorts after any dev release
+            .then_with(|| (self.dev.is_none(), self.dev).cmp(&(other.dev.is_none(), other.dev)))
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Hand-written parser for the PEP 440 version grammar
+///
+/// Accepts the permissive forms allowed by the specification (`v` prefix,
+/// `-`/`_`/`.` separators, alternate spellings and implicit numbers) and
+/// produces the normalized representation.
+struct VersionParser {
+    input: Vec<u8>,
+    pos: usize,
+}
+
+impl VersionParser {
+    fn new(input: &str) -> Self {
+        Self {
+            input: input.trim().to_ascii_lowercase().into_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn parse(mut self) -> Option<Version> {
+        self.eat(b'v');
+
+        let first = self.number()?;
+        let (epoch, first) = if self.eat(b'!') {
+            (first, self.number()?)
+        } else {
+            (0, first)
+        };
+
+        let mut release = vec![first];
+        while self.peek() == Some(b'.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            release.push(self.number()?);
+        }
+
+        let pre = self.pre_release();
+        let post = self.post_release();
+        let dev = self.dev_release();
+        let local = if self.eat(b'+') { self.local()? } else { Vec::new() };
+
+        if self.pos != self.input.len() {
+            return None;
+        }
+
+        Some(Version {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            local,
+        })
+    }
+
+    fn pre_release(&mut self) -> Option<PreRelease> {
+        let start = self.pos;
+        self.separator();
+        let kind = if self.keyword(&["alpha", "a"]) {
+            PreReleaseKind::Alpha
+        } else if self.keyword(&["beta", "b"]) {
+            PreReleaseKind::Beta
+        } else if self.keyword(&["preview", "pre", "rc", "c"]) {
+            PreReleaseKind::ReleaseCandidate
+        } else {
+            self.pos = start;
+            return None;
+        };
+        Some(PreRelease::new(kind, self.implicit_number()?))
+    }
+
+    fn post_release(&mut self) -> Option<u64> {
+        let start = self.pos;
+
+        // Implicit post release: `1.0-1`
+        if self.eat(b'-') && self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return self.number();
+        }
+        self.pos = start;
+
+        self.separator();
+        if self.keyword(&["post", "rev", "r"]) {
+            self.implicit_number()
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn dev_release(&mut self) -> Option<u64> {
+        let start = self.pos;
+        self.separator();
+        if self.keyword(&["dev"]) {
+            self.implicit_number()
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn local(&mut self) -> Option<Vec<LocalSegment>> {
+        let mut segments = Vec::new();
+        loop {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return None;
+            }
+            let segment = std::str::from_utf8(&self.input[start..self.pos]).ok()?;
+            segments.push(match segment.parse::<u64>() {
+                Ok(n) if segment.bytes().all(|c| c.is_ascii_digit()) => LocalSegment::Number(n),
+                _ => LocalSegment::String(segment.to_string()),
+            });
+            if !self.separator() {
+                return Some(segments);
+            }
+        }
+    }
+
+    /// Optional `[-_.]? N` following a pre/post/dev keyword; defaults to 0
+    fn implicit_number(&mut self) -> Option<u64> {
+        let start = self.pos;
+        self.separator();
+        if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.number()
+        } else {
+            self.pos = start;
+            Some(0)
+        }
+    }
+
+    fn number(&mut self) -> Option<u64> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        std::str::from_utf8(&self.input[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn keyword(&mut self, keywords: &[&str]) -> bool {
+        for keyword in keywords {
+            if self.input[self.pos..].starts_with(keyword.as_bytes()) {
+                self.pos += keyword.len();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn separator(&mut self) -> bool {
+        if matches!(self.peek(), Some(b'-' | b'_' | b'.')) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.input.get(self.pos + offset).copied()
+    }
+}
+
+/// Comparison operator of a version specifier
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Operator {
+    /// Version matching (`==1.0`)
+    Equal,
+    /// Prefix matching (`==1.0.*`)
+    EqualStar,
+    /// Version exclusion (`!=1.0`)
+    NotEqual,
+    /// Prefix exclusion (`!=1.0.*`)
+    NotEqualStar,
+    /// Compatible release (`~=1.4.2`)
+    Compatible,
+    /// Exclusive ordered comparison (`<1.0`)
+    LessThan,
+    /// Inclusive ordered comparison (`<=1.0`)
+    LessThanEqual,
+    /// Exclusive ordered comparison (`>1.0`)
+    GreaterThan,
+    /// Inclusive ordered comparison (`>=1.0`)
+    GreaterThanEqual,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Equal | Self::EqualStar => write!(f, "=="),
+            Self::NotEqual | Self::NotEqualStar => write!(f, "!="),
+            Self::Compatible => write!(f, "~="),
+            Self::LessThan => write!(f, "<"),
+            Self::LessThanEqual => write!(f, "<="),
+            Self::GreaterThan => write!(f, ">"),
+            Self::GreaterThanEqual => write!(f, ">="),
+        }
+    }
+}
+
+/// Single PEP 440 version specifier, e.g. `>=1.4` or `!=1.5.*`
+#[derive(Debug, Clone)]
+pub enum VersionSpecifier {
+    /// Comparison against a version
+    Version(Operator, Version),
+    /// Arbitrary equality against a literal string (`===foobar`)
+    Arbitrary(String),
+}
+
+impl VersionSpecifier {
+    /// Parse a single version specifier
+    pub fn parse(specifier: &str) -> BlastResult<Self> {
+        let specifier = specifier.trim();
+        let invalid = |reason: &str| {
+            BlastError::version(format!("Invalid version specifier '{}': {}", specifier, reason))
+        };
+
+        if let Some(literal) = specifier.strip_prefix("===") {
+            let literal = literal.trim();
+            if literal.is_empty() || literal.contains(char::is_whitespace) {
+                return Err(invalid("expected a single literal after '==='"));
+            }
+            return Ok(Self::Arbitrary(literal.to_string()));
+        }
+
+        const OPERATORS: [(&str, Operator); 7] = [
+            ("~=", Operator::Compatible),
+            ("==", Operator::Equal),
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessThanEqual),
+            (">=", Operator::GreaterThanEqual),
+            ("<", Operator::LessThan),
+            (">", Operator::GreaterThan),
+        ];
+        let (operator, operand) = OPERATORS.iter()
+            .find_map(|(token, operator)| specifier.strip_prefix(token).map(|rest| (*operator, rest.trim())))
+            // A bare version is treated as an exact pin
+            .unwrap_or((Operator::Equal, specifier));
+
+        let (operator, operand) = match operand.strip_suffix(".*") {
+            Some(prefix) => match operator {
+                Operator::Equal => (Operator::EqualStar, prefix),
+                Operator::NotEqual => (Operator::NotEqualStar, prefix),
+                _ => return Err(invalid("'.*' is only allowed with '==' and '!='")),
+            },
+            None => (operator, operand),
+        };
+
+        let version = Version::parse(operand).map_err(|_| invalid("not a valid PEP 440 version"))?;
+        match operator {
+            Operator::EqualStar | Operator::NotEqualStar
+                if version.pre.is_some() || version.post.is_some() || version.dev.is_some() || version.is_local() =>
+            {
+                return Err(invalid("prefix matching requires a release-only version"));
+            }
+            Operator::Equal | Operator::NotEqual => {}
+            _ if version.is_local() => {
+                return Err(invalid("local versions are only allowed with '==' and '!='"));
+            }
+            Operator::Compatible if version.release.len() < 2 => {
+                return Err(invalid("'~=' requires at least two release segments"));
+            }
+            _ => {}
+        }
+
+        Ok(Self::Version(operator, version))
+    }
+
+    /// Get the operator, or `None` for arbitrary equality
+    pub fn operator(&self) -> Option<Operator> {
+        match self {
+            Self::Version(operator, _) => Some(*operator),
+            Self::Arbitrary(_) => None,
+        }
+    }
+
+    /// Get the version operand, or `None` for arbitrary equality
+    pub fn version(&self) -> Option<&Version> {
+        match self {
+            Self::Version(_, version) => Some(version),
+            Self::Arbitrary(_) => None,
+        }
+    }
+
+    /// Check if a version matches this specifier
+    ///
+    /// Pre-releases are matched like any other version; whether they should
+    /// be considered at all is decided by the caller.
+    pub fn matches(&self, candidate: &Version) -> bool {
+        let (operator, version) = match self {
+            Self::Version(operator, version) => (*operator, version),
+            Self::Arbitrary(literal) => return candidate.to_string().eq_ignore_ascii_case(literal),
+        };
+
+        match operator {
+            Operator::Equal => Self::matches_exact(version, candidate),
+            Operator::NotEqual => !Self::matches_exact(version, candidate),
+            Operator::EqualStar => version.is_prefix_of(candidate),
+            Operator::NotEqualStar => !version.is_prefix_of(candidate),
+            Operator::Compatible => candidate >= version && version.compatible_prefix().is_prefix_of(candidate),
+            Operator::LessThan => {
+                // `<V` excludes pre-releases of V unless V is itself a pre-release
+                candidate < version
+                    && (version.is_prerelease()
+                        || !(candidate.base() == version.base() && candidate.phase_key() != PhaseKey::Final))
+            }
+            Operator::LessThanEqual => candidate.public() <= *version,
+            Operator::GreaterThan => {
+                // `>V` excludes local versions of V, and its post-releases
+                // unless V is itself a post or development release
+                candidate > version
+                    && if version.post.is_none() && version.dev.is_none() {
+                        candidate.release_group() != version.release_group()
+                    } else {
+                        candidate.public() != *version
+                    }
+            }
+            Operator::GreaterThanEqual => candidate >= version,
+        }
+    }
+
+    /// `==V` ignores the candidate's local label unless V has one
+    fn matches_exact(version: &Version, candidate: &Version) -> bool {
+        if version.is_local() {
+            candidate == version
+        } else {
+            candidate.public() == *version
+        }
+    }
+}
+
+impl Version {
+    /// Check if this release-only version is a prefix of another version's release
+    fn is_prefix_of(&self, other: &Version) -> bool {
+        self.epoch == other.epoch
+            && self.release.iter().enumerate().all(|(i, &n)| other.release_part(i) == n)
+    }
+
+    /// Release prefix used by `~=`, e.g. `1.4` for `~=1.4.2`
+    fn compatible_prefix(&self) -> Version {
+        Version::new(self.release[..self.release.len() - 1].iter().copied()).with_epoch(self.epoch)
+    }
+
+    /// Epoch, release and pre-release phase, ignoring post, dev and local segments
+    fn release_group(&self) -> (u64, &[u64], PhaseKey) {
+        (self.epoch, self.trimmed_release(), self.phase_key())
+    }
+}
+
+impl fmt::Display for VersionSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version(operator @ (Operator::EqualStar | Operator::NotEqualStar), version) => {
+                write!(f, "{}{}.*", operator, version)
+            }
+            Self::Version(operator, version) => write!(f, "{}{}", operator, version),
+            Self::Arbitrary(literal) => write!(f, "==={}", literal),
+        }
+    }
+}
+
+impl PartialEq for VersionSpecifier {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare textually so `==1.*` and `==1.0.*` stay distinct
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for VersionSpecifier {}
+
+impl Hash for VersionSpecifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_string().hash(state);
+    }
+}
+
+impl FromStr for VersionSpecifier {
+    type Err = BlastError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
     }
 }
 
 /// Version constraint following PEP 440
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
-pub struct VersionConstraint(VersionReq);
+///
+/// A constraint is a union of specifier sets, each of which is an
+/// intersection of specifiers such as `>=1.4, !=1.5.*`. PEP 440 itself
+/// only has single sets; parsing also accepts unions written with `||`
+/// between sets, such as `<1.0 || >=2.0`.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct VersionConstraint {
+    sets: Vec<Vec<VersionSpecifier>>,
+}
 
 impl Default for VersionConstraint {
     fn default() -> Self {
@@ -66,29 +767,111 @@ impl Default for VersionConstraint {
 }
 
 impl VersionConstraint {
-    /// Create a new version constraint
-    pub fn new(req: VersionReq) -> Self {
-        Self(req)
+    /// Create a new version constraint from a set of specifiers
+    pub fn new(specifiers: impl IntoIterator<Item = VersionSpecifier>) -> Self {
+        Self {
+            sets: vec![dedup(specifiers)],
+        }
     }
 
     /// Create a constraint that matches any version
     pub fn any() -> Self {
-        Self(VersionReq::STAR)
+        Self { sets: vec![Vec::new()] }
+    }
+
+    /// Create a constraint that matches exactly one version
+    pub fn exact(version: Version) -> Self {
+        Self::new([VersionSpecifier::Version(Operator::Equal, version)])
     }
 
     /// Parse a version constraint string
     pub fn parse(constraint: &str) -> BlastResult<Self> {
-        Ok(Self(VersionReq::parse(constraint).map_err(|e| {
-            BlastError::version(format!("Invalid version constraint '{}': {}", constraint, e))
-        })?))
+        let sets = constraint.split("||")
+            .map(|set| {
+                set.split(',')
+                    .map(str::trim)
+                    .filter(|specifier| !specifier.is_empty() && *specifier != "*")
+                    .map(VersionSpecifier::parse)
+                    .collect::<BlastResult<Vec<_>>>()
+                    .map(dedup)
+            })
+            .collect::<BlastResult<Vec<_>>>()?;
+        Ok(Self { sets }.simplified())
     }
 
     /// Check if a version matches this constraint
     pub fn matches(&self, version: &Version) -> bool {
-        self.0.matches(version.as_semver())
+        self.sets.iter().any(|set| set.iter().all(|specifier| specifier.matches(version)))
+    }
+
+    /// Check if this constraint matches any version
+    pub fn is_any(&self) -> bool {
+        self.sets.iter().any(|set| set.is_empty())
+    }
+
+    /// Get the version this constraint pins with `==`, if it is a single
+    /// exact pin such as `==1.4` or `>=1, ==1.4`
+    pub fn pinned(&self) -> Option<&Version> {
+        match self.sets.as_slice() {
+            [set] => set.iter().find_map(|specifier| match specifier {
+                VersionSpecifier::Version(Operator::Equal, version) => Some(version),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Check if this constraint explicitly mentions a pre-release, which
+    /// opts it into matching pre-releases
+    pub fn includes_prereleases(&self) -> bool {
+        self.specifiers().any(|specifier| match specifier {
+            VersionSpecifier::Version(Operator::NotEqual | Operator::NotEqualStar, _) => false,
+            VersionSpecifier::Version(_, version) => version.is_prerelease(),
+            VersionSpecifier::Arbitrary(_) => false,
+        })
+    }
+
+    /// Get the specifier sets whose union forms this constraint
+    pub fn sets(&self) -> &[Vec<VersionSpecifier>] {
+        &self.sets
+    }
+
+    /// Iterate over every specifier in this constraint
+    pub fn specifiers(&self) -> impl Iterator<Item = &VersionSpecifier> {
+        self.sets.iter().flatten()
+    }
+
+    /// Get a constraint matching versions matched by both constraints
+    pub fn intersection(&self, other: &Self) -> Self {
+        let sets = self.sets.iter()
+            .flat_map(|a| other.sets.iter().map(move |b| dedup(a.iter().chain(b).cloned())))
+            .collect();
+        Self { sets }.simplified()
+    }
+
+    /// Get a constraint matching versions matched by either constraint
+    pub fn union(&self, other: &Self) -> Self {
+        let sets = self.sets.iter().chain(&other.sets).cloned().collect();
+        Self { sets }.simplified()
+    }
+
+    /// Drop duplicate sets, collapsing to `any` if one set is unconstrained
+    fn simplified(mut self) -> Self {
+        if self.is_any() {
+            return Self::any();
+        }
+        let mut seen = HashSet::new();
+        self.sets.retain(|set| seen.insert(set.clone()));
+        self
     }
 }
 
+/// Remove repeated specifiers while keeping their order
+fn dedup(specifiers: impl IntoIterator<Item = VersionSpecifier>) -> Vec<VersionSpecifier> {
+    let mut seen = HashSet::new();
+    specifiers.into_iter().filter(|specifier| seen.insert(specifier.clone())).collect()
+}
+
 impl FromStr for VersionConstraint {
     type Err = BlastError;
 
@@ -99,6 +882,28 @@ impl FromStr for VersionConstraint {
 
 impl fmt::Display for VersionConstraint {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        let sets: Vec<String> = self.sets.iter()
+            .map(|set| {
+                if set.is_empty() {
+                    "*".to_string()
+                } else {
+                    set.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                }
+            })
+            .collect();
+        write!(f, "{}", sets.join(" || "))
+    }
+}
+
+impl Serialize for VersionConstraint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionConstraint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::parse(&s).map_err(serde::de::Error::custom)
     }
-} 
\ No newline at end of file
+}
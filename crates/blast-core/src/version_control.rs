@@ -281,7 +281,7 @@ impl VersionManager {
         }
 
         // Check pre-release
-        if !policy.allow_prereleases && target_version.as_semver().pre.len() > 0 {
+        if !policy.allow_prereleases && target_version.is_prerelease() {
             return Ok(false);
         }
 
@@ -22,11 +22,11 @@ pub enum VersionImpact {
 impl VersionImpact {
     /// Determine impact level from version change
     pub fn from_version_change(from: &Version, to: &Version) -> Self {
-        if from.as_semver().major != to.as_semver().major {
+        if from.epoch() != to.epoch() || from.major() != to.major() {
             Self::Breaking
-        } else if from.as_semver().minor != to.as_semver().minor {
+        } else if from.minor() != to.minor() {
             Self::Major
-        } else if from.as_semver().patch != to.as_semver().patch {
+        } else if from.micro() != to.micro() {
             Self::Minor
         } else {
             Self::None
@@ -101,6 +101,10 @@ impl UpdateServiceState {
             update_strategy: Default::default(),
             dependencies: DependenciesConfig::default(),
             dev_dependencies: None,
+            offline: false,
+            constraints: Vec::new(),
+            overrides: Vec::new(),
+            dependency_groups: Default::default(),
         };
 
         self.environment_manager.0
@@ -11,7 +11,7 @@ pub mod layer;
 pub mod compression;
 pub mod error;
 
-pub use platform::{PlatformInfo, PlatformRequirements, GpuRequirements};
+pub use platform::{Libc, PlatformInfo, PlatformRequirements, GpuRequirements};
 pub use hooks::{EnvironmentHooks, PathModifications};
 pub use validation::{
     ImageValidator, ValidationResult, ValidationError, ValidationWarning,
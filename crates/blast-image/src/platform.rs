@@ -3,6 +3,9 @@
 //! This module provides types and functionality for managing platform-specific
 //! requirements and capabilities for Python environments.
 
+use std::process::Command;
+use std::sync::OnceLock;
+
 use serde::{Deserialize, Serialize};
 
 /// Platform-specific requirements
@@ -37,13 +40,71 @@ pub struct GpuRequirements {
     pub required_features: Vec<String>,
 }
 
+/// C library of a Linux platform, which decides the manylinux and
+/// musllinux wheels it can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Libc {
+    /// GNU C library (major, minor)
+    Glibc(u32, u32),
+    /// musl (major, minor)
+    Musl(u32, u32),
+}
+
+impl Libc {
+    /// Detect the C library of the host
+    ///
+    /// The result is cached, as it takes running `ldd --version`. `None`
+    /// off Linux or when the library cannot be identified.
+    pub fn detect() -> Option<Self> {
+        static DETECTED: OnceLock<Option<Libc>> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            if std::env::consts::OS != "linux" {
+                return None;
+            }
+            // musl's ldd prints its version to stderr and exits with an error
+            let output = Command::new("ldd").arg("--version").output().ok()?;
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Self::parse_ldd(&text)
+        })
+    }
+
+    /// Identify the C library from the output of `ldd --version`
+    pub fn parse_ldd(output: &str) -> Option<Self> {
+        let version = |text: &str| {
+            let (major, minor) = text.trim().split_once('.')?;
+            let minor: String = minor.chars().take_while(char::is_ascii_digit).collect();
+            Some((major.parse().ok()?, minor.parse().ok()?))
+        };
+
+        let first_line = output.lines().next()?;
+        if first_line.starts_with("musl") {
+            let (major, minor) = output.lines()
+                .find_map(|line| line.strip_prefix("Version"))
+                .and_then(version)?;
+            return Some(Self::Musl(major, minor));
+        }
+        // e.g. "ldd (GNU libc) 2.36" or "ldd (Ubuntu GLIBC 2.35-0ubuntu3) 2.35"
+        let lower = first_line.to_lowercase();
+        if lower.contains("glibc") || lower.contains("gnu libc") {
+            let (major, minor) = version(first_line.rsplit(' ').next()?)?;
+            return Some(Self::Glibc(major, minor));
+        }
+        None
+    }
+}
+
 /// Platform-specific information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlatformInfo {
     /// Operating system
     pub os: String,
     /// Architecture
     pub arch: String,
+    /// C library, on Linux
+    #[serde(default)]
+    pub libc: Option<Libc>,
     /// Required system dependencies
     pub system_deps: Vec<String>,
     /// Minimum required disk space in bytes
@@ -72,6 +133,7 @@ impl PlatformInfo {
         Self {
             os: std::env::consts::OS.to_string(),
             arch: std::env::consts::ARCH.to_string(),
+            libc: Libc::detect(),
             system_deps: Vec::new(),
             min_disk_space: 1024 * 1024 * 1024, // 1GB
             min_memory: 512 * 1024 * 1024,      // 512MB
@@ -0,0 +1,434 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Cursor;
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::process::Command;
+use tracing::{debug, info};
+
+use blast_cache::{ArtifactCache, CachedArtifact};
+use blast_core::error::{BlastError, BlastResult};
+
+use crate::core_metadata::CoreMetadata;
+use crate::filename::DistFilename;
+use crate::tags::WheelTags;
+use crate::target::PythonTarget;
+
+/// Backend used for projects without a `[build-system]` table (PEP 517)
+const LEGACY_BACKEND: &str = "setuptools.build_meta:__legacy__";
+
+/// Build requirements of projects without a `[build-system]` table (PEP 518)
+const LEGACY_REQUIRES: &[&str] = &["setuptools>=40.8.0", "wheel"];
+
+/// Script calling a backend hook inside the build environment
+///
+/// Invoked as `runner.py <hook> <output> <args>` from the source tree, it
+/// writes `{"return": ...}` to the output file, or `{"missing": true}` if
+/// the backend does not implement an optional hook.
+const HOOK_RUNNER: &str = r#"import importlib, json, os, sys
+
+hook, output, args = sys.argv[1], sys.argv[2], json.loads(sys.argv[3])
+sys.path[:0] = [os.path.abspath(path) for path in json.loads(os.environ["BLAST_BACKEND_PATH"])]
+module, _, attrs = os.environ["BLAST_BUILD_BACKEND"].partition(":")
+backend = importlib.import_module(module)
+for attr in filter(None, attrs.split(".")):
+    backend = getattr(backend, attr)
+
+function = getattr(backend, hook, None)
+result = {"missing": True} if function is None else {"return": function(*args)}
+with open(output, "w") as f:
+    json.dump(result, f)
+"#;
+
+/// `[build-system]` table of a `pyproject.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BuildSystem {
+    /// Requirements installed into the build environment
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Import path of the backend, `module` or `module:object`
+    #[serde(rename = "build-backend", default = "legacy_backend")]
+    pub build_backend: String,
+    /// Directories of the source tree holding an in-tree backend
+    #[serde(rename = "backend-path", default)]
+    pub backend_path: Vec<String>,
+}
+
+impl Default for BuildSystem {
+    fn default() -> Self {
+        Self {
+            requires: LEGACY_REQUIRES.iter().map(|requirement| requirement.to_string()).collect(),
+            build_backend: legacy_backend(),
+            backend_path: Vec::new(),
+        }
+    }
+}
+
+impl BuildSystem {
+    /// Read the build system of a source tree, falling back to setuptools
+    /// when `pyproject.toml` does not declare one
+    pub fn from_source_tree(source_tree: &Path) -> BlastResult<Self> {
+        #[derive(Deserialize)]
+        struct PyProject {
+            #[serde(rename = "build-system")]
+            build_system: Option<BuildSystem>,
+        }
+
+        let path = source_tree.join("pyproject.toml");
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let pyproject: PyProject = toml::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| BlastError::package(format!("Invalid {}: {}", path.display(), e)))?;
+        let system = pyproject.build_system.unwrap_or_default();
+
+        // An in-tree backend must stay inside the tree
+        if let Some(path) = system.backend_path.iter().find(|path| {
+            Path::new(path).components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        }) {
+            return Err(BlastError::package(format!("backend-path entry {} leaves the source tree", path)));
+        }
+        Ok(system)
+    }
+}
+
+fn legacy_backend() -> String {
+    LEGACY_BACKEND.to_string()
+}
+
+/// PEP 517 build frontend
+///
+/// Source distributions and source trees are built by their declared
+/// backend in a fresh virtual environment holding only the build
+/// requirements. Metadata comes from `prepare_metadata_for_build_wheel`
+/// without a full build where the backend supports it, and built wheels are
+/// kept in the artifact cache next to the sdist they came from.
+#[derive(Debug, Clone)]
+pub struct BuildFrontend {
+    /// Interpreter build environments are created from
+    python: PathBuf,
+    /// Cache built wheels are stored in
+    cache: ArtifactCache,
+    /// Index build requirements are installed from
+    index_url: Option<String>,
+    /// Local directories of build requirement distributions
+    find_links: Vec<PathBuf>,
+    /// Install build requirements from `find_links` only
+    offline: bool,
+    /// Tags of the interpreters built wheels are installed on
+    tags: WheelTags,
+}
+
+/// A source tree with its build environment
+struct BuildEnv {
+    /// Holds the unpacked sdist and the environment
+    dir: TempDir,
+    /// Directory holding `pyproject.toml`
+    source_tree: PathBuf,
+    /// Interpreter of the build environment
+    python: PathBuf,
+    /// Declared build system
+    system: BuildSystem,
+}
+
+impl BuildFrontend {
+    /// Create a frontend building with the given interpreter
+    pub fn new(python: impl Into<PathBuf>, cache: ArtifactCache) -> Self {
+        Self {
+            python: python.into(),
+            cache,
+            index_url: None,
+            find_links: Vec::new(),
+            offline: false,
+            tags: WheelTags::new(&PythonTarget::default()),
+        }
+    }
+
+    /// Install build requirements from this index instead of pip's default
+    pub fn with_index_url(mut self, index_url: impl Into<String>) -> Self {
+        self.index_url = Some(index_url.into());
+        self
+    }
+
+    /// Also look for build requirements in local directories
+    pub fn with_find_links(mut self, find_links: Vec<PathBuf>) -> Self {
+        self.find_links = find_links;
+        self
+    }
+
+    /// Never reach an index for build requirements
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Only reuse cached wheels the interpreters of a target can install
+    pub fn with_python_target(mut self, python: &PythonTarget) -> Self {
+        self.tags = WheelTags::new(python);
+        self
+    }
+
+    /// Get the metadata a source distribution or source tree would build
+    /// a wheel with
+    ///
+    /// Backends without `prepare_metadata_for_build_wheel` get a full build,
+    /// whose wheel is cached for the install.
+    pub async fn metadata(&self, source: &Path) -> BlastResult<CoreMetadata> {
+        let env = self.prepare(source, "get_requires_for_build_wheel").await?;
+        let metadata_dir = env.dir.path().join("metadata");
+        std::fs::create_dir_all(&metadata_dir)?;
+
+        let result = env.call_hook("prepare_metadata_for_build_wheel", json!([path_arg(&metadata_dir)?])).await?;
+        if let Some(dist_info) = result {
+            let dist_info = dist_info.as_str()
+                .ok_or_else(|| BlastError::python("prepare_metadata_for_build_wheel did not return a directory name"))?;
+            let content = std::fs::read_to_string(metadata_dir.join(dist_info).join("METADATA"))?;
+            return CoreMetadata::parse(&content);
+        }
+
+        debug!("{} does not prepare metadata, building a wheel", env.system.build_backend);
+        let wheel = self.build(&env).await?;
+        CoreMetadata::from_wheel(&std::fs::read(&wheel.path)?)
+    }
+
+    /// Get the metadata of a downloaded sdist, from its `PKG-INFO` when that
+    /// is static and from the backend otherwise
+    pub async fn sdist_metadata(&self, filename: &str, data: &[u8]) -> BlastResult<CoreMetadata> {
+        match CoreMetadata::from_sdist(filename, data) {
+            Ok(metadata) => Ok(metadata),
+            Err(e) => {
+                debug!("Building metadata for {}: {}", filename, e);
+                let dir = tempfile::Builder::new().prefix("blast-sdist-").tempdir()?;
+                let name = Path::new(filename).file_name()
+                    .ok_or_else(|| BlastError::package(format!("Invalid sdist filename: {}", filename)))?;
+                let path = dir.path().join(name);
+                std::fs::write(&path, data)?;
+                self.metadata(&path).await
+            }
+        }
+    }
+
+    /// Build a wheel from a source distribution or source tree into the
+    /// artifact cache
+    ///
+    /// A cached wheel of the sdist's release is reused if the target can
+    /// install it, the best ranked one if there are several.
+    pub async fn build_wheel(&self, source: &Path) -> BlastResult<CachedArtifact> {
+        if let Some(sdist) = sdist_filename(source) {
+            let cached = self.cache.get_all(&sdist.name, &sdist.version.to_string()).await?
+                .into_iter()
+                .filter_map(|artifact| {
+                    let rank = DistFilename::parse(&artifact.filename)
+                        .ok()
+                        .filter(DistFilename::is_wheel)
+                        .and_then(|wheel| self.tags.rank(&wheel))?;
+                    Some((rank, artifact))
+                })
+                .min_by_key(|(rank, _)| *rank);
+            if let Some((_, cached)) = cached {
+                debug!("Using cached wheel {} for {}", cached.filename, source.display());
+                return Ok(cached);
+            }
+        }
+        let env = self.prepare(source, "get_requires_for_build_wheel").await?;
+        self.build(&env).await
+    }
+
+    /// Build an editable wheel of a source tree into `wheel_dir` (PEP 660),
+    /// returning its path
+    ///
+    /// Editable wheels point back at the tree, so they are not cached.
+    pub async fn build_editable(&self, source_tree: &Path, wheel_dir: &Path) -> BlastResult<PathBuf> {
+        let env = self.prepare(source_tree, "get_requires_for_build_editable").await?;
+        std::fs::create_dir_all(wheel_dir)?;
+
+        info!("Building editable wheel for {} with {}", source_tree.display(), env.system.build_backend);
+        let filename = env.call_hook("build_editable", json!([path_arg(wheel_dir)?]))
+            .await?
+            .and_then(|filename| filename.as_str().map(str::to_string))
+            .ok_or_else(|| BlastError::python(format!(
+                "{} does not support editable installs",
+                env.system.build_backend
+            )))?;
+        Ok(wheel_dir.join(filename))
+    }
+
+    /// Get the cache built wheels are stored in
+    pub fn cache(&self) -> &ArtifactCache {
+        &self.cache
+    }
+
+    /// Run `build_wheel` and store the wheel in the cache
+    async fn build(&self, env: &BuildEnv) -> BlastResult<CachedArtifact> {
+        let wheel_dir = env.dir.path().join("wheel");
+        std::fs::create_dir_all(&wheel_dir)?;
+
+        info!("Building wheel for {} with {}", env.source_tree.display(), env.system.build_backend);
+        let filename = env.call_hook("build_wheel", json!([path_arg(&wheel_dir)?]))
+            .await?
+            .and_then(|filename| filename.as_str().map(str::to_string))
+            .ok_or_else(|| BlastError::python(format!("{} does not build wheels", env.system.build_backend)))?;
+
+        let wheel = DistFilename::parse(&filename)
+            .ok()
+            .filter(DistFilename::is_wheel)
+            .ok_or_else(|| BlastError::python(format!("Build backend returned an invalid wheel name: {}", filename)))?;
+        let data = std::fs::read(wheel_dir.join(&filename))?;
+        self.cache.store(&wheel.name, &wheel.version.to_string(), &filename, &data).await
+    }
+
+    /// Unpack a source and set up its isolated build environment, with what
+    /// the `requires_hook` of the backend asks for on top of the declared
+    /// requirements
+    async fn prepare(&self, source: &Path, requires_hook: &str) -> BlastResult<BuildEnv> {
+        let dir = tempfile::Builder::new().prefix("blast-build-").tempdir()?;
+        let source_tree = if source.is_dir() {
+            source.to_path_buf()
+        } else {
+            unpack_sdist(source, &dir.path().join("src"))?
+        };
+        let system = BuildSystem::from_source_tree(&source_tree)?;
+
+        // Only environments that install requirements need pip
+        let env_dir = dir.path().join("env");
+        let mut venv = Command::new(&self.python);
+        venv.args(["-m", "venv"]);
+        if system.requires.is_empty() {
+            venv.arg("--without-pip");
+        }
+        run(venv.arg(&env_dir), "create the build environment").await?;
+
+        let env = BuildEnv {
+            python: venv_python(&env_dir),
+            dir,
+            source_tree,
+            system,
+        };
+        self.install(&env, &env.system.requires).await?;
+
+        // The backend may need more than it declares statically
+        let extra: Vec<String> = match env.call_hook(requires_hook, json!([])).await? {
+            Some(requires) => serde_json::from_value(requires)
+                .map_err(|e| BlastError::python(format!("Invalid {} result: {}", requires_hook, e)))?,
+            None => Vec::new(),
+        };
+        let declared: BTreeSet<&String> = env.system.requires.iter().collect();
+        let extra: Vec<String> = extra.into_iter().filter(|requirement| !declared.contains(requirement)).collect();
+        self.install(&env, &extra).await?;
+        Ok(env)
+    }
+
+    /// Install requirements into a build environment
+    async fn install(&self, env: &BuildEnv, requirements: &[String]) -> BlastResult<()> {
+        if requirements.is_empty() {
+            return Ok(());
+        }
+        debug!("Installing build requirements: {}", requirements.join(", "));
+
+        let mut pip = Command::new(&env.python);
+        pip.args(["-m", "pip", "install", "--disable-pip-version-check", "--no-input", "--quiet"])
+            .env_remove("PYTHONPATH")
+            .env("PYTHONNOUSERSITE", "1");
+        if self.offline {
+            pip.arg("--no-index");
+        } else if let Some(index_url) = &self.index_url {
+            pip.args(["--index-url", index_url]);
+        }
+        for dir in &self.find_links {
+            pip.arg("--find-links").arg(dir);
+        }
+        run(pip.args(requirements), "install build requirements").await
+    }
+}
+
+impl BuildEnv {
+    /// Call a backend hook, returning `None` if the backend lacks it
+    async fn call_hook(&self, hook: &str, args: Value) -> BlastResult<Option<Value>> {
+        let runner = self.dir.path().join("hook_runner.py");
+        if !runner.exists() {
+            std::fs::write(&runner, HOOK_RUNNER)?;
+        }
+        let output = self.dir.path().join(format!("{}.json", hook));
+
+        let mut command = Command::new(&self.python);
+        command.arg(&runner)
+            .arg(hook)
+            .arg(&output)
+            .arg(args.to_string())
+            .current_dir(&self.source_tree)
+            .env_remove("PYTHONPATH")
+            .env("PYTHONNOUSERSITE", "1")
+            .env("BLAST_BUILD_BACKEND", &self.system.build_backend)
+            .env("BLAST_BACKEND_PATH", json!(self.system.backend_path).to_string());
+        run(&mut command, &format!("run {} of {}", hook, self.system.build_backend)).await?;
+
+        let result: Value = serde_json::from_str(&std::fs::read_to_string(&output)?)
+            .map_err(|e| BlastError::python(format!("Invalid {} result: {}", hook, e)))?;
+        if result.get("missing").is_some() {
+            return Ok(None);
+        }
+        Ok(result.get("return").cloned())
+    }
+}
+
+/// Run a command, failing with its stderr if it does not succeed
+async fn run(command: &mut Command, action: &str) -> BlastResult<()> {
+    let output = command.output().await
+        .map_err(|e| BlastError::python(format!("Failed to {}: {}", action, e)))?;
+    if !output.status.success() {
+        return Err(BlastError::python(format!(
+            "Failed to {}: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Unpack a `.tar.gz` or `.zip` sdist, returning its source tree
+fn unpack_sdist(sdist: &Path, dest: &Path) -> BlastResult<PathBuf> {
+    let name = sdist.display().to_string();
+    if name.ends_with(".zip") {
+        let data = std::fs::read(sdist)?;
+        zip::ZipArchive::new(Cursor::new(data))
+            .and_then(|mut archive| archive.extract(dest))
+            .map_err(|e| BlastError::package(format!("Failed to unpack {}: {}", name, e)))?;
+    } else {
+        tar::Archive::new(GzDecoder::new(File::open(sdist)?))
+            .unpack(dest)
+            .map_err(|e| BlastError::package(format!("Failed to unpack {}: {}", name, e)))?;
+    }
+
+    // Sdists hold a single `{name}-{version}` directory
+    let entries = std::fs::read_dir(dest)?.collect::<Result<Vec<_>, _>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().is_dir() => Ok(entry.path()),
+        _ => Ok(dest.to_path_buf()),
+    }
+}
+
+/// Parse the filename of a path if it names an sdist
+fn sdist_filename(path: &Path) -> Option<DistFilename> {
+    let filename = DistFilename::parse(path.file_name()?.to_str()?).ok()?;
+    (!filename.is_wheel()).then_some(filename)
+}
+
+/// Get the interpreter of a virtual environment
+fn venv_python(env_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        env_dir.join("Scripts").join("python.exe")
+    } else {
+        env_dir.join("bin").join("python")
+    }
+}
+
+/// Pass a path to a hook as a string
+fn path_arg(path: &Path) -> BlastResult<String> {
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| BlastError::python(format!("Path is not valid UTF-8: {}", path.display())))
+}
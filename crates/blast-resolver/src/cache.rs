@@ -59,11 +59,20 @@ impl Cache {
         }
     }
 
+    /// Iterate over all cached packages
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages.values().map(|entry| &entry.package)
+    }
+
     /// Save the cache to disk
     async fn save(&self) -> BlastResult<()> {
         let cache_file = self.cache_dir.join(CACHE_FILE_NAME);
-        let json = serde_json::to_string(&self.packages)
+        // JSON objects need string keys, so entries are stored as a list
+        let entries: Vec<&CacheEntry> = self.packages.values().collect();
+        let json = serde_json::to_string(&entries)
             .map_err(|e| BlastError::cache(format!("Failed to serialize cache: {}", e)))?;
+        fs::create_dir_all(&self.cache_dir).await
+            .map_err(|e| BlastError::cache(format!("Failed to create cache directory: {}", e)))?;
         fs::write(cache_file, json).await
             .map_err(|e| BlastError::cache(format!("Failed to write cache file: {}", e)))
     }
@@ -77,8 +86,11 @@ impl Cache {
 
         let json = fs::read_to_string(cache_file).await
             .map_err(|e| BlastError::cache(format!("Failed to read cache file: {}", e)))?;
-        self.packages = serde_json::from_str(&json)
+        let entries: Vec<CacheEntry> = serde_json::from_str(&json)
             .map_err(|e| BlastError::cache(format!("Failed to deserialize cache: {}", e)))?;
+        self.packages = entries.into_iter()
+            .map(|entry| (entry.package.id().clone(), entry))
+            .collect();
         Ok(())
     }
 
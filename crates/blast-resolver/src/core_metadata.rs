@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::metadata::PackageMetadata;
+use blast_core::package::Package;
+use blast_core::version::{Version, VersionConstraint};
+use flate2::read::GzDecoder;
+
+use crate::filename::normalize_name;
+use crate::marker::{normalize_extra, MarkerEnvironment};
+use crate::pypi::Dependency;
+
+/// Dependencies of a package, split into base and per-extra requirements
+pub type SplitRequirements = (
+    HashMap<String, VersionConstraint>,
+    HashMap<String, HashMap<String, VersionConstraint>>,
+);
+
+/// Core metadata of a distribution (`METADATA` / `PKG-INFO`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreMetadata {
+    /// Metadata format version
+    pub metadata_version: String,
+    /// Project name
+    pub name: String,
+    /// Distribution version
+    pub version: Version,
+    /// One line summary
+    pub summary: Option<String>,
+    /// Author name
+    pub author: Option<String>,
+    /// Project home page
+    pub home_page: Option<String>,
+    /// License
+    pub license: Option<String>,
+    /// Supported Python versions
+    pub requires_python: Option<String>,
+    /// PEP 508 requirements
+    pub requires_dist: Vec<String>,
+    /// Declared extras
+    pub provides_extra: Vec<String>,
+    /// Fields left to be computed at build time (metadata 2.2+)
+    pub dynamic: Vec<String>,
+}
+
+impl CoreMetadata {
+    /// Parse the RFC 822 style headers of a metadata file
+    pub fn parse(content: &str) -> BlastResult<Self> {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in content.lines() {
+            if line.is_empty() {
+                // The message body holds the long description
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push('\n');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        let first = |key: &str| {
+            headers.iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .filter(|v| !v.is_empty() && v != "UNKNOWN")
+        };
+        let all = |key: &str| {
+            headers.iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let name = first("name")
+            .ok_or_else(|| BlastError::package("Package metadata is missing the Name field"))?;
+        let version = first("version")
+            .ok_or_else(|| BlastError::package(format!("Package metadata for {} is missing the Version field", name)))?;
+
+        Ok(Self {
+            metadata_version: first("metadata-version").unwrap_or_else(|| "1.0".to_string()),
+            version: Version::parse(&version)?,
+            name,
+            summary: first("summary"),
+            author: first("author"),
+            home_page: first("home-page"),
+            license: first("license"),
+            requires_python: first("requires-python"),
+            requires_dist: all("requires-dist"),
+            provides_extra: all("provides-extra"),
+            dynamic: all("dynamic").iter().map(|field| field.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    /// Read the `.dist-info/METADATA` file of a wheel
+    pub fn from_wheel(wheel: &[u8]) -> BlastResult<Self> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(wheel))
+            .map_err(|e| BlastError::package(format!("Invalid wheel archive: {}", e)))?;
+
+        let metadata_path = archive.file_names()
+            .find(|path| is_wheel_metadata_path(path))
+            .map(str::to_string)
+            .ok_or_else(|| BlastError::package("Wheel does not contain a .dist-info/METADATA file"))?;
+
+        let mut content = String::new();
+        archive.by_name(&metadata_path)
+            .map_err(|e| BlastError::package(format!("Failed to open {}: {}", metadata_path, e)))?
+            .read_to_string(&mut content)?;
+        Self::parse(&content)
+    }
+
+    /// Read the `PKG-INFO` file of a source distribution
+    ///
+    /// Only metadata 2.2+ guarantees that `PKG-INFO` matches what a build
+    /// would produce, so older or dynamic dependency metadata is rejected.
+    pub fn from_sdist(filename: &str, sdist: &[u8]) -> BlastResult<Self> {
+        let content = if filename.ends_with(".zip") {
+            read_zip_pkg_info(sdist)?
+        } else {
+            read_tar_pkg_info(sdist)?
+        }
+        .ok_or_else(|| BlastError::package(format!("{} does not contain a PKG-INFO file", filename)))?;
+
+        let metadata = Self::parse(&content)?;
+        if !metadata.is_static() {
+            return Err(BlastError::package(format!(
+                "{} does not declare static dependency metadata (Metadata-Version {})",
+                filename, metadata.metadata_version
+            )));
+        }
+        Ok(metadata)
+    }
+
+    /// Check if the dependency metadata can be trusted without a build
+    pub fn is_static(&self) -> bool {
+        let version = Version::parse(&self.metadata_version).unwrap_or_else(|_| Version::new([1, 0]));
+        version >= Version::new([2, 2])
+            && !self.dynamic.iter().any(|field| field == "requires-dist" || field == "requires-python")
+    }
+
+    /// Get the supported Python versions
+    pub fn python_constraint(&self) -> BlastResult<VersionConstraint> {
+        self.requires_python.as_deref()
+            .map(VersionConstraint::parse)
+            .transpose()
+            .map(|constraint| constraint.unwrap_or_else(VersionConstraint::any))
+    }
+
+    /// Convert into a package whose dependencies apply to the given environments
+    pub fn into_package(self, environments: &[MarkerEnvironment]) -> BlastResult<Package> {
+        let python_constraint = self.python_constraint()?;
+        let (dependencies, extras) = split_requirements(&self.requires_dist, environments);
+
+        let mut metadata = PackageMetadata::new(
+            self.name.clone(),
+            self.version.to_string(),
+            dependencies,
+            python_constraint.clone(),
+        );
+        metadata.description = self.summary;
+        metadata.author = self.author;
+        metadata.homepage = self.home_page;
+        metadata.license = self.license;
+        metadata.extras = extras;
+
+        Package::new(self.name, self.version.to_string(), metadata, python_constraint)
+    }
+}
+
+/// Check if a path inside a wheel is its top level `.dist-info/METADATA`
+pub fn is_wheel_metadata_path(path: &str) -> bool {
+    path.strip_suffix("/METADATA")
+        .is_some_and(|dir| dir.ends_with(".dist-info") && !dir.contains('/'))
+}
+
+/// Split PEP 508 requirements into base and per-extra dependencies,
+/// keeping only those whose markers hold for a targeted environment
+///
+/// Dependencies are keyed by normalized name, and every requirement on a
+/// package must hold. A requirement with extras, such as
+/// `requests[socks]>=2`, also adds `requests[socks]` (see
+/// [`extra_requirement`]), so the extra's own dependencies get resolved.
+pub fn split_requirements(requires_dist: &[String], environments: &[MarkerEnvironment]) -> SplitRequirements {
+    let applies_to_any = |dep: &Dependency, extra: Option<&str>| {
+        environments.iter().any(|env| dep.applies_to(env, extra))
+    };
+
+    let mut base = HashMap::new();
+    let mut extras: HashMap<String, HashMap<String, VersionConstraint>> = HashMap::new();
+    for requirement in requires_dist {
+        let dep = match Dependency::parse(requirement) {
+            Ok(dep) => dep,
+            Err(_) => continue,
+        };
+
+        if applies_to_any(&dep, None) {
+            add_requirement(&mut base, &dep);
+            continue;
+        }
+
+        let marker_extras = dep.marker.as_ref().map(|marker| marker.extras()).unwrap_or_default();
+        for extra in marker_extras {
+            if applies_to_any(&dep, Some(&extra)) {
+                add_requirement(extras.entry(extra).or_default(), &dep);
+            }
+        }
+    }
+    (base, extras)
+}
+
+/// Add a requirement to a package's dependencies, intersected with those
+/// already there on the same package
+fn add_requirement(dependencies: &mut HashMap<String, VersionConstraint>, dep: &Dependency) {
+    let names = std::iter::once(normalize_name(&dep.package))
+        .chain(dep.extras.iter().map(|extra| extra_requirement(&dep.package, extra)));
+    for name in names {
+        dependencies.entry(name)
+            .and_modify(|existing| *existing = existing.intersection(&dep.version_constraint))
+            .or_insert_with(|| dep.version_constraint.clone());
+    }
+}
+
+/// Get the name a requirement on a package with an extra goes by, such as
+/// `requests[socks]`
+///
+/// The solver treats it as a package of its own, depending on the package
+/// at the same version and on the extra's dependencies.
+pub fn extra_requirement(name: &str, extra: &str) -> String {
+    format!("{}[{}]", normalize_name(name), normalize_extra(extra))
+}
+
+/// Split a dependency name into the package and the extra it asks for,
+/// if any
+pub fn split_extra(name: &str) -> (&str, Option<&str>) {
+    match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+        Some((package, extra)) => (package, Some(extra)),
+        None => (name, None),
+    }
+}
+
+/// Top level `PKG-INFO` of a gzipped tarball (`name-version/PKG-INFO`)
+fn read_tar_pkg_info(sdist: &[u8]) -> BlastResult<Option<String>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(sdist));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let is_pkg_info = entry.path()?.components().count() == 2
+            && entry.path()?.file_name().is_some_and(|name| name == "PKG-INFO");
+        if is_pkg_info {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            return Ok(Some(content));
+        }
+    }
+    Ok(None)
+}
+
+/// Top level `PKG-INFO` of a zip source distribution
+fn read_zip_pkg_info(sdist: &[u8]) -> BlastResult<Option<String>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(sdist))
+        .map_err(|e| BlastError::package(format!("Invalid sdist archive: {}", e)))?;
+    let path = archive.file_names()
+        .find(|path| path.split('/').count() == 2 && path.ends_with("/PKG-INFO"))
+        .map(str::to_string);
+
+    match path {
+        Some(path) => {
+            let mut content = String::new();
+            archive.by_name(&path)
+                .map_err(|e| BlastError::package(format!("Failed to open {}: {}", path, e)))?
+                .read_to_string(&mut content)?;
+            Ok(Some(content))
+        }
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use tracing::debug;
+
+use blast_core::config::DependencySource;
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{Package, PackageId};
+use blast_core::version::Version;
+
+use crate::build::BuildFrontend;
+use crate::core_metadata::CoreMetadata;
+use crate::filename::{normalize_name, DistFilename};
+use crate::git::GitCache;
+use crate::lockfile::download;
+use crate::marker::MarkerEnvironment;
+use crate::source::{Artifact, ArtifactInfo, DirectReference, PackageSource};
+use crate::target::PythonTarget;
+
+/// Directory of git clones below the cache directory
+const GIT_DIR: &str = "git";
+
+/// Directory editable wheels are built in below the cache directory
+const EDITABLE_DIR: &str = "editable";
+
+/// Package source serving a single project from a local path, an archive
+/// URL or a git repository
+///
+/// The project offers exactly the version its metadata declares. Wheels
+/// are read directly, everything else goes through the project's PEP 517
+/// backend. A git reference is resolved to a commit once; the lockfile
+/// records it and locked installs check that commit out again.
+pub struct DirectSource {
+    /// Normalized project name
+    project: String,
+    source: DependencySource,
+    /// Source as shown in the lockfile
+    display: String,
+    /// Directory relative paths are resolved against
+    root: PathBuf,
+    frontend: Arc<BuildFrontend>,
+    cache_dir: PathBuf,
+    offline: bool,
+    environments: Vec<MarkerEnvironment>,
+    /// Commit to check out instead of resolving the git reference
+    commit: Option<String>,
+    /// What the source points at, looked up once
+    resolved: OnceCell<Resolved>,
+}
+
+/// Location and metadata of a direct source
+struct Resolved {
+    /// Project directory or distribution file
+    location: PathBuf,
+    /// Commit a git source resolved to
+    commit: Option<String>,
+    /// Hex encoded SHA-256 digest of a downloaded archive
+    sha256: Option<String>,
+    metadata: CoreMetadata,
+}
+
+impl DirectSource {
+    /// Create a source for a project, building with `frontend` and keeping
+    /// git clones in `cache_dir`
+    pub fn new(name: &str, source: DependencySource, frontend: Arc<BuildFrontend>, cache_dir: impl Into<PathBuf>) -> Self {
+        let target = PythonTarget::default();
+        Self {
+            project: normalize_name(name),
+            display: source.to_string(),
+            source,
+            root: PathBuf::from("."),
+            frontend,
+            cache_dir: cache_dir.into(),
+            offline: false,
+            environments: target.marker_environments(target.platform()),
+            commit: None,
+            resolved: OnceCell::new(),
+        }
+    }
+
+    /// Resolve relative paths against a project root
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Set the Python interpreter(s) that markers are checked against
+    pub fn with_python_target(mut self, target: PythonTarget) -> Self {
+        self.environments = target.marker_environments(target.platform());
+        self
+    }
+
+    /// Only use git clones and archives that are already cached
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Check out a locked commit instead of resolving the git reference
+    pub fn with_commit(mut self, commit: Option<String>) -> Self {
+        self.commit = commit;
+        self
+    }
+
+    /// Get a wheel to install the project from, building it if needed
+    ///
+    /// Editable path dependencies get a PEP 660 wheel pointing back at the
+    /// project directory.
+    pub async fn wheel(&self) -> BlastResult<PathBuf> {
+        let resolved = self.resolve().await?;
+        if let DependencySource::Path { editable: true, .. } = &self.source {
+            if !resolved.location.is_dir() {
+                return Err(BlastError::package(format!(
+                    "Only project directories can be installed as editable, not {}",
+                    resolved.location.display()
+                )));
+            }
+            let wheel_dir = self.cache_dir.join(EDITABLE_DIR).join(&self.project);
+            if wheel_dir.exists() {
+                std::fs::remove_dir_all(&wheel_dir)?;
+            }
+            return self.frontend.build_editable(&resolved.location, &wheel_dir).await;
+        }
+
+        if is_wheel(&resolved.location) {
+            return Ok(resolved.location.clone());
+        }
+        Ok(self.frontend.build_wheel(&resolved.location).await?.path)
+    }
+
+    /// Find what the source points at and read its metadata
+    async fn resolve(&self) -> BlastResult<&Resolved> {
+        self.resolved.get_or_try_init(|| async {
+            let (location, commit, sha256) = match &self.source {
+                DependencySource::Path { path, .. } => {
+                    let path = self.root.join(path);
+                    if !path.exists() {
+                        return Err(BlastError::package(format!("{} does not exist", path.display())));
+                    }
+                    (path, None, None)
+                }
+                DependencySource::Url { url } => {
+                    let (path, sha256) = self.fetch_archive(url).await?;
+                    (path, None, Some(sha256))
+                }
+                DependencySource::Git { git: url, .. } => {
+                    let url = self.repository_url(url);
+                    let git = GitCache::new(self.cache_dir.join(GIT_DIR), self.offline);
+                    let commit = match &self.commit {
+                        Some(commit) => commit.clone(),
+                        None => {
+                            let reference = self.source.git_reference().unwrap_or_else(|| "HEAD".to_string());
+                            git.resolve(&url, &reference).await?
+                        }
+                    };
+                    debug!("{} is at commit {}", self.display, commit);
+                    (git.checkout(&url, &commit).await?, Some(commit), None)
+                }
+            };
+
+            let metadata = if location.is_dir() {
+                self.frontend.metadata(&location).await?
+            } else if is_wheel(&location) {
+                CoreMetadata::from_wheel(&std::fs::read(&location)?)?
+            } else {
+                let filename = location.display().to_string();
+                self.frontend.sdist_metadata(&filename, &std::fs::read(&location)?).await?
+            };
+            if normalize_name(&metadata.name) != self.project {
+                return Err(BlastError::package(format!(
+                    "{} provides {}, not {}",
+                    self.display, metadata.name, self.project
+                )));
+            }
+            Ok(Resolved { location, commit, sha256, metadata })
+        })
+        .await
+    }
+
+    /// Download an archive into the artifact cache, unless it is there
+    async fn fetch_archive(&self, url: &str) -> BlastResult<(PathBuf, String)> {
+        let filename = url.split(['#', '?']).next().unwrap_or(url).rsplit('/').next().unwrap_or_default();
+        let dist = DistFilename::parse(filename)?;
+        let cache = self.frontend.cache();
+        let version = dist.version.to_string();
+        if let Some(cached) = cache.get(&dist.name, &version).await?.filter(|cached| cached.filename == filename) {
+            return Ok((cached.path, cached.sha256));
+        }
+        if self.offline && !url.starts_with("file:") {
+            return Err(BlastError::package(format!("Cannot download {} offline: not in the artifact cache", url)));
+        }
+
+        let data = download(url).await?;
+        let cached = cache.store(&dist.name, &version, filename, &data).await?;
+        Ok((cached.path, hex::encode(Sha256::digest(&data))))
+    }
+
+    /// Make a local repository path absolute, leaving URLs alone
+    fn repository_url(&self, url: &str) -> String {
+        if url.contains("://") || url.contains('@') {
+            return url.to_string();
+        }
+        self.root.join(url).display().to_string()
+    }
+
+    /// Get the resolution if `id` is the release this source offers
+    async fn release(&self, id: &PackageId) -> BlastResult<&Resolved> {
+        let resolved = self.resolve().await?;
+        if normalize_name(id.name()) != self.project || id.version() != &resolved.metadata.version {
+            return Err(BlastError::package(format!("{} does not provide {}", self.display, id)));
+        }
+        Ok(resolved)
+    }
+}
+
+#[async_trait]
+impl PackageSource for DirectSource {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        let resolved = self.release(id).await?;
+        resolved.metadata.clone().into_package(&self.environments)
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        if normalize_name(package_name) != self.project {
+            return Err(BlastError::package(format!("{} does not provide {}", self.display, package_name)));
+        }
+        Ok(vec![self.resolve().await?.metadata.version.clone()])
+    }
+
+    async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
+        Ok(self.download_artifact(id).await?.data)
+    }
+
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        let resolved = self.release(id).await?;
+        let path = if resolved.location.is_file() {
+            resolved.location.clone()
+        } else {
+            self.frontend.build_wheel(&resolved.location).await?.path
+        };
+        Ok(Artifact {
+            filename: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            data: std::fs::read(&path)?,
+        })
+    }
+
+    async fn list_artifacts(&self, id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        // Only downloaded archives are pinned by hash; paths and checkouts
+        // are built at install time
+        let resolved = self.release(id).await?;
+        match (&self.source, &resolved.sha256) {
+            (DependencySource::Url { url }, Some(sha256)) => Ok(vec![ArtifactInfo {
+                filename: resolved.location.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+                url: url.clone(),
+                sha256: Some(sha256.clone()),
+            }]),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        Ok(self.release(id).await.is_ok())
+    }
+
+    async fn direct_reference(&self, id: &PackageId) -> BlastResult<Option<DirectReference>> {
+        let resolved = self.release(id).await?;
+        Ok(Some(DirectReference {
+            source: self.source.clone(),
+            commit: resolved.commit.clone(),
+        }))
+    }
+
+    fn is_direct(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        &self.display
+    }
+
+    fn priority(&self) -> u32 {
+        0
+    }
+}
+
+fn is_wheel(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "whl")
+}
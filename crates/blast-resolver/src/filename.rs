@@ -0,0 +1,113 @@
+use std::fmt;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::version::Version;
+
+pub use blast_core::package::normalize_name;
+
+/// Kind of a distribution file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DistKind {
+    /// Built distribution (`.whl`)
+    Wheel,
+    /// Source distribution (`.tar.gz` or `.zip`)
+    SourceDist,
+}
+
+/// Name, version and tags encoded in a distribution filename
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistFilename {
+    /// Project name as written in the filename
+    pub name: String,
+    /// Distribution version
+    pub version: Version,
+    /// Kind of distribution
+    pub kind: DistKind,
+    /// Optional wheel build tag
+    pub build_tag: Option<String>,
+    /// Wheel Python tags (e.g. `py3`, `cp311`)
+    pub python_tags: Vec<String>,
+    /// Wheel ABI tags (e.g. `none`, `abi3`)
+    pub abi_tags: Vec<String>,
+    /// Wheel platform tags (e.g. `any`, `manylinux_2_17_x86_64`)
+    pub platform_tags: Vec<String>,
+}
+
+/// Source distribution archive extensions
+const SDIST_EXTENSIONS: [&str; 2] = [".tar.gz", ".zip"];
+
+impl DistFilename {
+    /// Parse a wheel (PEP 427) or source distribution filename
+    pub fn parse(filename: &str) -> BlastResult<Self> {
+        let invalid = |reason: &str| {
+            BlastError::package(format!("Invalid distribution filename '{}': {}", filename, reason))
+        };
+
+        if let Some(stem) = filename.strip_suffix(".whl") {
+            let parts: Vec<&str> = stem.split('-').collect();
+            let (name, version, build_tag, tags) = match parts.as_slice() {
+                [name, version, python, abi, platform] => (*name, *version, None, [*python, *abi, *platform]),
+                [name, version, build, python, abi, platform] => {
+                    if !build.starts_with(|c: char| c.is_ascii_digit()) {
+                        return Err(invalid("build tag must start with a digit"));
+                    }
+                    (*name, *version, Some(build.to_string()), [*python, *abi, *platform])
+                }
+                _ => return Err(invalid("expected name-version[-build]-python-abi-platform")),
+            };
+            if name.is_empty() {
+                return Err(invalid("missing project name"));
+            }
+            let split_tags = |tag: &str| tag.split('.').map(str::to_string).collect::<Vec<_>>();
+
+            return Ok(Self {
+                name: name.to_string(),
+                version: Version::parse(version).map_err(|_| invalid("invalid version"))?,
+                kind: DistKind::Wheel,
+                build_tag,
+                python_tags: split_tags(tags[0]),
+                abi_tags: split_tags(tags[1]),
+                platform_tags: split_tags(tags[2]),
+            });
+        }
+
+        let stem = SDIST_EXTENSIONS.iter()
+            .find_map(|extension| filename.strip_suffix(extension))
+            .ok_or_else(|| invalid("unsupported archive type"))?;
+
+        // Project names may contain dashes, versions may not
+        let (name, version) = stem.rsplit_once('-').ok_or_else(|| invalid("missing version"))?;
+        if name.is_empty() {
+            return Err(invalid("missing project name"));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            version: Version::parse(version).map_err(|_| invalid("invalid version"))?,
+            kind: DistKind::SourceDist,
+            build_tag: None,
+            python_tags: Vec::new(),
+            abi_tags: Vec::new(),
+            platform_tags: Vec::new(),
+        })
+    }
+
+    /// Get the normalized project name
+    pub fn normalized_name(&self) -> String {
+        normalize_name(&self.name)
+    }
+
+    /// Check if this is a wheel
+    pub fn is_wheel(&self) -> bool {
+        self.kind == DistKind::Wheel
+    }
+}
+
+impl fmt::Display for DistKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistKind::Wheel => write!(f, "wheel"),
+            DistKind::SourceDist => write!(f, "sdist"),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use url::Url;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{Package, PackageId};
+use blast_core::version::Version;
+
+use crate::build::BuildFrontend;
+use crate::core_metadata::CoreMetadata;
+use crate::filename::{normalize_name, DistFilename};
+use crate::marker::MarkerEnvironment;
+use crate::source::{Artifact, ArtifactInfo, PackageSource};
+use crate::tags::WheelTags;
+use crate::target::PythonTarget;
+
+/// Package source serving distributions from a local directory
+///
+/// This is the equivalent of pip's `--find-links` pointing at a wheelhouse:
+/// names and versions come from the filenames, metadata from the wheels'
+/// `METADATA` (or an sdist's static `PKG-INFO`), and downloads are plain
+/// file reads.
+pub struct FindLinksSource {
+    dir: PathBuf,
+    name: String,
+    priority: u32,
+    environments: Vec<MarkerEnvironment>,
+    /// Wheel tags the target supports
+    tags: WheelTags,
+    /// Whether source distributions are left out
+    no_build: bool,
+    /// Builds metadata of sdists without static `PKG-INFO`
+    build_frontend: Option<Arc<BuildFrontend>>,
+    /// Distributions found in the directory, keyed by normalized name
+    distributions: HashMap<String, Vec<LocalDistribution>>,
+}
+
+/// A distribution file found in a find-links directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalDistribution {
+    /// Parsed filename
+    pub filename: DistFilename,
+    /// Path of the file
+    pub path: PathBuf,
+}
+
+impl FindLinksSource {
+    /// Scan a directory of wheels and source distributions
+    pub fn new(dir: impl Into<PathBuf>, priority: u32) -> BlastResult<Self> {
+        let dir = dir.into();
+        let distributions = scan_directory(&dir)?;
+        let target = PythonTarget::default();
+
+        Ok(Self {
+            name: dir.display().to_string(),
+            dir,
+            priority,
+            environments: target.marker_environments(target.platform()),
+            tags: WheelTags::new(&target),
+            no_build: false,
+            build_frontend: None,
+            distributions,
+        })
+    }
+
+    /// Set the Python interpreter(s) that wheels and markers are checked against
+    pub fn with_python_target(mut self, target: PythonTarget) -> Self {
+        self.environments = target.marker_environments(target.platform());
+        self.tags = WheelTags::new(&target);
+        self
+    }
+
+    /// Only offer releases that have a wheel for the target
+    pub fn with_no_build(mut self, no_build: bool) -> Self {
+        self.no_build = no_build;
+        self
+    }
+
+    /// Build sdists whose `PKG-INFO` is not static to get their metadata
+    pub fn with_build_frontend(mut self, frontend: Option<Arc<BuildFrontend>>) -> Self {
+        self.build_frontend = frontend;
+        self
+    }
+
+    /// Get the scanned directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Rescan the directory for added or removed files
+    pub fn refresh(&mut self) -> BlastResult<()> {
+        self.distributions = scan_directory(&self.dir)?;
+        Ok(())
+    }
+
+    /// Get the distributions available for a package
+    pub fn distributions(&self, package_name: &str) -> &[LocalDistribution] {
+        self.distributions
+            .get(&normalize_name(package_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the installable files of a specific release, best wheel first
+    fn release_files(&self, id: &PackageId) -> BlastResult<Vec<&LocalDistribution>> {
+        let mut files: Vec<_> = self.distributions(id.name())
+            .iter()
+            .filter(|dist| dist.filename.version == *id.version())
+            .filter_map(|dist| Some((self.tags.preference(&dist.filename, self.no_build)?, dist)))
+            .collect();
+
+        if files.is_empty() {
+            return Err(BlastError::package(format!(
+                "No installable files for {} in {}",
+                id,
+                self.dir.display()
+            )));
+        }
+        files.sort_by_key(|(preference, _)| *preference);
+        Ok(files.into_iter().map(|(_, dist)| dist).collect())
+    }
+}
+
+#[async_trait]
+impl PackageSource for FindLinksSource {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        let mut last_error = None;
+
+        // Fall back to the next file if one has unusable metadata
+        for dist in self.release_files(id)? {
+            let data = tokio::fs::read(&dist.path).await?;
+            let filename = dist.path.display().to_string();
+            let metadata = if dist.filename.is_wheel() {
+                CoreMetadata::from_wheel(&data)
+            } else if let Some(frontend) = &self.build_frontend {
+                frontend.sdist_metadata(&filename, &data).await
+            } else {
+                CoreMetadata::from_sdist(&filename, &data)
+            };
+
+            match metadata {
+                Ok(metadata) => return metadata.into_package(&self.environments),
+                Err(e) => {
+                    debug!("Unable to read metadata from {}: {}", dist.path.display(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| BlastError::package(format!("No metadata found for {}", id))))
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        let distributions = self.distributions(package_name);
+        if distributions.is_empty() {
+            return Err(BlastError::package(format!(
+                "Package not found: {} in {}",
+                package_name,
+                self.dir.display()
+            )));
+        }
+
+        // Files are sorted by version, so each release's files are adjacent
+        let versions = distributions
+            .chunk_by(|a, b| a.filename.version == b.filename.version)
+            .filter(|files| self.tags.installable(files.iter().map(|dist| &dist.filename), self.no_build))
+            .map(|files| files[0].filename.version.clone())
+            .collect();
+        Ok(versions)
+    }
+
+    async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
+        let dist = self.release_files(id)?[0];
+        debug!("Reading {}", dist.path.display());
+        Ok(tokio::fs::read(&dist.path).await?)
+    }
+
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        let dist = self.release_files(id)?[0];
+        let filename = dist.path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Artifact {
+            filename,
+            data: tokio::fs::read(&dist.path).await?,
+        })
+    }
+
+    async fn list_artifacts(&self, id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        let mut artifacts = Vec::new();
+        for dist in self.release_files(id)? {
+            let data = tokio::fs::read(&dist.path).await?;
+            let path = std::path::absolute(&dist.path)?;
+            artifacts.push(ArtifactInfo {
+                filename: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+                url: Url::from_file_path(&path)
+                    .map_err(|_| BlastError::package(format!("Invalid path: {}", path.display())))?
+                    .to_string(),
+                sha256: Some(hex::encode(Sha256::digest(&data))),
+            });
+        }
+        Ok(artifacts)
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        Ok(self.release_files(id).is_ok())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+}
+
+/// Index the distribution files directly inside a directory
+fn scan_directory(dir: &Path) -> BlastResult<HashMap<String, Vec<LocalDistribution>>> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        BlastError::package(format!("Failed to read find-links directory {}: {}", dir.display(), e))
+    })?;
+
+    let mut distributions: HashMap<String, Vec<LocalDistribution>> = HashMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let filename = match path.file_name().and_then(|name| name.to_str()).map(DistFilename::parse) {
+            Some(Ok(filename)) => filename,
+            _ => {
+                debug!("Skipping {}: not a distribution file", path.display());
+                continue;
+            }
+        };
+
+        distributions
+            .entry(filename.normalized_name())
+            .or_default()
+            .push(LocalDistribution { filename, path });
+    }
+
+    // Directory order is arbitrary; keep listings stable
+    for files in distributions.values_mut() {
+        files.sort_by(|a, b| a.filename.version.cmp(&b.filename.version).then_with(|| a.path.cmp(&b.path)));
+    }
+    Ok(distributions)
+}
@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use tracing::debug;
+
+use blast_core::error::{BlastError, BlastResult};
+
+/// Local clones of git dependencies
+///
+/// Every repository is mirrored once as a bare clone under `db/`, and each
+/// commit that is built gets its own checkout under `checkouts/`, which
+/// never changes once made.
+#[derive(Debug, Clone)]
+pub(crate) struct GitCache {
+    root: PathBuf,
+    offline: bool,
+}
+
+impl GitCache {
+    /// Create a cache of clones rooted at `root`
+    pub(crate) fn new(root: impl Into<PathBuf>, offline: bool) -> Self {
+        Self {
+            root: root.into(),
+            offline,
+        }
+    }
+
+    /// Get the commit a revision, tag, branch or `HEAD` of a repository
+    /// points at, fetching the repository first
+    pub(crate) async fn resolve(&self, url: &str, reference: &str) -> BlastResult<String> {
+        let db = self.fetch(url).await?;
+        let output = git(&db, &["rev-parse", "--verify", "--quiet", "--end-of-options", &format!("{}^{{commit}}", reference)]).await
+            .map_err(|_| BlastError::package(format!("{} has no revision {}", url, reference)))?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Check out a commit of a repository, returning the working tree
+    ///
+    /// Commits may come from a lockfile, so anything but a full commit id is
+    /// rejected before it reaches a path or the git command line.
+    pub(crate) async fn checkout(&self, url: &str, commit: &str) -> BlastResult<PathBuf> {
+        if !is_commit_id(commit) {
+            return Err(BlastError::package(format!(
+                "Invalid commit {:?} for {}: expected a full SHA-1 or SHA-256 commit id",
+                commit, url
+            )));
+        }
+        let dir = self.root.join("checkouts").join(repository_key(url)).join(commit);
+        if dir.is_dir() {
+            return Ok(dir);
+        }
+
+        let db = self.db_path(url);
+        let spec = format!("{}^{{commit}}", commit);
+        if !db.is_dir() || git(&db, &["cat-file", "-e", &spec]).await.is_err() {
+            self.fetch(url).await?;
+        }
+
+        // Check out next to the final directory so a failure leaves nothing behind
+        let partial = dir.with_extension("partial");
+        if partial.exists() {
+            std::fs::remove_dir_all(&partial)?;
+        }
+        std::fs::create_dir_all(&partial)?;
+        git(&partial, &["clone", "--quiet", "--no-checkout", "--", &path_arg(&db)?, "."]).await?;
+        git(&partial, &["checkout", "--quiet", "--detach", commit]).await?;
+        std::fs::rename(&partial, &dir)?;
+        Ok(dir)
+    }
+
+    /// Bring the bare clone of a repository up to date
+    async fn fetch(&self, url: &str) -> BlastResult<PathBuf> {
+        let db = self.db_path(url);
+        if self.offline {
+            if db.is_dir() {
+                return Ok(db);
+            }
+            return Err(BlastError::package(format!("Cannot clone {} offline: not in the git cache", url)));
+        }
+
+        if db.is_dir() {
+            debug!("Fetching {}", url);
+            git(&db, &["fetch", "--quiet", "--force", "--prune", "origin", "+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"]).await?;
+        } else {
+            debug!("Cloning {}", url);
+            std::fs::create_dir_all(&db)?;
+            git(&db, &["clone", "--quiet", "--bare", "--", url, "."]).await?;
+        }
+        Ok(db)
+    }
+
+    /// Get the bare clone of a repository
+    fn db_path(&self, url: &str) -> PathBuf {
+        self.root.join("db").join(repository_key(url))
+    }
+}
+
+/// Run git in a directory, returning its standard output
+async fn git(dir: &Path, args: &[&str]) -> BlastResult<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .await
+        .map_err(|e| BlastError::package(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(BlastError::package(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Check if a string is a full commit id, 40 hex digits for SHA-1
+/// repositories or 64 for SHA-256 ones
+fn is_commit_id(commit: &str) -> bool {
+    matches!(commit.len(), 40 | 64) && commit.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Name the clones of a repository after a digest of its URL
+fn repository_key(url: &str) -> String {
+    hex::encode(Sha256::digest(url.trim_end_matches('/').as_bytes()))[..16].to_string()
+}
+
+fn path_arg(path: &Path) -> BlastResult<String> {
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| BlastError::package(format!("Path is not valid UTF-8: {}", path.display())))
+}
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use blast_core::error::{BlastError, BlastResult};
+
+use crate::filename::normalize_name;
+
+/// Directory of the import map inside the cache directory
+const IMPORT_MAP_DIR: &str = "blast-resolver";
+
+/// File the learned mappings are kept in
+const IMPORT_MAP_FILE: &str = "import-map.json";
+
+/// Curated mappings of popular distributions whose top-level module is
+/// named differently, sorted by module
+const BUNDLED: &[(&str, &str)] = &[
+    ("Bio", "biopython"),
+    ("Crypto", "pycryptodome"),
+    ("MySQLdb", "mysqlclient"),
+    ("OpenGL", "PyOpenGL"),
+    ("OpenSSL", "pyOpenSSL"),
+    ("PIL", "Pillow"),
+    ("attr", "attrs"),
+    ("bs4", "beautifulsoup4"),
+    ("cairo", "pycairo"),
+    ("cv2", "opencv-python"),
+    ("dateutil", "python-dateutil"),
+    ("discord", "discord.py"),
+    ("dns", "dnspython"),
+    ("docx", "python-docx"),
+    ("dotenv", "python-dotenv"),
+    ("engineio", "python-engineio"),
+    ("faiss", "faiss-cpu"),
+    ("fitz", "PyMuPDF"),
+    ("gi", "PyGObject"),
+    ("git", "GitPython"),
+    ("grpc", "grpcio"),
+    ("jose", "python-jose"),
+    ("jwt", "PyJWT"),
+    ("kafka", "kafka-python"),
+    ("ldap", "python-ldap"),
+    ("magic", "python-magic"),
+    ("mpl_toolkits", "matplotlib"),
+    ("multipart", "python-multipart"),
+    ("nacl", "PyNaCl"),
+    ("pkg_resources", "setuptools"),
+    ("pptx", "python-pptx"),
+    ("pythoncom", "pywin32"),
+    ("serial", "pyserial"),
+    ("skimage", "scikit-image"),
+    ("sklearn", "scikit-learn"),
+    ("slugify", "python-slugify"),
+    ("socketio", "python-socketio"),
+    ("socks", "PySocks"),
+    ("telegram", "python-telegram-bot"),
+    ("umap", "umap-learn"),
+    ("usb", "pyusb"),
+    ("win32api", "pywin32"),
+    ("win32con", "pywin32"),
+    ("wx", "wxPython"),
+    ("yaml", "PyYAML"),
+    ("zmq", "pyzmq"),
+];
+
+/// Where an import-name mapping comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingSource {
+    /// An installed distribution's `top_level.txt` or `RECORD`
+    Installed,
+    /// A distribution that was installed for the import before
+    Learned,
+    /// The bundled table of popular packages
+    Bundled,
+}
+
+/// Distribution providing an imported module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportResolution {
+    /// A single distribution provides the module
+    Found {
+        /// Distribution name
+        distribution: String,
+        /// Where the mapping comes from
+        source: MappingSource,
+    },
+    /// Several installed distributions provide the module
+    Ambiguous(Vec<String>),
+    /// Nothing is known about the module
+    Unknown,
+}
+
+impl fmt::Display for ImportResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Found { distribution, .. } => write!(f, "{}", distribution),
+            Self::Ambiguous(distributions) => write!(f, "one of {}", distributions.join(", ")),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Mapping of import names to the distributions providing them
+///
+/// `import yaml` needs `PyYAML` and `import sklearn` needs `scikit-learn`,
+/// so the import name alone does not say what to install. Mappings are
+/// looked up in installed distributions first, then in what earlier
+/// installs taught, then in a bundled table of popular packages.
+#[derive(Debug, Clone, Default)]
+pub struct ImportMap {
+    /// Distributions providing each module, read from site-packages
+    installed: BTreeMap<String, BTreeSet<String>>,
+    /// Distributions installed for a module before
+    learned: BTreeMap<String, String>,
+    /// File the learned mappings are kept in
+    path: Option<PathBuf>,
+}
+
+impl ImportMap {
+    /// Create a map knowing only the bundled mappings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the mappings learned in a cache directory
+    pub fn load(cache_dir: &Path) -> BlastResult<Self> {
+        let path = cache_dir.join(IMPORT_MAP_DIR).join(IMPORT_MAP_FILE);
+        let learned = if path.is_file() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| BlastError::cache(format!("Invalid import map {}: {}", path.display(), e)))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            learned,
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+
+    /// Save the learned mappings to the file they were loaded from
+    pub fn save(&self) -> BlastResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.learned)
+            .map_err(|e| BlastError::cache(format!("Failed to serialize import map: {}", e)))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Read the modules of every distribution installed in a site-packages
+    /// directory
+    pub fn scan_site_packages(&mut self, site_packages: &Path) -> BlastResult<()> {
+        for entry in std::fs::read_dir(site_packages)? {
+            let dist_info = entry?.path();
+            let Some(distribution) = distribution_name(&dist_info) else {
+                continue;
+            };
+            for module in top_level_modules(&dist_info)? {
+                self.installed.entry(module).or_default().insert(distribution.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Remember that installing `distribution` provided `import_name`
+    pub fn learn(&mut self, import_name: &str, distribution: &str) {
+        let module = root_module(import_name).to_string();
+        debug!("Learned import mapping {} -> {}", module, distribution);
+        self.learned.insert(module, distribution.to_string());
+    }
+
+    /// Remember the modules an installed distribution provides, returning them
+    pub fn learn_installed(&mut self, dist_info: &Path) -> BlastResult<Vec<String>> {
+        let distribution = distribution_name(dist_info)
+            .ok_or_else(|| BlastError::package(format!("{} is not a .dist-info directory", dist_info.display())))?;
+        let modules = top_level_modules(dist_info)?;
+        for module in &modules {
+            self.learn(module, &distribution);
+        }
+        Ok(modules)
+    }
+
+    /// Find the distribution providing an imported module
+    ///
+    /// Only the top-level module counts, so `PIL.Image` maps like `PIL`.
+    pub fn resolve(&self, import_name: &str) -> ImportResolution {
+        let module = root_module(import_name);
+        if let Some(distributions) = self.installed.get(module) {
+            if distributions.len() > 1 {
+                return ImportResolution::Ambiguous(distributions.iter().cloned().collect());
+            }
+            if let Some(distribution) = distributions.first() {
+                return ImportResolution::Found {
+                    distribution: distribution.clone(),
+                    source: MappingSource::Installed,
+                };
+            }
+        }
+        if let Some(distribution) = self.learned.get(module) {
+            return ImportResolution::Found {
+                distribution: distribution.clone(),
+                source: MappingSource::Learned,
+            };
+        }
+        match bundled(module) {
+            Some(distribution) => ImportResolution::Found {
+                distribution: distribution.to_string(),
+                source: MappingSource::Bundled,
+            },
+            None => ImportResolution::Unknown,
+        }
+    }
+
+    /// Get the distribution to install for an import, assuming a module
+    /// nothing is known about is named after its distribution
+    pub fn distribution_for(&self, import_name: &str) -> BlastResult<String> {
+        match self.resolve(import_name) {
+            ImportResolution::Found { distribution, .. } => Ok(distribution),
+            ImportResolution::Ambiguous(distributions) => Err(BlastError::resolution(format!(
+                "Module '{}' is provided by several distributions: {}; add the one to use as a dependency",
+                root_module(import_name),
+                distributions.join(", ")
+            ))),
+            ImportResolution::Unknown => Ok(normalize_name(root_module(import_name))),
+        }
+    }
+}
+
+/// Look a module up in the bundled table
+pub fn bundled(module: &str) -> Option<&'static str> {
+    BUNDLED.binary_search_by_key(&module, |(module, _)| module)
+        .ok()
+        .map(|i| BUNDLED[i].1)
+}
+
+/// List the top-level modules of an installed distribution, from its
+/// `top_level.txt` or else the files in its `RECORD`
+pub fn top_level_modules(dist_info: &Path) -> BlastResult<Vec<String>> {
+    let top_level = dist_info.join("top_level.txt");
+    let modules: BTreeSet<String> = if top_level.is_file() {
+        std::fs::read_to_string(top_level)?
+            .lines()
+            .map(|line| line.trim().replace('/', "."))
+            .filter_map(|module| module.split('.').next().map(str::to_string))
+            .filter(|module| is_identifier(module))
+            .collect()
+    } else if dist_info.join("RECORD").is_file() {
+        std::fs::read_to_string(dist_info.join("RECORD"))?
+            .lines()
+            .filter_map(|line| line.split(',').next())
+            .filter_map(|path| record_module(path.trim_matches('"')))
+            .collect()
+    } else {
+        BTreeSet::new()
+    };
+    Ok(modules.into_iter().collect())
+}
+
+/// Get the top-level module a file listed in `RECORD` belongs to
+fn record_module(path: &str) -> Option<String> {
+    let (first, rest) = match path.split_once('/') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    let module = match rest {
+        // A package directory, unless it holds metadata, data or caches
+        Some(_) if first.ends_with(".dist-info") || first.ends_with(".data") || first == "__pycache__" => return None,
+        Some(_) => first,
+        // A single-file module or extension, e.g. `six.py` or
+        // `_cffi_backend.cpython-311-x86_64-linux-gnu.so`
+        None if [".py", ".so", ".pyd"].iter().any(|ext| first.ends_with(ext)) => first.split('.').next()?,
+        None => return None,
+    };
+    is_identifier(module).then(|| module.to_string())
+}
+
+/// Get the normalized distribution name of a `.dist-info` directory
+fn distribution_name(dist_info: &Path) -> Option<String> {
+    let name = dist_info.file_name()?.to_str()?.strip_suffix(".dist-info")?;
+    let (name, _version) = name.split_once('-')?;
+    Some(normalize_name(name))
+}
+
+/// Get the top-level module of a dotted import name
+fn root_module(import_name: &str) -> &str {
+    import_name.split('.').next().unwrap_or(import_name)
+}
+
+/// Check if a name can be imported
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -3,48 +3,322 @@
 //! This crate provides the dependency resolution functionality for Blast,
 //! implementing the PubGrub algorithm for Python packages.
 
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
-use blast_core::error::BlastResult;
+use blast_cache::{ArtifactCache, ARTIFACTS_DIR};
+use chrono::{DateTime, Utc};
+use blast_core::config::{BlastConfig, DependencySource, DependencySpec};
+use blast_core::environment::package::InstallScheme;
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::metadata::PackageMetadata;
 use blast_core::package::Package;
+use blast_core::version::VersionConstraint;
+use blast_core::types::CacheSettings;
+use tracing::warn;
 
 mod cache;
-mod pypi;
+pub mod build;
+pub mod core_metadata;
+pub mod direct;
+pub mod filename;
+pub mod find_links;
+mod git;
+pub mod imports;
+pub mod lockfile;
+pub mod marker;
+pub mod offline;
+pub mod pypi;
+mod prefetch;
 mod pubgrub;
+mod remote_wheel;
+mod report;
 mod resolution;
 pub mod resolver;
+pub mod simple;
+pub mod source;
+pub mod tags;
+mod target;
 
+pub use build::{BuildFrontend, BuildSystem};
 pub use cache::Cache;
+pub use core_metadata::CoreMetadata;
+pub use direct::DirectSource;
+pub use filename::{normalize_name, DistFilename};
+pub use find_links::FindLinksSource;
+pub use imports::{ImportMap, ImportResolution};
+pub use lockfile::{FetchOptions, LockTarget, LockedArtifact, LockedPackage, Lockfile, LOCKFILE_NAME};
+pub use marker::{MarkerEnvironment, MarkerTree};
+pub use offline::CacheSource;
 pub use pypi::PyPIClient;
 pub use resolver::DependencyResolver;
-pub use resolution::{ResolutionStrategy, ResolutionResult, ResolutionGraph};
+pub use resolution::{
+    AppliedRequirement, RequirementKind, ResolutionMetrics, ResolutionMode, ResolutionStrategy, ResolutionResult,
+    ResolutionGraph,
+};
+pub use simple::SimpleIndexSource;
+pub use source::{DirectReference, PackageSource, PackageSourceChain, YankedRelease};
+pub use tags::{Tag, WheelTags};
+pub use target::PythonTarget;
+
+/// Priority of the first configured package index; later ones follow it
+const INDEX_PRIORITY: u32 = 10;
+
+/// Default priority of find-links directories, ahead of every index
+pub const FIND_LINKS_PRIORITY: u32 = 5;
+
+/// Interpreter used to build sdists unless configured otherwise
+const DEFAULT_BUILD_PYTHON: &str = if cfg!(windows) { "python" } else { "python3" };
 
 /// Configuration for the resolver
 #[derive(Debug, Clone)]
 pub struct Config {
     /// Maximum number of concurrent requests
     pub max_concurrent_requests: u64,
+    /// Connect timeout in seconds
+    pub connect_timeout: u64,
     /// Request timeout in seconds
     pub request_timeout: u64,
     /// Whether to verify SSL certificates
     pub verify_ssl: bool,
     /// Whether to allow pre-releases
     pub allow_prereleases: bool,
+    /// Only use wheels; releases without one for the target are skipped
+    pub no_build: bool,
+    /// Install locked files the lock records no hash for
+    pub allow_unhashed: bool,
     /// Additional package sources
     pub additional_sources: Vec<String>,
+    /// Index URL that individual packages are looked up in exclusively
+    pub package_indexes: HashMap<String, String>,
+    /// Local directories of wheels and sdists
+    pub find_links: Vec<PathBuf>,
+    /// Source priority of the find-links directories; indexes start at 10
+    /// and PyPI sits at 100, lower values are consulted first
+    pub find_links_priority: u32,
+    /// Python interpreter(s) to resolve for
+    pub python: PythonTarget,
+    /// Directory holding the metadata and artifact caches
+    pub cache_dir: PathBuf,
+    /// Resolve only from the metadata cache and local sources
+    pub offline: bool,
+    /// Prefer the highest or lowest compatible versions
+    pub resolution: ResolutionMode,
+    /// Ignore files uploaded to an index after this time
+    ///
+    /// Local find-links directories and the offline metadata cache carry no
+    /// upload times and are not filtered.
+    pub exclude_newer: Option<DateTime<Utc>>,
+    /// Version limits applied whenever a package is required, by name
+    pub constraints: HashMap<String, VersionConstraint>,
+    /// Requirements replacing what dependencies declare, by name
+    pub overrides: HashMap<String, VersionConstraint>,
+    /// Interpreter source distributions are built with
+    pub build_python: PathBuf,
+    /// Directory relative paths of direct references are resolved against
+    pub project_root: PathBuf,
+    /// Path, URL and git sources that packages are taken from instead of an
+    /// index, by name
+    pub direct_sources: HashMap<String, DependencySource>,
+    /// Commits git sources stay at instead of resolving their reference
+    /// again, by name
+    pub git_commits: HashMap<String, String>,
+    /// Site-packages of the project's environment, whose distributions say
+    /// which one provides an imported module
+    pub site_packages: Option<PathBuf>,
+}
+
+impl Config {
+    /// Create a configuration for a project's `blast.toml`
+    pub fn from_blast_config(config: &BlastConfig) -> BlastResult<Self> {
+        let dependencies = std::iter::once(&config.dependencies).chain(config.dev_dependencies.as_ref());
+
+        let mut resolver_config = Self {
+            python: PythonTarget::version(config.python_version.clone()),
+            cache_dir: config.cache_settings.cache_dir.clone(),
+            offline: config.offline,
+            constraints: requirement_map(&config.constraints, "constraint")?,
+            overrides: requirement_map(&config.overrides, "override")?,
+            project_root: config.project_root.clone(),
+            site_packages: Some(InstallScheme::for_env(&config.env_path(), &config.python_version.to_string()).purelib),
+            ..Self::default()
+        };
+        for spec in config.all_requirements()? {
+            if let Some(source) = spec.source {
+                resolver_config.direct_sources.insert(normalize_name(&spec.name), source);
+            }
+        }
+        for deps in dependencies {
+            resolver_config.allow_prereleases |= deps.allow_prereleases;
+            resolver_config.no_build |= deps.no_build;
+            resolver_config.allow_unhashed |= deps.allow_unhashed;
+            for index in deps.package_index.iter().flatten() {
+                if !resolver_config.additional_sources.contains(index) {
+                    resolver_config.additional_sources.push(index.clone());
+                }
+            }
+            for dir in deps.find_links.iter().flatten() {
+                let dir = config.project_root.join(dir);
+                if !resolver_config.find_links.contains(&dir) {
+                    resolver_config.find_links.push(dir);
+                }
+            }
+            for spec in &deps.packages {
+                if let Some(index) = &spec.index {
+                    resolver_config.package_indexes.insert(normalize_name(&spec.name), index.clone());
+                }
+            }
+        }
+        Ok(resolver_config)
+    }
+
+    /// Get the store of downloaded distribution files
+    pub fn artifact_cache(&self) -> ArtifactCache {
+        ArtifactCache::new(self.cache_dir.join(ARTIFACTS_DIR))
+    }
+
+    /// Get how the files of a lockfile are fetched for this configuration's
+    /// interpreter
+    pub fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            python: self.python.clone(),
+            no_build: self.no_build,
+            offline: self.offline,
+            allow_unhashed: self.allow_unhashed,
+        }
+    }
+
+    /// Get a frontend building sdists into the artifact cache, with build
+    /// requirements taken from the configured indexes and directories
+    pub fn build_frontend(&self) -> BuildFrontend {
+        let frontend = BuildFrontend::new(&self.build_python, self.artifact_cache())
+            .with_find_links(self.find_links.clone())
+            .with_offline(self.offline)
+            .with_python_target(&self.python);
+        match self.additional_sources.first() {
+            Some(index) => frontend.with_index_url(index.clone()),
+            None => frontend,
+        }
+    }
+
+    /// Get the source of a package taken from a path, URL or git repository
+    pub fn direct_source(&self, name: &str, source: DependencySource) -> DirectSource {
+        DirectSource::new(name, source, Arc::new(self.build_frontend()), &self.cache_dir)
+            .with_root(&self.project_root)
+            .with_python_target(self.python.clone())
+            .with_offline(self.offline)
+            .with_commit(self.git_commits.get(&normalize_name(name)).cloned())
+    }
+
+    /// Keep the git sources that a lockfile pinned at the commits it
+    /// records, as long as their reference is unchanged
+    pub fn with_locked_commits(mut self, lockfile: &Lockfile) -> Self {
+        for package in &lockfile.packages {
+            let name = normalize_name(&package.name);
+            if let (Some(commit), Some(direct)) = (&package.commit, &package.direct) {
+                if self.direct_sources.get(&name) == Some(direct) {
+                    self.git_commits.insert(name, commit.clone());
+                }
+            }
+        }
+        self
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             max_concurrent_requests: 10,
+            connect_timeout: 10,
             request_timeout: 30,
             verify_ssl: true,
             allow_prereleases: false,
+            no_build: false,
+            allow_unhashed: false,
             additional_sources: Vec::new(),
+            package_indexes: HashMap::new(),
+            find_links: Vec::new(),
+            find_links_priority: FIND_LINKS_PRIORITY,
+            python: PythonTarget::default(),
+            cache_dir: CacheSettings::default().cache_dir,
+            offline: false,
+            resolution: ResolutionMode::default(),
+            exclude_newer: None,
+            constraints: HashMap::new(),
+            overrides: HashMap::new(),
+            build_python: PathBuf::from(DEFAULT_BUILD_PYTHON),
+            project_root: PathBuf::from("."),
+            direct_sources: HashMap::new(),
+            git_commits: HashMap::new(),
+            site_packages: None,
         }
     }
 }
 
+/// Build the root package of a project from its `blast.toml` requirements,
+/// every dependency group included so they share one resolution, for [`DependencyResolver::resolve_project`] and [`DependencyResolver::lock`]
+///
+/// A requirement with extras also adds the package with each extra, see
+/// [`core_metadata::extra_requirement`].
+pub fn project_package(config: &BlastConfig) -> BlastResult<Package> {
+    let mut dependencies: HashMap<String, VersionConstraint> = HashMap::new();
+    for spec in applicable_requirements(config, config.all_requirements()?)? {
+        let constraint = VersionConstraint::parse(&spec.version).map_err(|e| {
+            BlastError::config(format!("Invalid version for dependency {}: {}", spec.name, e))
+        })?;
+        // A package required by several groups must satisfy all of them,
+        // however each spells its name
+        let names = std::iter::once(normalize_name(&spec.name))
+            .chain(spec.extras.iter().flatten().map(|extra| core_metadata::extra_requirement(&spec.name, extra)));
+        for name in names {
+            dependencies.entry(name)
+                .and_modify(|existing| *existing = existing.intersection(&constraint))
+                .or_insert_with(|| constraint.clone());
+        }
+    }
+
+    let metadata = PackageMetadata::new(
+        config.name.clone(),
+        config.version.clone(),
+        dependencies,
+        VersionConstraint::any(),
+    );
+    Package::new(config.name.clone(), config.version.clone(), metadata, VersionConstraint::any())
+}
+
+/// Keep the requirements whose environment marker holds for the project's
+/// Python on this platform, or that have none
+pub fn applicable_requirements(config: &BlastConfig, specs: Vec<DependencySpec>) -> BlastResult<Vec<DependencySpec>> {
+    let target = PythonTarget::version(config.python_version.clone());
+    let environments = target.marker_environments(target.platform());
+
+    let mut applicable = Vec::with_capacity(specs.len());
+    for spec in specs {
+        if let Some(marker) = &spec.marker {
+            let marker = MarkerTree::parse(marker).map_err(|e| {
+                BlastError::config(format!("Invalid marker for dependency {}: {}", spec.name, e))
+            })?;
+            if !environments.iter().any(|env| marker.evaluate(env, None)) {
+                continue;
+            }
+        }
+        applicable.push(spec);
+    }
+    Ok(applicable)
+}
+
+/// Parse the version constraints of configured requirements by package name
+fn requirement_map(specs: &[DependencySpec], kind: &str) -> BlastResult<HashMap<String, VersionConstraint>> {
+    specs.iter()
+        .map(|spec| {
+            let constraint = VersionConstraint::parse(&spec.version).map_err(|e| {
+                BlastError::config(format!("Invalid version for {} {}: {}", kind, spec.name, e))
+            })?;
+            Ok((spec.name.clone(), constraint))
+        })
+        .collect()
+}
+
 /// Create a new resolver with default configuration
 pub async fn create_resolver() -> BlastResult<Arc<DependencyResolver>> {
     create_resolver_with_config(Config::default()).await
@@ -52,18 +326,141 @@ pub async fn create_resolver() -> BlastResult<Arc<DependencyResolver>> {
 
 /// Create a new resolver with the given configuration
 pub async fn create_resolver_with_config(config: Config) -> BlastResult<Arc<DependencyResolver>> {
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| blast_core::error::BlastError::cache("Failed to get cache directory".to_string()))?
-        .join("blast");
+    let mut cache = Cache::new(config.cache_dir.clone());
+    if let Err(e) = cache.load().await {
+        warn!("Ignoring unreadable metadata cache: {}", e);
+    }
+
+    let mut imports = ImportMap::load(&config.cache_dir).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable import map: {}", e);
+        ImportMap::new()
+    });
+    if let Some(site_packages) = config.site_packages.as_ref().filter(|dir| dir.is_dir()) {
+        if let Err(e) = imports.scan_site_packages(site_packages) {
+            warn!("Ignoring unreadable site-packages {}: {}", site_packages.display(), e);
+        }
+    }
 
     let pypi_client = PyPIClient::new(
-        config.max_concurrent_requests,
+        config.connect_timeout,
         config.request_timeout,
         config.verify_ssl,
-    )?;
+    )?
+    .with_python_target(config.python.clone())
+    .with_no_build(config.no_build)
+    .with_exclude_newer(config.exclude_newer);
+
+    // Sdists without static metadata are built, unless builds are off
+    let build_frontend = (!config.no_build).then(|| Arc::new(config.build_frontend()));
+
+    if config.offline {
+        let sources = offline_sources(&config, &cache, build_frontend.as_ref())?;
+        let resolver = DependencyResolver::with_sources(pypi_client, sources, cache)
+            .with_offline(true)
+            .with_import_map(imports)
+            .with_mode(config.resolution)
+            .with_python_target(config.python.clone())
+            .with_prereleases(config.allow_prereleases)
+            .with_max_concurrent_requests(config.max_concurrent_requests as usize)
+            .with_exclude_newer(config.exclude_newer)
+            .with_constraints(config.constraints.clone())
+            .with_overrides(config.overrides.clone());
+        return Ok(Arc::new(with_direct_sources(resolver, &config)));
+    }
+
+    // Configured indexes are consulted before PyPI, in the order given
+    let mut sources = PackageSourceChain::new();
+    sources.add_source(Box::new(pypi_client.clone()));
+    let mut index_sources: HashMap<&str, Arc<dyn PackageSource>> = HashMap::new();
+    for (i, index) in config.additional_sources.iter().enumerate() {
+        let source: Arc<dyn PackageSource> = Arc::new(
+            SimpleIndexSource::new(index, INDEX_PRIORITY + i as u32)?
+                .with_python_target(config.python.clone())
+                .with_no_build(config.no_build)
+                .with_exclude_newer(config.exclude_newer)
+                .with_build_frontend(build_frontend.clone()),
+        );
+        sources.add_shared_source(source.clone());
+        index_sources.insert(index, source);
+    }
+    for dir in &config.find_links {
+        sources.add_source(Box::new(
+            FindLinksSource::new(dir, config.find_links_priority)?
+                .with_python_target(config.python.clone())
+                .with_no_build(config.no_build)
+                .with_build_frontend(build_frontend.clone()),
+        ));
+    }
+
+    let mut resolver = DependencyResolver::with_sources(pypi_client, sources, cache)
+        .with_import_map(imports)
+        .with_mode(config.resolution)
+        .with_python_target(config.python.clone())
+        .with_prereleases(config.allow_prereleases)
+        .with_max_concurrent_requests(config.max_concurrent_requests as usize)
+        .with_exclude_newer(config.exclude_newer)
+        .with_constraints(config.constraints.clone())
+        .with_overrides(config.overrides.clone());
+    for (package, index) in &config.package_indexes {
+        let source = match index_sources.get(index.as_str()) {
+            Some(source) => source.clone(),
+            None => Arc::new(
+                SimpleIndexSource::new(index, INDEX_PRIORITY)?
+                    .with_python_target(config.python.clone())
+                    .with_no_build(config.no_build)
+                    .with_exclude_newer(config.exclude_newer)
+                    .with_build_frontend(build_frontend.clone()),
+            ),
+        };
+        resolver = resolver.with_package_index(package, source);
+    }
+    Ok(Arc::new(with_direct_sources(resolver, &config)))
+}
+
+/// Take every package with a direct reference from that reference alone
+fn with_direct_sources(mut resolver: DependencyResolver, config: &Config) -> DependencyResolver {
+    for (package, source) in &config.direct_sources {
+        resolver = resolver.with_package_index(package, Arc::new(config.direct_source(package, source.clone())));
+    }
+    resolver
+}
+
+/// Build the sources usable without network access: the metadata cache,
+/// find-links directories and indexes on the local filesystem
+fn offline_sources(
+    config: &Config,
+    cache: &Cache,
+    build_frontend: Option<&Arc<BuildFrontend>>,
+) -> BlastResult<PackageSourceChain> {
+    let mut sources = PackageSourceChain::new();
+    sources.add_source(Box::new(CacheSource::new(cache.packages())));
+
+    let indexes = config.additional_sources.iter().chain(config.package_indexes.values());
+    let mut seen = HashSet::new();
+    let local_indexes: Vec<&String> = indexes.filter(|index| !is_remote(index) && seen.insert(*index)).collect();
+    for (i, index) in local_indexes.into_iter().enumerate() {
+        sources.add_source(Box::new(
+            SimpleIndexSource::new(index, INDEX_PRIORITY + i as u32)?
+                .with_python_target(config.python.clone())
+                .with_no_build(config.no_build)
+                .with_exclude_newer(config.exclude_newer)
+                .with_build_frontend(build_frontend.cloned()),
+        ));
+    }
+    for dir in &config.find_links {
+        sources.add_source(Box::new(
+            FindLinksSource::new(dir, config.find_links_priority)?
+                .with_python_target(config.python.clone())
+                .with_no_build(config.no_build)
+                .with_build_frontend(build_frontend.cloned()),
+        ));
+    }
+    Ok(sources)
+}
 
-    let cache = Cache::new(cache_dir);
-    Ok(Arc::new(DependencyResolver::new(pypi_client, cache)))
+/// Check if an index is reached over the network
+fn is_remote(index: &str) -> bool {
+    index.starts_with("http://") || index.starts_with("https://")
 }
 
 /// Resolve dependencies for a package
@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use url::Url;
+
+use blast_cache::{ArtifactCache, CachedArtifact};
+use blast_core::config::DependencySource;
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{normalize_name, Package};
+use blast_core::version::VersionConstraint;
+
+use crate::filename::DistFilename;
+use crate::resolution::{ResolutionMode, ResolutionResult};
+use crate::source::ArtifactInfo;
+use crate::tags::WheelTags;
+use crate::target::PythonTarget;
+use crate::Config;
+
+/// File name of the lockfile in a project root
+pub const LOCKFILE_NAME: &str = "blast.lock";
+
+/// Version of the lockfile format written by this crate
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Exact record of a resolution (`blast.lock`)
+///
+/// Packages are kept sorted by name, with sorted dependencies and files, so
+/// the same resolution always serializes to the same TOML.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Lockfile format version
+    pub version: u32,
+    /// Project the lock was generated for
+    pub project: String,
+    /// Version preference the lock was resolved with
+    #[serde(default)]
+    pub resolution: ResolutionMode,
+    /// Upload-time cutoff the lock was resolved with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_newer: Option<DateTime<Utc>>,
+    /// Interpreters and platform the lock was resolved for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<LockTarget>,
+    /// Requirements of the project, normalized name to version constraint
+    #[serde(default)]
+    pub requirements: BTreeMap<String, String>,
+    /// Constraints the lock was resolved with, by normalized name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub constraints: BTreeMap<String, String>,
+    /// Overrides the lock was resolved with, by normalized name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+    /// Locked packages
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// Interpreters and platform a lock was resolved for, which decide the
+/// files it records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockTarget {
+    /// Python version or range, such as `3.11` or `>=3.9`
+    pub python: String,
+    /// Operating system
+    pub os: String,
+    /// Architecture
+    pub arch: String,
+}
+
+impl LockTarget {
+    /// Describe the target of a resolution
+    pub fn new(target: &PythonTarget) -> Self {
+        Self {
+            python: target.to_string(),
+            os: target.platform().os.clone(),
+            arch: target.platform().arch.clone(),
+        }
+    }
+}
+
+/// How [`Lockfile::fetch_artifacts`] picks and checks locked files
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Interpreter and platform the files are installed for
+    pub python: PythonTarget,
+    /// Never build source distributions, so only wheels will do
+    pub no_build: bool,
+    /// Only read `file:` URLs, reporting anything else not yet cached
+    pub offline: bool,
+    /// Accept locked files the lock records no hash for
+    pub allow_unhashed: bool,
+}
+
+/// A resolved package pinned in the lockfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    /// Package name
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Source the package was found in (index URL, directory or `pypi`)
+    #[serde(default)]
+    pub source: String,
+    /// Names of the locked packages this one depends on
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Commit a git source was locked at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Path, URL or git repository the package is taken from instead of an
+    /// index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct: Option<DependencySource>,
+    /// Distribution files, preferred file first
+    #[serde(default, rename = "artifact")]
+    pub artifacts: Vec<LockedArtifact>,
+}
+
+/// A distribution file pinned in the lockfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedArtifact {
+    /// Distribution filename
+    pub filename: String,
+    /// Download URL
+    pub url: String,
+    /// Hex encoded SHA-256 digest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl From<ArtifactInfo> for LockedArtifact {
+    fn from(info: ArtifactInfo) -> Self {
+        Self {
+            filename: info.filename,
+            url: info.url,
+            sha256: info.sha256,
+        }
+    }
+}
+
+impl Lockfile {
+    /// Create a lockfile from a project's resolution
+    ///
+    /// Sources and files are left empty; they are filled in by
+    /// [`DependencyResolver::lock`](crate::DependencyResolver::lock).
+    pub fn from_resolution(project: &Package, result: &ResolutionResult) -> Self {
+        let mut edges: HashMap<String, BTreeSet<String>> = HashMap::new();
+        if let Some(graph) = &result.graph {
+            for relationship in &graph.relationships {
+                edges
+                    .entry(normalize_name(relationship.from.name()))
+                    .or_default()
+                    .insert(normalize_name(relationship.to.name()));
+            }
+        }
+
+        let mut packages: Vec<LockedPackage> = result.packages.iter()
+            .map(|package| {
+                let name = normalize_name(package.name());
+                LockedPackage {
+                    dependencies: edges.remove(&name).map(|deps| deps.into_iter().collect()).unwrap_or_default(),
+                    name,
+                    version: package.version().to_string(),
+                    source: String::new(),
+                    commit: None,
+                    direct: None,
+                    artifacts: Vec::new(),
+                }
+            })
+            .collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            version: LOCKFILE_VERSION,
+            project: project.name().to_string(),
+            resolution: ResolutionMode::default(),
+            exclude_newer: None,
+            target: None,
+            requirements: requirements(project),
+            constraints: BTreeMap::new(),
+            overrides: BTreeMap::new(),
+            packages,
+        }
+    }
+
+    /// Parse a lockfile from TOML
+    pub fn from_toml(content: &str) -> BlastResult<Self> {
+        let lockfile: Self = toml::from_str(content)
+            .map_err(|e| BlastError::serialization(format!("Invalid lockfile: {}", e)))?;
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(BlastError::serialization(format!(
+                "Unsupported lockfile version {} (expected {})",
+                lockfile.version, LOCKFILE_VERSION
+            )));
+        }
+        Ok(lockfile)
+    }
+
+    /// Serialize the lockfile to TOML
+    pub fn to_toml(&self) -> BlastResult<String> {
+        let mut lockfile = self.clone();
+        lockfile.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        for package in &mut lockfile.packages {
+            package.dependencies.sort();
+            package.dependencies.dedup();
+        }
+
+        let content = toml::to_string_pretty(&lockfile)
+            .map_err(|e| BlastError::serialization(format!("Failed to serialize lockfile: {}", e)))?;
+        Ok(format!("# This file is generated by blast. Do not edit it by hand.\n\n{}", content))
+    }
+
+    /// Read a lockfile from disk
+    pub fn read(path: impl AsRef<Path>) -> BlastResult<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the lockfile to disk
+    pub fn write(&self, path: impl AsRef<Path>) -> BlastResult<()> {
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Get a locked package by name
+    pub fn package(&self, name: &str) -> Option<&LockedPackage> {
+        let name = normalize_name(name);
+        self.packages.iter().find(|package| package.name == name)
+    }
+
+    /// Check if the lock was generated for the project's current
+    /// requirements, constraints, overrides and target
+    pub fn is_up_to_date(&self, project: &Package, config: &Config) -> bool {
+        self.requirements == requirements(project)
+            && self.constraints == constraint_map(&config.constraints)
+            && self.overrides == constraint_map(&config.overrides)
+            && self.target == Some(LockTarget::new(&config.python))
+    }
+
+    /// Narrow the lock to the given requirements and everything they depend
+    /// on, e.g. to install only some dependency groups
+    pub fn subset<'a>(&self, requirements: impl IntoIterator<Item = &'a str>) -> BlastResult<Self> {
+        let mut selected = BTreeSet::new();
+        let mut pending: Vec<String> = requirements.into_iter().map(normalize_name).collect();
+        while let Some(name) = pending.pop() {
+            if selected.contains(&name) {
+                continue;
+            }
+            let package = self.package(&name).ok_or_else(|| {
+                BlastError::package(format!("{} is not in the lockfile, run `blast lock` to update it", name))
+            })?;
+            pending.extend(package.dependencies.iter().cloned());
+            selected.insert(name);
+        }
+
+        let mut subset = self.clone();
+        subset.packages.retain(|package| selected.contains(&package.name));
+        Ok(subset)
+    }
+
+    /// Get the locked packages with every package after its dependencies
+    ///
+    /// Packages in a dependency cycle are ordered by name.
+    pub fn install_order(&self) -> Vec<&LockedPackage> {
+        let mut remaining: BTreeMap<&str, &LockedPackage> = self.packages.iter()
+            .map(|package| (package.name.as_str(), package))
+            .collect();
+        let mut order = Vec::with_capacity(remaining.len());
+
+        while !remaining.is_empty() {
+            let ready: Vec<&str> = remaining.values()
+                .filter(|package| package.dependencies.iter().all(|dep| !remaining.contains_key(dep.as_str())))
+                .map(|package| package.name.as_str())
+                .collect();
+            // Break cycles by taking the first remaining package
+            let ready = if ready.is_empty() {
+                vec![*remaining.keys().next().unwrap()]
+            } else {
+                ready
+            };
+            for name in ready {
+                order.extend(remaining.remove(name));
+            }
+        }
+        order
+    }
+
+    /// Put the preferred file of every locked package into the artifact
+    /// cache, verifying the recorded hashes, without resolving anything
+    ///
+    /// The file installed is the locked one the target interpreter and
+    /// platform prefer. Files without a recorded hash are refused unless
+    /// allowed. Offline, only `file:` URLs are read and every package that
+    /// is not already cached is reported at once.
+    pub async fn fetch_artifacts(&self, cache: &ArtifactCache, options: &FetchOptions) -> BlastResult<Vec<CachedArtifact>> {
+        let unlocked: Vec<String> = self.packages.iter()
+            .filter(|package| package.artifacts.is_empty() && !package.is_built_locally())
+            .map(|package| format!("{}=={}", package.name, package.version))
+            .collect();
+        if !unlocked.is_empty() {
+            return Err(BlastError::package(format!(
+                "Lockfile has no distribution files for: {}",
+                unlocked.join(", ")
+            )));
+        }
+
+        // Every file is chosen and checked before anything is downloaded
+        let tags = WheelTags::new(&options.python);
+        let mut chosen = Vec::new();
+        let mut incompatible = Vec::new();
+        let mut unhashed = Vec::new();
+        for package in self.install_order().into_iter().filter(|package| !package.is_built_locally()) {
+            match package.preferred_artifact(&tags, options.no_build) {
+                Some(artifact) if artifact.sha256.is_none() && !options.allow_unhashed => {
+                    unhashed.push(artifact.filename.clone());
+                }
+                Some(artifact) => chosen.push((package, artifact)),
+                None => incompatible.push(format!("{}=={}", package.name, package.version)),
+            }
+        }
+        if !incompatible.is_empty() {
+            let platform = options.python.platform();
+            return Err(BlastError::package(format!(
+                "No locked file can be installed on Python {} ({} {}) for: {}; run `blast lock` to update the lockfile",
+                options.python,
+                platform.os,
+                platform.arch,
+                incompatible.join(", ")
+            )));
+        }
+        if !unhashed.is_empty() {
+            return Err(BlastError::security(format!(
+                "Lockfile records no hash for: {}; set allow_unhashed to install them anyway",
+                unhashed.join(", ")
+            )));
+        }
+
+        let mut fetched = Vec::new();
+        let mut missing = Vec::new();
+        for (package, artifact) in chosen {
+            if let Some(cached) = cache.get_file(&package.name, &package.version, &artifact.filename).await? {
+                let matches = artifact.sha256.as_ref()
+                    .is_none_or(|sha256| cached.sha256.eq_ignore_ascii_case(sha256));
+                if matches {
+                    debug!("Using cached artifact {}", cached.filename);
+                    fetched.push(cached);
+                    continue;
+                }
+            }
+
+            if options.offline && !artifact.url.starts_with("file:") {
+                missing.push(format!("{}=={}", package.name, package.version));
+                continue;
+            }
+            let data = download(&artifact.url).await?;
+            if let Some(expected) = &artifact.sha256 {
+                let actual = hex::encode(Sha256::digest(&data));
+                if !actual.eq_ignore_ascii_case(expected) {
+                    return Err(BlastError::security(format!(
+                        "Hash mismatch for {}: expected {}, got {}",
+                        artifact.filename, expected, actual
+                    )));
+                }
+            }
+            fetched.push(cache.store(&package.name, &package.version, &artifact.filename, &data).await?);
+        }
+
+        if !missing.is_empty() {
+            return Err(BlastError::package(format!(
+                "Cannot install offline, missing from the artifact cache: {}",
+                missing.join(", ")
+            )));
+        }
+        Ok(fetched)
+    }
+}
+
+impl LockedPackage {
+    /// Check if the package is built from a local path or git checkout at
+    /// install time rather than from a locked file
+    pub fn is_built_locally(&self) -> bool {
+        matches!(self.direct, Some(DependencySource::Path { .. } | DependencySource::Git { .. }))
+    }
+
+    /// Get the locked file a target prefers, earlier files first among
+    /// equals, or `None` if it can install none of them
+    fn preferred_artifact(&self, tags: &WheelTags, no_build: bool) -> Option<&LockedArtifact> {
+        self.artifacts.iter()
+            .enumerate()
+            .filter_map(|(i, artifact)| {
+                let filename = DistFilename::parse(&artifact.filename).ok()?;
+                Some(((tags.preference(&filename, no_build)?, i), artifact))
+            })
+            .min_by_key(|(preference, _)| *preference)
+            .map(|(_, artifact)| artifact)
+    }
+}
+
+/// Get a project's requirements keyed by normalized name
+fn requirements(project: &Package) -> BTreeMap<String, String> {
+    constraint_map(&project.metadata().dependencies)
+}
+
+/// Get version constraints as strings keyed by normalized name
+pub(crate) fn constraint_map(constraints: &HashMap<String, VersionConstraint>) -> BTreeMap<String, String> {
+    constraints.iter()
+        .map(|(name, constraint)| (normalize_name(name), constraint.to_string()))
+        .collect()
+}
+
+/// Download a file from a `file:` or HTTP(S) URL
+pub(crate) async fn download(url: &str) -> BlastResult<Vec<u8>> {
+    let parsed = Url::parse(url).map_err(|e| BlastError::network(format!("Invalid URL {}: {}", url, e)))?;
+    debug!("Downloading {}", url);
+
+    if parsed.scheme() == "file" {
+        let path = parsed.to_file_path()
+            .map_err(|_| BlastError::network(format!("Invalid file URL: {}", url)))?;
+        return Ok(tokio::fs::read(path).await?);
+    }
+
+    let response = reqwest::get(parsed).await.map_err(|e| BlastError::network(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(BlastError::network(format!(
+            "Failed to download {} (status: {})",
+            url,
+            response.status()
+        )));
+    }
+    Ok(response.bytes().await.map_err(|e| BlastError::network(e.to_string()))?.to_vec())
+}
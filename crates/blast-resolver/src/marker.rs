@@ -0,0 +1,495 @@
+//! PEP 508 environment markers.
+//!
+//! Markers such as `sys_platform == "win32" and extra == "dev"` decide
+//! whether a requirement applies to a given interpreter. They are parsed into
+//! a [`MarkerTree`] and evaluated against a [`MarkerEnvironment`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::python::PythonVersion;
+use blast_core::version::{Version, VersionSpecifier};
+use blast_image::platform::PlatformInfo;
+
+use crate::filename::normalize_name;
+
+/// Values of the PEP 508 marker variables for a target interpreter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerEnvironment {
+    /// `implementation_name`, e.g. `cpython`
+    pub implementation_name: String,
+    /// `implementation_version`, e.g. `3.11.4`
+    pub implementation_version: String,
+    /// `os_name`, e.g. `posix`
+    pub os_name: String,
+    /// `platform_machine`, e.g. `x86_64`
+    pub platform_machine: String,
+    /// `platform_python_implementation`, e.g. `CPython`
+    pub platform_python_implementation: String,
+    /// `platform_release`, e.g. `6.1.0`
+    pub platform_release: String,
+    /// `platform_system`, e.g. `Linux`
+    pub platform_system: String,
+    /// `platform_version`
+    pub platform_version: String,
+    /// `python_full_version`, e.g. `3.11.4`
+    pub python_full_version: String,
+    /// `python_version`, e.g. `3.11`
+    pub python_version: String,
+    /// `sys_platform`, e.g. `linux`
+    pub sys_platform: String,
+}
+
+impl MarkerEnvironment {
+    /// Create a CPython marker environment for the given interpreter and platform
+    pub fn new(python: &PythonVersion, platform: &PlatformInfo) -> Self {
+        let full_version = format!(
+            "{}.{}.{}",
+            python.major(),
+            python.minor(),
+            python.patch().unwrap_or(0)
+        );
+
+        let (sys_platform, platform_system, os_name) = match platform.os.as_str() {
+            "linux" => ("linux", "Linux", "posix"),
+            "macos" | "darwin" => ("darwin", "Darwin", "posix"),
+            "windows" => ("win32", "Windows", "nt"),
+            "freebsd" => ("freebsd", "FreeBSD", "posix"),
+            other => (other, other, "posix"),
+        };
+
+        // Windows and macOS report their own machine names
+        let platform_machine = match (sys_platform, platform.arch.as_str()) {
+            ("win32", "x86_64") => "AMD64",
+            ("win32", "aarch64") => "ARM64",
+            ("darwin", "aarch64") => "arm64",
+            (_, arch) => arch,
+        };
+
+        Self {
+            implementation_name: "cpython".to_string(),
+            implementation_version: full_version.clone(),
+            os_name: os_name.to_string(),
+            platform_machine: platform_machine.to_string(),
+            platform_python_implementation: "CPython".to_string(),
+            platform_release: String::new(),
+            platform_system: platform_system.to_string(),
+            platform_version: String::new(),
+            python_full_version: full_version,
+            python_version: format!("{}.{}", python.major(), python.minor()),
+            sys_platform: sys_platform.to_string(),
+        }
+    }
+
+    /// Create a marker environment for the given interpreter on this machine
+    pub fn current(python: &PythonVersion) -> Self {
+        Self::new(python, &PlatformInfo::current())
+    }
+
+    /// Get the value of a marker variable
+    pub fn get(&self, variable: MarkerVariable) -> &str {
+        match variable {
+            MarkerVariable::ImplementationName => &self.implementation_name,
+            MarkerVariable::ImplementationVersion => &self.implementation_version,
+            MarkerVariable::OsName => &self.os_name,
+            MarkerVariable::PlatformMachine => &self.platform_machine,
+            MarkerVariable::PlatformPythonImplementation => &self.platform_python_implementation,
+            MarkerVariable::PlatformRelease => &self.platform_release,
+            MarkerVariable::PlatformSystem => &self.platform_system,
+            MarkerVariable::PlatformVersion => &self.platform_version,
+            MarkerVariable::PythonFullVersion => &self.python_full_version,
+            MarkerVariable::PythonVersion => &self.python_version,
+            MarkerVariable::SysPlatform => &self.sys_platform,
+            MarkerVariable::Extra => "",
+        }
+    }
+}
+
+/// Marker variable defined by PEP 508
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum MarkerVariable {
+    ImplementationName,
+    ImplementationVersion,
+    OsName,
+    PlatformMachine,
+    PlatformPythonImplementation,
+    PlatformRelease,
+    PlatformSystem,
+    PlatformVersion,
+    PythonFullVersion,
+    PythonVersion,
+    SysPlatform,
+    /// The extra being installed; only known at evaluation time
+    Extra,
+}
+
+impl MarkerVariable {
+    fn from_name(name: &str) -> Option<Self> {
+        // Dotted names are legacy aliases still found in old metadata
+        Some(match name {
+            "implementation_name" => Self::ImplementationName,
+            "implementation_version" => Self::ImplementationVersion,
+            "os_name" | "os.name" => Self::OsName,
+            "platform_machine" | "platform.machine" => Self::PlatformMachine,
+            "platform_python_implementation" | "platform.python_implementation" | "python_implementation" => {
+                Self::PlatformPythonImplementation
+            }
+            "platform_release" => Self::PlatformRelease,
+            "platform_system" => Self::PlatformSystem,
+            "platform_version" | "platform.version" => Self::PlatformVersion,
+            "python_full_version" => Self::PythonFullVersion,
+            "python_version" => Self::PythonVersion,
+            "sys_platform" | "sys.platform" => Self::SysPlatform,
+            "extra" => Self::Extra,
+            _ => return None,
+        })
+    }
+
+    /// Get the PEP 508 name of this variable
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ImplementationName => "implementation_name",
+            Self::ImplementationVersion => "implementation_version",
+            Self::OsName => "os_name",
+            Self::PlatformMachine => "platform_machine",
+            Self::PlatformPythonImplementation => "platform_python_implementation",
+            Self::PlatformRelease => "platform_release",
+            Self::PlatformSystem => "platform_system",
+            Self::PlatformVersion => "platform_version",
+            Self::PythonFullVersion => "python_full_version",
+            Self::PythonVersion => "python_version",
+            Self::SysPlatform => "sys_platform",
+            Self::Extra => "extra",
+        }
+    }
+}
+
+impl fmt::Display for MarkerVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Operand of a marker expression
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum MarkerValue {
+    /// Marker variable such as `python_version`
+    Variable(MarkerVariable),
+    /// Quoted string literal
+    Literal(String),
+}
+
+impl fmt::Display for MarkerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Variable(variable) => write!(f, "{}", variable),
+            Self::Literal(value) if value.contains('"') => write!(f, "'{}'", value),
+            Self::Literal(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+/// Comparison operator of a marker expression
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum MarkerOperator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+    Compatible,
+    ArbitraryEqual,
+    In,
+    NotIn,
+}
+
+impl fmt::Display for MarkerOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::LessThan => "<",
+            Self::LessThanEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterThanEqual => ">=",
+            Self::Compatible => "~=",
+            Self::ArbitraryEqual => "===",
+            Self::In => "in",
+            Self::NotIn => "not in",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+/// Single marker comparison, e.g. `python_version >= "3.8"`
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct MarkerExpression {
+    pub lhs: MarkerValue,
+    pub operator: MarkerOperator,
+    pub rhs: MarkerValue,
+}
+
+impl MarkerExpression {
+    fn evaluate(&self, env: &MarkerEnvironment, extra: Option<&str>) -> bool {
+        let is_extra = |value: &MarkerValue| value == &MarkerValue::Variable(MarkerVariable::Extra);
+        if is_extra(&self.lhs) || is_extra(&self.rhs) {
+            // Extras are compared by their normalized names (PEP 685)
+            let resolve = |value: &MarkerValue| match value {
+                MarkerValue::Variable(MarkerVariable::Extra) => extra.map(normalize_extra).unwrap_or_default(),
+                MarkerValue::Variable(variable) => normalize_extra(env.get(*variable)),
+                MarkerValue::Literal(literal) => normalize_extra(literal),
+            };
+            return compare_strings(&resolve(&self.lhs), self.operator, &resolve(&self.rhs));
+        }
+
+        let resolve = |value: &MarkerValue| match value {
+            MarkerValue::Variable(variable) => env.get(*variable).to_string(),
+            MarkerValue::Literal(literal) => literal.clone(),
+        };
+        let (lhs, rhs) = (resolve(&self.lhs), resolve(&self.rhs));
+
+        // Version comparison when both sides are versions, string otherwise
+        if !matches!(self.operator, MarkerOperator::In | MarkerOperator::NotIn) {
+            let specifier = VersionSpecifier::parse(&format!("{}{}", self.operator, rhs));
+            if let (Ok(specifier), Ok(version)) = (specifier, Version::parse(&lhs)) {
+                return specifier.matches(&version);
+            }
+        }
+        compare_strings(&lhs, self.operator, &rhs)
+    }
+
+    fn extra(&self) -> Option<&str> {
+        match (&self.lhs, &self.rhs) {
+            (MarkerValue::Variable(MarkerVariable::Extra), MarkerValue::Literal(extra))
+            | (MarkerValue::Literal(extra), MarkerValue::Variable(MarkerVariable::Extra)) => Some(extra),
+            _ => None,
+        }
+    }
+}
+
+fn compare_strings(lhs: &str, operator: MarkerOperator, rhs: &str) -> bool {
+    match operator {
+        MarkerOperator::Equal | MarkerOperator::ArbitraryEqual => lhs == rhs,
+        MarkerOperator::NotEqual => lhs != rhs,
+        MarkerOperator::In => rhs.contains(lhs),
+        MarkerOperator::NotIn => !rhs.contains(lhs),
+        // Ordering is undefined for non-version strings
+        _ => false,
+    }
+}
+
+/// Normalize an extra name as described by PEP 685
+pub fn normalize_extra(name: &str) -> String {
+    normalize_name(name)
+}
+
+impl fmt::Display for MarkerExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.operator, self.rhs)
+    }
+}
+
+/// Parsed PEP 508 marker
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum MarkerTree {
+    /// Single comparison
+    Expression(MarkerExpression),
+    /// All markers must hold
+    And(Vec<MarkerTree>),
+    /// At least one marker must hold
+    Or(Vec<MarkerTree>),
+}
+
+impl MarkerTree {
+    /// Parse a marker string
+    pub fn parse(marker: &str) -> BlastResult<Self> {
+        let mut parser = MarkerParser::new(marker);
+        let tree = parser.parse_or();
+        parser.skip_whitespace();
+        match tree {
+            Some(tree) if parser.pos == parser.input.len() => Ok(tree),
+            _ => Err(BlastError::package(format!(
+                "Invalid marker '{}' at position {}",
+                marker, parser.pos
+            ))),
+        }
+    }
+
+    /// Evaluate the marker, optionally with an extra being installed
+    pub fn evaluate(&self, env: &MarkerEnvironment, extra: Option<&str>) -> bool {
+        match self {
+            Self::Expression(expression) => expression.evaluate(env, extra),
+            Self::And(trees) => trees.iter().all(|tree| tree.evaluate(env, extra)),
+            Self::Or(trees) => trees.iter().any(|tree| tree.evaluate(env, extra)),
+        }
+    }
+
+    /// Get the normalized names of the extras this marker refers to
+    pub fn extras(&self) -> Vec<String> {
+        let mut extras = Vec::new();
+        self.collect_extras(&mut extras);
+        extras
+    }
+
+    fn collect_extras(&self, extras: &mut Vec<String>) {
+        match self {
+            Self::Expression(expression) => {
+                if let Some(extra) = expression.extra().map(normalize_extra) {
+                    if !extras.contains(&extra) {
+                        extras.push(extra);
+                    }
+                }
+            }
+            Self::And(trees) | Self::Or(trees) => {
+                for tree in trees {
+                    tree.collect_extras(extras);
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for MarkerTree {
+    type Err = BlastError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for MarkerTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expression(expression) => write!(f, "{}", expression),
+            Self::And(trees) => {
+                let parts: Vec<String> = trees.iter()
+                    .map(|tree| match tree {
+                        Self::Or(_) => format!("({})", tree),
+                        _ => tree.to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", parts.join(" and "))
+            }
+            Self::Or(trees) => {
+                let parts: Vec<String> = trees.iter().map(|tree| tree.to_string()).collect();
+                write!(f, "{}", parts.join(" or "))
+            }
+        }
+    }
+}
+
+/// Recursive descent parser for the PEP 508 marker grammar
+struct MarkerParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> MarkerParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn parse_or(&mut self) -> Option<MarkerTree> {
+        let mut trees = vec![self.parse_and()?];
+        while self.keyword("or") {
+            trees.push(self.parse_and()?);
+        }
+        Some(if trees.len() == 1 { trees.remove(0) } else { MarkerTree::Or(trees) })
+    }
+
+    fn parse_and(&mut self) -> Option<MarkerTree> {
+        let mut trees = vec![self.parse_atom()?];
+        while self.keyword("and") {
+            trees.push(self.parse_atom()?);
+        }
+        Some(if trees.len() == 1 { trees.remove(0) } else { MarkerTree::And(trees) })
+    }
+
+    fn parse_atom(&mut self) -> Option<MarkerTree> {
+        self.skip_whitespace();
+        if self.eat("(") {
+            let tree = self.parse_or()?;
+            self.skip_whitespace();
+            return self.eat(")").then_some(tree);
+        }
+
+        let lhs = self.value()?;
+        let operator = self.operator()?;
+        let rhs = self.value()?;
+        Some(MarkerTree::Expression(MarkerExpression { lhs, operator, rhs }))
+    }
+
+    fn value(&mut self) -> Option<MarkerValue> {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let end = rest[1..].find(quote)?;
+            self.pos += end + 2;
+            return Some(MarkerValue::Literal(rest[1..end + 1].to_string()));
+        }
+
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(rest.len());
+        let variable = MarkerVariable::from_name(&rest[..len])?;
+        self.pos += len;
+        Some(MarkerValue::Variable(variable))
+    }
+
+    fn operator(&mut self) -> Option<MarkerOperator> {
+        self.skip_whitespace();
+        const OPERATORS: [(&str, MarkerOperator); 8] = [
+            ("===", MarkerOperator::ArbitraryEqual),
+            ("==", MarkerOperator::Equal),
+            ("!=", MarkerOperator::NotEqual),
+            ("<=", MarkerOperator::LessThanEqual),
+            (">=", MarkerOperator::GreaterThanEqual),
+            ("~=", MarkerOperator::Compatible),
+            ("<", MarkerOperator::LessThan),
+            (">", MarkerOperator::GreaterThan),
+        ];
+        for (token, operator) in OPERATORS {
+            if self.eat(token) {
+                return Some(operator);
+            }
+        }
+        if self.keyword("in") {
+            return Some(MarkerOperator::In);
+        }
+        if self.keyword("not") && self.keyword("in") {
+            return Some(MarkerOperator::NotIn);
+        }
+        None
+    }
+
+    /// Consume a keyword that is not immediately followed by a name character
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let after = match rest.strip_prefix(keyword) {
+            Some(after) => after,
+            None => return false,
+        };
+        if !matches!(after.chars().next(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{Package, PackageId};
+use blast_core::version::Version;
+
+use crate::filename::normalize_name;
+use crate::source::{PackageSource, YankedRelease};
+
+/// Priority of the metadata cache, ahead of every other source
+pub const METADATA_CACHE_PRIORITY: u32 = 0;
+
+/// Package source answering from previously resolved package metadata
+///
+/// Used in offline mode: only versions whose metadata is already on disk can
+/// take part in a resolution. It serves metadata only, distribution files
+/// come from the artifact cache or local sources.
+pub struct CacheSource {
+    /// Cached packages keyed by normalized name
+    packages: HashMap<String, Vec<Package>>,
+}
+
+impl CacheSource {
+    /// Create a source over a snapshot of cached packages
+    pub fn new<'a>(packages: impl IntoIterator<Item = &'a Package>) -> Self {
+        let mut by_name: HashMap<String, Vec<Package>> = HashMap::new();
+        for package in packages {
+            by_name.entry(normalize_name(package.name())).or_default().push(package.clone());
+        }
+        Self { packages: by_name }
+    }
+
+    /// Get the cached releases of a package
+    fn releases(&self, package_name: &str) -> &[Package] {
+        self.packages
+            .get(&normalize_name(package_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl PackageSource for CacheSource {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        self.releases(id.name())
+            .iter()
+            .find(|package| package.version() == id.version())
+            .cloned()
+            .ok_or_else(|| BlastError::package(format!("Package not found in metadata cache: {}", id)))
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        let releases = self.releases(package_name);
+        let mut versions: Vec<Version> = releases.iter()
+            .filter(|package| !package.metadata().yanked)
+            .map(|package| package.version().clone())
+            .collect();
+        if releases.is_empty() {
+            return Err(BlastError::package(format!(
+                "Package not found in metadata cache: {}",
+                package_name
+            )));
+        }
+
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    async fn get_yanked_versions(&self, package_name: &str) -> BlastResult<Vec<YankedRelease>> {
+        let mut yanked: Vec<YankedRelease> = self.releases(package_name)
+            .iter()
+            .filter(|package| package.metadata().yanked)
+            .map(|package| YankedRelease {
+                version: package.version().clone(),
+                reason: package.metadata().yanked_reason.clone(),
+            })
+            .collect();
+        yanked.sort_by(|a, b| a.version.cmp(&b.version));
+        yanked.dedup_by(|a, b| a.version == b.version);
+        Ok(yanked)
+    }
+
+    async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
+        Err(BlastError::package(format!(
+            "The metadata cache holds no distribution files for {}",
+            id
+        )))
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        Ok(self.releases(id.name()).iter().any(|package| package.version() == id.version()))
+    }
+
+    fn name(&self) -> &str {
+        "metadata-cache"
+    }
+
+    fn priority(&self) -> u32 {
+        METADATA_CACHE_PRIORITY
+    }
+}
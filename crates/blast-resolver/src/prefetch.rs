@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use pubgrub::range::Range;
+use tokio::runtime::Handle;
+use tokio::sync::{OnceCell, RwLock, Semaphore};
+use tokio::task::AbortHandle;
+use tracing::debug;
+
+use blast_core::error::BlastError;
+use blast_core::package::{Package, PackageId};
+use blast_core::version::Version;
+
+use crate::cache::Cache;
+use crate::filename::normalize_name;
+use crate::pubgrub::PubgrubVersion;
+use crate::resolution::ResolutionMetrics;
+use crate::resolver::PubGrubProvider;
+
+/// Outcome of a lookup, shared by everyone waiting on it
+pub(crate) type Fetched<T> = Result<Arc<T>, Arc<BlastError>>;
+
+/// Lookups by key, each performed at most once
+struct Lookups<K, T> {
+    cells: Mutex<HashMap<K, Arc<OnceCell<Fetched<T>>>>>,
+}
+
+impl<K: Eq + Hash, T> Lookups<K, T> {
+    fn new() -> Self {
+        Self {
+            cells: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the lookup of `key`, whether started already or not
+    fn cell(&self, key: K) -> Arc<OnceCell<Fetched<T>>> {
+        self.cells.lock().unwrap().entry(key).or_default().clone()
+    }
+}
+
+/// Metadata lookups of one resolution
+///
+/// Every version list and release is requested from the sources at most
+/// once, however many times the solver asks for it and whether or not a
+/// prefetch already started the request. Dependencies are prefetched in the
+/// background as soon as a release's requirements are known, with no more
+/// than `max_concurrent_requests` requests in flight at a time.
+pub(crate) struct Prefetcher {
+    provider: PubGrubProvider,
+    cache: Arc<RwLock<Cache>>,
+    runtime: Handle,
+    /// Permits for requests to the sources
+    permits: Semaphore,
+    versions: Lookups<String, Vec<Version>>,
+    packages: Lookups<PackageId, Package>,
+    /// Normalized names of the packages already prefetched
+    prefetched: Mutex<HashSet<String>>,
+    tasks: Mutex<Vec<AbortHandle>>,
+    network_requests: AtomicUsize,
+    cache_hits: AtomicUsize,
+}
+
+impl Prefetcher {
+    /// Create a prefetcher looking packages up through the provider's
+    /// sources and the metadata cache
+    pub fn new(provider: PubGrubProvider, cache: Arc<RwLock<Cache>>, runtime: Handle) -> Arc<Self> {
+        Arc::new(Self {
+            permits: Semaphore::new(provider.max_concurrent_requests.max(1)),
+            provider,
+            cache,
+            runtime,
+            versions: Lookups::new(),
+            packages: Lookups::new(),
+            prefetched: Mutex::new(HashSet::new()),
+            tasks: Mutex::new(Vec::new()),
+            network_requests: AtomicUsize::new(0),
+            cache_hits: AtomicUsize::new(0),
+        })
+    }
+
+    /// Get the available versions of a package
+    pub async fn versions(&self, name: &str) -> Fetched<Vec<Version>> {
+        let cell = self.versions.cell(normalize_name(name));
+        cell.get_or_init(|| async {
+            let _permit = self.permits.acquire().await;
+            self.network_requests.fetch_add(1, Ordering::Relaxed);
+            debug!("Fetching versions of {}", name);
+            self.provider.source_for(name).get_versions(name).await.map(Arc::new).map_err(Arc::new)
+        })
+        .await
+        .clone()
+    }
+
+    /// Get the metadata of a release, from the metadata cache if it has it
+    pub async fn package(&self, id: &PackageId) -> Fetched<Package> {
+        let cell = self.packages.cell(id.clone());
+        cell.get_or_init(|| async {
+            // What a direct source points at can change under the same version
+            let cached = match self.is_direct(id.name()) {
+                true => None,
+                false => self.cache.write().await.get_package(id).cloned(),
+            };
+            if let Some(package) = cached {
+                debug!("Using cached package {}", id);
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Arc::new(package));
+            }
+            let _permit = self.permits.acquire().await;
+            self.network_requests.fetch_add(1, Ordering::Relaxed);
+            debug!("Fetching metadata of {}", id);
+            self.provider.source_for(id.name()).get_package(id).await.map(Arc::new).map_err(Arc::new)
+        })
+        .await
+        .clone()
+    }
+
+    /// Check if a package comes from a path, URL or git source
+    pub fn is_direct(&self, name: &str) -> bool {
+        self.provider.source_for(name).is_direct()
+    }
+
+    /// Start looking up the versions of a dependency and the metadata of
+    /// the release within `range` the solver will likely pick, without
+    /// waiting for either
+    ///
+    /// `prereleases` tells whether a requirement names a pre-release of the
+    /// dependency, as the solver's choice depends on it.
+    pub fn prefetch(self: &Arc<Self>, name: &str, range: Range<PubgrubVersion>, lowest: bool, prereleases: bool) {
+        if !self.prefetched.lock().unwrap().insert(normalize_name(name)) {
+            return;
+        }
+        let prefetcher = self.clone();
+        let name = name.to_string();
+        let task = self.runtime.spawn(async move {
+            let Ok(versions) = prefetcher.versions(&name).await else {
+                return;
+            };
+            let likely = prefetcher.provider.best_version(&versions, &range, lowest, prereleases);
+            if let Some(version) = likely {
+                let _ = prefetcher.package(&PackageId::new(name, version.version().clone())).await;
+            }
+        });
+        self.tasks.lock().unwrap().push(task.abort_handle());
+    }
+
+    /// Stop the prefetches still running
+    pub fn cancel(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+
+    /// Add the requests made and the cache hits to resolution metrics
+    pub fn record(&self, metrics: &mut ResolutionMetrics) {
+        metrics.network_requests += self.network_requests.load(Ordering::Relaxed);
+        metrics.cache_hits += self.cache_hits.load(Ordering::Relaxed);
+    }
+}
@@ -1,22 +1,119 @@
 use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
-use blast_core::version::Version;
+use blast_core::version::{Operator, Version, VersionConstraint, VersionSpecifier};
+use pubgrub::range::Range;
 use pubgrub::version::Version as PubgrubVersionTrait;
 
 /// Version wrapper for PubGrub compatibility
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub(crate) struct PubgrubVersion(pub(crate) Version);
+///
+/// PEP 440 versions have no "next version", but PubGrub builds exact ranges
+/// as `[v, v.bump())`. Bumping therefore yields a synthetic successor that
+/// sorts after `v` and before every real version greater than `v`.
+///
+/// Specifiers such as `<=1.0` or `>1.0` also need bounds that sit after an
+/// unbounded family of real versions (`1.0+local`, `1.0.post1`, ...), which
+/// are represented by the synthetic [`Bound`] markers.
+#[derive(Debug, Clone)]
+pub struct PubgrubVersion {
+    version: Version,
+    bound: Bound,
+    /// Number of bumps applied to `version`; zero for real versions
+    successor: u32,
+}
+
+/// Position of a version relative to the real versions sharing its prefix
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+enum Bound {
+    /// The version itself
+    Exact,
+    /// After every local version of a public version
+    AfterLocals,
+    /// After every post, development and local release of a final or
+    /// pre-release version
+    AfterPosts,
+}
+
+impl PubgrubVersion {
+    /// Get the underlying PEP 440 version
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Check if this is a real version rather than a synthetic bound
+    pub fn is_real(&self) -> bool {
+        self.bound == Bound::Exact && self.successor == 0
+    }
+
+    /// Bound sorting after `version` and all of its local versions
+    fn after_locals(version: &Version) -> Self {
+        Self {
+            version: version.public(),
+            bound: Bound::AfterLocals,
+            successor: 0,
+        }
+    }
+
+    /// Bound sorting after `version` and all of its post and local releases
+    fn after_posts(version: &Version) -> Self {
+        Self {
+            version: release_group(version),
+            bound: Bound::AfterPosts,
+            successor: 0,
+        }
+    }
+
+    /// Lowest version starting with the given release prefix
+    fn prefix_start(epoch: u64, release: &[u64]) -> Self {
+        Version::new(release.iter().copied())
+            .with_epoch(epoch)
+            .with_dev(Some(0))
+            .into()
+    }
+}
+
+/// Representative of the versions sharing an epoch, release and pre-release
+/// phase with `version`
+fn release_group(version: &Version) -> Version {
+    let group = version.base().with_pre(version.pre());
+    if version.pre().is_none() && version.post().is_none() && version.dev().is_some() {
+        group.with_dev(Some(0))
+    } else {
+        group
+    }
+}
 
 impl From<Version> for PubgrubVersion {
-    fn from(v: Version) -> Self {
-        Self(v)
+    fn from(version: Version) -> Self {
+        Self {
+            version,
+            bound: Bound::Exact,
+            successor: 0,
+        }
     }
 }
 
 impl fmt::Display for PubgrubVersion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.version)
+    }
+}
+
+impl PartialEq for PubgrubVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PubgrubVersion {}
+
+impl Hash for PubgrubVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Bounds are stored normalized, so equal values hash identically
+        self.version.hash(state);
+        self.bound.hash(state);
+        self.successor.hash(state);
     }
 }
 
@@ -28,19 +125,188 @@ impl PartialOrd for PubgrubVersion {
 
 impl Ord for PubgrubVersion {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+        if self.bound == Bound::Exact && other.bound == Bound::Exact {
+            return self.version.cmp(&other.version)
+                .then(self.successor.cmp(&other.successor));
+        }
+
+        // Bounds are stored normalized, so each comparison stage only needs
+        // to know whether a side sits after everything else in that stage
+        release_group(&self.version).cmp(&release_group(&other.version))
+            .then_with(|| (self.bound == Bound::AfterPosts).cmp(&(other.bound == Bound::AfterPosts)))
+            .then_with(|| self.version.public().cmp(&other.version.public()))
+            .then_with(|| (self.bound == Bound::AfterLocals).cmp(&(other.bound == Bound::AfterLocals)))
+            .then_with(|| self.version.cmp(&other.version))
+            .then(self.successor.cmp(&other.successor))
     }
 }
 
 impl PubgrubVersionTrait for PubgrubVersion {
     fn lowest() -> Self {
-        Self(Version::parse("0.0.0").unwrap())
+        // `0.dev0` sorts before every other version in epoch 0
+        Version::new([0]).with_dev(Some(0)).into()
     }
 
     fn bump(&self) -> Self {
-        // Simple increment of patch version
-        // In practice, we should use proper version bumping logic
-        let version_str = format!("{}.0", self.0);
-        Self(Version::parse(&version_str).unwrap())
+        Self {
+            version: self.version.clone(),
+            bound: self.bound,
+            successor: self.successor + 1,
+        }
     }
-} 
\ No newline at end of file
+}
+
+/// Convert a version constraint into the exact set of versions it matches
+pub fn constraint_to_range(constraint: &VersionConstraint) -> Range<PubgrubVersion> {
+    constraint.sets().iter().fold(Range::none(), |union, set| {
+        let intersection = set.iter()
+            .fold(Range::any(), |range, specifier| range.intersection(&specifier_to_range(specifier)));
+        union.union(&intersection)
+    })
+}
+
+/// Convert a single version specifier into a PubGrub range
+pub fn specifier_to_range(specifier: &VersionSpecifier) -> Range<PubgrubVersion> {
+    let (operator, version) = match specifier {
+        VersionSpecifier::Version(operator, version) => (*operator, version),
+        VersionSpecifier::Arbitrary(literal) => {
+            return match Version::parse(literal) {
+                Ok(version) if version.to_string().eq_ignore_ascii_case(literal) => {
+                    Range::exact(PubgrubVersion::from(version))
+                }
+                _ => Range::none(),
+            };
+        }
+    };
+
+    match operator {
+        Operator::Equal => exact_range(version),
+        Operator::NotEqual => exact_range(version).negate(),
+        Operator::EqualStar => prefix_range(version.epoch(), version.release()),
+        Operator::NotEqualStar => prefix_range(version.epoch(), version.release()).negate(),
+        Operator::Compatible => {
+            let release = version.release();
+            Range::higher_than(version.clone())
+                .intersection(&prefix_range(version.epoch(), &release[..release.len() - 1]))
+        }
+        Operator::LessThan if version.is_prerelease() => Range::strictly_lower_than(version.clone()),
+        Operator::LessThan => {
+            // Pre-releases of the base release are excluded, but its final
+            // and post releases below `version` are not
+            let base = version.base();
+            let below = Range::strictly_lower_than(PubgrubVersion::prefix_start(base.epoch(), base.release()));
+            if *version > base {
+                below.union(&Range::between(base, version.clone()))
+            } else {
+                below
+            }
+        }
+        Operator::LessThanEqual => Range::strictly_lower_than(PubgrubVersion::after_locals(version)),
+        Operator::GreaterThan if version.post().is_none() && version.dev().is_none() => {
+            Range::higher_than(PubgrubVersion::after_posts(version))
+        }
+        Operator::GreaterThan => Range::higher_than(PubgrubVersion::after_locals(version)),
+        Operator::GreaterThanEqual => Range::higher_than(version.clone()),
+    }
+}
+
+/// Format a range as PEP 440 specifiers, such as `>=1.0,<2`
+///
+/// Disjoint intervals are joined with ` || ` and the range of every version
+/// is written as `*`. Synthetic bounds are written as the specifier they
+/// came from, so `<2` is not shown as `<2.dev0`.
+pub fn range_to_string(range: &Range<PubgrubVersion>) -> String {
+    let intervals = intervals(range);
+    if intervals.is_empty() {
+        return "<none>".to_string();
+    }
+    intervals.iter()
+        .map(|(start, end)| interval_to_string(start, end.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" || ")
+}
+
+/// Split a range into its disjoint `[start, end)` intervals
+fn intervals(range: &Range<PubgrubVersion>) -> Vec<(PubgrubVersion, Option<PubgrubVersion>)> {
+    let complement = range.negate();
+    let mut rest = range.clone();
+    let mut intervals = Vec::new();
+
+    while let Some(start) = rest.lowest_version() {
+        let end = complement.intersection(&Range::higher_than(start.clone())).lowest_version();
+        rest = match &end {
+            Some(end) => rest.intersection(&Range::higher_than(end.clone())),
+            None => Range::none(),
+        };
+        intervals.push((start, end));
+    }
+    intervals
+}
+
+fn interval_to_string(start: &PubgrubVersion, end: Option<&PubgrubVersion>) -> String {
+    let lowest = *start == PubgrubVersion::lowest();
+    if let Some(end) = end {
+        if *end == start.bump() || (end.bound == Bound::AfterLocals && start.is_real() && end.version == start.version) {
+            return format!("=={}", start.version);
+        }
+        if let (Some(prefix), Some(next)) = (release_start(start), release_start(end)) {
+            let release = prefix.release();
+            let mut expected = release.to_vec();
+            if let Some(last) = expected.last_mut() {
+                *last += 1;
+            }
+            if !lowest && prefix.epoch() == next.epoch() && next.release() == expected.as_slice() {
+                return format!("=={}.*", prefix);
+            }
+        }
+    }
+
+    let mut specifiers = Vec::new();
+    if !lowest {
+        specifiers.push(match start.bound {
+            Bound::Exact if start.successor == 0 => format!(">={}", start.version),
+            _ => format!(">{}", start.version),
+        });
+    }
+    if let Some(end) = end {
+        specifiers.push(match end.bound {
+            Bound::Exact if end.successor == 0 => format!("<{}", release_start(end).unwrap_or_else(|| end.version.clone())),
+            _ => format!("<={}", end.version),
+        });
+    }
+
+    if specifiers.is_empty() {
+        "*".to_string()
+    } else {
+        specifiers.join(",")
+    }
+}
+
+/// Get the release a version is the lowest possible version of, if it is
+/// a `.dev0` bound produced for a release prefix
+fn release_start(version: &PubgrubVersion) -> Option<Version> {
+    let version = &version.version;
+    let bare = version.pre().is_none() && version.post().is_none() && !version.is_local();
+    (bare && version.dev() == Some(0)).then(|| version.base())
+}
+
+/// Range for `==version`, which matches any local label unless one is given
+fn exact_range(version: &Version) -> Range<PubgrubVersion> {
+    if version.is_local() {
+        Range::exact(version.clone())
+    } else {
+        Range::between(version.clone(), PubgrubVersion::after_locals(version))
+    }
+}
+
+/// Range of every version whose release starts with `release`
+fn prefix_range(epoch: u64, release: &[u64]) -> Range<PubgrubVersion> {
+    let mut next = release.to_vec();
+    if let Some(last) = next.last_mut() {
+        *last += 1;
+    }
+    Range::between(
+        PubgrubVersion::prefix_start(epoch, release),
+        PubgrubVersion::prefix_start(epoch, &next),
+    )
+}
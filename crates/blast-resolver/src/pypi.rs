@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
@@ -11,25 +13,49 @@ use blast_core::error::{BlastError, BlastResult};
 use blast_core::package::{Package, PackageId};
 use blast_core::metadata::PackageMetadata;
 use blast_core::{Version, VersionConstraint};
+use blast_image::platform::PlatformInfo;
 use blast_image::compression::{
     CompressionType, CompressionLevel, CompressionStrategy,
     NoopStrategy, ZstdStrategy, GzipStrategy,
 };
-use pubgrub::range::Range;
 use pubgrub::solver::Dependencies;
 
+use crate::core_metadata::split_requirements;
+use crate::marker::{MarkerEnvironment, MarkerTree};
+use crate::filename::DistFilename;
+use crate::imports::ImportMap;
+use crate::tags::WheelTags;
+use crate::target::PythonTarget;
+use crate::pubgrub::constraint_to_range;
+use crate::source::{uploaded_by, yank_reason, Artifact, ArtifactInfo, PackageSource, YankedRelease};
+
 const PYPI_BASE_URL: &str = "https://pypi.org/pypi";
 
+/// Priority of PyPI in a source chain; configured indexes sort ahead of it
+pub const PYPI_PRIORITY: u32 = 100;
+
 /// PyPI API client
 #[derive(Clone)]
 pub struct PyPIClient {
     client: Client,
+    target: PythonTarget,
+    environments: Vec<MarkerEnvironment>,
+    /// Wheel tags the target supports
+    tags: WheelTags,
+    /// Whether source distributions are left out
+    no_build: bool,
+    /// Files uploaded after this time are ignored
+    exclude_newer: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PyPIResponse {
     info: PackageInfo,
+    #[serde(default)]
     releases: HashMap<String, Vec<ReleaseInfo>>,
+    /// Files of the requested release
+    #[serde(default)]
+    urls: Vec<ReleaseInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,11 +75,17 @@ struct PackageInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ReleaseInfo {
     yanked: Option<bool>,
+    #[serde(default)]
+    yanked_reason: Option<String>,
     requires_dist: Option<Vec<String>>,
     requires_python: Option<String>,
     filename: String,
     python_version: String,
     url: String,
+    #[serde(default)]
+    digests: HashMap<String, String>,
+    #[serde(default)]
+    upload_time_iso_8601: Option<DateTime<Utc>>,
 }
 
 // Helper function to convert reqwest errors to BlastError
@@ -71,7 +103,74 @@ impl PyPIClient {
             .build()
             .map_err(handle_reqwest_error)?;
 
-        Ok(Self { client })
+        let target = PythonTarget::default();
+        Ok(Self {
+            client,
+            environments: target.marker_environments(target.platform()),
+            tags: WheelTags::new(&target),
+            target,
+            no_build: false,
+            exclude_newer: None,
+        })
+    }
+
+    /// Set the Python interpreter(s) that releases and markers are checked against
+    pub fn with_python_target(mut self, target: PythonTarget) -> Self {
+        self.environments = target.marker_environments(target.platform());
+        self.tags = WheelTags::new(&target);
+        self.target = target;
+        self
+    }
+
+    /// Only offer releases that have a wheel for the target
+    pub fn with_no_build(mut self, no_build: bool) -> Self {
+        self.no_build = no_build;
+        self
+    }
+
+    /// Ignore files uploaded after `cutoff`
+    pub fn with_exclude_newer(mut self, cutoff: Option<DateTime<Utc>>) -> Self {
+        self.exclude_newer = cutoff;
+        self
+    }
+
+    /// Get the Python interpreter(s) that releases and markers are checked against
+    pub fn python_target(&self) -> &PythonTarget {
+        &self.target
+    }
+
+    /// Get the marker environments of the targeted interpreters
+    pub fn marker_environments(&self) -> &[MarkerEnvironment] {
+        &self.environments
+    }
+
+    /// Rank a release file for installation on the target, `None` if it
+    /// cannot be installed
+    fn preference(&self, release: &ReleaseInfo) -> Option<usize> {
+        let filename = DistFilename::parse(&release.filename).ok()?;
+        self.tags.preference(&filename, self.no_build)
+    }
+
+    /// Check if a release file's `requires_python` admits the target
+    fn supports_target(&self, release: &ReleaseInfo) -> bool {
+        match release.requires_python.as_deref().map(VersionConstraint::parse) {
+            Some(Ok(requires_python)) => self.target.allows(&requires_python),
+            // Missing or unparseable metadata does not exclude a release
+            _ => true,
+        }
+    }
+
+    /// Check if a release is yanked, returning the reason given for it
+    ///
+    /// Only the files usable on the target count, see [`yank_reason`].
+    fn release_yank_reason(&self, files: &[ReleaseInfo]) -> Option<Option<String>> {
+        let usable = files.iter().filter(|file| {
+            self.supports_target(file)
+                && uploaded_by(file.upload_time_iso_8601, self.exclude_newer)
+                && DistFilename::parse(&file.filename)
+                    .is_ok_and(|filename| self.tags.preference(&filename, self.no_build).is_some())
+        });
+        yank_reason(usable.map(|file| (file.yanked.unwrap_or(false), file.yanked_reason.as_deref())))
     }
 
     /// Get package metadata from PyPI
@@ -98,45 +197,25 @@ impl PyPIClient {
             .await
             .map_err(|e| BlastError::package(format!("Invalid package metadata: {}", e)))?;
 
+        self.package_from_response(data)
+    }
+
+    /// Build a package from the `info` section of a JSON API response
+    fn package_from_response(&self, data: PyPIResponse) -> BlastResult<Package> {
+        let yanked = self.release_yank_reason(&data.urls);
         let python_constraint = data.info.requires_python.as_deref()
-            .map(|v| VersionConstraint::parse(v))
+            .map(VersionConstraint::parse)
             .transpose()?
             .unwrap_or_else(VersionConstraint::any);
 
-        // Track dependencies by extras
-        let mut base_dependencies = HashMap::new();
-        let mut extra_dependencies = HashMap::new();
-
-        if let Some(release) = data.releases.get(&data.info.version) {
-            if let Some(first_release) = release.first() {
-                if let Some(requires_dist) = &first_release.requires_dist {
-                    for req in requires_dist {
-                        if let Ok(dep) = Dependency::parse(req) {
-                            // Only add dependency if Python version constraint is met
-                            if dep.python_constraint.as_ref().map_or(true, |pc| pc.matches(&Version::parse("3.7.0").unwrap())) {
-                                if dep.extras.is_empty() {
-                                    // Base dependency
-                                    base_dependencies.insert(dep.package, dep.version_constraint);
-                                } else {
-                                    // Extra dependency
-                                    for extra in &dep.extras {
-                                        extra_dependencies
-                                            .entry(extra.clone())
-                                            .or_insert_with(HashMap::new)
-                                            .insert(dep.package.clone(), dep.version_constraint.clone());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        // Only keep dependencies whose markers hold for a targeted interpreter
+        let requires_dist = data.info.requires_dist.unwrap_or_default();
+        let (base_dependencies, extra_dependencies) = split_requirements(&requires_dist, &self.environments);
 
         let mut metadata = PackageMetadata::new(
             data.info.name.clone(),
             data.info.version.clone(),
-            base_dependencies.clone(),
+            base_dependencies,
             python_constraint.clone(),
         );
 
@@ -145,24 +224,33 @@ impl PyPIClient {
         metadata.author = data.info.author;
         metadata.homepage = data.info.home_page;
         metadata.license = data.info.license;
+        metadata.extras = extra_dependencies;
+        if let Some(reason) = yanked {
+            metadata.yanked = true;
+            metadata.yanked_reason = reason;
+        }
 
-        let mut pkg = Package::new(
+        Package::new(
             data.info.name.clone(),
             data.info.version.clone(),
             metadata,
             python_constraint,
-        )?;
-
-        // Add extras dependencies
-        for (extra_name, deps) in extra_dependencies {
-            pkg.metadata_mut().extras.insert(extra_name, deps);
-        }
-
-        Ok(pkg)
+        )
     }
 
     /// Get available versions for a package
     pub async fn get_package_versions(&self, package: &str) -> BlastResult<Vec<Version>> {
+        Ok(self.get_releases(package).await?.0)
+    }
+
+    /// Get the yanked releases of a package
+    pub async fn get_yanked_releases(&self, package: &str) -> BlastResult<Vec<YankedRelease>> {
+        Ok(self.get_releases(package).await?.1)
+    }
+
+    /// Get the installable versions of a package along with its yanked
+    /// releases, both sorted
+    async fn get_releases(&self, package: &str) -> BlastResult<(Vec<Version>, Vec<YankedRelease>)> {
         let url = format!("{}/{}/json", PYPI_BASE_URL, package);
         debug!("Fetching package versions from {}", url);
 
@@ -186,25 +274,54 @@ impl PyPIClient {
             .map_err(|e| BlastError::package(format!("Invalid package metadata: {}", e)))?;
 
         let mut versions = Vec::new();
-        for (version_str, releases) in data.releases {
-            // Skip yanked releases
-            if releases.iter().any(|r| r.yanked.unwrap_or(false)) {
+        let mut yanked = Vec::new();
+        for (version_str, mut releases) in data.releases {
+            // Only files uploaded by the cutoff count, a release without
+            // any is treated as not yet published
+            if self.exclude_newer.is_some() {
+                releases.retain(|r| uploaded_by(r.upload_time_iso_8601, self.exclude_newer));
+                if releases.is_empty() {
+                    debug!("Skipping {} {}: uploaded after the cutoff", package, version_str);
+                    continue;
+                }
+            }
+
+            // Skip releases that cannot be installed on the target interpreter
+            if !releases.is_empty() && !releases.iter().any(|r| self.supports_target(r)) {
+                debug!("Skipping {} {}: requires a different Python", package, version_str);
                 continue;
             }
 
-            if let Ok(version) = Version::parse(&version_str) {
-                versions.push(version);
+            // Skip releases without a wheel or sdist usable on the target
+            if !releases.is_empty() {
+                let filenames: Vec<DistFilename> = releases.iter()
+                    .filter_map(|r| DistFilename::parse(&r.filename).ok())
+                    .collect();
+                if !self.tags.installable(&filenames, self.no_build) {
+                    debug!("Skipping {} {}: no installable file for {}", package, version_str, self.target);
+                    continue;
+                }
+            }
+
+            let Ok(version) = Version::parse(&version_str) else {
+                continue;
+            };
+            // Yanked releases are only listed separately
+            match self.release_yank_reason(&releases) {
+                Some(reason) => yanked.push(YankedRelease { version, reason }),
+                None => versions.push(version),
             }
         }
 
         versions.sort();
-        Ok(versions)
+        yanked.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok((versions, yanked))
     }
 
-    /// Get package dependencies
-    pub async fn get_package_dependencies(&self, package: &str, version: &Version) -> BlastResult<HashMap<String, VersionConstraint>> {
+    /// Get the JSON API response of a specific release
+    async fn get_release(&self, package: &str, version: &str) -> BlastResult<PyPIResponse> {
         let url = format!("{}/{}/{}/json", PYPI_BASE_URL, package, version);
-        debug!("Fetching package dependencies from {}", url);
+        debug!("Fetching release metadata from {}", url);
 
         let response = self.client
             .get(&url)
@@ -221,21 +338,33 @@ impl PyPIClient {
             )));
         }
 
-        let data: PyPIResponse = response
+        response
             .json()
             .await
-            .map_err(|e| BlastError::package(format!("Invalid package metadata: {}", e)))?;
+            .map_err(|e| BlastError::package(format!("Invalid package metadata: {}", e)))
+    }
+
+    /// Get the installable files of a release uploaded by the cutoff, best
+    /// file first
+    async fn get_release_files(&self, id: &PackageId) -> BlastResult<Vec<ReleaseInfo>> {
+        let mut files: Vec<(usize, ReleaseInfo)> = self.get_release(id.name(), &id.version().to_string()).await?
+            .urls
+            .into_iter()
+            .filter(|file| uploaded_by(file.upload_time_iso_8601, self.exclude_newer))
+            .filter_map(|file| Some((self.preference(&file)?, file)))
+            .collect();
+        files.sort_by_key(|(preference, _)| *preference);
+        Ok(files.into_iter().map(|(_, file)| file).collect())
+    }
+
+    /// Get package dependencies
+    pub async fn get_package_dependencies(&self, package: &str, version: &Version) -> BlastResult<HashMap<String, VersionConstraint>> {
+        let data = self.get_release(package, &version.to_string()).await?;
 
         let mut dependencies = HashMap::new();
-        if let Some(release) = data.releases.get(&version.to_string()) {
-            if let Some(first_release) = release.first() {
-                if let Some(requires_dist) = &first_release.requires_dist {
-                    for req in requires_dist {
-                        if let Some((name, constraint)) = parse_requirement(req) {
-                            dependencies.insert(name, constraint);
-                        }
-                    }
-                }
+        for req in data.info.requires_dist.iter().flatten() {
+            if let Some((name, constraint)) = parse_requirement(req, &self.environments) {
+                dependencies.insert(name, constraint);
             }
         }
 
@@ -247,106 +376,41 @@ impl PyPIClient {
         package: &str,
         version: &str,
     ) -> BlastResult<Dependencies<String, PyPIVersion>> {
-        let url = format!("{}/{}/{}/json", PYPI_BASE_URL, package, version);
-        debug!("Fetching package dependencies from {}", url);
-
-        let response = self.client
-            .get(&url)
-            .send()
-            .await
-            .map_err(handle_reqwest_error)?;
-
-        if !response.status().is_success() {
-            return Err(BlastError::package(format!(
-                "Package not found: {}=={} (status: {})",
-                package,
-                version,
-                response.status()
-            )));
-        }
-
-        let data: PyPIResponse = response
-            .json()
-            .await
-            .map_err(|e| BlastError::package(format!("Invalid package metadata: {}", e)))?;
-
-        let mut dependencies = HashMap::new();
-        if let Some(release) = data.releases.get(version) {
-            if let Some(first_release) = release.first() {
-                if let Some(requires_dist) = &first_release.requires_dist {
-                    for req in requires_dist {
-                        if let Some((name, constraint)) = parse_requirement(req) {
-                            dependencies.insert(name, constraint);
-                        }
-                    }
-                }
-            }
-        }
+        let version = Version::parse(version)?;
+        let dependencies = self.get_package_dependencies(package, &version).await?;
 
         let mut ranges = FxHashMap::default();
         for (name, constraint) in dependencies {
-            let range = match constraint.to_string().as_str() {
-                "*" => Range::any(),
-                constraint => {
-                    let version = Version::parse(constraint.trim_matches(|c| c == '*'))
-                        .unwrap_or_else(|_| Version::parse("0.0.0").unwrap());
-                    Range::exact(PyPIVersion(version))
-                }
-            };
-            
-            ranges.insert(name, range);
+            ranges.insert(name, constraint_to_range(&constraint));
         }
         Ok(Dependencies::Known(ranges))
     }
 
-    #[allow(dead_code)]
-    async fn get_package(&self, package_id: &PackageId) -> BlastResult<Package> {
-        let metadata = self.get_package_metadata(package_id.name()).await?;
-        let _base_dependencies = self.get_dependencies(package_id.name(), &package_id.version().to_string()).await?;
-
-        let pkg = Package::new(
-            package_id.name().to_string(),
-            package_id.version().to_string(),
-            metadata.metadata().clone(),
-            metadata.metadata().python_version.clone(),
-        )?;
-
-        Ok(pkg)
-    }
-
-    pub async fn resolve_import(&self, import_name: &str) -> BlastResult<Option<Package>> {
-        let package_name = match self.get_package_metadata(import_name).await {
-            Ok(metadata) => metadata.name().to_string(),
-            Err(_) => return Ok(None),
-        };
-
-        let client = PyPIClient::new(10, 30, false)?;
-        let metadata = client.get_package_metadata(&package_name).await?;
-        let _base_dependencies = client.get_dependencies(&package_name, metadata.version().to_string().as_str()).await?;
-
-        Package::new(
-            metadata.name().to_string(),
-            metadata.version().to_string(),
-            metadata.metadata().clone(),
-            metadata.metadata().python_version.clone(),
-        ).map(Some)
+    /// Get the latest release of the distribution providing an imported
+    /// module, or `None` if PyPI does not have it
+    pub async fn resolve_import(&self, import_name: &str, imports: &ImportMap) -> BlastResult<Option<Package>> {
+        let package_name = imports.distribution_for(import_name)?;
+        match self.get_package_metadata(&package_name).await {
+            Ok(package) => Ok(Some(package)),
+            Err(e) => {
+                debug!("No distribution {} on PyPI for import {}: {}", package_name, import_name, e);
+                Ok(None)
+            }
+        }
     }
 
-    pub async fn is_available(&self, import_name: &str) -> bool {
-        if let Some(package_name) = self.get_package_name(import_name) {
-            if let Ok(client) = PyPIClient::new(10, 30, false) {
-                client.get_package_metadata(package_name.as_str()).await.is_ok()
-            } else {
-                false
-            }
-        } else {
-            false
+    /// Check if PyPI has the distribution providing an imported module
+    pub async fn is_available(&self, import_name: &str, imports: &ImportMap) -> bool {
+        match imports.distribution_for(import_name) {
+            Ok(package_name) => self.get_package_metadata(&package_name).await.is_ok(),
+            Err(_) => false,
         }
     }
 
+    /// Get the distribution providing an imported module from the bundled
+    /// mappings, or `None` if that is ambiguous
     pub fn get_package_name(&self, import_name: &str) -> Option<String> {
-        // Basic implementation - in real world this would need a more sophisticated mapping
-        Some(import_name.to_string())
+        ImportMap::new().distribution_for(import_name).ok()
     }
 
     #[allow(dead_code)]
@@ -372,7 +436,7 @@ impl PyPIClient {
         let mut dependencies = HashMap::new();
         if let Some(deps) = pypi_data.info.requires_dist {
             for dep_str in deps {
-                if let Some((name, constraint)) = parse_requirement(&dep_str) {
+                if let Some((name, constraint)) = parse_requirement(&dep_str, &self.environments) {
                     dependencies.insert(name, constraint);
                 }
             }
@@ -420,7 +484,7 @@ impl PyPIClient {
         if let Some(requires_dist) = info["requires_dist"].as_array() {
             for req in requires_dist {
                 if let Some(req_str) = req.as_str() {
-                    if let Some((name, constraint)) = parse_requirement(req_str) {
+                    if let Some((name, constraint)) = parse_requirement(req_str, &self.environments) {
                         dependencies.insert(name, constraint);
                     }
                 }
@@ -434,84 +498,187 @@ impl PyPIClient {
     }
 }
 
-fn parse_requirement(req: &str) -> Option<(String, VersionConstraint)> {
+#[async_trait]
+impl PackageSource for PyPIClient {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        let data = self.get_release(id.name(), &id.version().to_string()).await?;
+        self.package_from_response(data)
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        self.get_package_versions(package_name).await
+    }
+
+    async fn get_yanked_versions(&self, package_name: &str) -> BlastResult<Vec<YankedRelease>> {
+        self.get_yanked_releases(package_name).await
+    }
+
+    async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
+        Ok(self.download_artifact(id).await?.data)
+    }
+
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        let data = self.get_release_files(id).await?;
+        let file = data.first()
+            .ok_or_else(|| BlastError::package(format!("No installable files published for {}", id)))?;
+        debug!("Downloading {}", file.url);
+
+        let response = self.client
+            .get(&file.url)
+            .send()
+            .await
+            .map_err(handle_reqwest_error)?;
+        if !response.status().is_success() {
+            return Err(BlastError::network(format!(
+                "Failed to download {} (status: {})",
+                file.url,
+                response.status()
+            )));
+        }
+        Ok(Artifact {
+            filename: file.filename.clone(),
+            data: response.bytes().await.map_err(handle_reqwest_error)?.to_vec(),
+        })
+    }
+
+    async fn list_artifacts(&self, id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        Ok(self.get_release_files(id).await?
+            .into_iter()
+            .map(|file| ArtifactInfo {
+                sha256: file.digests.get("sha256").cloned(),
+                filename: file.filename,
+                url: file.url,
+            })
+            .collect())
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        match self.get_package_versions(id.name()).await {
+            Ok(versions) => Ok(versions.contains(id.version())),
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "pypi"
+    }
+
+    fn priority(&self) -> u32 {
+        PYPI_PRIORITY
+    }
+}
+
+fn parse_requirement(req: &str, envs: &[MarkerEnvironment]) -> Option<(String, VersionConstraint)> {
     match Dependency::parse(req) {
-        Ok(dep) => Some((dep.package, dep.version_constraint)),
-        Err(_) => None
+        Ok(dep) if applies_to_any(&dep, envs, None) => Some((dep.package, dep.version_constraint)),
+        _ => None
     }
 }
 
+/// A dependency is needed if any targeted interpreter needs it
+fn applies_to_any(dep: &Dependency, envs: &[MarkerEnvironment], extra: Option<&str>) -> bool {
+    envs.iter().any(|env| dep.applies_to(env, extra))
+}
+
 /// Package dependency
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Dependency {
     pub package: String,
-    pub version_constraint: VersionConstraint,
-    pub python_constraint: Option<VersionConstraint>,
     pub extras: Vec<String>,
+    pub version_constraint: VersionConstraint,
+    /// Direct URL reference (`name @ url`)
+    pub url: Option<String>,
+    pub marker: Option<MarkerTree>,
 }
 
 impl Dependency {
     /// Parse a dependency string according to PEP 508
     pub fn parse(dep_str: &str) -> BlastResult<Self> {
-        // Split package name from version/extras
-        let parts: Vec<&str> = dep_str.split(';').collect();
-        let main_req = parts[0].trim();
-
-        // Parse extras and version constraints
-        let (package_name, extras, version_req) = if main_req.contains('[') {
-            // Has extras
-            let bracket_idx = main_req.find('[').unwrap();
-            let close_idx = main_req.find(']')
-                .ok_or_else(|| BlastError::package("Invalid extras format"))?;
-            
-            let name = main_req[..bracket_idx].trim();
-            let extras_str = main_req[bracket_idx + 1..close_idx].trim();
-            let extras: Vec<String> = extras_str.split(',')
-                .map(|s| s.trim().to_string())
-                .collect();
-
-            let version_part = main_req[close_idx + 1..].trim();
-            let version_req = if version_part.is_empty() {
-                "*"
-            } else {
-                version_part.trim_start_matches(|c| c == '(' || c == '=' || c == ' ')
-                    .trim_end_matches(|c| c == ')' || c == ' ')
-            };
-
-            (name, extras, version_req)
-        } else if main_req.contains('=') || main_req.contains('>') || main_req.contains('<') || main_req.contains('~') {
-            // Has version constraint
-            let mut parts = main_req.splitn(2, |c| c == '=' || c == '>' || c == '<' || c == '~');
-            let name = parts.next().unwrap().trim();
-            let version_req = parts.next().unwrap_or("*").trim();
-            (name, Vec::new(), version_req)
-        } else {
-            // Just package name
-            (main_req, Vec::new(), "*")
+        let invalid = |reason: &str| {
+            BlastError::package(format!("Invalid requirement '{}': {}", dep_str, reason))
         };
+        let input = dep_str.trim();
+
+        // Package name
+        let name_len = input
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')))
+            .unwrap_or(input.len());
+        let package = &input[..name_len];
+        if !is_valid_name(package) {
+            return Err(invalid("expected a package name"));
+        }
+        let mut rest = input[name_len..].trim_start();
+
+        // Extras
+        let mut extras = Vec::new();
+        if let Some(after) = rest.strip_prefix('[') {
+            let close = after.find(']').ok_or_else(|| invalid("unterminated extras"))?;
+            for extra in after[..close].split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                if !is_valid_name(extra) {
+                    return Err(invalid("invalid extra name"));
+                }
+                extras.push(extra.to_string());
+            }
+            rest = after[close + 1..].trim_start();
+        }
 
-        // Parse Python version constraint if present
-        let python_constraint = if parts.len() > 1 {
-            let python_req = parts[1].trim();
-            if python_req.starts_with("python_version") {
-                let version_str = python_req.split_once(|c| c == '>' || c == '<' || c == '=')
-                    .map(|(_, v)| v.trim())
-                    .unwrap_or("*");
-                Some(VersionConstraint::parse(version_str)?)
-            } else {
-                None
+        // Direct URL reference or version specifiers, then markers
+        let (version_constraint, url, marker) = if let Some(after) = rest.strip_prefix('@') {
+            let after = after.trim_start();
+            let url_len = after.find(char::is_whitespace).unwrap_or(after.len());
+            let url = &after[..url_len];
+            if url.is_empty() {
+                return Err(invalid("expected a URL after '@'"));
             }
+            let marker = after[url_len..].trim_start();
+            let marker = match marker.strip_prefix(';') {
+                Some(marker) => Some(marker),
+                None if marker.is_empty() => None,
+                None => return Err(invalid("expected ';' before markers")),
+            };
+            (VersionConstraint::any(), Some(url.to_string()), marker)
         } else {
-            None
+            let (specifiers, marker) = match rest.split_once(';') {
+                Some((specifiers, marker)) => (specifiers, Some(marker)),
+                None => (rest, None),
+            };
+            let specifiers = specifiers.trim();
+            let specifiers = match specifiers.strip_prefix('(') {
+                Some(inner) => inner.strip_suffix(')').ok_or_else(|| invalid("unbalanced parentheses"))?,
+                None => specifiers,
+            };
+            if !specifiers.trim().is_empty() && !specifiers.trim_start().starts_with(['<', '>', '=', '!', '~']) {
+                return Err(invalid("expected version specifiers"));
+            }
+            (VersionConstraint::parse(specifiers)?, None, marker)
         };
 
+        let marker = marker.map(MarkerTree::parse).transpose()?;
+
         Ok(Self {
-            package: package_name.to_string(),
-            version_constraint: VersionConstraint::parse(version_req)?,
-            python_constraint,
+            package: package.to_string(),
             extras,
+            version_constraint,
+            url,
+            marker,
         })
     }
+
+    /// Check if this dependency applies to an environment, optionally while
+    /// installing one of the requiring package's extras
+    pub fn applies_to(&self, env: &MarkerEnvironment, extra: Option<&str>) -> bool {
+        self.marker.as_ref().is_none_or(|marker| marker.evaluate(env, extra))
+    }
+}
+
+/// Check a package or extra name against PEP 508
+fn is_valid_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    let inner_valid = bytes.iter().all(|c| c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-' | b'_'));
+    match (bytes.first(), bytes.last()) {
+        (Some(first), Some(last)) => inner_valid && first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric(),
+        _ => false,
+    }
 }
 
 /// PyPI package resolver
@@ -519,7 +686,8 @@ impl Dependency {
 pub struct PyPIResolver {
     client: Client,
     package_cache: HashMap<String, PackageMetadata>,
-    import_map: HashMap<String, String>,
+    import_map: ImportMap,
+    environments: Vec<MarkerEnvironment>,
 }
 
 impl Default for PyPIResolver {
@@ -530,42 +698,23 @@ impl Default for PyPIResolver {
 
 impl PyPIResolver {
     pub fn new() -> Self {
-        let mut resolver = Self {
+        Self {
             client: Client::new(),
             package_cache: HashMap::new(),
-            import_map: HashMap::new(),
-        };
-        
-        // Add common import mappings
-        resolver.add_default_mappings();
-        resolver
-    }
-
-    fn add_default_mappings(&mut self) {
-        let default_mappings = [
-            ("numpy", "numpy"),
-            ("pandas", "pandas"),
-            ("requests", "requests"),
-            ("tensorflow", "tensorflow"),
-            ("torch", "torch"),
-            ("sklearn", "scikit-learn"),
-            ("matplotlib", "matplotlib"),
-            ("pytest", "pytest"),
-        ];
-
-        for (import_name, package_name) in default_mappings {
-            self.add_import_mapping(import_name.to_string(), package_name.to_string());
+            import_map: ImportMap::new(),
+            environments: PythonTarget::default().marker_environments(&PlatformInfo::current()),
         }
     }
 
     pub fn add_import_mapping(&mut self, import_name: String, package_name: String) {
-        debug!("Adding import mapping: {} -> {}", import_name, package_name);
-        self.import_map.insert(import_name, package_name);
+        self.import_map.learn(&import_name, &package_name);
     }
 
+    /// Get the distribution providing an imported module, or `None` if
+    /// several installed distributions do
     #[allow(dead_code)]
-    pub fn get_package_name(&self, import_name: &str) -> Option<&String> {
-        self.import_map.get(import_name)
+    pub fn get_package_name(&self, import_name: &str) -> Option<String> {
+        self.import_map.distribution_for(import_name).ok()
     }
 
     #[allow(dead_code)]
@@ -588,7 +737,7 @@ impl PyPIResolver {
         
         for import_name in imports {
             if let Some(package_name) = self.get_package_name(import_name) {
-                match client.get_package_metadata(package_name).await {
+                match client.get_package_metadata(&package_name).await {
                     Ok(package) => packages.push(package),
                     Err(_) => continue,
                 }
@@ -603,7 +752,7 @@ impl PyPIResolver {
         let mut result = HashMap::new();
         
         for dep_str in deps {
-            if let Some((name, constraint)) = parse_requirement(dep_str) {
+            if let Some((name, constraint)) = parse_requirement(dep_str, &self.environments) {
                 result.insert(name, constraint);
             }
         }
@@ -620,42 +769,8 @@ impl PyPIResolver {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-pub struct PyPIVersion(pub(crate) Version);
-
-impl std::fmt::Display for PyPIVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-impl From<Version> for PyPIVersion {
-    fn from(v: Version) -> Self {
-        PyPIVersion(v)
-    }
-}
-
-impl pubgrub::version::Version for PyPIVersion {
-    fn lowest() -> Self {
-        PyPIVersion(Version::parse("0.0.0").unwrap())
-    }
-
-    fn bump(&self) -> Self {
-        // Since we can't access Version's internals directly,
-        // we'll parse the version string and increment
-        let version_str = self.0.to_string();
-        let parts: Vec<&str> = version_str.split('.').collect();
-        if parts.len() >= 3 {
-            let major: u64 = parts[0].parse().unwrap_or(0);
-            let minor: u64 = parts[1].parse().unwrap_or(0);
-            let patch: u64 = parts[2].parse().unwrap_or(0);
-            let new_version = format!("{}.{}.{}", major, minor, patch + 1);
-            PyPIVersion(Version::parse(&new_version).unwrap())
-        } else {
-            PyPIVersion(Version::parse("0.0.1").unwrap())
-        }
-    }
-}
+/// PubGrub version type used by the PyPI client
+pub type PyPIVersion = crate::pubgrub::PubgrubVersion;
 
 #[allow(dead_code)]
 pub fn create_strategy(
@@ -0,0 +1,297 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use tracing::debug;
+use url::Url;
+
+use blast_core::error::{BlastError, BlastResult};
+
+use crate::core_metadata::{is_wheel_metadata_path, CoreMetadata};
+
+/// Bytes requested from the end of a wheel, enough to hold the central
+/// directory of most wheels
+const TAIL_SIZE: u64 = 64 * 1024;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_END_OF_DIRECTORY_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+
+/// Size of the fixed part of a local file header
+const LOCAL_HEADER_SIZE: usize = 30;
+/// Size of the fixed part of a central directory header
+const CENTRAL_HEADER_SIZE: usize = 46;
+/// Size of the end of central directory record, without comment
+const END_OF_DIRECTORY_SIZE: usize = 22;
+/// Size of the zip64 end of central directory record, without extensions
+const ZIP64_END_OF_DIRECTORY_SIZE: u64 = 56;
+/// Size of the zip64 end of central directory locator
+const ZIP64_LOCATOR_SIZE: usize = 20;
+
+/// Read the core metadata of a remote wheel with HTTP range requests
+///
+/// Only the end of the wheel, its central directory and the `METADATA`
+/// entry are fetched. A server that ignores the `Range` header answers the
+/// first request with the whole wheel, which is then read instead.
+pub async fn wheel_metadata(client: &Client, url: &Url) -> BlastResult<CoreMetadata> {
+    let mut wheel = match RemoteFile::open(client, url).await? {
+        Opened::Ranged(wheel) => wheel,
+        Opened::Whole(data) => {
+            debug!("{} does not support range requests, read the whole wheel", url);
+            return CoreMetadata::from_wheel(&data);
+        }
+    };
+
+    let (directory_offset, directory_size) = wheel.central_directory().await?;
+    let directory = wheel.read(directory_offset, directory_offset + directory_size).await?;
+    let entries = parse_central_directory(&directory)?;
+    let entry = entries.iter()
+        .find(|entry| is_wheel_metadata_path(&entry.name))
+        .ok_or_else(|| BlastError::package("Wheel does not contain a .dist-info/METADATA file"))?;
+
+    // Entries are stored back to back, so this one ends where the next
+    // begins, or at the central directory
+    let end = entries.iter()
+        .map(|other| other.local_offset)
+        .filter(|&offset| offset > entry.local_offset)
+        .min()
+        .unwrap_or(directory_offset);
+    let local = wheel.read(entry.local_offset, end).await?;
+    let content = entry.decompress(&local)?;
+    let content = String::from_utf8(content)
+        .map_err(|_| BlastError::package(format!("{} of {} is not valid UTF-8", entry.name, url)))?;
+    CoreMetadata::parse(&content)
+}
+
+/// Result of the first request for a remote file
+enum Opened<'a> {
+    /// The server answered with the requested range
+    Ranged(RemoteFile<'a>),
+    /// The server sent the whole file
+    Whole(Vec<u8>),
+}
+
+/// A remote file read piecewise with range requests
+struct RemoteFile<'a> {
+    client: &'a Client,
+    url: &'a Url,
+    /// Total size of the file
+    len: u64,
+    /// Ranges fetched so far, by start offset
+    chunks: Vec<(u64, Vec<u8>)>,
+}
+
+impl<'a> RemoteFile<'a> {
+    /// Fetch the end of a file
+    async fn open(client: &'a Client, url: &'a Url) -> BlastResult<Opened<'a>> {
+        let response = client.get(url.clone())
+            .header(RANGE, format!("bytes=-{}", TAIL_SIZE))
+            .send()
+            .await
+            .map_err(|e| BlastError::network(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let data = response.bytes().await.map_err(|e| BlastError::network(e.to_string()))?;
+                Ok(Opened::Whole(data.to_vec()))
+            }
+            StatusCode::PARTIAL_CONTENT => {
+                let len = response.headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.rsplit_once('/'))
+                    .and_then(|(_, len)| len.parse::<u64>().ok())
+                    .ok_or_else(|| BlastError::network(format!("Missing or invalid Content-Range from {}", url)))?;
+                let tail = response.bytes().await.map_err(|e| BlastError::network(e.to_string()))?.to_vec();
+                if tail.len() as u64 > len {
+                    return Err(BlastError::network(format!("Invalid range response from {}", url)));
+                }
+                Ok(Opened::Ranged(Self {
+                    client,
+                    url,
+                    len,
+                    chunks: vec![(len - tail.len() as u64, tail)],
+                }))
+            }
+            status => Err(BlastError::network(format!("Failed to fetch {} (status: {})", url, status))),
+        }
+    }
+
+    /// Get the bytes from `start` up to `end`, fetching them unless an
+    /// earlier request already did
+    async fn read(&mut self, start: u64, end: u64) -> BlastResult<Vec<u8>> {
+        if start > end || end > self.len {
+            return Err(self.invalid("entry outside of the archive"));
+        }
+        for (offset, chunk) in &self.chunks {
+            if *offset <= start && end <= offset + chunk.len() as u64 {
+                return Ok(chunk[(start - offset) as usize..(end - offset) as usize].to_vec());
+            }
+        }
+        if start == end {
+            return Ok(Vec::new());
+        }
+
+        debug!("Fetching bytes {}-{} of {}", start, end - 1, self.url);
+        let response = self.client.get(self.url.clone())
+            .header(RANGE, format!("bytes={}-{}", start, end - 1))
+            .send()
+            .await
+            .map_err(|e| BlastError::network(e.to_string()))?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(BlastError::network(format!(
+                "Range request for {} failed (status: {})",
+                self.url,
+                response.status()
+            )));
+        }
+        let data = response.bytes().await.map_err(|e| BlastError::network(e.to_string()))?.to_vec();
+        if data.len() as u64 != end - start {
+            return Err(BlastError::network(format!("Invalid range response from {}", self.url)));
+        }
+        self.chunks.push((start, data.clone()));
+        Ok(data)
+    }
+
+    /// Locate the central directory from the end of central directory
+    /// record, returning its offset and size
+    async fn central_directory(&mut self) -> BlastResult<(u64, u64)> {
+        let (tail_start, tail) = self.chunks.first().cloned().unwrap_or_default();
+        let record = (0..=tail.len().saturating_sub(END_OF_DIRECTORY_SIZE))
+            .rev()
+            .find(|&pos| read_u32(&tail, pos) == Some(END_OF_DIRECTORY_SIGNATURE))
+            .ok_or_else(|| self.invalid("no end of central directory record"))?;
+
+        let size = read_u32(&tail, record + 12).unwrap_or_default();
+        let offset = read_u32(&tail, record + 16).unwrap_or_default();
+        if size != u32::MAX && offset != u32::MAX {
+            return Ok((u64::from(offset), u64::from(size)));
+        }
+
+        // Zip64 archives point to a larger record holding the real values
+        let locator = record.checked_sub(ZIP64_LOCATOR_SIZE)
+            .filter(|&pos| read_u32(&tail, pos) == Some(ZIP64_LOCATOR_SIGNATURE))
+            .and_then(|pos| read_u64(&tail, pos + 8))
+            .ok_or_else(|| self.invalid("missing zip64 end of central directory locator"))?;
+        let end = (locator + ZIP64_END_OF_DIRECTORY_SIZE).min(tail_start + record as u64);
+        let zip64 = self.read(locator, end).await?;
+        if read_u32(&zip64, 0) != Some(ZIP64_END_OF_DIRECTORY_SIGNATURE) {
+            return Err(self.invalid("invalid zip64 end of central directory record"));
+        }
+        match (read_u64(&zip64, 48), read_u64(&zip64, 40)) {
+            (Some(offset), Some(size)) => Ok((offset, size)),
+            _ => Err(self.invalid("truncated zip64 end of central directory record")),
+        }
+    }
+
+    fn invalid(&self, reason: &str) -> BlastError {
+        BlastError::package(format!("Invalid wheel archive {}: {}", self.url, reason))
+    }
+}
+
+/// A file listed in the central directory
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    /// Compression method, 0 (stored) or 8 (deflate) in wheels
+    method: u16,
+    compressed_size: u64,
+    /// Offset of the entry's local header
+    local_offset: u64,
+}
+
+impl Entry {
+    /// Extract the entry's contents from the bytes starting at its local header
+    fn decompress(&self, local: &[u8]) -> BlastResult<Vec<u8>> {
+        let invalid = || BlastError::package(format!("Invalid local header for {}", self.name));
+        if read_u32(local, 0) != Some(LOCAL_HEADER_SIGNATURE) {
+            return Err(invalid());
+        }
+        let name_len = read_u16(local, 26).ok_or_else(invalid)? as usize;
+        let extra_len = read_u16(local, 28).ok_or_else(invalid)? as usize;
+        let start = LOCAL_HEADER_SIZE + name_len + extra_len;
+        let data = usize::try_from(self.compressed_size).ok()
+            .and_then(|size| local.get(start..start.checked_add(size)?))
+            .ok_or_else(invalid)?;
+
+        match self.method {
+            0 => Ok(data.to_vec()),
+            8 => {
+                let mut content = Vec::new();
+                DeflateDecoder::new(data).read_to_end(&mut content)?;
+                Ok(content)
+            }
+            method => Err(BlastError::package(format!(
+                "Unsupported compression method {} for {}",
+                method, self.name
+            ))),
+        }
+    }
+}
+
+/// Parse the headers of a central directory
+fn parse_central_directory(directory: &[u8]) -> BlastResult<Vec<Entry>> {
+    let invalid = || BlastError::package("Invalid wheel central directory");
+    let mut entries = Vec::new();
+    let mut pos = 0;
+
+    while read_u32(directory, pos) == Some(CENTRAL_HEADER_SIGNATURE) {
+        let header = directory.get(pos..pos + CENTRAL_HEADER_SIZE).ok_or_else(invalid)?;
+        let method = read_u16(header, 10).ok_or_else(invalid)?;
+        let name_len = read_u16(header, 28).ok_or_else(invalid)? as usize;
+        let extra_len = read_u16(header, 30).ok_or_else(invalid)? as usize;
+        let comment_len = read_u16(header, 32).ok_or_else(invalid)? as usize;
+
+        let name_start = pos + CENTRAL_HEADER_SIZE;
+        let name = directory.get(name_start..name_start + name_len).ok_or_else(invalid)?;
+        let extra = directory.get(name_start + name_len..name_start + name_len + extra_len).ok_or_else(invalid)?;
+
+        // Sizes and offsets too large for 32 bits move to the zip64 extra
+        // field, in this order
+        let mut zip64 = zip64_values(extra).into_iter();
+        let mut value = |offset: usize| match read_u32(header, offset) {
+            Some(u32::MAX) => zip64.next(),
+            value => value.map(u64::from),
+        };
+        let _uncompressed_size = value(24);
+        let compressed_size = value(20).ok_or_else(invalid)?;
+        let local_offset = value(42).ok_or_else(invalid)?;
+
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method,
+            compressed_size,
+            local_offset,
+        });
+        pos = name_start + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Values of the zip64 extended information extra field
+fn zip64_values(extra: &[u8]) -> Vec<u64> {
+    let mut pos = 0;
+    while let (Some(id), Some(len)) = (read_u16(extra, pos), read_u16(extra, pos + 2)) {
+        let data = extra.get(pos + 4..pos + 4 + len as usize).unwrap_or_default();
+        if id == 0x0001 {
+            return (0..data.len() / 8).filter_map(|i| read_u64(data, i * 8)).collect();
+        }
+        pos += 4 + len as usize;
+    }
+    Vec::new()
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(pos..pos + 8)?.try_into().ok()?))
+}
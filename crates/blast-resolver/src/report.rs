@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use pubgrub::range::Range;
+use pubgrub::report::{DerivationTree, Derived, External};
+use pubgrub::term::Term;
+use pubgrub::type_aliases::Map;
+
+use blast_core::diagnostics::{Diagnostic, DiagnosticCategory, DiagnosticLevel, DiagnosticSuggestion};
+
+use crate::pubgrub::{range_to_string, PubgrubVersion};
+
+type Tree = DerivationTree<String, PubgrubVersion>;
+
+/// Why the solver was told the releases of a package are unavailable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Exclusion {
+    /// Its requirements conflict with this configured constraint
+    Constraint(String),
+    /// Its Requires-Python does not admit the target interpreters
+    RequiresPython {
+        /// Python versions the release supports
+        requires: String,
+        /// Interpreters resolved for
+        target: String,
+    },
+}
+
+/// Turn the derivation tree of a failed resolution into a diagnostic
+///
+/// The details explain the conflict step by step, in the style of
+/// "Because a depends on b>=2 and c depends on b<2, a and c are
+/// incompatible.", and the suggestions name the constraints worth relaxing.
+///
+/// `excluded` maps packages whose releases were made unavailable to the
+/// reason, a conflicting constraint or their Requires-Python.
+pub(crate) fn conflict_diagnostic(
+    root: &str,
+    root_version: &PubgrubVersion,
+    tree: &Tree,
+    excluded: &HashMap<String, Exclusion>,
+) -> Diagnostic {
+    let mut tree = tree.clone();
+    collapse_no_versions(&mut tree);
+    let tree = &tree;
+
+    let mut report = Report::new(root, excluded);
+    match tree {
+        DerivationTree::External(external) => {
+            report.lines.push(format!("Because {}, version solving failed.", report.external(external)));
+        }
+        DerivationTree::Derived(derived) => report.explain(derived),
+    }
+
+    let mut diagnostic = Diagnostic::new(
+        DiagnosticLevel::Error,
+        format!("No solution found for {}", root),
+        DiagnosticCategory::Dependency,
+    )
+    .with_details(report.lines.join("\n"))
+    .with_operation_context(format!("{}=={}", root, root_version));
+
+    for description in report.suggestions(tree) {
+        diagnostic = diagnostic.with_suggestion(DiagnosticSuggestion {
+            description,
+            fix: None,
+            context: None,
+            estimated_time: None,
+            auto_fixable: false,
+        });
+    }
+    diagnostic
+}
+
+/// Explanation being built for a derivation tree
+struct Report<'a> {
+    root: &'a str,
+    excluded: &'a HashMap<String, Exclusion>,
+    lines: Vec<String>,
+    /// Line references of shared incompatibilities already explained
+    refs: HashMap<usize, usize>,
+    next_ref: usize,
+}
+
+impl<'a> Report<'a> {
+    fn new(root: &'a str, excluded: &'a HashMap<String, Exclusion>) -> Self {
+        Self {
+            root,
+            excluded,
+            lines: Vec::new(),
+            refs: HashMap::new(),
+            next_ref: 1,
+        }
+    }
+
+    /// Explain a derived incompatibility, ending on a line concluding its terms
+    fn explain(&mut self, derived: &Derived<String, PubgrubVersion>) {
+        let conclusion = self.terms(&derived.terms);
+        match (&*derived.cause1, &*derived.cause2) {
+            (DerivationTree::External(first), DerivationTree::External(second)) => {
+                self.lines.push(format!(
+                    "Because {} and {}, {}.",
+                    self.external(first),
+                    self.external(second),
+                    conclusion
+                ));
+            }
+            (DerivationTree::Derived(cause), DerivationTree::External(external))
+            | (DerivationTree::External(external), DerivationTree::Derived(cause)) => {
+                if let Some(line) = cause.shared_id.and_then(|id| self.refs.get(&id)) {
+                    self.lines.push(format!(
+                        "Because {} and {} ({}), {}.",
+                        self.external(external),
+                        self.terms(&cause.terms),
+                        line,
+                        conclusion
+                    ));
+                } else {
+                    self.explain(cause);
+                    if cause.shared_id.is_some() {
+                        self.reference(cause);
+                    }
+                    self.lines.push(format!("And because {}, {}.", self.external(external), conclusion));
+                }
+            }
+            (DerivationTree::Derived(first), DerivationTree::Derived(second)) => {
+                let first_line = self.explained(first);
+                let second_line = self.explained(second);
+                self.lines.push(format!(
+                    "And because {} ({}) and {} ({}), {}.",
+                    self.terms(&first.terms),
+                    first_line,
+                    self.terms(&second.terms),
+                    second_line,
+                    conclusion
+                ));
+            }
+        }
+    }
+
+    /// Explain a derived incompatibility unless it already was, returning
+    /// the number of the line concluding it
+    fn explained(&mut self, derived: &Derived<String, PubgrubVersion>) -> usize {
+        if let Some(&line) = derived.shared_id.and_then(|id| self.refs.get(&id)) {
+            return line;
+        }
+        self.explain(derived);
+        self.reference(derived)
+    }
+
+    /// Number the last line so later lines can refer back to it
+    fn reference(&mut self, derived: &Derived<String, PubgrubVersion>) -> usize {
+        let line = self.next_ref;
+        self.next_ref += 1;
+        if let Some(last) = self.lines.last_mut() {
+            last.push_str(&format!(" ({})", line));
+        }
+        if let Some(id) = derived.shared_id {
+            self.refs.insert(id, line);
+        }
+        line
+    }
+
+    fn external(&self, external: &External<String, PubgrubVersion>) -> String {
+        match external {
+            External::NotRoot(package, version) => {
+                format!("{} {} is the package being resolved", package, version)
+            }
+            External::NoVersions(package, range) if *range == Range::any() => {
+                format!("there are no versions of {}", package)
+            }
+            External::NoVersions(package, range) => {
+                format!("no versions of {} match {}", package, range_to_string(range))
+            }
+            External::UnavailableDependencies(package, range) => match self.excluded.get(package) {
+                Some(Exclusion::Constraint(constraint)) => format!(
+                    "the requirements of {} conflict with the constraint {}",
+                    self.package(package, range),
+                    constraint
+                ),
+                Some(Exclusion::RequiresPython { requires, target }) => format!(
+                    "{} requires Python {}, which excludes the target Python {}",
+                    self.package(package, range),
+                    requires,
+                    target
+                ),
+                None => format!("the dependencies of {} could not be determined", self.package(package, range)),
+            },
+            External::FromDependencyOf(package, range, dependency, dependency_range) => format!(
+                "{} depends on {}",
+                self.package(package, range),
+                self.package(dependency, dependency_range)
+            ),
+        }
+    }
+
+    fn terms(&self, terms: &Map<String, Term<PubgrubVersion>>) -> String {
+        let mut terms: Vec<(&String, &Term<PubgrubVersion>)> = terms.iter().collect();
+        terms.sort_by_key(|(package, _)| (package.as_str() != self.root, package.as_str()));
+
+        match terms.as_slice() {
+            [] => "version solving failed".to_string(),
+            [(package, Term::Positive(_))] if package.as_str() == self.root => {
+                format!("the requirements of {} cannot be satisfied", self.root)
+            }
+            [(package, Term::Positive(range))] => format!("{} cannot be used", self.package(package, range)),
+            [(package, Term::Negative(range))] => format!("{} is required", self.package(package, range)),
+            [(package, Term::Positive(range)), (dependency, Term::Negative(dependency_range))]
+            | [(dependency, Term::Negative(dependency_range)), (package, Term::Positive(range))] => format!(
+                "{} depends on {}",
+                self.package(package, range),
+                self.package(dependency, dependency_range)
+            ),
+            _ => {
+                let mut names: Vec<String> = terms.iter()
+                    .map(|(package, term)| match term {
+                        Term::Positive(range) => self.package(package, range),
+                        Term::Negative(range) => format!("not {}", self.package(package, range)),
+                    })
+                    .collect();
+                let last = names.pop().unwrap_or_default();
+                format!("{} and {} are incompatible", names.join(", "), last)
+            }
+        }
+    }
+
+    /// Format a package with its range; the root is named on its own
+    fn package(&self, package: &str, range: &Range<PubgrubVersion>) -> String {
+        if package == self.root {
+            return package.to_string();
+        }
+        match range_to_string(range).as_str() {
+            "*" => package.to_string(),
+            specifiers => format!("{}{}", package, specifiers),
+        }
+    }
+
+    /// Suggest fixes for the external causes of a conflict, the root's own
+    /// requirements first
+    fn suggestions(&self, tree: &Tree) -> Vec<String> {
+        let mut externals = Vec::new();
+        collect_externals(tree, &mut externals);
+
+        let mut suggestions: Vec<(bool, String)> = Vec::new();
+        for external in &externals {
+            let suggestion = match external {
+                External::FromDependencyOf(package, _, dependency, range) if package == self.root => (
+                    true,
+                    format!("Relax the requirement {} of {}", self.package(dependency, range), self.root),
+                ),
+                External::FromDependencyOf(package, range, dependency, dependency_range) => {
+                    let required = self.package(dependency, dependency_range);
+                    if *range == Range::any() {
+                        (false, format!("Every version of {} requires {}; consider replacing {}", package, required, package))
+                    } else {
+                        (false, format!(
+                            "{} requires {}; allowing another version of {} may avoid the conflict",
+                            self.package(package, range),
+                            required,
+                            package
+                        ))
+                    }
+                }
+                // Only ranges that were actually asked for are worth
+                // mentioning, not the ones the solver explored
+                External::NoVersions(package, range) if !is_requested(&externals, package, range) => continue,
+                External::NoVersions(package, range) if *range == Range::any() => (
+                    false,
+                    format!("Check that {} is spelled correctly and published on the configured package sources", package),
+                ),
+                External::NoVersions(package, range) => (
+                    false,
+                    format!(
+                        "No version of {} matches {}; relax that requirement or add a package source providing one",
+                        package,
+                        range_to_string(range)
+                    ),
+                ),
+                External::UnavailableDependencies(package, range) => match self.excluded.get(package) {
+                    Some(Exclusion::Constraint(constraint)) => (true, format!("Relax the constraint {}", constraint)),
+                    Some(Exclusion::RequiresPython { target, .. }) => (
+                        false,
+                        format!("Allow a release of {} that supports Python {}, or target another Python", package, target),
+                    ),
+                    None => (
+                        false,
+                        format!("Check that the metadata of {} can be fetched", self.package(package, range)),
+                    ),
+                },
+                External::NotRoot(..) => continue,
+            };
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+        }
+
+        suggestions.sort_by_key(|(root, _)| !root);
+        suggestions.into_iter().map(|(_, description)| description).collect()
+    }
+}
+
+fn collect_externals<'t>(tree: &'t Tree, externals: &mut Vec<&'t External<String, PubgrubVersion>>) {
+    match tree {
+        DerivationTree::External(external) => externals.push(external),
+        DerivationTree::Derived(derived) => {
+            collect_externals(&derived.cause1, externals);
+            collect_externals(&derived.cause2, externals);
+        }
+    }
+}
+
+/// Check if some package depends on `package` within `range`
+fn is_requested(externals: &[&External<String, PubgrubVersion>], package: &str, range: &Range<PubgrubVersion>) -> bool {
+    externals.iter().any(|external| match external {
+        External::FromDependencyOf(_, _, dependency, dependency_range) => {
+            dependency == package && dependency_range.intersection(range) == *dependency_range
+        }
+        _ => false,
+    })
+}
+
+/// Fold "no versions of p match r" into a dependency of p next to it, so
+/// "c==1.0 depends on b<2" and "no versions of c match >1.0" read as
+/// "c>=1.0 depends on b<2"
+fn collapse_no_versions(tree: &mut Tree) {
+    let DerivationTree::Derived(derived) = tree else {
+        return;
+    };
+    collapse_no_versions(&mut derived.cause1);
+    collapse_no_versions(&mut derived.cause2);
+
+    let merged = match (&*derived.cause1, &*derived.cause2) {
+        (
+            DerivationTree::External(External::NoVersions(package, range)),
+            DerivationTree::External(External::FromDependencyOf(dependent, dependent_range, dependency, dependency_range)),
+        )
+        | (
+            DerivationTree::External(External::FromDependencyOf(dependent, dependent_range, dependency, dependency_range)),
+            DerivationTree::External(External::NoVersions(package, range)),
+        ) if package == dependent => External::FromDependencyOf(
+            dependent.clone(),
+            dependent_range.union(range),
+            dependency.clone(),
+            dependency_range.clone(),
+        ),
+        _ => return,
+    };
+    *tree = DerivationTree::External(merged);
+}
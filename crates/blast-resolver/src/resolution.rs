@@ -1,9 +1,13 @@
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
-use blast_core::error::BlastResult;
+use blast_core::error::{BlastError, BlastResult};
 use blast_core::package::Package;
+use blast_core::version::VersionConstraint;
 
 use crate::cache::Cache;
 use crate::pypi::PyPIClient;
@@ -17,6 +21,46 @@ pub struct ResolutionResult {
     pub graph: Option<ResolutionGraph>,
     /// Resolution metrics
     pub metrics: ResolutionMetrics,
+    /// Constraints and overrides that changed requirements of resolved packages
+    pub applied: Vec<AppliedRequirement>,
+}
+
+/// Way a configured requirement changes what a package declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequirementKind {
+    /// Narrowed by a constraint
+    Constraint,
+    /// Replaced by an override
+    Override,
+}
+
+/// A constraint or override applied to a declared requirement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedRequirement {
+    /// Whether the requirement was narrowed or replaced
+    pub kind: RequirementKind,
+    /// Package declaring the requirement
+    pub package: String,
+    /// Package being required
+    pub dependency: String,
+    /// Requirement as declared by `package`
+    pub declared: VersionConstraint,
+    /// Configured constraint or override
+    pub applied: VersionConstraint,
+}
+
+impl fmt::Display for AppliedRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self.kind {
+            RequirementKind::Constraint => "constrained",
+            RequirementKind::Override => "overridden",
+        };
+        write!(
+            f,
+            "{} requires {} {}, {} by {}",
+            self.package, self.dependency, self.declared, verb, self.applied
+        )
+    }
 }
 
 /// Dependency resolution graph
@@ -69,6 +113,62 @@ pub struct ResolutionMetrics {
     pub conflicts_resolved: usize,
 }
 
+/// Which version of a package the resolver tries first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolutionMode {
+    /// Prefer the highest compatible version of every package
+    #[default]
+    Highest,
+    /// Prefer the lowest compatible version of every package
+    Lowest,
+    /// Prefer the lowest compatible version of direct dependencies and the
+    /// highest of everything else
+    LowestDirect,
+}
+
+impl ResolutionMode {
+    /// Get the name used in configuration and on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Highest => "highest",
+            Self::Lowest => "lowest",
+            Self::LowestDirect => "lowest-direct",
+        }
+    }
+
+    /// Check if the lowest version is preferred for a package
+    pub fn prefers_lowest(&self, direct: bool) -> bool {
+        match self {
+            Self::Highest => false,
+            Self::Lowest => true,
+            Self::LowestDirect => direct,
+        }
+    }
+}
+
+impl fmt::Display for ResolutionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResolutionMode {
+    type Err = BlastError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "highest" => Ok(Self::Highest),
+            "lowest" => Ok(Self::Lowest),
+            "lowest-direct" => Ok(Self::LowestDirect),
+            _ => Err(BlastError::config(format!(
+                "Unknown resolution mode {} (expected highest, lowest or lowest-direct)",
+                s
+            ))),
+        }
+    }
+}
+
 /// Strategy for resolving dependencies
 #[async_trait]
 pub trait ResolutionStrategy: Send + Sync {
@@ -1,25 +1,44 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use std::borrow::Borrow;
 use std::error::Error as StdError;
 use rustc_hash::FxHashMap;
 
+use pubgrub::error::PubGrubError;
 use pubgrub::range::Range;
 use pubgrub::solver::{Dependencies, DependencyProvider};
-use pubgrub::version::Version as PubgrubVersionTrait;
+use tokio::runtime::Handle;
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, warn};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use blast_core::error::{BlastError, BlastResult};
+use blast_core::hot_reload::ImportResolver;
 use blast_core::package::{Package, PackageId};
-use blast_core::version::Version;
+use blast_core::version::{Version, VersionConstraint};
 use blast_core::security::{PackageVerification, PolicyResult, SecurityPolicy, VerificationResult, Vulnerability};
+use blast_cache::{ArtifactCache, CachedArtifact};
 
 use crate::cache::Cache;
+use crate::core_metadata::split_extra;
+use crate::filename::normalize_name;
+use crate::imports::ImportMap;
 use crate::pypi::PyPIClient;
-use crate::resolution::{ResolutionStrategy, ResolutionResult};
+use crate::lockfile::{constraint_map, LockTarget, LockedArtifact, Lockfile};
+use crate::target::PythonTarget;
+use crate::prefetch::Prefetcher;
+use crate::report::{conflict_diagnostic, Exclusion};
+use crate::resolution::{
+    AppliedRequirement, DependencyKind, DependencyRelationship, ResolutionGraph, ResolutionMetrics, ResolutionMode,
+    ResolutionResult, ResolutionStrategy, RequirementKind,
+};
+use crate::source::{PackageSource, PackageSourceChain, YankedRelease};
+
+pub use crate::pubgrub::{constraint_to_range, range_to_string, PubgrubVersion};
+
+/// Default limit of metadata requests in flight during a resolution
+const DEFAULT_CONCURRENT_REQUESTS: usize = 10;
 
 /// Dependency resolver for Python packages
 pub struct DependencyResolver {
@@ -27,22 +46,178 @@ pub struct DependencyResolver {
     cache: Arc<RwLock<Cache>>,
     resolution_cache: Arc<RwLock<HashMap<PackageId, Vec<Package>>>>,
     resolution_strategy: PubGrubProvider,
+    /// Upload-time cutoff the sources were configured with
+    exclude_newer: Option<DateTime<Utc>>,
+    /// Interpreters and platform the sources were configured for
+    python: PythonTarget,
+    /// Distributions providing imported modules
+    imports: Arc<RwLock<ImportMap>>,
 }
 
 impl DependencyResolver {
     /// Create a new resolver
     pub fn new(pypi: PyPIClient, cache: Cache) -> Self {
+        let mut sources = PackageSourceChain::new();
+        sources.add_source(Box::new(pypi.clone()));
+        Self::with_sources(pypi, sources, cache)
+    }
+
+    /// Create a resolver that looks packages up in the given sources
+    pub fn with_sources(pypi: PyPIClient, sources: PackageSourceChain, cache: Cache) -> Self {
         Self {
-            pypi: pypi.clone(),
+            pypi,
             cache: Arc::new(RwLock::new(cache)),
             resolution_cache: Arc::new(RwLock::new(HashMap::new())),
-            resolution_strategy: PubGrubProvider::new(pypi),
+            resolution_strategy: PubGrubProvider::new(sources),
+            exclude_newer: None,
+            python: PythonTarget::default(),
+            imports: Arc::new(RwLock::new(ImportMap::new())),
+        }
+    }
+
+    /// Look a package up only in the given source, ignoring all others
+    pub fn with_package_index(mut self, package: &str, source: Arc<dyn PackageSource>) -> Self {
+        self.resolution_strategy.indexes.insert(normalize_name(package), source);
+        self
+    }
+
+    /// Report lookups that fail as missing from the local caches and sources
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.resolution_strategy.offline = offline;
+        self
+    }
+
+    /// Limit the versions of packages whenever they are required, without
+    /// adding them to the resolution (pip `-c` semantics)
+    pub fn with_constraints(mut self, constraints: impl IntoIterator<Item = (String, VersionConstraint)>) -> Self {
+        self.resolution_strategy.constraints
+            .extend(constraints.into_iter().map(|(name, constraint)| (normalize_name(&name), constraint)));
+        self
+    }
+
+    /// Replace the requirements every package declares on the given
+    /// packages, e.g. to force `urllib3<2` against a dependency's wishes
+    pub fn with_overrides(mut self, overrides: impl IntoIterator<Item = (String, VersionConstraint)>) -> Self {
+        self.resolution_strategy.overrides
+            .extend(overrides.into_iter().map(|(name, constraint)| (normalize_name(&name), constraint)));
+        self
+    }
+
+    /// Consider pre-releases of every package, not only of those whose
+    /// requirements name one or that have no final release to offer
+    pub fn with_prereleases(mut self, allow_prereleases: bool) -> Self {
+        self.resolution_strategy.allow_prereleases = allow_prereleases;
+        self
+    }
+
+    /// Prefer the highest or lowest compatible versions
+    pub fn with_mode(mut self, mode: ResolutionMode) -> Self {
+        self.resolution_strategy.mode = mode;
+        self
+    }
+
+    /// Get the version preference of the resolver
+    pub fn mode(&self) -> ResolutionMode {
+        self.resolution_strategy.mode
+    }
+
+    /// Limit how many metadata requests a resolution keeps in flight
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.resolution_strategy.max_concurrent_requests = max_concurrent_requests;
+        self
+    }
+
+    /// Get the metrics of the last resolution
+    pub fn metrics(&self) -> ResolutionMetrics {
+        self.resolution_strategy.get_metrics()
+    }
+
+    /// Record the upload-time cutoff applied by the sources, so locks
+    /// made by this resolver can be reproduced
+    ///
+    /// The cutoff itself is enforced by the sources, see
+    /// [`PyPIClient::with_exclude_newer`] and
+    /// [`SimpleIndexSource::with_exclude_newer`](crate::SimpleIndexSource::with_exclude_newer).
+    pub fn with_exclude_newer(mut self, cutoff: Option<DateTime<Utc>>) -> Self {
+        self.exclude_newer = cutoff;
+        self
+    }
+
+    /// Get the upload-time cutoff of the resolver
+    pub fn exclude_newer(&self) -> Option<DateTime<Utc>> {
+        self.exclude_newer
+    }
+
+    /// Record the interpreters and platform the sources select files for,
+    /// so locks made by this resolver say what they can be installed on
+    ///
+    /// The files themselves are selected by the sources, see
+    /// [`PyPIClient::with_python_target`], but releases whose Requires-Python
+    /// excludes the target are skipped whichever source offers them.
+    pub fn with_python_target(mut self, python: PythonTarget) -> Self {
+        self.resolution_strategy.python = python.clone();
+        self.python = python;
+        self
+    }
+
+    /// Map imported modules to distributions with the given mappings
+    pub fn with_import_map(mut self, imports: ImportMap) -> Self {
+        self.imports = Arc::new(RwLock::new(imports));
+        self
+    }
+
+    /// Check if the resolver runs without network access
+    pub fn is_offline(&self) -> bool {
+        self.resolution_strategy.offline
+    }
+
+    /// Get a distribution file for each package, downloading into the
+    /// artifact cache what it does not already hold
+    ///
+    /// In offline mode nothing is downloaded from the network; every package
+    /// that is neither cached nor in a local source is reported at once.
+    pub async fn fetch_artifacts(&self, packages: &[Package], artifacts: &ArtifactCache) -> BlastResult<Vec<CachedArtifact>> {
+        let mut fetched = Vec::new();
+        let mut missing = Vec::new();
+
+        for package in packages {
+            let version = package.version().to_string();
+            if let Some(artifact) = artifacts.get(package.name(), &version).await? {
+                debug!("Using cached artifact {}", artifact.filename);
+                fetched.push(artifact);
+                continue;
+            }
+
+            match self.resolution_strategy.source_for(package.name()).download_artifact(package.id()).await {
+                Ok(artifact) => {
+                    fetched.push(artifacts.store(package.name(), &version, &artifact.filename, &artifact.data).await?);
+                }
+                Err(e) if self.is_offline() => {
+                    debug!("No artifact for {}: {}", package.id(), e);
+                    missing.push(package.id().to_string());
+                }
+                Err(e) => return Err(e),
+            }
         }
+
+        if !missing.is_empty() {
+            return Err(BlastError::package(format!(
+                "Cannot install offline, missing from the artifact cache and local sources: {}",
+                missing.join(", ")
+            )));
+        }
+        Ok(fetched)
     }
 
     /// Get available versions for a package
     pub async fn get_package_versions(&self, name: &str) -> BlastResult<Vec<Version>> {
-        self.pypi.get_package_versions(name).await
+        self.resolution_strategy.source_for(name).get_versions(name).await
+    }
+
+    /// Look up whether a release has been yanked from its source
+    pub async fn yanked_release(&self, name: &str, version: &Version) -> BlastResult<Option<YankedRelease>> {
+        let yanked = self.resolution_strategy.source_for(name).get_yanked_versions(name).await?;
+        Ok(yanked.into_iter().find(|release| release.version == *version))
     }
 
     /// Resolve dependencies for a package
@@ -63,6 +238,54 @@ impl DependencyResolver {
         Ok(packages)
     }
 
+    /// Resolve the dependencies declared by a project
+    ///
+    /// Unlike [`resolve`](Self::resolve), the root is not looked up in any
+    /// source; its metadata's dependencies are the requirements.
+    pub async fn resolve_project(&self, project: &Package) -> BlastResult<ResolutionResult> {
+        self.resolution_strategy.solve(project, &self.cache, true).await
+    }
+
+    /// Resolve a project and record the result, with the files of every
+    /// package, as a lockfile
+    pub async fn lock(&self, project: &Package) -> BlastResult<Lockfile> {
+        let result = self.resolve_project(project).await?;
+        self.lock_resolution(project, &result).await
+    }
+
+    /// Record a project resolution, with the files of every package, as a
+    /// lockfile
+    pub async fn lock_resolution(&self, project: &Package, result: &ResolutionResult) -> BlastResult<Lockfile> {
+        let mut lockfile = Lockfile::from_resolution(project, result);
+        lockfile.resolution = self.mode();
+        lockfile.exclude_newer = self.exclude_newer;
+        lockfile.target = Some(LockTarget::new(&self.python));
+        lockfile.constraints = constraint_map(&self.resolution_strategy.constraints);
+        lockfile.overrides = constraint_map(&self.resolution_strategy.overrides);
+
+        for locked in &mut lockfile.packages {
+            let id = PackageId::new(locked.name.clone(), Version::parse(&locked.version)?);
+            let found = match self.resolution_strategy.indexes.get(&normalize_name(&locked.name)) {
+                Some(index) => {
+                    if let Some(reference) = index.direct_reference(&id).await? {
+                        locked.direct = Some(reference.source);
+                        locked.commit = reference.commit;
+                    }
+                    index.list_artifacts(&id).await.ok().map(|files| (index.name().to_string(), files))
+                }
+                None => self.resolution_strategy.sources.find_artifacts(&id).await,
+            };
+            match found {
+                Some((source, artifacts)) => {
+                    locked.source = source;
+                    locked.artifacts = artifacts.into_iter().map(LockedArtifact::from).collect();
+                }
+                None => warn!("No distribution files found for {}", id),
+            }
+        }
+        Ok(lockfile)
+    }
+
     /// Clear resolution cache
     pub async fn clear_cache(&self) {
         self.resolution_cache.write().await.clear();
@@ -85,12 +308,39 @@ impl DependencyResolver {
         Ok(resolved)
     }
 
+    /// Get the latest release of the distribution providing an imported
+    /// module
+    ///
+    /// Fails if several installed distributions provide the module, rather
+    /// than guessing which one is meant.
     pub async fn resolve_import(&self, import_name: &str) -> BlastResult<Option<Package>> {
-        self.pypi.resolve_import(import_name).await
+        let imports = self.imports.read().await;
+        self.pypi.resolve_import(import_name, &imports).await
     }
 
+    /// Check if the distribution providing an imported module is available
     pub async fn is_available(&self, import_name: &str) -> bool {
-        self.pypi.is_available(import_name).await
+        let imports = self.imports.read().await;
+        self.pypi.is_available(import_name, &imports).await
+    }
+
+    /// Remember that installing `distribution` provided an imported module,
+    /// saving the mapping to the cache
+    pub async fn learn_import(&self, import_name: &str, distribution: &str) -> BlastResult<()> {
+        let mut imports = self.imports.write().await;
+        imports.learn(import_name, distribution);
+        imports.save()
+    }
+}
+
+#[async_trait]
+impl ImportResolver for DependencyResolver {
+    async fn distribution_for(&self, import_name: &str) -> BlastResult<String> {
+        self.imports.read().await.distribution_for(import_name)
+    }
+
+    async fn learn_import(&self, import_name: &str, distribution: &str) -> BlastResult<()> {
+        DependencyResolver::learn_import(self, import_name, distribution).await
     }
 }
 
@@ -121,115 +371,414 @@ impl PackageVerification for DependencyResolver {
 }
 
 /// PubGrub dependency provider
-struct PubGrubProvider {
-    pypi: PyPIClient,
+#[derive(Clone)]
+pub(crate) struct PubGrubProvider {
+    sources: PackageSourceChain,
+    /// Sources pinned to individual packages, keyed by normalized name
+    indexes: HashMap<String, Arc<dyn PackageSource>>,
+    /// Whether failed lookups are reported as missing offline data
+    offline: bool,
+    /// Version preference when choosing among compatible releases
+    mode: ResolutionMode,
+    /// Version limits by normalized name
+    constraints: HashMap<String, VersionConstraint>,
+    /// Replacement requirements by normalized name
+    overrides: HashMap<String, VersionConstraint>,
+    /// Whether pre-releases are candidates even when nothing asks for them
+    allow_prereleases: bool,
+    /// Interpreters every release's Requires-Python must admit
+    python: PythonTarget,
+    /// Limit of metadata requests in flight at once
+    pub(crate) max_concurrent_requests: usize,
+    /// Metrics of the last resolution
+    metrics: Arc<Mutex<ResolutionMetrics>>,
 }
 
-/// Wrapper type for Version to implement PubgrubVersion
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub struct PubgrubVersion(pub(crate) Version);
+impl PubGrubProvider {
+    pub fn new(sources: PackageSourceChain) -> Self {
+        Self {
+            sources,
+            indexes: HashMap::new(),
+            offline: false,
+            mode: ResolutionMode::default(),
+            constraints: HashMap::new(),
+            overrides: HashMap::new(),
+            allow_prereleases: false,
+            python: PythonTarget::default(),
+            max_concurrent_requests: DEFAULT_CONCURRENT_REQUESTS,
+            metrics: Arc::new(Mutex::new(ResolutionMetrics::default())),
+        }
+    }
 
-impl std::fmt::Display for PubgrubVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+    /// Get the source a package is looked up in
+    pub(crate) fn source_for(&self, package: &str) -> &dyn PackageSource {
+        match self.indexes.get(&normalize_name(package)) {
+            Some(source) => source.as_ref(),
+            None => &self.sources,
+        }
+    }
+
+    /// Get the versions a declared requirement allows once overrides and
+    /// constraints are applied
+    fn requirement_range(&self, name: &str, declared: &VersionConstraint) -> Range<PubgrubVersion> {
+        let name = package_name(name);
+        let range = constraint_to_range(self.overrides.get(&name).unwrap_or(declared));
+        match self.constraints.get(&name) {
+            Some(constraint) => range.intersection(&constraint_to_range(constraint)),
+            None => range,
+        }
     }
-}
 
-impl PubgrubVersionTrait for PubgrubVersion {
-    fn lowest() -> Self {
-        PubgrubVersion(Version::parse("0.0.0").unwrap())
-    }
-
-    fn bump(&self) -> Self {
-        // Since we can't access Version's internals directly,
-        // we'll parse the version string and increment
-        let version_str = self.0.to_string();
-        let parts: Vec<&str> = version_str.split('.').collect();
-        if parts.len() >= 3 {
-            let major: u64 = parts[0].parse().unwrap_or(0);
-            let minor: u64 = parts[1].parse().unwrap_or(0);
-            let patch: u64 = parts[2].parse().unwrap_or(0);
-            let new_version = format!("{}.{}.{}", major, minor, patch + 1);
-            PubgrubVersion(Version::parse(&new_version).unwrap())
+    /// Choose the preferred release within `range`
+    ///
+    /// As PEP 440 asks, pre-releases are only chosen when `prereleases` is
+    /// set because a requirement names one, when they are allowed for every
+    /// package, or when no final release is within the range.
+    pub(crate) fn best_version(
+        &self,
+        versions: &[Version],
+        range: &Range<PubgrubVersion>,
+        lowest: bool,
+        prereleases: bool,
+    ) -> Option<PubgrubVersion> {
+        let compatible: Vec<PubgrubVersion> = versions.iter()
+            .map(|version| PubgrubVersion::from(version.clone()))
+            .filter(|version| range.contains(version))
+            .collect();
+        let finals = compatible.iter().filter(|version| !version.version().is_prerelease());
+        let candidates: Vec<&PubgrubVersion> = if prereleases || self.allow_prereleases || finals.clone().next().is_none() {
+            compatible.iter().collect()
         } else {
-            // Fallback to a simple increment
-            PubgrubVersion(Version::parse("0.0.1").unwrap())
+            finals.collect()
+        };
+        let best = if lowest { candidates.into_iter().min() } else { candidates.into_iter().max() };
+        best.cloned()
+    }
+
+    /// List the constraints and overrides that changed the requirements
+    /// between resolved packages
+    fn applied_requirements(&self, root: &Package, packages: &[Package]) -> Vec<AppliedRequirement> {
+        let resolved: HashSet<String> = packages.iter().map(|package| normalize_name(package.name())).collect();
+        let mut applied = Vec::new();
+
+        for package in std::iter::once(root).chain(packages) {
+            for (dependency, declared) in &package.metadata().dependencies {
+                let name = normalize_name(dependency);
+                if !resolved.contains(&name) {
+                    continue;
+                }
+                let configured = [
+                    (RequirementKind::Override, self.overrides.get(&name)),
+                    (RequirementKind::Constraint, self.constraints.get(&name)),
+                ];
+                for (kind, constraint) in configured {
+                    if let Some(constraint) = constraint {
+                        applied.push(AppliedRequirement {
+                            kind,
+                            package: package.name().to_string(),
+                            dependency: dependency.clone(),
+                            declared: declared.clone(),
+                            applied: constraint.clone(),
+                        });
+                    }
+                }
+            }
         }
+        applied.sort_by(|a, b| (&a.dependency, &a.package).cmp(&(&b.dependency, &b.package)));
+        applied
     }
-}
 
-impl From<Version> for PubgrubVersion {
-    fn from(v: Version) -> Self {
-        PubgrubVersion(v)
+    /// Walk the dependency closure of `root` through the best available
+    /// releases and collect everything that cannot be looked up
+    ///
+    /// The solver gives up at the first unavailable package, this finds the
+    /// rest so an offline failure can list them all at once.
+    async fn find_missing(&self, root: &Package, project: bool) -> BTreeSet<String> {
+        let mut missing = BTreeSet::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut queue = vec![root.id().clone()];
+
+        while let Some(id) = queue.pop() {
+            let lookup = if project && id == *root.id() {
+                Ok(root.clone())
+            } else {
+                self.source_for(id.name()).get_package(&id).await
+            };
+            let package = match lookup {
+                Ok(package) => package,
+                Err(_) => {
+                    missing.insert(id.to_string());
+                    continue;
+                }
+            };
+
+            for (dependency, constraint) in &package.metadata().dependencies {
+                let name = split_extra(dependency).0;
+                if !seen.insert(normalize_name(name)) {
+                    continue;
+                }
+                let range = self.requirement_range(name, constraint);
+                let versions = self.source_for(name).get_versions(name).await.unwrap_or_default();
+                match versions.into_iter().filter(|v| range.contains(&PubgrubVersion::from(v.clone()))).max() {
+                    Some(version) => queue.push(PackageId::new(name, version)),
+                    None => {
+                        missing.insert(name.to_string());
+                    }
+                }
+            }
+        }
+        missing
     }
 }
 
-impl PartialOrd for PubgrubVersion {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+/// PubGrub solver state, driving async sources from a blocking thread
+struct Solver {
+    provider: PubGrubProvider,
+    runtime: Handle,
+    /// Deduplicated, prefetching metadata lookups
+    prefetcher: Arc<Prefetcher>,
+    /// Name of the package being resolved
+    root: String,
+    /// Project root, which no source knows about
+    project: Option<Package>,
+    /// Normalized names of the root's dependencies
+    direct: Mutex<HashSet<String>>,
+    /// Packages and releases that could not be found offline
+    missing: Mutex<BTreeSet<String>>,
+    /// Why releases of a package were made unavailable, by package
+    excluded: Mutex<HashMap<String, Exclusion>>,
+    /// Versions some requirement pins with `==`, by normalized name
+    pins: Mutex<HashMap<String, Vec<Version>>>,
+    /// Normalized names of the packages some requirement names a
+    /// pre-release of, such as `>=2.0b1`
+    prereleases: Mutex<HashSet<String>>,
 }
 
-impl Ord for PubgrubVersion {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+impl Solver {
+    /// Record a lookup that failed in offline mode
+    fn record_missing(&self, item: String, error: &BlastError) {
+        debug!("Offline lookup failed for {}: {}", item, error);
+        self.missing.lock().unwrap().insert(item);
     }
-}
 
-impl PubGrubProvider {
-    pub fn new(pypi: PyPIClient) -> Self {
-        Self { pypi }
+    /// Record the versions a requirement on `name` pins with `==`, whether
+    /// declared, overridden or constrained
+    fn record_pins(&self, name: &str, declared: &VersionConstraint) {
+        let name = package_name(name);
+        let requirements = [
+            Some(self.provider.overrides.get(&name).unwrap_or(declared)),
+            self.provider.constraints.get(&name),
+        ];
+        let mut pins = self.pins.lock().unwrap();
+        for version in requirements.into_iter().flatten().filter_map(VersionConstraint::pinned) {
+            let pinned = pins.entry(name.clone()).or_default();
+            if !pinned.contains(version) {
+                pinned.push(version.clone());
+            }
+        }
+    }
+
+    /// Record whether a requirement on `name` names a pre-release, which
+    /// makes its pre-releases candidates
+    fn record_prereleases(&self, name: &str, declared: &VersionConstraint) {
+        let name = package_name(name);
+        let requirements = [
+            Some(self.provider.overrides.get(&name).unwrap_or(declared)),
+            self.provider.constraints.get(&name),
+        ];
+        if requirements.into_iter().flatten().any(VersionConstraint::includes_prereleases) {
+            self.prereleases.lock().unwrap().insert(name);
+        }
+    }
+
+    /// Check if pre-releases of a package were asked for by a requirement
+    fn wants_prereleases(&self, name: &str) -> bool {
+        self.prereleases.lock().unwrap().contains(&package_name(name))
+    }
+
+    /// Find a yanked release of `name` within `range` that a requirement
+    /// pins exactly, which PEP 592 still allows to be installed
+    fn pinned_yanked(&self, name: &str, range: &Range<PubgrubVersion>) -> Option<PubgrubVersion> {
+        let name = split_extra(name).0;
+        let pins = self.pins.lock().unwrap().get(&normalize_name(name)).cloned()?;
+        let yanked = self.runtime.block_on(self.provider.source_for(name).get_yanked_versions(name)).ok()?;
+        let release = yanked.into_iter().find(|release| {
+            pins.contains(&release.version) && range.contains(&PubgrubVersion::from(release.version.clone()))
+        })?;
+        debug!("Considering yanked release {}=={}, pinned with ==", name, release.version);
+        Some(PubgrubVersion::from(release.version))
     }
 }
 
-#[async_trait]
-impl ResolutionStrategy for PubGrubProvider {
-    async fn resolve(
-        &self,
-        package: &Package,
-        pypi: &PyPIClient,
-        cache: &Arc<RwLock<Cache>>,
-    ) -> BlastResult<ResolutionResult> {
+impl PubGrubProvider {
+    /// Run the solver for `package`
+    ///
+    /// A project root is not published anywhere, so its dependencies are
+    /// taken from its own metadata instead of being looked up.
+    async fn solve(&self, package: &Package, cache: &Arc<RwLock<Cache>>, project: bool) -> BlastResult<ResolutionResult> {
         let start_time = std::time::Instant::now();
-        let mut metrics = crate::resolution::ResolutionMetrics::default();
+        let mut metrics = ResolutionMetrics::default();
 
         let root = package.name().to_string();
-        let root_version = PubgrubVersion(package.version().clone());
-
-        let solution = pubgrub::solver::resolve(self, root.clone(), root_version)
-            .map_err(|e| BlastError::resolution(format!(
-                "No solution found for package {}: {:?}",
-                package.name(),
-                e
-            )))?;
+        let root_version = PubgrubVersion::from(package.version().clone());
+        let prefetcher = Prefetcher::new(self.clone(), cache.clone(), Handle::current());
+
+        // The solver is synchronous, so it runs off the async workers and
+        // blocks on source lookups through the runtime handle
+        let solver = Solver {
+            provider: self.clone(),
+            runtime: Handle::current(),
+            prefetcher: prefetcher.clone(),
+            root: root.clone(),
+            project: project.then(|| package.clone()),
+            direct: Mutex::new(HashSet::new()),
+            missing: Mutex::new(BTreeSet::new()),
+            excluded: Mutex::new(HashMap::new()),
+            pins: Mutex::new(HashMap::new()),
+            prereleases: Mutex::new(HashSet::new()),
+        };
+        let solver_root = root.clone();
+        let (solution, missing) = tokio::task::spawn_blocking(move || {
+            let solution = pubgrub::solver::resolve(&solver, solver_root.clone(), root_version.clone())
+                .map_err(|e| match e {
+                    PubGrubError::NoSolution(tree) => {
+                        let excluded = solver.excluded.lock().unwrap();
+                        BlastError::conflict(conflict_diagnostic(&solver_root, &root_version, &tree, &excluded))
+                    }
+                    e => BlastError::resolution(format!("No solution found for package {}: {}", solver_root, e)),
+                });
+            (solution, solver.missing.into_inner().unwrap())
+        })
+        .await
+        .map_err(|e| BlastError::resolution(format!("Resolver task failed: {}", e)))?;
+
+        let solution = match solution {
+            Ok(solution) => solution,
+            Err(e) if missing.is_empty() => {
+                prefetcher.cancel();
+                return Err(e);
+            }
+            Err(_) => {
+                prefetcher.cancel();
+                let mut missing = missing;
+                missing.extend(self.find_missing(package, project).await);
+                return Err(BlastError::resolution(format!(
+                    "Cannot resolve {} offline, not found in the metadata cache or local sources: {}",
+                    package.name(),
+                    missing.into_iter().collect::<Vec<_>>().join(", ")
+                )));
+            }
+        };
 
+        let mut root_package = package.clone();
         let mut packages = Vec::new();
+        let mut extras: HashMap<String, Vec<String>> = HashMap::new();
         for (name, version) in solution.into_iter() {
-            if name != root {
-                let pkg_id = PackageId::new(name.clone(), version.0.clone());
-                let pkg = if let Some(cached_pkg) = cache.write().await.get_package(&pkg_id) {
-                    debug!("Using cached package {}", pkg_id);
-                    metrics.cache_hits += 1;
-                    cached_pkg.clone()
-                } else {
-                    metrics.network_requests += 1;
-                    let pkg = pypi.get_package_metadata(&name).await?;
-                    cache.write().await.store_package(pkg.clone()).await?;
-                    pkg
-                };
+            if name == root && project {
+                continue;
+            }
+            if let (base, Some(extra)) = split_extra(&name) {
+                extras.entry(normalize_name(base)).or_default().push(extra.to_string());
+                continue;
+            }
+            let pkg_id = PackageId::new(name.clone(), version.version().clone());
+            let pkg = prefetcher.package(&pkg_id).await.map_err(|e| BlastError::resolution(e.to_string()))?;
+            let pkg = Package::clone(&pkg);
+            // The root is cached too, so it can be resolved again offline
+            let mut cache = cache.write().await;
+            if cache.get_package(&pkg_id).is_none() && !prefetcher.is_direct(&name) {
+                cache.store_package(pkg.clone()).await?;
+            }
+            drop(cache);
+            if pkg.metadata().yanked {
+                warn!(
+                    "{}=={} has been yanked{}",
+                    pkg.name(),
+                    pkg.version(),
+                    pkg.metadata().yanked_reason.as_ref().map(|reason| format!(": {}", reason)).unwrap_or_default()
+                );
+            }
+            if name == root {
+                root_package = pkg;
+            } else {
                 packages.push(pkg);
             }
         }
+        // Packages wanted with extras depend on what the extras require
+        for package in &mut packages {
+            if let Some(extras) = extras.get(&normalize_name(package.name())) {
+                package.metadata_mut().dependencies = package.all_dependencies(extras);
+            }
+        }
 
+        prefetcher.cancel();
+        prefetcher.record(&mut metrics);
         metrics.package_count = packages.len();
         metrics.resolution_time_ms = start_time.elapsed().as_millis() as u64;
+        *self.metrics.lock().unwrap() = metrics.clone();
 
         Ok(ResolutionResult {
+            graph: Some(build_graph(&root_package, &packages)),
+            applied: self.applied_requirements(&root_package, &packages),
             packages,
-            graph: None,
             metrics,
         })
     }
+}
+
+/// Get the normalized name of the package a dependency is on, leaving out
+/// any extra
+fn package_name(name: &str) -> String {
+    normalize_name(split_extra(name).0)
+}
+
+/// Build the dependency edges between a root and its resolved packages
+fn build_graph(root: &Package, packages: &[Package]) -> ResolutionGraph {
+    let by_name: HashMap<String, &Package> = packages.iter()
+        .map(|package| (normalize_name(package.name()), package))
+        .collect();
+
+    let mut graph = ResolutionGraph {
+        direct_deps: Vec::new(),
+        transitive_deps: Vec::new(),
+        relationships: Vec::new(),
+    };
+    for from in std::iter::once(root).chain(packages) {
+        let is_root = std::ptr::eq(from, root);
+        let mut names: Vec<&String> = from.metadata().dependencies.keys().collect();
+        names.sort();
+
+        for name in names {
+            let Some(&to) = by_name.get(&normalize_name(name)) else {
+                continue;
+            };
+            graph.relationships.push(DependencyRelationship {
+                from: from.clone(),
+                to: to.clone(),
+                kind: if is_root { DependencyKind::Direct } else { DependencyKind::Transitive },
+            });
+            if is_root {
+                graph.direct_deps.push(to.clone());
+            }
+        }
+    }
+    graph.transitive_deps = packages.iter()
+        .filter(|package| !graph.direct_deps.iter().any(|direct| direct.id() == package.id()))
+        .cloned()
+        .collect();
+    graph
+}
+
+#[async_trait]
+impl ResolutionStrategy for PubGrubProvider {
+    async fn resolve(
+        &self,
+        package: &Package,
+        _pypi: &PyPIClient,
+        cache: &Arc<RwLock<Cache>>,
+    ) -> BlastResult<ResolutionResult> {
+        self.solve(package, cache, false).await
+    }
 
     fn has_conflict(&self, package: &Package, resolved: &[Package]) -> bool {
         for dep in resolved {
@@ -240,27 +789,85 @@ impl ResolutionStrategy for PubGrubProvider {
         false
     }
 
-    fn get_metrics(&self) -> crate::resolution::ResolutionMetrics {
-        crate::resolution::ResolutionMetrics::default()
+    fn get_metrics(&self) -> ResolutionMetrics {
+        self.metrics.lock().unwrap().clone()
     }
 }
 
-impl DependencyProvider<String, PubgrubVersion> for PubGrubProvider {
+impl DependencyProvider<String, PubgrubVersion> for Solver {
     fn get_dependencies(
         &self,
         package: &String,
         version: &PubgrubVersion,
     ) -> Result<Dependencies<String, PubgrubVersion>, Box<dyn StdError>> {
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| Box::new(BlastError::resolution(e.to_string())) as Box<dyn StdError>)?;
-        
-        let deps_map = rt.block_on(self.pypi.get_package_dependencies(package, &version.0))
-            .map_err(|e| Box::new(BlastError::resolution(e.to_string())) as Box<dyn StdError>)?;
+        let (base, extra) = split_extra(package);
+        let metadata = match self.project.as_ref().filter(|project| project.name() == package) {
+            Some(project) => project.clone(),
+            None => {
+                let id = PackageId::new(base, version.version().clone());
+                match self.runtime.block_on(self.prefetcher.package(&id)) {
+                    Ok(metadata) => Package::clone(&metadata),
+                    Err(e) if self.provider.offline => {
+                        self.record_missing(id.to_string(), &e);
+                        return Ok(Dependencies::Unknown);
+                    }
+                    Err(e) => return Err(Box::new(BlastError::resolution(e.to_string()))),
+                }
+            }
+        };
+
+        // Not every source filters releases by Requires-Python, so releases
+        // for other interpreters are made unavailable here
+        let requires_python = &metadata.metadata().python_version;
+        if *package != self.root && !self.provider.python.allows(requires_python) {
+            debug!("{} {} requires Python {}, excluding the target {}", package, version, requires_python, self.provider.python);
+            self.excluded.lock().unwrap().insert(package.clone(), Exclusion::RequiresPython {
+                requires: requires_python.to_string(),
+                target: self.provider.python.to_string(),
+            });
+            return Ok(Dependencies::Unknown);
+        }
+
+        // A package with an extra is the package itself at the same version
+        // plus the extra's dependencies
+        let no_dependencies = HashMap::new();
+        let dependencies = match extra {
+            Some(extra) => metadata.metadata().extras.get(extra).unwrap_or_else(|| {
+                warn!("{} {} does not provide the extra {}", base, version, extra);
+                &no_dependencies
+            }),
+            None => &metadata.metadata().dependencies,
+        };
+        if *package == self.root {
+            self.direct.lock().unwrap().extend(dependencies.keys().map(|name| normalize_name(name)));
+        }
 
         let mut ranges = FxHashMap::default();
-        for (name, _constraint) in deps_map {
-            let range = Range::any(); // TODO: Convert VersionConstraint to PubGrub Range
-            ranges.insert(name, range);
+        for (name, constraint) in dependencies {
+            self.record_pins(name, constraint);
+            self.record_prereleases(name, constraint);
+            let range = self.provider.requirement_range(name, constraint);
+            // PubGrub rejects dependencies on nothing outright, so a release
+            // whose requirement a constraint rules out is made unavailable
+            if range == Range::none() {
+                if let Some(limit) = self.provider.constraints.get(&package_name(name)) {
+                    debug!("{} {} requires {} {}, excluded by the constraint {}", package, version, name, constraint, limit);
+                    self.excluded.lock().unwrap().insert(package.clone(), Exclusion::Constraint(format!("{}{}", name, limit)));
+                    return Ok(Dependencies::Unknown);
+                }
+            }
+            ranges.insert(name.clone(), range);
+        }
+        if extra.is_some() {
+            ranges.insert(base.to_string(), Range::exact(version.clone()));
+        }
+
+        // Look the dependencies up while the solver works on something else
+        for (name, range) in &ranges {
+            if self.project.as_ref().is_none_or(|project| project.name() != name) {
+                let lowest = self.provider.mode.prefers_lowest(*package == self.root);
+                self.prefetcher.prefetch(split_extra(name).0, range.clone(), lowest, self.wants_prereleases(name));
+            }
         }
         Ok(Dependencies::Known(ranges))
     }
@@ -273,29 +880,32 @@ impl DependencyProvider<String, PubgrubVersion> for PubGrubProvider {
         T: Borrow<String>,
         U: Borrow<Range<PubgrubVersion>>,
     {
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| Box::new(BlastError::resolution(e.to_string())) as Box<dyn StdError>)?;
-
         if let Some((package, range)) = available_versions.next() {
-            let versions = rt.block_on(self.pypi.get_package_versions(package.borrow()))
-                .map_err(|e| Box::new(BlastError::resolution(e.to_string())) as Box<dyn StdError>)?;
-
-            let mut best_version = None;
-            for version in versions {
-                let pubgrub_version = PubgrubVersion(version);
-                if range.borrow().contains(&pubgrub_version) {
-                    match best_version {
-                        None => best_version = Some(pubgrub_version),
-                        Some(ref current) if pubgrub_version > *current => {
-                            best_version = Some(pubgrub_version)
-                        }
-                        _ => {}
-                    }
-                }
+            let name: &String = package.borrow();
+            if let Some(project) = self.project.as_ref().filter(|project| project.name() == name) {
+                return Ok((package, Some(PubgrubVersion::from(project.version().clone()))));
             }
+            let versions = match self.runtime.block_on(self.prefetcher.versions(split_extra(name).0)) {
+                Ok(versions) => versions,
+                Err(e) if self.provider.offline => {
+                    self.record_missing(name.clone(), &e);
+                    return Ok((package, None));
+                }
+                Err(e) => return Err(Box::new(BlastError::resolution(e.to_string()))),
+            };
+
+            let direct = self.direct.lock().unwrap().contains(&normalize_name(name));
+            let best_version = self.provider.best_version(
+                &versions,
+                range.borrow(),
+                self.provider.mode.prefers_lowest(direct),
+                self.wants_prereleases(name),
+            );
+            // Yanked releases are only used when nothing else will do
+            let best_version = best_version.or_else(|| self.pinned_yanked(name, range.borrow()));
             Ok((package, best_version))
         } else {
             Err(Box::new(BlastError::resolution("No versions available".to_string())))
         }
     }
-} 
\ No newline at end of file
+}
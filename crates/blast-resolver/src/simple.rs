@@ -0,0 +1,742 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use tracing::debug;
+use url::Url;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{Package, PackageId};
+use blast_core::version::{Version, VersionConstraint};
+
+use crate::build::BuildFrontend;
+use crate::core_metadata::CoreMetadata;
+use crate::filename::{normalize_name, DistFilename};
+use crate::marker::MarkerEnvironment;
+use crate::remote_wheel;
+use crate::source::{uploaded_by, yank_reason, Artifact, ArtifactInfo, PackageSource, YankedRelease};
+use crate::tags::WheelTags;
+use crate::target::PythonTarget;
+
+/// Content types accepted from a Simple API index, preferring PEP 691 JSON
+const SIMPLE_ACCEPT: &str =
+    "application/vnd.pypi.simple.v1+json, application/vnd.pypi.simple.v1+html;q=0.2, text/html;q=0.01";
+
+/// Package source reading a PEP 503 (HTML) or PEP 691 (JSON) simple index
+///
+/// The index may be served over HTTP(S) or live on disk, in which case each
+/// project directory holds an `index.json` or `index.html` page.
+pub struct SimpleIndexSource {
+    index_url: Url,
+    client: Client,
+    priority: u32,
+    target: PythonTarget,
+    environments: Vec<MarkerEnvironment>,
+    /// Wheel tags the target supports
+    tags: WheelTags,
+    /// Whether source distributions are left out
+    no_build: bool,
+    /// Files uploaded after this time are ignored
+    exclude_newer: Option<DateTime<Utc>>,
+    /// Builds metadata of sdists without static `PKG-INFO`
+    build_frontend: Option<Arc<BuildFrontend>>,
+    /// Project pages already fetched, keyed by normalized name
+    projects: RwLock<HashMap<String, Arc<Vec<DistributionFile>>>>,
+}
+
+/// A file listed on a project page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistributionFile {
+    /// Distribution filename
+    pub filename: String,
+    /// Absolute download URL, without hash fragment
+    pub url: Url,
+    /// Hashes of the file by algorithm name
+    pub hashes: HashMap<String, String>,
+    /// `Requires-Python` of the distribution
+    pub requires_python: Option<String>,
+    /// Whether the file has been yanked (PEP 592)
+    pub yanked: bool,
+    /// Reason given for yanking
+    pub yanked_reason: Option<String>,
+    /// Upload time (PEP 700), only published by JSON pages
+    pub upload_time: Option<DateTime<Utc>>,
+    /// Hashes of the file's core metadata, served next to it with a
+    /// `.metadata` suffix (PEP 658); `None` if the index does not serve it
+    pub core_metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonProjectPage {
+    meta: JsonMeta,
+    files: Vec<JsonFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonMeta {
+    #[serde(rename = "api-version")]
+    api_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFile {
+    filename: String,
+    url: String,
+    #[serde(default)]
+    hashes: HashMap<String, String>,
+    #[serde(rename = "requires-python")]
+    requires_python: Option<String>,
+    #[serde(default)]
+    yanked: JsonYanked,
+    #[serde(rename = "upload-time")]
+    upload_time: Option<DateTime<Utc>>,
+    #[serde(rename = "core-metadata")]
+    core_metadata: Option<JsonCoreMetadata>,
+    /// Name of `core-metadata` before PEP 714
+    #[serde(rename = "dist-info-metadata")]
+    dist_info_metadata: Option<JsonCoreMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonYanked {
+    Flag(bool),
+    Reason(String),
+}
+
+impl Default for JsonYanked {
+    fn default() -> Self {
+        JsonYanked::Flag(false)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonCoreMetadata {
+    Flag(bool),
+    Hashes(HashMap<String, String>),
+}
+
+impl SimpleIndexSource {
+    /// Create a source for an index URL or a local index directory
+    pub fn new(index: &str, priority: u32) -> BlastResult<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| BlastError::network(e.to_string()))?;
+
+        let target = PythonTarget::default();
+        Ok(Self {
+            index_url: index_url(index)?,
+            client,
+            priority,
+            environments: target.marker_environments(target.platform()),
+            tags: WheelTags::new(&target),
+            target,
+            no_build: false,
+            exclude_newer: None,
+            build_frontend: None,
+            projects: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Set the Python interpreter(s) that files and markers are checked against
+    pub fn with_python_target(mut self, target: PythonTarget) -> Self {
+        self.environments = target.marker_environments(target.platform());
+        self.tags = WheelTags::new(&target);
+        self.target = target;
+        self
+    }
+
+    /// Only offer releases that have a wheel for the target
+    pub fn with_no_build(mut self, no_build: bool) -> Self {
+        self.no_build = no_build;
+        self
+    }
+
+    /// Ignore files uploaded after `cutoff`
+    pub fn with_exclude_newer(mut self, cutoff: Option<DateTime<Utc>>) -> Self {
+        self.exclude_newer = cutoff;
+        self
+    }
+
+    /// Build sdists whose `PKG-INFO` is not static to get their metadata
+    pub fn with_build_frontend(mut self, frontend: Option<Arc<BuildFrontend>>) -> Self {
+        self.build_frontend = frontend;
+        self
+    }
+
+    /// Get the index root URL
+    pub fn index_url(&self) -> &Url {
+        &self.index_url
+    }
+
+    /// Get every file listed for a project
+    pub async fn project_files(&self, project: &str) -> BlastResult<Arc<Vec<DistributionFile>>> {
+        let project = normalize_name(project);
+        if let Some(files) = self.projects.read().await.get(&project) {
+            return Ok(files.clone());
+        }
+
+        let files = Arc::new(self.fetch_project_page(&project).await?);
+        self.projects.write().await.insert(project, files.clone());
+        Ok(files)
+    }
+
+    /// Fetch and parse the page of a normalized project name
+    async fn fetch_project_page(&self, project: &str) -> BlastResult<Vec<DistributionFile>> {
+        let page_url = self.index_url.join(&format!("{}/", project))
+            .map_err(|e| BlastError::package(format!("Invalid project URL for {}: {}", project, e)))?;
+        debug!("Fetching project page {}", page_url);
+
+        if page_url.scheme() == "file" {
+            let dir = file_path(&page_url)?;
+            for (page, is_json) in [("index.json", true), ("index.html", false)] {
+                let path = dir.join(page);
+                if path.is_file() {
+                    let content = tokio::fs::read_to_string(&path).await?;
+                    return parse_page(&content, is_json, &page_url);
+                }
+            }
+            return Err(BlastError::package(format!(
+                "Package not found: {} (no project page in {})",
+                project,
+                dir.display()
+            )));
+        }
+
+        let response = self.client
+            .get(page_url.clone())
+            .header(ACCEPT, SIMPLE_ACCEPT)
+            .send()
+            .await
+            .map_err(|e| BlastError::network(e.to_string()))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(BlastError::package(format!("Package not found: {} on {}", project, self.index_url)));
+        }
+        if !response.status().is_success() {
+            return Err(BlastError::network(format!(
+                "Failed to fetch {} (status: {})",
+                page_url,
+                response.status()
+            )));
+        }
+
+        // Redirects (e.g. to add a trailing slash) move the base of relative links
+        let page_url = response.url().clone();
+        let is_json = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("json"));
+        let content = response.text().await.map_err(|e| BlastError::network(e.to_string()))?;
+        parse_page(&content, is_json, &page_url)
+    }
+
+    /// Get the installable versions of a project along with its yanked
+    /// releases, both sorted
+    ///
+    /// A release is yanked when every file of it that can be used is (see
+    /// [`yank_reason`]), and left out when its files do not cover every
+    /// targeted interpreter.
+    async fn releases(&self, package_name: &str) -> BlastResult<(Vec<Version>, Vec<YankedRelease>)> {
+        let project = normalize_name(package_name);
+        let project_files = self.project_files(&project).await?;
+        let mut releases: BTreeMap<Version, (Vec<&DistributionFile>, Vec<DistFilename>)> = BTreeMap::new();
+
+        for file in project_files.iter() {
+            // Skip files that cannot be installed on the target and files
+            // uploaded after the cutoff
+            if !self.supports_target(file) || !uploaded_by(file.upload_time, self.exclude_newer) {
+                continue;
+            }
+            let filename = match DistFilename::parse(&file.filename) {
+                Ok(filename) if filename.normalized_name() == project => filename,
+                _ => {
+                    debug!("Skipping unrecognized file {} for {}", file.filename, project);
+                    continue;
+                }
+            };
+            if self.tags.preference(&filename, self.no_build).is_none() {
+                continue;
+            }
+            let (usable, filenames) = releases.entry(filename.version.clone()).or_default();
+            usable.push(file);
+            filenames.push(filename);
+        }
+
+        let mut versions = Vec::new();
+        let mut yanked = Vec::new();
+        for (version, (usable, filenames)) in releases {
+            if !self.tags.installable(&filenames, self.no_build) {
+                debug!("Skipping {} {}: no installable file for {}", project, version, self.target);
+                continue;
+            }
+            match yank_reason(usable.iter().map(|file| (file.yanked, file.yanked_reason.as_deref()))) {
+                Some(reason) => yanked.push(YankedRelease { version, reason }),
+                None => versions.push(version),
+            }
+        }
+        Ok((versions, yanked))
+    }
+
+    /// Get the installable files of a specific release, best wheel first
+    /// and yanked files last
+    async fn release_files(&self, id: &PackageId) -> BlastResult<Vec<(DistributionFile, DistFilename)>> {
+        let mut files: Vec<_> = self.project_files(id.name()).await?
+            .iter()
+            .filter_map(|file| {
+                let filename = DistFilename::parse(&file.filename).ok()?;
+                let matches = filename.version == *id.version() && uploaded_by(file.upload_time, self.exclude_newer);
+                let preference = self.tags.preference(&filename, self.no_build).filter(|_| matches)?;
+                Some((file.yanked, preference, file.clone(), filename))
+            })
+            .collect();
+
+        if files.is_empty() {
+            return Err(BlastError::package(format!("No installable files for {} on {}", id, self.index_url)));
+        }
+        files.sort_by_key(|(yanked, preference, _, _)| (*yanked, *preference));
+        Ok(files.into_iter().map(|(_, _, file, filename)| (file, filename)).collect())
+    }
+
+    /// Download a file and check it against the hash published by the index
+    async fn fetch_file(&self, file: &DistributionFile) -> BlastResult<Vec<u8>> {
+        let data = self.fetch_url(&file.url).await?;
+        verify_sha256(&file.filename, &file.hashes, &data)?;
+        Ok(data)
+    }
+
+    /// Download a file from a `file:` or HTTP(S) URL
+    async fn fetch_url(&self, url: &Url) -> BlastResult<Vec<u8>> {
+        debug!("Downloading {}", url);
+        if url.scheme() == "file" {
+            return Ok(tokio::fs::read(file_path(url)?).await?);
+        }
+
+        let response = self.client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| BlastError::network(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(BlastError::network(format!(
+                "Failed to download {} (status: {})",
+                url,
+                response.status()
+            )));
+        }
+        Ok(response.bytes().await.map_err(|e| BlastError::network(e.to_string()))?.to_vec())
+    }
+
+    /// Read the core metadata of a wheel without downloading all of it
+    ///
+    /// The index's PEP 658 metadata file is used when it serves one, then
+    /// range requests for the wheel's zip directory and `METADATA` entry.
+    /// Only when neither works is the whole wheel downloaded.
+    async fn wheel_metadata(&self, file: &DistributionFile) -> BlastResult<CoreMetadata> {
+        if let Some(hashes) = &file.core_metadata {
+            let mut url = file.url.clone();
+            url.set_path(&format!("{}.metadata", file.url.path()));
+            match self.fetch_url(&url).await {
+                Ok(data) => {
+                    verify_sha256(&format!("{}.metadata", file.filename), hashes, &data)?;
+                    let content = String::from_utf8(data)
+                        .map_err(|_| BlastError::package(format!("{} is not valid UTF-8", url)))?;
+                    return CoreMetadata::parse(&content);
+                }
+                Err(e) => debug!("Falling back from the metadata file of {}: {}", file.filename, e),
+            }
+        }
+
+        if file.url.scheme() != "file" {
+            match remote_wheel::wheel_metadata(&self.client, &file.url).await {
+                Ok(metadata) => return Ok(metadata),
+                Err(e) => debug!("Failed to read the metadata of {} with range requests: {}", file.filename, e),
+            }
+        }
+        CoreMetadata::from_wheel(&self.fetch_file(file).await?)
+    }
+
+    /// Check if a file can be installed on every targeted interpreter
+    fn supports_target(&self, file: &DistributionFile) -> bool {
+        match file.requires_python.as_deref().map(VersionConstraint::parse) {
+            Some(Ok(requires_python)) => self.target.allows(&requires_python),
+            // Missing or unparseable metadata does not exclude a file
+            _ => true,
+        }
+    }
+}
+
+#[async_trait]
+impl PackageSource for SimpleIndexSource {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        let (file, filename) = self.release_files(id).await?.remove(0);
+        let metadata = if filename.is_wheel() {
+            self.wheel_metadata(&file).await?
+        } else {
+            let data = self.fetch_file(&file).await?;
+            match &self.build_frontend {
+                Some(frontend) => frontend.sdist_metadata(&file.filename, &data).await?,
+                None => CoreMetadata::from_sdist(&file.filename, &data)?,
+            }
+        };
+        let mut package = metadata.into_package(&self.environments)?;
+        package.metadata_mut().yanked = file.yanked;
+        package.metadata_mut().yanked_reason = file.yanked_reason;
+        Ok(package)
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        Ok(self.releases(package_name).await?.0)
+    }
+
+    async fn get_yanked_versions(&self, package_name: &str) -> BlastResult<Vec<YankedRelease>> {
+        Ok(self.releases(package_name).await?.1)
+    }
+
+    async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
+        let (file, _) = self.release_files(id).await?.remove(0);
+        self.fetch_file(&file).await
+    }
+
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        let (file, _) = self.release_files(id).await?.remove(0);
+        Ok(Artifact {
+            data: self.fetch_file(&file).await?,
+            filename: file.filename,
+        })
+    }
+
+    async fn list_artifacts(&self, id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        Ok(self.release_files(id).await?
+            .into_iter()
+            .map(|(file, _)| ArtifactInfo {
+                sha256: file.hashes.get("sha256").cloned(),
+                url: file.url.to_string(),
+                filename: file.filename,
+            })
+            .collect())
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        match self.get_versions(id.name()).await {
+            Ok(versions) => Ok(versions.contains(id.version())),
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.index_url.as_str()
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+}
+
+/// Turn an index URL or local directory into a base URL ending in `/`
+fn index_url(index: &str) -> BlastResult<Url> {
+    let invalid = |reason: String| BlastError::config(format!("Invalid package index '{}': {}", index, reason));
+
+    let mut url = match Url::parse(index) {
+        Ok(url) if matches!(url.scheme(), "http" | "https" | "file") => url,
+        _ => {
+            let path = std::env::current_dir()?.join(index);
+            Url::from_directory_path(&path).map_err(|_| invalid("not an absolute path".to_string()))?
+        }
+    };
+
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+/// Convert a `file://` URL into a local path
+fn file_path(url: &Url) -> BlastResult<PathBuf> {
+    url.to_file_path()
+        .map_err(|_| BlastError::package(format!("Invalid file URL: {}", url)))
+}
+
+/// Parse a project page in either format
+fn parse_page(content: &str, is_json: bool, page_url: &Url) -> BlastResult<Vec<DistributionFile>> {
+    if is_json {
+        parse_json_page(content, page_url)
+    } else {
+        Ok(parse_html_page(content, page_url))
+    }
+}
+
+/// Parse a PEP 691 JSON project page
+pub fn parse_json_page(content: &str, page_url: &Url) -> BlastResult<Vec<DistributionFile>> {
+    let page: JsonProjectPage = serde_json::from_str(content)
+        .map_err(|e| BlastError::package(format!("Invalid project page {}: {}", page_url, e)))?;
+
+    if page.meta.api_version.split('.').next() != Some("1") {
+        return Err(BlastError::package(format!(
+            "Unsupported Simple API version {} at {}",
+            page.meta.api_version, page_url
+        )));
+    }
+
+    let mut files = Vec::new();
+    for file in page.files {
+        let url = match page_url.join(&file.url) {
+            Ok(url) => url,
+            Err(e) => {
+                debug!("Skipping {} with invalid URL {}: {}", file.filename, file.url, e);
+                continue;
+            }
+        };
+        let (url, mut hashes) = split_hash_fragment(url);
+        hashes.extend(file.hashes.into_iter().map(|(name, hash)| (name.to_ascii_lowercase(), hash)));
+        let (yanked, yanked_reason) = match file.yanked {
+            JsonYanked::Flag(yanked) => (yanked, None),
+            JsonYanked::Reason(reason) => (true, Some(reason).filter(|r| !r.is_empty())),
+        };
+        let core_metadata = match file.core_metadata.or(file.dist_info_metadata) {
+            Some(JsonCoreMetadata::Flag(true)) => Some(HashMap::new()),
+            Some(JsonCoreMetadata::Hashes(hashes)) => {
+                Some(hashes.into_iter().map(|(name, hash)| (name.to_ascii_lowercase(), hash)).collect())
+            }
+            Some(JsonCoreMetadata::Flag(false)) | None => None,
+        };
+
+        files.push(DistributionFile {
+            filename: file.filename,
+            url,
+            hashes,
+            requires_python: file.requires_python,
+            yanked,
+            yanked_reason,
+            upload_time: file.upload_time,
+            core_metadata,
+        });
+    }
+    Ok(files)
+}
+
+/// Parse a PEP 503 HTML project page
+///
+/// Every anchor is a file: its text is the filename, `href` the download
+/// URL with an optional `#<hash>=<value>` fragment, and the
+/// `data-requires-python` / `data-yanked` attributes carry release metadata.
+pub fn parse_html_page(content: &str, page_url: &Url) -> Vec<DistributionFile> {
+    let mut files = Vec::new();
+    let mut rest = content;
+
+    while let Some(anchor) = find_tag(rest, "a") {
+        let after_name = &rest[anchor + 2..];
+        let tag_len = match tag_end(after_name) {
+            Some(len) => len,
+            None => break,
+        };
+        let attributes = parse_attributes(&after_name[..tag_len]);
+        let body = &after_name[tag_len + 1..];
+        let text_len = find_closing_anchor(body).unwrap_or(body.len());
+        let text = unescape_html(body[..text_len].trim());
+        rest = &body[text_len..];
+
+        let attribute = |name: &str| {
+            attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+        };
+        let href = match attribute("href") {
+            Some(href) => href,
+            None => continue,
+        };
+        let url = match page_url.join(&href) {
+            Ok(url) => url,
+            Err(e) => {
+                debug!("Skipping link with invalid URL {}: {}", href, e);
+                continue;
+            }
+        };
+        let (url, hashes) = split_hash_fragment(url);
+
+        let filename = if text.is_empty() {
+            url.path_segments().and_then(|mut segments| segments.next_back()).unwrap_or_default().to_string()
+        } else {
+            text
+        };
+        let yanked_reason = attribute("data-yanked");
+        let core_metadata = attribute("data-core-metadata").or_else(|| attribute("data-dist-info-metadata"));
+
+        files.push(DistributionFile {
+            filename,
+            url,
+            hashes,
+            requires_python: attribute("data-requires-python").filter(|value| !value.is_empty()),
+            yanked: yanked_reason.is_some(),
+            yanked_reason: yanked_reason.filter(|reason| !reason.is_empty()),
+            upload_time: None,
+            core_metadata: core_metadata.and_then(|value| parse_core_metadata_attribute(&value)),
+        });
+    }
+    files
+}
+
+/// Parse a `data-core-metadata` value, `true` or `<hash>=<value>`
+fn parse_core_metadata_attribute(value: &str) -> Option<HashMap<String, String>> {
+    match value.split_once('=') {
+        Some((name, hash)) => Some(HashMap::from([(name.to_ascii_lowercase(), hash.to_string())])),
+        None if value.eq_ignore_ascii_case("true") => Some(HashMap::new()),
+        None => None,
+    }
+}
+
+/// Check data against the SHA-256 hash an index published for it, if any
+fn verify_sha256(filename: &str, hashes: &HashMap<String, String>, data: &[u8]) -> BlastResult<()> {
+    if let Some(expected) = hashes.get("sha256") {
+        let actual = hex::encode(Sha256::digest(data));
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(BlastError::security(format!(
+                "Hash mismatch for {}: expected sha256 {}, got {}",
+                filename, expected, actual
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Strip a `#sha256=...` style fragment off a file URL
+fn split_hash_fragment(mut url: Url) -> (Url, HashMap<String, String>) {
+    let mut hashes = HashMap::new();
+    if let Some((name, hash)) = url.fragment().and_then(|fragment| fragment.split_once('=')) {
+        hashes.insert(name.to_ascii_lowercase(), hash.to_string());
+    }
+    url.set_fragment(None);
+    (url, hashes)
+}
+
+/// Offset of the next opening tag with the given name
+fn find_tag(content: &str, name: &str) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut offset = 0;
+    while let Some(start) = content[offset..].find('<') {
+        let start = offset + start;
+        let name_end = start + 1 + name.len();
+        let is_match = bytes.get(start + 1..name_end).is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()))
+            && bytes.get(name_end).is_some_and(|c| c.is_ascii_whitespace() || *c == b'>' || *c == b'/');
+        if is_match {
+            return Some(start);
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+/// Length of a tag's attribute list, up to the unquoted `>`
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Offset of the `</a>` closing an anchor
+fn find_closing_anchor(body: &str) -> Option<usize> {
+    let bytes = body.as_bytes();
+    body.match_indices("</").map(|(i, _)| i).find(|&i| {
+        bytes.get(i + 2).is_some_and(|c| c.eq_ignore_ascii_case(&b'a'))
+            && bytes.get(i + 3).is_some_and(|c| c.is_ascii_whitespace() || *c == b'>')
+    })
+}
+
+/// Parse `name="value"` pairs, lowercasing names and unescaping values
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag.trim_start_matches('/').trim_start();
+
+    while !rest.is_empty() {
+        let name_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after.find(|c: char| c.is_ascii_whitespace()).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = unescape_html(raw);
+            rest = remaining;
+        }
+
+        if !name.is_empty() {
+            attributes.push((name, value));
+        } else if name_len == 0 {
+            // Skip stray characters such as a self-closing `/`
+            rest = rest.get(1..).unwrap_or_default();
+        }
+        rest = rest.trim_start();
+    }
+    attributes
+}
+
+/// Decode the character references used in index pages
+fn unescape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest[1..].find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end + 1];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok().and_then(char::from_u32),
+                    Some(decimal) => decimal.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, end + 2))
+        });
+
+        match decoded {
+            Some((c, len)) => {
+                result.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
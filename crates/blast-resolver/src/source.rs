@@ -1,7 +1,82 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use blast_core::config::DependencySource;
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::package::{Package, PackageId};
+use blast_core::version::Version;
+
+/// A downloaded distribution file
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// Distribution filename (wheel or sdist)
+    pub filename: String,
+    /// File contents
+    pub data: Vec<u8>,
+}
+
+/// Location of a distribution file published by a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactInfo {
+    /// Distribution filename (wheel or sdist)
+    pub filename: String,
+    /// URL the file is downloaded from
+    pub url: String,
+    /// Hex encoded SHA-256 digest, if the source publishes one
+    pub sha256: Option<String>,
+}
+
+/// Exact origin of a package taken from a path, URL or git repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectReference {
+    /// Source as configured
+    pub source: DependencySource,
+    /// Commit a git source resolved to
+    pub commit: Option<String>,
+}
+
+/// A release withdrawn by its publisher (PEP 592)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YankedRelease {
+    /// Yanked version
+    pub version: Version,
+    /// Reason given for yanking it, if any
+    pub reason: Option<String>,
+}
 
-use blast_core::error::BlastResult;
-use blast_core::package::{Package, PackageId, Version};
+/// Check if a file was uploaded no later than an upload-time cutoff
+///
+/// Files without a known upload time cannot be shown to predate the cutoff
+/// and are excluded whenever one is set.
+pub fn uploaded_by(upload_time: Option<DateTime<Utc>>, cutoff: Option<DateTime<Utc>>) -> bool {
+    match (cutoff, upload_time) {
+        (None, _) => true,
+        (Some(cutoff), Some(upload_time)) => upload_time <= cutoff,
+        (Some(_), None) => false,
+    }
+}
+
+/// Check if a release is yanked from whether each of its usable files is
+/// and the reason given, returning the first reason
+///
+/// A release is yanked when every file of it that can be used is (PEP 592),
+/// so a release without usable files is not.
+pub fn yank_reason<'a>(files: impl IntoIterator<Item = (bool, Option<&'a str>)>) -> Option<Option<String>> {
+    let mut release_reason = None;
+    let mut any = false;
+    for (yanked, reason) in files {
+        if !yanked {
+            return None;
+        }
+        any = true;
+        if release_reason.is_none() {
+            release_reason = reason.filter(|reason| !reason.is_empty()).map(str::to_string);
+        }
+    }
+    any.then_some(release_reason)
+}
 
 /// Interface for package sources (e.g., PyPI, local directory, custom index)
 #[async_trait]
@@ -12,12 +87,42 @@ pub trait PackageSource: Send + Sync + 'static {
     /// Get available versions for a package
     async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>>;
 
+    /// Get the yanked releases of a package, which `get_versions` leaves out
+    async fn get_yanked_versions(&self, _package_name: &str) -> BlastResult<Vec<YankedRelease>> {
+        Ok(Vec::new())
+    }
+
     /// Download package
     async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>>;
 
+    /// Download a package's preferred distribution file along with its name
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        Err(BlastError::package(format!(
+            "{} cannot provide distribution files for {}",
+            self.name(),
+            id
+        )))
+    }
+
+    /// List the distribution files of a release, preferred file first
+    async fn list_artifacts(&self, _id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        Ok(Vec::new())
+    }
+
     /// Check if a package exists
     async fn package_exists(&self, id: &PackageId) -> BlastResult<bool>;
 
+    /// Get where a release from a path, URL or git source exactly comes from
+    async fn direct_reference(&self, _id: &PackageId) -> BlastResult<Option<DirectReference>> {
+        Ok(None)
+    }
+
+    /// Check if the source serves a project from a path, URL or git
+    /// repository, whose metadata may change without a new version
+    fn is_direct(&self) -> bool {
+        false
+    }
+
     /// Get source name
     fn name(&self) -> &str;
 
@@ -26,8 +131,9 @@ pub trait PackageSource: Send + Sync + 'static {
 }
 
 /// A chain of package sources that are tried in order of priority
+#[derive(Clone, Default)]
 pub struct PackageSourceChain {
-    sources: Vec<Box<dyn PackageSource>>,
+    sources: Vec<Arc<dyn PackageSource>>,
 }
 
 impl PackageSourceChain {
@@ -40,17 +146,32 @@ impl PackageSourceChain {
 
     /// Add a package source to the chain
     pub fn add_source(&mut self, source: Box<dyn PackageSource>) {
-        // Insert source in order of priority
-        let pos = self.sources
-            .binary_search_by_key(&source.priority(), |s| s.priority())
-            .unwrap_or_else(|e| e);
+        self.add_shared_source(Arc::from(source));
+    }
+
+    /// Add a package source that is also used elsewhere
+    pub fn add_shared_source(&mut self, source: Arc<dyn PackageSource>) {
+        // Insert source in order of priority, after sources of equal priority
+        let pos = self.sources.partition_point(|s| s.priority() <= source.priority());
         self.sources.insert(pos, source);
     }
 
     /// Get all sources in the chain
-    pub fn sources(&self) -> &[Box<dyn PackageSource>] {
+    pub fn sources(&self) -> &[Arc<dyn PackageSource>] {
         &self.sources
     }
+
+    /// Find the first source publishing files for a release, returning its
+    /// name along with the files
+    pub async fn find_artifacts(&self, id: &PackageId) -> Option<(String, Vec<ArtifactInfo>)> {
+        for source in &self.sources {
+            match source.list_artifacts(id).await {
+                Ok(artifacts) if !artifacts.is_empty() => return Some((source.name().to_string(), artifacts)),
+                _ => continue,
+            }
+        }
+        None
+    }
 }
 
 #[async_trait]
@@ -98,6 +219,26 @@ impl PackageSource for PackageSourceChain {
         Ok(versions)
     }
 
+    async fn get_yanked_versions(&self, package_name: &str) -> BlastResult<Vec<YankedRelease>> {
+        let mut yanked: Vec<YankedRelease> = Vec::new();
+        for source in &self.sources {
+            for release in source.get_yanked_versions(package_name).await.unwrap_or_default() {
+                if !yanked.iter().any(|known| known.version == release.version) {
+                    yanked.push(release);
+                }
+            }
+        }
+
+        // A release another source still offers is not yanked
+        if !yanked.is_empty() {
+            if let Ok(versions) = self.get_versions(package_name).await {
+                yanked.retain(|release| !versions.contains(&release.version));
+            }
+        }
+        yanked.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok(yanked)
+    }
+
     async fn download_package(&self, id: &PackageId) -> BlastResult<Vec<u8>> {
         let mut last_error = None;
 
@@ -116,6 +257,25 @@ impl PackageSource for PackageSourceChain {
         }))
     }
 
+    async fn download_artifact(&self, id: &PackageId) -> BlastResult<Artifact> {
+        let mut last_error = None;
+
+        for source in &self.sources {
+            match source.download_artifact(id).await {
+                Ok(artifact) => return Ok(artifact),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            BlastError::package(format!("No package sources available for {}", id))
+        }))
+    }
+
+    async fn list_artifacts(&self, id: &PackageId) -> BlastResult<Vec<ArtifactInfo>> {
+        Ok(self.find_artifacts(id).await.map(|(_, artifacts)| artifacts).unwrap_or_default())
+    }
+
     async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
         for source in &self.sources {
             if source.package_exists(id).await? {
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use blast_core::python::PythonVersion;
+use blast_image::platform::{Libc, PlatformInfo};
+
+use crate::filename::DistFilename;
+use crate::target::PythonTarget;
+
+/// Newest macOS major release considered when listing compatible wheels
+const LATEST_MACOS_MAJOR: u32 = 15;
+
+/// Legacy manylinux names and the glibc minor version they stand for
+const LEGACY_MANYLINUX: [(u32, &str); 3] = [(17, "manylinux2014"), (12, "manylinux2010"), (5, "manylinux1")];
+
+/// A PEP 425 compatibility tag, e.g. `cp311-abi3-manylinux_2_28_x86_64`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    /// Python tag (e.g. `cp311`, `py3`)
+    pub python: String,
+    /// ABI tag (e.g. `cp311`, `abi3`, `none`)
+    pub abi: String,
+    /// Platform tag (e.g. `manylinux_2_28_x86_64`, `any`)
+    pub platform: String,
+}
+
+impl Tag {
+    /// Create a tag from its three parts
+    pub fn new(python: impl Into<String>, abi: impl Into<String>, platform: impl Into<String>) -> Self {
+        Self {
+            python: python.into(),
+            abi: abi.into(),
+            platform: platform.into(),
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.python, self.abi, self.platform)
+    }
+}
+
+/// Wheel tags supported by the interpreters of a target
+///
+/// Tags are ranked like pip ranks them: the interpreter's own ABI on the
+/// most specific platform first, pure Python `py3-none-any` last. A lower
+/// rank is preferred.
+#[derive(Debug, Clone)]
+pub struct WheelTags {
+    /// Rank of every supported tag, one map per targeted interpreter
+    ranks: Vec<HashMap<Tag, usize>>,
+}
+
+impl WheelTags {
+    /// Compute the supported tags of every interpreter of a target
+    pub fn new(target: &PythonTarget) -> Self {
+        let ranks = target.versions().iter()
+            .map(|python| {
+                let mut ranks = HashMap::new();
+                for (rank, tag) in supported_tags(python, target.platform()).into_iter().enumerate() {
+                    ranks.entry(tag).or_insert(rank);
+                }
+                ranks
+            })
+            .collect();
+        Self { ranks }
+    }
+
+    /// Get the best rank of a wheel on any targeted interpreter, or `None`
+    /// if none of them can install it
+    pub fn rank(&self, filename: &DistFilename) -> Option<usize> {
+        (0..self.ranks.len()).filter_map(|i| self.rank_for(i, filename)).min()
+    }
+
+    /// Rank a wheel for the interpreter at `index`
+    fn rank_for(&self, index: usize, filename: &DistFilename) -> Option<usize> {
+        let ranks = &self.ranks[index];
+        let mut best = None;
+        for python in &filename.python_tags {
+            for abi in &filename.abi_tags {
+                for platform in &filename.platform_tags {
+                    if let Some(&rank) = ranks.get(&Tag::new(python.as_str(), abi.as_str(), platform.as_str())) {
+                        best = Some(best.map_or(rank, |best: usize| best.min(rank)));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Rank a distribution file for installation; wheels by their tags,
+    /// source distributions after every wheel unless they may not be built
+    pub fn preference(&self, filename: &DistFilename, no_build: bool) -> Option<usize> {
+        if filename.is_wheel() {
+            self.rank(filename)
+        } else if no_build {
+            None
+        } else {
+            Some(usize::MAX)
+        }
+    }
+
+    /// Check if a release with these files can be installed on every
+    /// targeted interpreter
+    pub fn installable<'a>(&self, files: impl IntoIterator<Item = &'a DistFilename>, no_build: bool) -> bool {
+        let mut covered = vec![false; self.ranks.len()];
+        for filename in files {
+            if !filename.is_wheel() {
+                if !no_build {
+                    return true;
+                }
+                continue;
+            }
+            for (i, covered) in covered.iter_mut().enumerate() {
+                *covered |= self.rank_for(i, filename).is_some();
+            }
+        }
+        covered.iter().all(|covered| *covered)
+    }
+}
+
+/// List the tags a CPython interpreter supports on a platform, most
+/// preferred first
+///
+/// This follows `packaging.tags.sys_tags`: the interpreter's ABI, then
+/// `abi3` back to 3.2, then `none`, each on every platform tag, followed by
+/// the generic `py3` tags.
+pub fn supported_tags(python: &PythonVersion, platform: &PlatformInfo) -> Vec<Tag> {
+    let (major, minor) = (python.major(), python.minor());
+    let interpreter = format!("cp{}{}", major, minor);
+    let platforms = platform_tags(platform);
+    let mut tags = Vec::new();
+
+    // Before 3.8 the default ABI carried the pymalloc `m` flag
+    let abi = if major == 3 && minor < 8 { format!("{}m", interpreter) } else { interpreter.clone() };
+    let abi3 = major == 3 && minor >= 2;
+    tags.extend(platforms.iter().map(|platform| Tag::new(&interpreter, &abi, platform)));
+    if abi3 {
+        tags.extend(platforms.iter().map(|platform| Tag::new(&interpreter, "abi3", platform)));
+    }
+    tags.extend(platforms.iter().map(|platform| Tag::new(&interpreter, "none", platform)));
+    if abi3 {
+        for older in (2..minor).rev() {
+            let older = format!("cp{}{}", major, older);
+            tags.extend(platforms.iter().map(|platform| Tag::new(&older, "abi3", platform)));
+        }
+    }
+
+    // Generic tags: py311, py3, py310, ..., py30
+    let mut generic = vec![format!("py{}{}", major, minor), format!("py{}", major)];
+    generic.extend((0..minor).rev().map(|older| format!("py{}{}", major, older)));
+    for python in &generic {
+        tags.extend(platforms.iter().map(|platform| Tag::new(python, "none", platform)));
+    }
+    tags.push(Tag::new(&interpreter, "none", "any"));
+    tags.extend(generic.iter().map(|python| Tag::new(python, "none", "any")));
+    tags
+}
+
+/// List the platform tags of a platform, most specific first
+pub fn platform_tags(platform: &PlatformInfo) -> Vec<String> {
+    let arch = platform.arch.as_str();
+    match platform.os.as_str() {
+        "linux" => linux_tags(arch, platform.libc),
+        "macos" => macos_tags(arch),
+        "windows" => match arch {
+            "x86_64" => vec!["win_amd64".to_string()],
+            "x86" => vec!["win32".to_string()],
+            "aarch64" => vec!["win_arm64".to_string()],
+            _ => Vec::new(),
+        },
+        os => vec![format!("{}_{}", os, arch)],
+    }
+}
+
+/// manylinux or musllinux tags down to the oldest supported libc, then the
+/// plain `linux_<arch>` tag
+fn linux_tags(arch: &str, libc: Option<Libc>) -> Vec<String> {
+    let arch = match arch {
+        "x86" => "i686",
+        "arm" => "armv7l",
+        "powerpc64" => "ppc64le",
+        arch => arch,
+    };
+    let mut tags = Vec::new();
+    match libc {
+        Some(Libc::Glibc(2, newest)) => {
+            // manylinux starts at glibc 2.5 on x86, 2.17 everywhere else
+            let oldest = if matches!(arch, "x86_64" | "i686") { 5 } else { 17 };
+            for minor in (oldest..=newest).rev() {
+                tags.push(format!("manylinux_2_{}_{}", minor, arch));
+                let legacy = LEGACY_MANYLINUX.iter().find(|(legacy, _)| *legacy == minor);
+                if let Some((_, name)) = legacy.filter(|(legacy, _)| *legacy == 17 || oldest == 5) {
+                    tags.push(format!("{}_{}", name, arch));
+                }
+            }
+        }
+        Some(Libc::Musl(1, newest)) => {
+            tags.extend((0..=newest).rev().map(|minor| format!("musllinux_1_{}_{}", minor, arch)));
+        }
+        _ => {}
+    }
+    tags.push(format!("linux_{}", arch));
+    tags
+}
+
+/// macOS tags for every release up to the newest known one, native
+/// architecture before `universal2`
+fn macos_tags(arch: &str) -> Vec<String> {
+    let arch = if arch == "aarch64" { "arm64" } else { arch };
+    let mut versions: Vec<(u32, u32)> = (11..=LATEST_MACOS_MAJOR).rev().map(|major| (major, 0)).collect();
+    // Intel Macs also run wheels built for macOS 10.x
+    if arch == "x86_64" {
+        versions.extend((4..=16).rev().map(|minor| (10, minor)));
+    }
+
+    let mut tags = Vec::new();
+    for (major, minor) in versions {
+        tags.push(format!("macosx_{}_{}_{}", major, minor, arch));
+        tags.push(format!("macosx_{}_{}_universal2", major, minor));
+    }
+    tags
+}
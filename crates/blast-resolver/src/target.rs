@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str::FromStr;
+
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::python::PythonVersion;
+use blast_core::version::VersionConstraint;
+use blast_image::platform::PlatformInfo;
+
+use crate::marker::MarkerEnvironment;
+
+/// Newest CPython 3 minor release considered when expanding a range of
+/// supported interpreters
+const LATEST_PYTHON_MINOR: u32 = 14;
+
+/// Python interpreter(s) a resolution has to support
+///
+/// A target is either the single interpreter of an environment, or a range
+/// of supported interpreters such as `>=3.9` which expands to every known
+/// CPython minor release within it. The interpreters run on the host
+/// platform unless another one is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonTarget {
+    versions: Vec<PythonVersion>,
+    constraint: VersionConstraint,
+    platform: PlatformInfo,
+}
+
+impl PythonTarget {
+    /// Target a single interpreter version
+    pub fn version(version: PythonVersion) -> Self {
+        let constraint = VersionConstraint::exact(version.to_version());
+        Self {
+            versions: vec![version],
+            constraint,
+            platform: PlatformInfo::current(),
+        }
+    }
+
+    /// Target every known interpreter matching a `requires_python` style range
+    pub fn range(constraint: VersionConstraint) -> BlastResult<Self> {
+        let versions: Vec<PythonVersion> = (0..=LATEST_PYTHON_MINOR)
+            .map(|minor| PythonVersion::new(3, minor, None))
+            .filter(|version| constraint.matches(&version.to_version()))
+            .collect();
+
+        if versions.is_empty() {
+            return Err(BlastError::resolution(format!(
+                "Python range '{}' does not contain any known Python version",
+                constraint
+            )));
+        }
+
+        Ok(Self {
+            versions,
+            constraint,
+            platform: PlatformInfo::current(),
+        })
+    }
+
+    /// Target interpreters running on another platform
+    pub fn with_platform(mut self, platform: PlatformInfo) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// Parse a target, either a version such as `3.11` or a range such as `>=3.9`
+    pub fn parse(target: &str) -> BlastResult<Self> {
+        match PythonVersion::parse(target.trim()) {
+            Ok(version) => Ok(Self::version(version)),
+            Err(_) => Self::range(VersionConstraint::parse(target)?),
+        }
+    }
+
+    /// Get the interpreter versions covered by this target
+    pub fn versions(&self) -> &[PythonVersion] {
+        &self.versions
+    }
+
+    /// Get the platform the interpreters run on
+    pub fn platform(&self) -> &PlatformInfo {
+        &self.platform
+    }
+
+    /// Check if a release's `requires_python` admits every targeted interpreter
+    pub fn allows(&self, requires_python: &VersionConstraint) -> bool {
+        self.versions.iter().all(|version| requires_python.matches(&version.to_version()))
+    }
+
+    /// Build the marker environments of every targeted interpreter on a platform
+    pub fn marker_environments(&self, platform: &PlatformInfo) -> Vec<MarkerEnvironment> {
+        self.versions.iter()
+            .map(|version| MarkerEnvironment::new(version, platform))
+            .collect()
+    }
+}
+
+impl Default for PythonTarget {
+    fn default() -> Self {
+        Self::version(PythonVersion::default())
+    }
+}
+
+impl From<PythonVersion> for PythonTarget {
+    fn from(version: PythonVersion) -> Self {
+        Self::version(version)
+    }
+}
+
+impl FromStr for PythonTarget {
+    type Err = BlastError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for PythonTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.versions.as_slice() {
+            [version] => write!(f, "{}", version),
+            _ => write!(f, "{}", self.constraint),
+        }
+    }
+}
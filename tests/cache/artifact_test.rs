@@ -0,0 +1,58 @@
+use blast_cache::ArtifactCache;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_artifact_store_and_get() {
+    let dir = tempdir().unwrap();
+    let artifacts = ArtifactCache::new(dir.path());
+
+    assert!(artifacts.get("Demo.Pkg", "1.0").await.unwrap().is_none());
+    assert!(!artifacts.contains("demo-pkg", "1.0").await.unwrap());
+
+    artifacts.store("Demo.Pkg", "1.0", "demo_pkg-1.0.tar.gz", b"sdist").await.unwrap();
+    let wheel = artifacts.store("demo_pkg", "1.0", "demo_pkg-1.0-py3-none-any.whl", b"wheel").await.unwrap();
+    assert_eq!(wheel.name, "demo-pkg");
+    assert_eq!(wheel.path, dir.path().join("demo-pkg/1.0/demo_pkg-1.0-py3-none-any.whl"));
+
+    // Wheels are preferred over source distributions
+    let cached = artifacts.get("DEMO-PKG", "1.0").await.unwrap().unwrap();
+    assert_eq!(cached, wheel);
+    assert_eq!(std::fs::read(&cached.path).unwrap(), b"wheel");
+    assert_eq!(cached.sha256.len(), 64);
+    assert!(artifacts.contains("demo-pkg", "1.0").await.unwrap());
+    assert!(!artifacts.contains("demo-pkg", "2.0").await.unwrap());
+
+    // Any one file of a release can be asked for by name
+    let sdist = artifacts.get_file("demo-pkg", "1.0", "demo_pkg-1.0.tar.gz").await.unwrap().unwrap();
+    assert_eq!(std::fs::read(&sdist.path).unwrap(), b"sdist");
+    assert!(artifacts.get_file("demo-pkg", "1.0", "demo_pkg-1.0-cp311-none-any.whl").await.unwrap().is_none());
+    assert!(artifacts.get_file("demo-pkg", "1.0", "../1.0/demo_pkg-1.0.tar.gz").await.unwrap().is_none());
+
+    let all = artifacts.get_all("demo-pkg", "1.0").await.unwrap();
+    assert_eq!(all, vec![wheel, sdist]);
+    assert!(artifacts.get_all("demo-pkg", "2.0").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_artifact_list() {
+    let dir = tempdir().unwrap();
+    let artifacts = ArtifactCache::new(dir.path());
+    assert!(artifacts.list().await.unwrap().is_empty());
+
+    artifacts.store("b", "2.0", "b-2.0-py3-none-any.whl", b"b").await.unwrap();
+    artifacts.store("a", "1.0", "a-1.0-py3-none-any.whl", b"a").await.unwrap();
+    // Leftovers of interrupted writes are not artifacts
+    std::fs::write(dir.path().join("a/1.0/.a-1.1.whl.partial"), b"").unwrap();
+
+    let listed: Vec<_> = artifacts.list().await.unwrap().into_iter().map(|a| a.filename).collect();
+    assert_eq!(listed, vec!["a-1.0-py3-none-any.whl", "b-2.0-py3-none-any.whl"]);
+}
+
+#[tokio::test]
+async fn test_artifact_invalid_filename() {
+    let dir = tempdir().unwrap();
+    let artifacts = ArtifactCache::new(dir.path());
+
+    assert!(artifacts.store("a", "1.0", "../escape.whl", b"").await.is_err());
+    assert!(artifacts.store("a", "1.0", "", b"").await.is_err());
+}
@@ -5,3 +5,5 @@ mod compression_test;
 mod lru_test;
 mod index_test;
 mod lib_test;
+mod artifact_test;
+mod store_test;
@@ -0,0 +1,207 @@
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use blast_cache::{PackageStore, STORE_DIR};
+use blast_core::environment::package::{UnpackedWheel, WheelInstaller, WheelStore};
+use blast_core::types::{CacheSettings, LinkMode};
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+use zip::write::FileOptions;
+
+/// Write `demo-1.0-py3-none-any.whl` with a module, a native library and a
+/// script
+fn write_wheel(dir: &Path) -> PathBuf {
+    let path = dir.join("demo-1.0-py3-none-any.whl");
+    let mut wheel = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    let mut record = String::new();
+    for (name, content, mode) in [
+        ("demo/__init__.py", "VALUE = 1\n", 0o644),
+        ("demo/_speedups.so", "\x7fELF", 0o755),
+        ("demo-1.0.data/scripts/demo-tool", "#!python\nprint('tool')\n", 0o644),
+        ("demo-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n", 0o644),
+        ("demo-1.0.dist-info/WHEEL", "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n", 0o644),
+    ] {
+        wheel.start_file(name, FileOptions::default().unix_permissions(mode)).unwrap();
+        wheel.write_all(content.as_bytes()).unwrap();
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(content.as_bytes()));
+        record.push_str(&format!("{},sha256={},{}\n", name, hash, content.len()));
+    }
+    record.push_str("demo-1.0.dist-info/RECORD,,\n");
+    wheel.start_file("demo-1.0.dist-info/RECORD", FileOptions::default()).unwrap();
+    wheel.write_all(record.as_bytes()).unwrap();
+    wheel.finish().unwrap();
+    path
+}
+
+fn installer(env: &Path, store: &PackageStore) -> WheelInstaller {
+    WheelInstaller::for_env(env, "3.11").with_store(Arc::new(store.clone()))
+}
+
+#[test]
+fn test_link_modes() {
+    assert_eq!(CacheSettings::default().link_modes(), vec![LinkMode::Reflink, LinkMode::Hardlink, LinkMode::Copy]);
+    let settings = CacheSettings {
+        use_cow: false,
+        ..CacheSettings::default()
+    };
+    assert_eq!(settings.link_modes(), vec![LinkMode::Hardlink, LinkMode::Copy]);
+
+    let store = PackageStore::from_settings(&settings);
+    assert_eq!(store.root(), settings.cache_dir.join(STORE_DIR));
+}
+
+#[test]
+fn test_store_unpack() {
+    let dir = tempdir().unwrap();
+    let store = PackageStore::new(dir.path().join("store"));
+    let wheel = write_wheel(dir.path());
+
+    let unpacked = store.unpack(&wheel).unwrap();
+    assert_eq!(unpacked.sha256, hex::encode(Sha256::digest(fs::read(&wheel).unwrap())));
+    assert_eq!(fs::read_to_string(unpacked.path.join("demo/__init__.py")).unwrap(), "VALUE = 1\n");
+    assert!(fs::metadata(unpacked.path.join("demo/_speedups.so")).unwrap().mode() & 0o111 != 0);
+
+    // The same wheel is unpacked only once
+    assert_eq!(store.unpack(&wheel).unwrap(), unpacked);
+    assert_eq!(store.get(&unpacked.sha256), Some(unpacked.clone()));
+    assert_eq!(store.list().unwrap(), vec![unpacked.sha256]);
+}
+
+#[test]
+fn test_store_rejects_invalid_wheel() {
+    let dir = tempdir().unwrap();
+    let store = PackageStore::new(dir.path().join("store"));
+    let wheel = write_wheel(dir.path());
+    let mut tampered = zip::ZipWriter::new_append(fs::OpenOptions::new().read(true).write(true).open(&wheel).unwrap()).unwrap();
+    tampered.start_file("demo/__init__.py", FileOptions::default()).unwrap();
+    tampered.write_all(b"VALUE = 2\n").unwrap();
+    tampered.finish().unwrap();
+
+    let error = store.unpack(&wheel).unwrap_err().to_string();
+    assert!(error.contains("does not match the hash"), "{}", error);
+    // Nothing is left behind, not even a partial unpack
+    assert_eq!(fs::read_dir(dir.path().join("store/wheels")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_store_install_links() {
+    let dir = tempdir().unwrap();
+    let wheel = write_wheel(dir.path());
+
+    // Reflinks are not supported on every filesystem, and installs fall
+    // back to the next mode where they are not
+    let store = PackageStore::new(dir.path().join("store")).with_link_modes(vec![LinkMode::Reflink, LinkMode::Hardlink]);
+    let env = dir.path().join("linked");
+    let installed = installer(&env, &store).install(&wheel).unwrap();
+    let unpacked = store.unpack(&wheel).unwrap();
+    let module = env.join("lib/python3.11/site-packages/demo/__init__.py");
+    let stored = unpacked.path.join("demo/__init__.py");
+    assert_eq!(fs::read_to_string(&module).unwrap(), "VALUE = 1\n");
+    assert!(fs::metadata(env.join("lib/python3.11/site-packages/demo/_speedups.so")).unwrap().mode() & 0o111 != 0);
+
+    // Scripts are rewritten for the environment, never linked
+    let tool = fs::read_to_string(env.join("bin/demo-tool")).unwrap();
+    assert!(tool.starts_with(&format!("#!{}", env.join("bin/python").display())), "{}", tool);
+    assert!(!fs::read_to_string(unpacked.path.join("demo-1.0.data/scripts/demo-tool")).unwrap().contains("bin/python"));
+
+    // RECORD lists the linked files with their hashes
+    let record = fs::read_to_string(installed.dist_info.join("RECORD")).unwrap();
+    let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(b"VALUE = 1\n"));
+    assert!(record.contains(&format!("demo/__init__.py,sha256={},10\n", hash)), "{}", record);
+
+    // Hard links share the stored file
+    let store = store.with_link_modes(vec![LinkMode::Hardlink]);
+    let env = dir.path().join("hardlinked");
+    installer(&env, &store).install(&wheel).unwrap();
+    let module = env.join("lib/python3.11/site-packages/demo/__init__.py");
+    assert_eq!(fs::metadata(&module).unwrap().ino(), fs::metadata(&stored).unwrap().ino());
+
+    // Copies do not, and reinstalling replaces the file instead of writing
+    // through a link into the store
+    let store = store.with_link_modes(vec![LinkMode::Copy]);
+    installer(&env, &store).install(&wheel).unwrap();
+    assert_ne!(fs::metadata(&module).unwrap().ino(), fs::metadata(&stored).unwrap().ino());
+    assert_eq!(fs::read_to_string(&module).unwrap(), "VALUE = 1\n");
+    assert_eq!(fs::read_to_string(&stored).unwrap(), "VALUE = 1\n");
+}
+
+#[test]
+fn test_store_gc() {
+    let dir = tempdir().unwrap();
+    let store = PackageStore::new(dir.path().join("store"));
+    let wheel = write_wheel(dir.path());
+    let first = installer(&dir.path().join("first"), &store);
+    let second = installer(&dir.path().join("second"), &store);
+    let a = first.install(&wheel).unwrap();
+    let b = second.install(&wheel).unwrap();
+    let sha256 = store.unpack(&wheel).unwrap().sha256;
+
+    let mut references = store.references(&sha256).unwrap();
+    references.sort();
+    assert_eq!(references, vec![a.dist_info, b.dist_info.clone()]);
+
+    // A wheel stays while any environment still has it installed
+    first.uninstall("demo").unwrap();
+    assert_eq!(store.references(&sha256).unwrap(), vec![b.dist_info]);
+    assert!(store.gc().unwrap().removed.is_empty());
+    assert!(store.get(&sha256).is_some());
+
+    // Deleting an environment outright releases its references too
+    fs::remove_dir_all(dir.path().join("second")).unwrap();
+    let collection = store.gc().unwrap();
+    assert_eq!(collection.removed, vec![sha256.clone()]);
+    assert!(collection.bytes > 0);
+    assert!(store.get(&sha256).is_none());
+    assert!(store.list().unwrap().is_empty());
+    assert!(!dir.path().join("store/refs").join(&sha256).exists());
+    assert_eq!(fs::read_dir(dir.path().join("store/refs-by-dist")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_store_reinstall_moves_reference() {
+    let dir = tempdir().unwrap();
+    let store = PackageStore::new(dir.path().join("store"));
+    let dist_info = dir.path().join("env/demo-1.0.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    let old = UnpackedWheel { sha256: "a".repeat(64), path: dir.path().join("old") };
+    let new = UnpackedWheel { sha256: "b".repeat(64), path: dir.path().join("new") };
+
+    store.add_reference(&old, &dist_info).unwrap();
+    assert_eq!(store.references(&old.sha256).unwrap(), vec![dist_info.clone()]);
+
+    // The distribution now comes from another wheel, which alone references it
+    store.add_reference(&new, &dist_info).unwrap();
+    assert!(store.references(&old.sha256).unwrap().is_empty());
+    assert_eq!(store.references(&new.sha256).unwrap(), vec![dist_info.clone()]);
+    store.add_reference(&new, &dist_info).unwrap();
+    assert_eq!(store.references(&new.sha256).unwrap(), vec![dist_info]);
+}
+
+#[test]
+fn test_store_gc_waits_for_installs() {
+    let dir = tempdir().unwrap();
+    let store = Arc::new(PackageStore::new(dir.path().join("store")));
+    let wheel = write_wheel(dir.path());
+
+    // A wheel unpacked by an install that has not linked it yet is
+    // unreferenced, but collection waits for the install to finish
+    let lock = store.lock_shared().unwrap();
+    let sha256 = store.unpack(&wheel).unwrap().sha256;
+    let gc = std::thread::spawn({
+        let store = store.clone();
+        move || store.gc().unwrap()
+    });
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert!(!gc.is_finished());
+    let installed = installer(&dir.path().join("env"), &store).install(&wheel);
+    drop(lock);
+
+    installed.unwrap();
+    assert!(gc.join().unwrap().removed.is_empty());
+    assert!(store.get(&sha256).is_some());
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use blast_core::config::{
+    BlastConfig, DependenciesConfig, DependencyGroupEntry, DependencySource, DependencySpec, GroupSelection,
+};
+use blast_core::python::PythonVersion;
+
+const GROUPS: &str = r#"
+[dependency-groups]
+test = ["pytest>=8", "coverage[toml]", { include-group = "Typing" }]
+typing = ["mypy==1.8.0"]
+dev = ["ruff"]
+"#;
+
+fn spec(name: &str, version: &str) -> DependencySpec {
+    DependencySpec {
+        name: name.to_string(),
+        version: version.to_string(),
+        extras: None,
+        index: None,
+        source: None,
+        marker: None,
+    }
+}
+
+/// A project config with main and dev dependencies and the groups above
+fn project_config() -> BlastConfig {
+    let mut config = BlastConfig::new("project", "0.1.0", PythonVersion::new(3, 11, None), PathBuf::from("."));
+    config.dependencies.packages.push(spec("requests", ">=2.31"));
+    config.dev_dependencies = Some(DependenciesConfig {
+        packages: vec![spec("ipython", "*")],
+        ..DependenciesConfig::default()
+    });
+    BlastConfig::from_toml(&format!("{}\n{}", config.to_toml().unwrap(), GROUPS)).unwrap()
+}
+
+fn names(specs: &[DependencySpec]) -> Vec<&str> {
+    specs.iter().map(|spec| spec.name.as_str()).collect()
+}
+
+#[test]
+fn test_parse_requirement() {
+    let spec = DependencySpec::parse("requests[socks, security] >=2.31,<3").unwrap();
+    assert_eq!(spec.name, "requests");
+    assert_eq!(spec.version, ">=2.31,<3");
+    assert_eq!(spec.extras, Some(vec!["socks".to_string(), "security".to_string()]));
+
+    assert_eq!(DependencySpec::parse("pytest").unwrap().version, "*");
+    let spec = DependencySpec::parse("pywin32>=306 ; sys_platform == 'win32'").unwrap();
+    assert_eq!(spec.version, ">=306");
+    assert_eq!(spec.marker.as_deref(), Some("sys_platform == 'win32'"));
+    assert_eq!(DependencySpec::parse("pytest").unwrap().marker, None);
+    assert!(DependencySpec::parse("pywin32;").is_err());
+    assert!(DependencySpec::parse(">=1.0").is_err());
+}
+
+#[test]
+fn test_dependency_groups() {
+    let config = project_config();
+    assert_eq!(config.dependency_groups["typing"], vec![DependencyGroupEntry::Requirement("mypy==1.8.0".to_string())]);
+
+    // Included groups are expanded, whatever the case of their name
+    let test = config.dependency_group("test").unwrap();
+    assert_eq!(names(&test), vec!["pytest", "coverage", "mypy"]);
+    assert_eq!(test[1].extras, Some(vec!["toml".to_string()]));
+
+    let selection = GroupSelection { groups: vec!["test".to_string()], dev: false };
+    assert_eq!(names(&config.requirements(&selection).unwrap()), vec!["requests", "pytest", "coverage", "mypy"]);
+
+    // The default selection brings dev_dependencies and the dev group
+    let requirements = config.requirements(&GroupSelection::default()).unwrap();
+    assert_eq!(names(&requirements), vec!["requests", "ipython", "ruff"]);
+    assert_eq!(config.all_requirements().unwrap().len(), 7);
+
+    let error = config.dependency_group("docs").unwrap_err();
+    assert!(error.to_string().contains("Unknown dependency group 'docs'"), "{}", error);
+
+    // Round trips through TOML under the PEP 735 table name
+    let toml = config.to_toml().unwrap();
+    assert!(toml.contains("[dependency-groups]"), "{}", toml);
+    assert_eq!(BlastConfig::from_toml(&toml).unwrap().dependency_groups, config.dependency_groups);
+}
+
+#[test]
+fn test_dependency_group_cycle() {
+    let mut config = project_config();
+    config.dependency_groups.get_mut("typing").unwrap().push(DependencyGroupEntry::Include {
+        include_group: "test".to_string(),
+    });
+    let error = config.dependency_group("test").unwrap_err();
+    assert!(error.to_string().contains("test -> typing -> test"), "{}", error);
+}
+
+#[test]
+fn test_direct_references() {
+    let spec = DependencySpec::parse("mylib @ git+https://token@github.com/org/mylib.git@v1.0").unwrap();
+    assert_eq!(spec.version, "*");
+    assert_eq!(spec.source, Some(DependencySource::Git {
+        git: "https://token@github.com/org/mylib.git".to_string(),
+        rev: Some("v1.0".to_string()),
+        tag: None,
+        branch: None,
+    }));
+    assert_eq!(spec.source.unwrap().git_reference().as_deref(), Some("v1.0"));
+
+    let spec = DependencySpec::parse("mylib[cli] @ https://example.com/mylib-1.0-py3-none-any.whl").unwrap();
+    assert_eq!(spec.extras, Some(vec!["cli".to_string()]));
+    assert_eq!(spec.source, Some(DependencySource::Url { url: "https://example.com/mylib-1.0-py3-none-any.whl".to_string() }));
+    let spec = DependencySpec::parse("mylib @ file:///src/mylib").unwrap();
+    assert_eq!(spec.source, Some(DependencySource::Path { path: PathBuf::from("/src/mylib"), editable: false }));
+    let spec = DependencySpec::parse("mylib @ https://example.com/mylib.zip;v=1 ; python_version >= '3.12'").unwrap();
+    assert_eq!(spec.source, Some(DependencySource::Url { url: "https://example.com/mylib.zip;v=1".to_string() }));
+    assert_eq!(spec.marker.as_deref(), Some("python_version >= '3.12'"));
+    assert!(DependencySpec::parse("mylib @ ftp://example.com/mylib.zip").is_err());
+
+    // Sources are tables next to the name in blast.toml
+    let config = project_config();
+    let toml = config.to_toml().unwrap().replace(
+        "[[dependencies.packages]]",
+        "[[dependencies.packages]]\nname = \"tool\"\ngit = \"https://example.com/tool.git\"\nbranch = \"main\"\n\n\
+         [[dependencies.packages]]\nname = \"local\"\npath = \"../local\"\neditable = true\n\n\
+         [[dependencies.packages]]",
+    );
+    let config = BlastConfig::from_toml(&toml).unwrap();
+    let sources: Vec<Option<DependencySource>> = config.dependencies.packages.iter().map(|spec| spec.source.clone()).collect();
+    assert_eq!(sources, vec![
+        Some(DependencySource::Git {
+            git: "https://example.com/tool.git".to_string(),
+            rev: None,
+            tag: None,
+            branch: Some("main".to_string()),
+        }),
+        Some(DependencySource::Path { path: PathBuf::from("../local"), editable: true }),
+        None,
+    ]);
+    assert_eq!(sources[0].as_ref().unwrap().git_reference().as_deref(), Some("refs/heads/main"));
+    let reparsed = BlastConfig::from_toml(&config.to_toml().unwrap()).unwrap();
+    assert_eq!(reparsed.dependencies.packages[1].source, sources[1]);
+}
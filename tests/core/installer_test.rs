@@ -0,0 +1,293 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use blast_core::environment::package::{
+    DependencyGraph, InstallationStep, PackageConfig, PackageInstaller, ProgressTracker, SchedulerConfig,
+    SyncAction, WheelInstaller,
+};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+use zip::write::FileOptions;
+
+/// Put a wheel with a single module into the artifact cache layout
+fn cache_wheel(cache_dir: &Path, name: &str, version: &str) {
+    cache_tagged_wheel(cache_dir, name, version, "py3-none-any", "");
+}
+
+/// Put a wheel with the given tag into the artifact cache layout, its
+/// module holding `content`
+fn cache_tagged_wheel(cache_dir: &Path, name: &str, version: &str, tag: &str, content: &str) {
+    let dir = cache_dir.join("artifacts").join(name).join(version);
+    fs::create_dir_all(&dir).unwrap();
+    let mut wheel = zip::ZipWriter::new(fs::File::create(dir.join(format!("{}-{}-{}.whl", name, version, tag))).unwrap());
+    let files = [
+        (format!("{}.py", name), content.to_string()),
+        (format!("{}-{}.dist-info/METADATA", name, version), format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n", name, version)),
+        (format!("{}-{}.dist-info/WHEEL", name, version), "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n".to_string()),
+    ];
+    let mut record = String::new();
+    for (path, content) in &files {
+        wheel.start_file(path.as_str(), FileOptions::default()).unwrap();
+        wheel.write_all(content.as_bytes()).unwrap();
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(content.as_bytes()));
+        record.push_str(&format!("{},sha256={},{}\n", path, hash, content.len()));
+    }
+    let record_path = format!("{}-{}.dist-info/RECORD", name, version);
+    record.push_str(&format!("{},,\n", record_path));
+    wheel.start_file(record_path, FileOptions::default()).unwrap();
+    wheel.write_all(record.as_bytes()).unwrap();
+    wheel.finish().unwrap();
+}
+
+/// app depends on web and db, which both depend on core
+fn app_graph() -> DependencyGraph {
+    let mut graph = DependencyGraph::new();
+    for name in ["app", "web", "db", "core"] {
+        graph.add_package(name, "1.0".to_string());
+    }
+    graph.add_dependency("app", "web");
+    graph.add_dependency("app", "db");
+    graph.add_dependency("web", "core");
+    graph.add_dependency("db", "core");
+    graph
+}
+
+fn level_names(graph: &DependencyGraph) -> Vec<Vec<&str>> {
+    graph.installation_levels()
+        .into_iter()
+        .map(|level| level.into_iter().map(|node| node.name.as_str()).collect())
+        .collect()
+}
+
+fn installer(dir: &Path) -> PackageInstaller {
+    PackageInstaller::new(PackageConfig {
+        python_version: "3.11".to_string(),
+        env_path: dir.join("env"),
+        cache_dir: dir.join("cache"),
+        offline: true,
+        ..PackageConfig::default()
+    })
+}
+
+#[test]
+fn test_installation_levels() {
+    let graph = app_graph();
+    assert_eq!(level_names(&graph), vec![vec!["core"], vec!["db", "web"], vec!["app"]]);
+
+    // Packages in or depending on a cycle go last, together
+    let mut graph = app_graph();
+    graph.add_package("plugin", "1.0".to_string());
+    graph.add_dependency("core", "plugin");
+    graph.add_dependency("plugin", "core");
+    graph.add_package("cli", "1.0".to_string());
+    assert_eq!(level_names(&graph), vec![vec!["cli"], vec!["app", "core", "db", "plugin", "web"]]);
+}
+
+#[tokio::test]
+async fn test_install_packages() {
+    let dir = TempDir::new().unwrap();
+    for name in ["app", "web", "db", "core"] {
+        cache_wheel(&dir.path().join("cache"), name, "1.0");
+    }
+    let tracker = Arc::new(ProgressTracker::new());
+    let mut updates = tracker.subscribe();
+    let installer = installer(dir.path())
+        .with_scheduler_config(&SchedulerConfig { max_concurrent_ops: 2, ..SchedulerConfig::default() })
+        .with_progress(tracker);
+
+    installer.install_packages(&app_graph()).await.unwrap();
+
+    let site_packages = dir.path().join("env/lib/python3.11/site-packages");
+    for name in ["app", "web", "db", "core"] {
+        assert!(site_packages.join(format!("{}.py", name)).is_file(), "{} is not installed", name);
+    }
+
+    // Every package reports its progress, and nothing is unpacked before
+    // its dependencies are complete
+    let mut events = Vec::new();
+    while let Ok(update) = updates.try_recv() {
+        events.push((update.package_name, update.step));
+    }
+    let position = |name: &str, step: InstallationStep| {
+        events.iter().position(|event| event.0 == name && event.1 == step)
+            .unwrap_or_else(|| panic!("no {:?} for {} in {:?}", step, name, events))
+    };
+    assert!(position("core", InstallationStep::Complete) < position("web", InstallationStep::Installing));
+    assert!(position("core", InstallationStep::Complete) < position("db", InstallationStep::Installing));
+    assert!(position("web", InstallationStep::Complete) < position("app", InstallationStep::Installing));
+    assert!(position("db", InstallationStep::Complete) < position("app", InstallationStep::Installing));
+}
+
+#[tokio::test]
+async fn test_install_missing_wheel() {
+    let dir = TempDir::new().unwrap();
+    for name in ["app", "web", "core"] {
+        cache_wheel(&dir.path().join("cache"), name, "1.0");
+    }
+    let installer = installer(dir.path());
+
+    let error = installer.install_packages(&app_graph()).await.unwrap_err().to_string();
+    assert!(error.contains("db==1.0"), "{}", error);
+    assert!(!dir.path().join("env").exists());
+}
+
+#[tokio::test]
+async fn test_install_ranked_wheel() {
+    let dir = TempDir::new().unwrap();
+    let cache = dir.path().join("cache");
+    cache_tagged_wheel(&cache, "core", "1.0", "cp311-cp311-manylinux_2_17_x86_64", "NATIVE = True\n");
+    cache_tagged_wheel(&cache, "core", "1.0", "cp311-cp311-win_amd64", "WINDOWS = True\n");
+    cache_tagged_wheel(&cache, "core", "1.0", "py3-none-any", "NATIVE = False\n");
+    let mut graph = DependencyGraph::new();
+    graph.add_package("core", "1.0".to_string());
+    // Linux wheels first, then pure ones; nothing else is installable
+    let ranking = |filename: &str| {
+        if filename.contains("manylinux") {
+            Some(0)
+        } else if filename.ends_with("-none-any.whl") {
+            Some(1)
+        } else {
+            None
+        }
+    };
+
+    installer(dir.path()).with_wheel_ranking(ranking).install_packages(&graph).await.unwrap();
+    let module = dir.path().join("env/lib/python3.11/site-packages/core.py");
+    assert_eq!(fs::read_to_string(&module).unwrap(), "NATIVE = True\n");
+
+    // Wheels for other platforms are never installed, even if they are all
+    // the cache has
+    let dir = TempDir::new().unwrap();
+    cache_tagged_wheel(&dir.path().join("cache"), "core", "1.0", "cp311-cp311-win_amd64", "WINDOWS = True\n");
+    let error = installer(dir.path()).with_wheel_ranking(ranking).install_packages(&graph).await.unwrap_err().to_string();
+    assert!(error.contains("no compatible wheel in the artifact cache for: core==1.0"), "{}", error);
+    assert!(!dir.path().join("env").exists());
+}
+
+#[tokio::test]
+async fn test_install_given_wheel() {
+    let dir = TempDir::new().unwrap();
+    let cache = dir.path().join("cache");
+    cache_tagged_wheel(&cache, "core", "1.0", "cp311-cp311-manylinux_2_17_x86_64", "NATIVE = True\n");
+    cache_tagged_wheel(&cache, "core", "1.0", "py3-none-any", "NATIVE = False\n");
+    let pinned = cache.join("artifacts/core/1.0/core-1.0-py3-none-any.whl");
+    let mut graph = DependencyGraph::new();
+    graph.add_package("Core", "1.0".to_string());
+
+    // The given wheel is installed, however the cached ones rank
+    installer(dir.path())
+        .with_wheel_ranking(|filename: &str| Some(usize::from(!filename.contains("manylinux"))))
+        .with_wheel("core", pinned)
+        .install_packages(&graph)
+        .await
+        .unwrap();
+    let module = dir.path().join("env/lib/python3.11/site-packages/core.py");
+    assert_eq!(fs::read_to_string(&module).unwrap(), "NATIVE = False\n");
+}
+
+/// An environment with core up to date, db older and web newer than
+/// app_graph wants, plus a package blast installed and one pip installed
+fn outdated_env(dir: &Path) -> WheelInstaller {
+    let cache = dir.join("cache");
+    for (name, version) in [("app", "1.0"), ("web", "1.0"), ("db", "1.0"), ("core", "1.0"), ("db", "0.9"), ("web", "2.0"), ("old", "1.0"), ("tool", "3.0")] {
+        cache_wheel(&cache, name, version);
+    }
+    let wheels = WheelInstaller::for_env(&dir.join("env"), "3.11");
+    for (name, version) in [("core", "1.0"), ("db", "0.9"), ("web", "2.0"), ("old", "1.0"), ("tool", "3.0")] {
+        let wheel = cache.join("artifacts").join(name).join(version).join(format!("{}-{}-py3-none-any.whl", name, version));
+        wheels.install(&wheel).unwrap();
+    }
+    let tool = wheels.find_installed("tool").unwrap().unwrap();
+    fs::write(tool.join("INSTALLER"), "pip\n").unwrap();
+    wheels
+}
+
+fn action(kind: &str, name: &str, versions: &[&str]) -> SyncAction {
+    let (name, v) = (name.to_string(), versions.iter().map(|v| v.to_string()).collect::<Vec<_>>());
+    match kind {
+        "install" => SyncAction::Install { name, version: v[0].clone() },
+        "upgrade" => SyncAction::Upgrade { name, from: v[0].clone(), to: v[1].clone() },
+        "downgrade" => SyncAction::Downgrade { name, from: v[0].clone(), to: v[1].clone() },
+        "reinstall" => SyncAction::Reinstall { name, from: v[0].clone(), to: v[1].clone() },
+        _ => SyncAction::Remove { name, version: v[0].clone() },
+    }
+}
+
+#[test]
+fn test_plan_sync() {
+    let dir = TempDir::new().unwrap();
+    outdated_env(dir.path());
+
+    let plan = installer(dir.path()).plan_sync(&app_graph()).unwrap();
+    assert_eq!(plan.actions, vec![
+        action("remove", "old", &["1.0"]),
+        action("remove", "tool", &["3.0"]),
+        action("upgrade", "db", &["0.9", "1.0"]),
+        action("downgrade", "web", &["2.0", "1.0"]),
+        action("install", "app", &["1.0"]),
+    ]);
+    assert_eq!(plan.unchanged, vec!["core"]);
+    assert!(plan.unmanaged.is_empty());
+    assert_eq!(plan.actions[3].to_string(), "downgrade web 2.0 -> 1.0");
+
+    // Packages other tools installed can be left alone
+    let plan = installer(dir.path()).with_keep_unmanaged(true).plan_sync(&app_graph()).unwrap();
+    assert_eq!(plan.actions[0], action("remove", "old", &["1.0"]));
+    assert!(!plan.actions.iter().any(|action| action.name() == "tool"));
+    assert_eq!(plan.unmanaged, vec!["tool"]);
+}
+
+#[tokio::test]
+async fn test_sync_packages() {
+    let dir = TempDir::new().unwrap();
+    let wheels = outdated_env(dir.path());
+
+    let plan = installer(dir.path()).with_keep_unmanaged(true).sync_packages(&app_graph()).await.unwrap();
+    assert_eq!(plan.actions.len(), 4);
+    let installed: Vec<(String, String)> = wheels.installed().unwrap()
+        .into_iter()
+        .map(|distribution| (distribution.name, distribution.version))
+        .collect();
+    let expected = [("app", "1.0"), ("core", "1.0"), ("db", "1.0"), ("tool", "3.0"), ("web", "1.0")];
+    assert_eq!(installed, expected.map(|(name, version)| (name.to_string(), version.to_string())));
+
+    // A synced environment has nothing left to change
+    let plan = installer(dir.path()).sync_packages(&app_graph()).await.unwrap();
+    assert_eq!(plan.actions, vec![action("remove", "tool", &["3.0"])]);
+    assert!(installer(dir.path()).plan_sync(&app_graph()).unwrap().is_empty());
+}
+
+#[test]
+fn test_plan_sync_unparseable_version() {
+    let dir = TempDir::new().unwrap();
+    outdated_env(dir.path());
+    let site_packages = dir.path().join("env/lib/python3.11/site-packages");
+    fs::rename(site_packages.join("core-1.0.dist-info"), site_packages.join("core-custom.dist-info")).unwrap();
+
+    // Versions that cannot be ordered are replaced, neither upgrade nor
+    // downgrade
+    let plan = installer(dir.path()).plan_sync(&app_graph()).unwrap();
+    assert!(plan.actions.contains(&action("reinstall", "core", &["custom", "1.0"])));
+    assert!(plan.unchanged.is_empty());
+}
+
+#[tokio::test]
+async fn test_sync_packages_partial_failure() {
+    let dir = TempDir::new().unwrap();
+    outdated_env(dir.path());
+    let wheel = dir.path().join("cache/artifacts/app/1.0/app-1.0-py3-none-any.whl");
+    fs::write(&wheel, "not a wheel").unwrap();
+
+    // app installs last, so the removals and replacements before it are
+    // reported as done
+    let error = installer(dir.path()).sync_packages(&app_graph()).await.unwrap_err().to_string();
+    assert!(error.contains("Sync stopped after 4 of 5 changes"), "{}", error);
+    for applied in ["remove old 1.0", "remove tool 3.0", "upgrade db 0.9 -> 1.0", "downgrade web 2.0 -> 1.0"] {
+        assert!(error.contains(applied), "{}", error);
+    }
+    assert!(!error.contains("install app"), "{}", error);
+}
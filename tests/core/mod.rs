@@ -12,3 +12,6 @@ mod version_history_test;
 mod security_test;
 mod sync_test;
 mod scheduler_test;
+mod config_test;
+mod wheel_test;
+mod installer_test;
@@ -11,4 +11,96 @@ fn test_version_constraint() {
     let version = Version::parse("1.0.0").unwrap();
     let constraint = VersionConstraint::parse(">=1.0.0").unwrap();
     assert!(constraint.matches(&version));
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_pep440_parsing() {
+    assert_eq!(Version::parse("2.0").unwrap().to_string(), "2.0");
+    assert_eq!(Version::parse("1.0.post1").unwrap().to_string(), "1.0.post1");
+    assert_eq!(Version::parse("3.0rc1").unwrap().to_string(), "3.0rc1");
+    assert_eq!(Version::parse("1!2.0").unwrap().epoch(), 1);
+    assert_eq!(Version::parse("1.2.3+local").unwrap().to_string(), "1.2.3+local");
+    assert!(Version::parse("1.0+").is_err());
+    assert!(Version::parse("1..0").is_err());
+}
+
+#[test]
+fn test_pep440_normalization() {
+    assert_eq!(Version::parse("v1.0-RC.1").unwrap().to_string(), "1.0rc1");
+    assert_eq!(Version::parse("1.0-1").unwrap().to_string(), "1.0.post1");
+    assert_eq!(Version::parse("1.0alpha").unwrap().to_string(), "1.0a0");
+    assert_eq!(Version::parse("1.0-dev").unwrap().to_string(), "1.0.dev0");
+    assert_eq!(Version::parse("1.0+Ubuntu-01").unwrap().to_string(), "1.0+ubuntu.1");
+    assert_eq!(Version::parse("1.0").unwrap(), Version::parse("1.0.0").unwrap());
+}
+
+#[test]
+fn test_pep440_ordering() {
+    let ordered = [
+        "1.0.dev456", "1.0a1", "1.0a2.dev456", "1.0a12", "1.0b2.post345.dev456",
+        "1.0b2.post345", "1.0rc1", "1.0", "1.0+abc.5", "1.0+5", "1.0.post456.dev34",
+        "1.0.post456", "1.1.dev1", "1!0.1",
+    ];
+    for pair in ordered.windows(2) {
+        let lower = Version::parse(pair[0]).unwrap();
+        let higher = Version::parse(pair[1]).unwrap();
+        assert!(lower < higher, "{} < {}", pair[0], pair[1]);
+    }
+}
+
+#[test]
+fn test_specifier_operators() {
+    let matches = |constraint: &str, version: &str| {
+        VersionConstraint::parse(constraint).unwrap().matches(&Version::parse(version).unwrap())
+    };
+
+    assert!(matches("~=1.4.2", "1.4.9"));
+    assert!(!matches("~=1.4.2", "1.5"));
+    assert!(matches("~=1.4", "1.9"));
+    assert!(!matches("!=1.5.*", "1.5.3"));
+    assert!(matches("==2.*", "2.7"));
+    assert!(matches("==1.0", "1.0+local"));
+    assert!(!matches("==1.0+local", "1.0"));
+    assert!(!matches("<3.0", "3.0rc1"));
+    assert!(!matches(">1.0", "1.0.post1"));
+    assert!(matches("<=1.0", "1.0+local"));
+    assert!(!matches("===foobar", "1.0"));
+    assert!(matches(">=1.0, <2.0, !=1.5", "1.4"));
+    assert!(!matches(">=1.0, <2.0, !=1.5", "1.5"));
+}
+
+#[test]
+fn test_invalid_specifiers() {
+    assert!(VersionConstraint::parse("~=1").is_err());
+    assert!(VersionConstraint::parse("==1.0a1.*").is_err());
+    assert!(VersionConstraint::parse(">=1.0+local").is_err());
+    assert!(VersionConstraint::parse("<1.*").is_err());
+}
+
+#[test]
+fn test_constraint_set_operations() {
+    let lower = VersionConstraint::parse(">=1.0").unwrap();
+    let upper = VersionConstraint::parse("<2.0").unwrap();
+
+    let both = lower.intersection(&upper);
+    assert_eq!(both.to_string(), ">=1.0, <2.0");
+    assert!(both.matches(&Version::parse("1.5").unwrap()));
+    assert!(!both.matches(&Version::parse("2.0").unwrap()));
+
+    let either = VersionConstraint::parse("<1.0").unwrap()
+        .union(&VersionConstraint::parse(">=2.0").unwrap());
+    assert!(either.matches(&Version::parse("0.5").unwrap()));
+    assert!(!either.matches(&Version::parse("1.5").unwrap()));
+    assert_eq!(VersionConstraint::parse(&either.to_string()).unwrap(), either);
+
+    assert!(lower.union(&VersionConstraint::any()).is_any());
+}
+
+#[test]
+fn test_pinned_constraint() {
+    let pinned = |constraint: &str| VersionConstraint::parse(constraint).unwrap().pinned().map(ToString::to_string);
+    assert_eq!(pinned("==1.4").as_deref(), Some("1.4"));
+    assert_eq!(pinned(">=1, ==1.4").as_deref(), Some("1.4"));
+    assert_eq!(pinned("==1.*"), None);
+    assert_eq!(pinned(">=1.4"), None);
+    assert_eq!(pinned("==1.4 || ==1.5"), None);
+}
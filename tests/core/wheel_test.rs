@@ -0,0 +1,279 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use blast_core::environment::package::{InstallScheme, WheelInstaller};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+use zip::write::FileOptions;
+
+const WHEEL: &str = "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n";
+
+const ENTRY_POINTS: &str = "\
+[console_scripts]
+demo = demo.cli:main [color]
+
+[gui_scripts]
+demo-gui = demo:App.run
+
+[demo.plugins]
+ignored = demo.plugins:Plugin
+";
+
+/// Write `demo-1.0-py3-none-any.whl` with a RECORD listing the hashes of
+/// `files`, each given as path, content and unix mode
+fn write_wheel(dir: &Path, files: &[(&str, &str, u32)]) -> PathBuf {
+    write_named_wheel(dir, "demo", "1.0", files)
+}
+
+fn write_named_wheel(dir: &Path, name: &str, version: &str, files: &[(&str, &str, u32)]) -> PathBuf {
+    let path = dir.join(format!("{}-{}-py3-none-any.whl", name, version));
+    let mut wheel = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    let mut record = String::new();
+    for (name, content, mode) in files {
+        wheel.start_file(*name, FileOptions::default().unix_permissions(*mode)).unwrap();
+        wheel.write_all(content.as_bytes()).unwrap();
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(content.as_bytes()));
+        record.push_str(&format!("{},sha256={},{}\n", name, hash, content.len()));
+    }
+    let record_path = format!("{}-{}.dist-info/RECORD", name, version);
+    record.push_str(&format!("{},,\n", record_path));
+    wheel.start_file(record_path, FileOptions::default()).unwrap();
+    wheel.write_all(record.as_bytes()).unwrap();
+    wheel.finish().unwrap();
+    path
+}
+
+fn demo_files() -> Vec<(&'static str, &'static str, u32)> {
+    vec![
+        ("demo/__init__.py", "class App:\n    @staticmethod\n    def run():\n        return 0\n", 0o644),
+        ("demo/cli.py", "import sys\n\ndef main():\n    print('demo ' + ' '.join(sys.argv[1:]))\n    return 3\n", 0o644),
+        ("demo/_speedups.so", "\x7fELF", 0o755),
+        ("demo-1.0.data/scripts/demo-tool", "#!python -u\nprint('tool')\n", 0o644),
+        ("demo-1.0.data/data/share/demo/config.txt", "color = true\n", 0o644),
+        ("demo-1.0.data/headers/demo.h", "#define DEMO 1\n", 0o644),
+        ("demo-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n", 0o644),
+        ("demo-1.0.dist-info/WHEEL", WHEEL, 0o644),
+        ("demo-1.0.dist-info/entry_points.txt", ENTRY_POINTS, 0o644),
+    ]
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).unwrap().permissions().mode() & 0o111 != 0
+}
+
+#[test]
+fn test_install_scheme() {
+    let env = Path::new("/envs/project");
+    let scheme = InstallScheme::for_env(env, "3.11.4");
+    assert_eq!(scheme.purelib, env.join("lib/python3.11/site-packages"));
+    assert_eq!(scheme.platlib, scheme.purelib);
+    assert_eq!(scheme.scripts, env.join("bin"));
+    assert_eq!(scheme.data, env);
+    assert_eq!(scheme.headers, env.join("include/site/python3.11"));
+    assert_eq!(scheme.prefix, env);
+}
+
+#[test]
+fn test_install_wheel() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("env");
+    let wheel = write_wheel(dir.path(), &demo_files());
+    let installer = WheelInstaller::for_env(&env, "3.11");
+    let installed = installer.install(&wheel).unwrap();
+
+    let site_packages = env.join("lib/python3.11/site-packages");
+    assert_eq!(installed.name, "demo");
+    assert_eq!(installed.version, "1.0");
+    assert_eq!(installed.dist_info, site_packages.join("demo-1.0.dist-info"));
+
+    // Each part of the wheel lands in its scheme directory
+    assert!(site_packages.join("demo/__init__.py").is_file());
+    assert_eq!(fs::read_to_string(env.join("share/demo/config.txt")).unwrap(), "color = true\n");
+    assert!(env.join("include/site/python3.11/demo/demo.h").is_file());
+    assert!(!site_packages.join("demo-1.0.data").exists());
+    assert!(is_executable(&site_packages.join("demo/_speedups.so")));
+
+    // Scripts run the environment's interpreter
+    let python = env.join("bin/python").display().to_string();
+    let tool = fs::read_to_string(env.join("bin/demo-tool")).unwrap();
+    assert_eq!(tool, format!("#!{} -u\nprint('tool')\n", python));
+    assert!(is_executable(&env.join("bin/demo-tool")));
+
+    let launcher = fs::read_to_string(env.join("bin/demo")).unwrap();
+    assert!(launcher.starts_with(&format!("#!{}\n", python)), "{}", launcher);
+    assert!(launcher.contains("from demo.cli import main\n"), "{}", launcher);
+    assert!(launcher.contains("sys.exit(main())"), "{}", launcher);
+    let gui = fs::read_to_string(env.join("bin/demo-gui")).unwrap();
+    assert!(gui.contains("from demo import App\n") && gui.contains("sys.exit(App.run())"), "{}", gui);
+    assert!(!env.join("bin/ignored").exists());
+
+    // RECORD lists everything installed, relative to site-packages
+    let dist_info = &installed.dist_info;
+    assert_eq!(fs::read_to_string(dist_info.join("INSTALLER")).unwrap(), "blast\n");
+    let record = fs::read_to_string(dist_info.join("RECORD")).unwrap();
+    let paths: Vec<&str> = record.lines().filter_map(|line| line.split(',').next()).collect();
+    for path in [
+        "demo/__init__.py",
+        "../../../bin/demo-tool",
+        "../../../bin/demo",
+        "../../../bin/demo-gui",
+        "../../../share/demo/config.txt",
+        "../../../include/site/python3.11/demo/demo.h",
+        "demo-1.0.dist-info/INSTALLER",
+        "demo-1.0.dist-info/RECORD",
+    ] {
+        assert!(paths.contains(&path), "{} missing from RECORD:\n{}", path, record);
+    }
+    let init = "class App:\n    @staticmethod\n    def run():\n        return 0\n";
+    let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(init.as_bytes()));
+    assert!(record.contains(&format!("demo/__init__.py,sha256={},{}\n", hash, init.len())), "{}", record);
+    assert!(record.ends_with("demo-1.0.dist-info/RECORD,,\n"));
+}
+
+#[test]
+fn test_run_console_script() {
+    // A path with a space cannot go in a shebang, so this also exercises
+    // the /bin/sh fallback
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("my env");
+    let status = Command::new("python3").args(["-m", "venv", "--without-pip"]).arg(&env).status().unwrap();
+    assert!(status.success());
+    let version = Command::new(env.join("bin/python"))
+        .args(["-c", "import sys; print('%d.%d' % sys.version_info[:2])"])
+        .output()
+        .unwrap();
+    let version = String::from_utf8(version.stdout).unwrap();
+
+    let wheel = write_wheel(dir.path(), &demo_files());
+    WheelInstaller::for_env(&env, version.trim()).install(&wheel).unwrap();
+
+    let output = Command::new(env.join("bin/demo")).arg("hello").output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "demo hello\n");
+    assert_eq!(output.status.code(), Some(3));
+    let output = Command::new(env.join("bin/demo-tool")).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "tool\n");
+}
+
+#[test]
+fn test_invalid_wheels() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("env");
+    let installer = WheelInstaller::for_env(&env, "3.11");
+
+    // Files must match the hashes in RECORD
+    let wheel = write_wheel(dir.path(), &demo_files());
+    let mut tampered = zip::ZipWriter::new_append(fs::OpenOptions::new().read(true).write(true).open(&wheel).unwrap()).unwrap();
+    tampered.start_file("demo/cli.py", FileOptions::default()).unwrap();
+    tampered.write_all(b"import os\n").unwrap();
+    tampered.finish().unwrap();
+    let error = installer.install(&wheel).unwrap_err().to_string();
+    assert!(error.contains("does not match the hash"), "{}", error);
+
+    // and hashes the wheel spec does not allow are not trusted
+    for algorithm in ["md5", "sha1", "foo"] {
+        let wheel = write_wheel(dir.path(), &demo_files());
+        let mut weak = zip::ZipWriter::new_append(fs::OpenOptions::new().read(true).write(true).open(&wheel).unwrap()).unwrap();
+        weak.start_file("demo-1.0.dist-info/RECORD", FileOptions::default()).unwrap();
+        weak.write_all(format!("demo/cli.py,{}=AAAA,10\ndemo-1.0.dist-info/RECORD,,\n", algorithm).as_bytes()).unwrap();
+        weak.finish().unwrap();
+        let error = installer.install(&wheel).unwrap_err().to_string();
+        assert!(error.contains(&format!("unsupported hash in RECORD: {}=AAAA", algorithm)), "{}", error);
+    }
+
+    // Nothing is written outside the environment
+    let mut files = demo_files();
+    files.push(("../escaped.py", "", 0o644));
+    let wheel = write_wheel(dir.path(), &files);
+    let error = installer.install(&wheel).unwrap_err().to_string();
+    assert!(error.contains("unsafe path"), "{}", error);
+    assert!(!dir.path().join("escaped.py").exists());
+
+    let wheel = dir.path().join("demo-1.0-py3-none-any.whl");
+    let mut archive = zip::ZipWriter::new(fs::File::create(&wheel).unwrap());
+    archive.start_file("demo/__init__.py", FileOptions::default()).unwrap();
+    archive.finish().unwrap();
+    let error = installer.install(&wheel).unwrap_err().to_string();
+    assert!(error.contains("no .dist-info directory"), "{}", error);
+}
+
+#[test]
+fn test_unsafe_script_name() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("env");
+    let installer = WheelInstaller::for_env(&env, "3.11");
+
+    for name in ["../../../escaped", "/tmp/escaped", "sub/escaped", ".."] {
+        let entry_points = format!("[console_scripts]\n{} = demo.cli:main\n", name);
+        let wheel = write_wheel(dir.path(), &[
+            ("demo/cli.py", "def main():\n    return 0\n", 0o644),
+            ("demo-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n", 0o644),
+            ("demo-1.0.dist-info/WHEEL", WHEEL, 0o644),
+            ("demo-1.0.dist-info/entry_points.txt", &entry_points, 0o644),
+        ]);
+        let error = installer.install(&wheel).unwrap_err().to_string();
+        assert!(error.contains("unsafe script name"), "{}", error);
+    }
+    // The wheel is rejected before any of it is written
+    assert!(!dir.path().join("escaped").exists());
+    assert!(!env.exists());
+}
+
+#[test]
+fn test_uninstall() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("env");
+    let installer = WheelInstaller::for_env(&env, "3.11");
+    let site_packages = env.join("lib/python3.11/site-packages");
+    installer.install(&write_wheel(dir.path(), &demo_files())).unwrap();
+    installer.install(&write_named_wheel(dir.path(), "other", "2.0", &[
+        ("other.py", "", 0o644),
+        ("other-2.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: other\nVersion: 2.0\n", 0o644),
+        ("other-2.0.dist-info/WHEEL", WHEEL, 0o644),
+    ])).unwrap();
+    fs::create_dir_all(site_packages.join("demo/__pycache__")).unwrap();
+    fs::write(site_packages.join("demo/__pycache__/cli.cpython-311.pyc"), "").unwrap();
+
+    assert_eq!(installer.find_installed("Demo").unwrap(), Some(site_packages.join("demo-1.0.dist-info")));
+    let removed = installer.uninstall("demo").unwrap().unwrap();
+    assert_eq!((removed.name.as_str(), removed.version.as_str()), ("demo", "1.0"));
+
+    // Files, bytecode, scripts and emptied directories are gone
+    for path in ["demo", "demo-1.0.dist-info"] {
+        assert!(!site_packages.join(path).exists(), "{} was left behind", path);
+    }
+    for path in ["bin/demo", "bin/demo-gui", "bin/demo-tool", "share", "include/site/python3.11/demo"] {
+        assert!(!env.join(path).exists(), "{} was left behind", path);
+    }
+    // Other distributions and the environment's own directories stay
+    assert!(site_packages.join("other.py").is_file());
+    assert!(env.join("bin").is_dir());
+    assert_eq!(installer.uninstall("demo").unwrap(), None);
+
+    installer.uninstall("other").unwrap();
+    assert!(site_packages.is_dir());
+}
+
+#[test]
+fn test_uninstall_outside_prefix() {
+    let dir = TempDir::new().unwrap();
+    let env = dir.path().join("env");
+    let installer = WheelInstaller::for_env(&env, "3.11");
+    let installed = installer.install(&write_wheel(dir.path(), &demo_files())).unwrap();
+
+    let outside = dir.path().join("outside.txt");
+    fs::write(&outside, "keep me").unwrap();
+    let record = installed.dist_info.join("RECORD");
+    let mut content = fs::read_to_string(&record).unwrap();
+    content.push_str("../../../../outside.txt,,\n");
+    fs::write(&record, content).unwrap();
+
+    let error = installer.uninstall("demo").unwrap_err().to_string();
+    assert!(error.contains("outside"), "{}", error);
+    assert!(outside.is_file());
+    assert!(env.join("lib/python3.11/site-packages/demo/__init__.py").is_file());
+}
@@ -1,4 +1,5 @@
 use blast_image::platform::{
+    Libc,
     PlatformRequirements,
     PlatformInfo,
     GpuRequirements,
@@ -124,3 +125,17 @@ fn test_platform_requirements_validation() {
         info.min_disk_space >= requirements.min_disk_space
     );
 }
+
+#[test]
+fn test_libc_from_ldd_output() {
+    let glibc = "ldd (GNU libc) 2.36\nCopyright (C) 2022 Free Software Foundation, Inc.\n";
+    assert_eq!(Libc::parse_ldd(glibc), Some(Libc::Glibc(2, 36)));
+
+    let ubuntu = "ldd (Ubuntu GLIBC 2.35-0ubuntu3.8) 2.35\n";
+    assert_eq!(Libc::parse_ldd(ubuntu), Some(Libc::Glibc(2, 35)));
+
+    let musl = "musl libc (x86_64)\nVersion 1.2.4\nDynamic Program Loader\n";
+    assert_eq!(Libc::parse_ldd(musl), Some(Libc::Musl(1, 2)));
+
+    assert_eq!(Libc::parse_ldd("ldd: unknown option\n"), None);
+}
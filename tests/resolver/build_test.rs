@@ -0,0 +1,179 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use blast_cache::ArtifactCache;
+use blast_core::package::PackageId;
+use blast_core::version::Version;
+use blast_resolver::source::PackageSource;
+use blast_resolver::{BuildFrontend, BuildSystem, CoreMetadata, FindLinksSource};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tempfile::TempDir;
+
+/// A flit-style in-tree backend computing its dependencies at build time
+const BACKEND: &str = r#"
+import os, zipfile
+
+NAME, VERSION = "demo", "1.0"
+DIST_INFO = f"{NAME}-{VERSION}.dist-info"
+
+def _metadata():
+    requires = open("requirements.txt").read().split()
+    try:
+        import buildhelper
+        requires.append(f"helper-runtime>={buildhelper.VERSION}")
+    except ImportError:
+        pass
+    lines = ["Metadata-Version: 2.1", f"Name: {NAME}", f"Version: {VERSION}"]
+    return "\n".join(lines + [f"Requires-Dist: {r}" for r in requires]) + "\n"
+
+def get_requires_for_build_wheel(config_settings=None):
+    return []
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    os.makedirs(os.path.join(metadata_directory, DIST_INFO))
+    with open(os.path.join(metadata_directory, DIST_INFO, "METADATA"), "w") as f:
+        f.write(_metadata())
+    return DIST_INFO
+
+def build_wheel(wheel_directory, config_settings=None, metadata_directory=None):
+    filename = f"{NAME}-{VERSION}-py3-none-any.whl"
+    with zipfile.ZipFile(os.path.join(wheel_directory, filename), "w") as wheel:
+        wheel.writestr(f"{NAME}/__init__.py", "")
+        wheel.writestr(f"{DIST_INFO}/METADATA", _metadata())
+        wheel.writestr(f"{DIST_INFO}/WHEEL", "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n")
+        wheel.writestr(f"{DIST_INFO}/RECORD", "")
+    return filename
+"#;
+
+/// Write a source tree built by the in-tree backend
+fn write_project(dir: &Path, requires: &[&str], prepare_metadata: bool) {
+    let build_requires: Vec<String> = requires.iter().map(|r| format!("\"{}\"", r)).collect();
+    fs::create_dir_all(dir.join("_build")).unwrap();
+    fs::write(dir.join("pyproject.toml"), format!(
+        "[build-system]\nrequires = [{}]\nbuild-backend = \"backend\"\nbackend-path = [\"_build\"]\n",
+        build_requires.join(", ")
+    ))
+    .unwrap();
+    let backend = if prepare_metadata {
+        BACKEND.to_string()
+    } else {
+        BACKEND.replace("def prepare_metadata_for_build_wheel", "def _prepare_metadata")
+    };
+    fs::write(dir.join("_build/backend.py"), backend).unwrap();
+    fs::write(dir.join("requirements.txt"), "requests>=2.0\n").unwrap();
+}
+
+/// Pack a source tree into `demo-1.0.tar.gz` with a `PKG-INFO` whose
+/// dependencies are not static
+fn write_sdist(dir: &Path) -> std::path::PathBuf {
+    let tree = TempDir::new().unwrap();
+    write_project(tree.path(), &[], true);
+    fs::write(tree.path().join("PKG-INFO"), "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n").unwrap();
+
+    let path = dir.join("demo-1.0.tar.gz");
+    let mut archive = tar::Builder::new(GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default()));
+    archive.append_dir_all("demo-1.0", tree.path()).unwrap();
+    archive.into_inner().unwrap().finish().unwrap();
+    path
+}
+
+/// Write an installable wheel of the `buildhelper` build requirement
+fn write_helper_wheel(dir: &Path) {
+    let file = fs::File::create(dir.join("buildhelper-2.5-py3-none-any.whl")).unwrap();
+    let mut wheel = zip::ZipWriter::new(file);
+    let files = [
+        ("buildhelper.py", "VERSION = '2.5'\n"),
+        ("buildhelper-2.5.dist-info/METADATA", "Metadata-Version: 2.1\nName: buildhelper\nVersion: 2.5\n"),
+        ("buildhelper-2.5.dist-info/WHEEL", "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n"),
+        ("buildhelper-2.5.dist-info/RECORD", ""),
+    ];
+    for (name, content) in files {
+        wheel.start_file(name, Default::default()).unwrap();
+        wheel.write_all(content.as_bytes()).unwrap();
+    }
+    wheel.finish().unwrap();
+}
+
+fn requires(metadata: &CoreMetadata) -> Vec<&str> {
+    metadata.requires_dist.iter().map(String::as_str).collect()
+}
+
+#[test]
+fn test_build_system() {
+    let project = TempDir::new().unwrap();
+    // Projects without a build system are built with setuptools
+    let system = BuildSystem::from_source_tree(project.path()).unwrap();
+    assert_eq!(system.build_backend, "setuptools.build_meta:__legacy__");
+    assert!(system.requires.iter().any(|requirement| requirement.starts_with("setuptools")));
+
+    write_project(project.path(), &["flit_core>=3.2"], true);
+    let system = BuildSystem::from_source_tree(project.path()).unwrap();
+    assert_eq!(system.requires, vec!["flit_core>=3.2"]);
+    assert_eq!(system.build_backend, "backend");
+    assert_eq!(system.backend_path, vec!["_build"]);
+
+    fs::write(project.path().join("pyproject.toml"), "[build-system]\nbackend-path = [\"../elsewhere\"]\n").unwrap();
+    assert!(BuildSystem::from_source_tree(project.path()).is_err());
+}
+
+#[tokio::test]
+async fn test_build_sdist() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let sdist = write_sdist(wheelhouse.path());
+    let cache = ArtifactCache::new(cache_dir.path());
+    let frontend = Arc::new(BuildFrontend::new("python3", cache.clone()));
+
+    // The resolver gets the dependencies the backend computes
+    let source = FindLinksSource::new(wheelhouse.path(), 5).unwrap();
+    let id = PackageId::new("demo", Version::parse("1.0").unwrap());
+    assert!(source.get_package(&id).await.is_err());
+    let source = source.with_build_frontend(Some(frontend.clone()));
+    let package = source.get_package(&id).await.unwrap();
+    assert!(package.metadata().dependencies.contains_key("requests"));
+
+    let wheel = frontend.build_wheel(&sdist).await.unwrap();
+    assert_eq!(wheel.filename, "demo-1.0-py3-none-any.whl");
+    assert_eq!(cache.get("demo", "1.0").await.unwrap(), Some(wheel.clone()));
+    assert_eq!(requires(&CoreMetadata::from_wheel(&fs::read(&wheel.path).unwrap()).unwrap()), vec!["requests>=2.0"]);
+
+    // The cached wheel is reused without another build, but not a cached
+    // wheel of the same release the target cannot install
+    cache.store("demo", "1.0", "demo-1.0-cp27-cp27m-win32.whl", b"wheel").await.unwrap();
+    let frontend = BuildFrontend::new("/nonexistent/python3", cache.clone());
+    assert_eq!(frontend.build_wheel(&sdist).await.unwrap(), wheel);
+
+    let other_dir = TempDir::new().unwrap();
+    let other = ArtifactCache::new(other_dir.path());
+    other.store("demo", "1.0", "demo-1.0-cp27-cp27m-win32.whl", b"wheel").await.unwrap();
+    let frontend = BuildFrontend::new("/nonexistent/python3", other);
+    assert!(frontend.build_wheel(&sdist).await.is_err());
+}
+
+#[tokio::test]
+async fn test_build_requirements() {
+    let project = TempDir::new().unwrap();
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_project(project.path(), &["buildhelper>=2"], false);
+    write_helper_wheel(wheelhouse.path());
+
+    // Build requirements come from the wheelhouse into an isolated environment,
+    // and a backend without prepare_metadata_for_build_wheel gets a full build
+    let cache = ArtifactCache::new(cache_dir.path());
+    let frontend = BuildFrontend::new("python3", cache.clone())
+        .with_find_links(vec![wheelhouse.path().to_path_buf()])
+        .with_offline(true);
+    let metadata = frontend.metadata(project.path()).await.unwrap();
+    assert_eq!(metadata.name, "demo");
+    assert_eq!(requires(&metadata), vec!["requests>=2.0", "helper-runtime>=2.5"]);
+    assert!(cache.contains("demo", "1.0").await.unwrap());
+
+    // Missing build requirements fail the build
+    let frontend = BuildFrontend::new("python3", ArtifactCache::new(cache_dir.path())).with_offline(true);
+    let error = frontend.build_wheel(project.path()).await.unwrap_err();
+    assert!(error.to_string().contains("install build requirements"), "{}", error);
+}
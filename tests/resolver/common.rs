@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use blast_core::config::{BlastConfig, DependencySpec};
+use blast_core::python::PythonVersion;
+use blast_resolver::source::PackageSourceChain;
+use blast_resolver::{Cache, DependencyResolver, FindLinksSource, PyPIClient};
+
+/// Write a minimal wheel containing only its METADATA file
+pub fn write_wheel(dir: &Path, name: &str, version: &str, requires: &[&str]) -> PathBuf {
+    let mut metadata = format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n", name, version);
+    for requirement in requires {
+        metadata.push_str(&format!("Requires-Dist: {}\n", requirement));
+    }
+    write_metadata_wheel(dir, name, version, &metadata)
+}
+
+/// Write a minimal wheel of a release supporting only some Python versions
+pub fn write_wheel_requiring_python(dir: &Path, name: &str, version: &str, requires_python: &str) -> PathBuf {
+    let metadata = format!(
+        "Metadata-Version: 2.1\nName: {}\nVersion: {}\nRequires-Python: {}\n",
+        name, version, requires_python
+    );
+    write_metadata_wheel(dir, name, version, &metadata)
+}
+
+fn write_metadata_wheel(dir: &Path, name: &str, version: &str, metadata: &str) -> PathBuf {
+    let path = dir.join(format!("{}-{}-py3-none-any.whl", name, version));
+    let mut wheel = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    wheel.start_file(format!("{}-{}.dist-info/METADATA", name, version), Default::default()).unwrap();
+    wheel.write_all(metadata.as_bytes()).unwrap();
+    wheel.finish().unwrap();
+    path
+}
+
+/// Require a package from the configured indexes
+pub fn spec(name: &str, version: &str) -> DependencySpec {
+    DependencySpec {
+        name: name.to_string(),
+        version: version.to_string(),
+        extras: None,
+        index: None,
+        source: None,
+        marker: None,
+    }
+}
+
+/// Configure a Python 3.11 project with the given requirements
+pub fn project_config(root: &Path, requirements: &[(&str, &str)]) -> BlastConfig {
+    let mut config = BlastConfig::new("project", "0.1.0", PythonVersion::new(3, 11, None), root.to_path_buf());
+    for (name, version) in requirements {
+        config.dependencies.packages.push(spec(name, version));
+    }
+    config
+}
+
+/// Create a resolver finding packages in a wheelhouse only
+pub fn resolver(wheelhouse: &Path, cache_dir: &Path) -> DependencyResolver {
+    let mut sources = PackageSourceChain::new();
+    sources.add_source(Box::new(FindLinksSource::new(wheelhouse, 5).unwrap()));
+    DependencyResolver::with_sources(
+        PyPIClient::new(10, 30, true).unwrap(),
+        sources,
+        Cache::new(cache_dir.to_path_buf()),
+    )
+}
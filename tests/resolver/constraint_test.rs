@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use blast_core::config::BlastConfig;
+use blast_core::version::VersionConstraint;
+use blast_resolver::{project_package, Config, DependencyResolver, RequirementKind};
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, spec, write_wheel};
+
+/// A wheelhouse where `app` insists on `urllib3>=2`
+fn create_wheelhouse() -> TempDir {
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["urllib3>=2"]);
+    write_wheel(wheelhouse.path(), "app", "2.0", &["urllib3>=2"]);
+    write_wheel(wheelhouse.path(), "urllib3", "1.26.18", &[]);
+    write_wheel(wheelhouse.path(), "urllib3", "2.0.7", &[]);
+    write_wheel(wheelhouse.path(), "unused", "1.0", &[]);
+    wheelhouse
+}
+
+/// Create a resolver applying the constraints and overrides of a config
+fn constrained_resolver(wheelhouse: &Path, cache_dir: &Path, config: &Config) -> DependencyResolver {
+    resolver(wheelhouse, cache_dir)
+        .with_constraints(config.constraints.clone())
+        .with_overrides(config.overrides.clone())
+}
+
+#[tokio::test]
+async fn test_override_replaces_declared_requirement() {
+    let wheelhouse = create_wheelhouse();
+    let cache_dir = TempDir::new().unwrap();
+    let mut config = project_config(wheelhouse.path(), &[("app", "*")]);
+    config.overrides.push(spec("urllib3", "<2"));
+
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    let result = constrained_resolver(wheelhouse.path(), cache_dir.path(), &resolver_config)
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap();
+
+    let urllib3 = result.packages.iter().find(|p| p.name() == "urllib3").unwrap();
+    assert_eq!(urllib3.version().to_string(), "1.26.18");
+
+    assert_eq!(result.applied.len(), 1);
+    let applied = &result.applied[0];
+    assert_eq!(applied.kind, RequirementKind::Override);
+    assert_eq!(applied.package, "app");
+    assert_eq!(applied.dependency, "urllib3");
+    assert_eq!(applied.applied, VersionConstraint::parse("<2").unwrap());
+    assert_eq!(applied.to_string(), "app requires urllib3 >=2, overridden by <2");
+}
+
+#[tokio::test]
+async fn test_constraints_limit_without_adding() {
+    let wheelhouse = create_wheelhouse();
+    let cache_dir = TempDir::new().unwrap();
+    let mut config = project_config(wheelhouse.path(), &[("app", "*")]);
+    config.constraints.push(spec("app", "<2"));
+    config.constraints.push(spec("unused", "==1.0"));
+
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    let result = constrained_resolver(wheelhouse.path(), cache_dir.path(), &resolver_config)
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap();
+
+    let mut resolved: Vec<String> = result.packages.iter()
+        .map(|p| format!("{}=={}", p.name(), p.version()))
+        .collect();
+    resolved.sort();
+    assert_eq!(resolved, vec!["app==1.0", "urllib3==2.0.7"]);
+
+    // Only constraints on resolved packages are reported
+    assert_eq!(result.applied.len(), 1);
+    assert_eq!(result.applied[0].kind, RequirementKind::Constraint);
+    assert_eq!(result.applied[0].package, "project");
+    assert_eq!(result.applied[0].dependency, "app");
+}
+
+#[tokio::test]
+async fn test_constraint_conflict() {
+    let wheelhouse = create_wheelhouse();
+    let cache_dir = TempDir::new().unwrap();
+    let mut config = project_config(wheelhouse.path(), &[("app", "*")]);
+    config.constraints.push(spec("urllib3", "<2"));
+
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    let error = constrained_resolver(wheelhouse.path(), cache_dir.path(), &resolver_config)
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap_err();
+    assert!(error.diagnostic().is_some(), "{}", error);
+}
+
+#[test]
+fn test_constraints_from_blast_toml() {
+    let mut config = BlastConfig::from_toml(&project_config(Path::new("/project"), &[("app", "*")]).to_toml().unwrap()).unwrap();
+    assert!(config.constraints.is_empty());
+
+    let content = format!(
+        "{}\n[[constraints]]\nname = \"urllib3\"\nversion = \"<2\"\n\n[[overrides]]\nname = \"Requests\"\nversion = \">=2.31\"\n",
+        config.to_toml().unwrap()
+    );
+    config = BlastConfig::from_toml(&content).unwrap();
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    assert_eq!(resolver_config.constraints.get("urllib3"), Some(&VersionConstraint::parse("<2").unwrap()));
+    assert_eq!(resolver_config.overrides.get("Requests"), Some(&VersionConstraint::parse(">=2.31").unwrap()));
+
+    config.overrides.push(spec("broken", "not a version"));
+    assert!(Config::from_blast_config(&config).is_err());
+}
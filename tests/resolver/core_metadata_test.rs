@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use blast_core::config::DependencyGroupEntry;
+use blast_core::python::PythonVersion;
+use blast_core::version::VersionConstraint;
+use blast_resolver::core_metadata::{split_extra, split_requirements};
+use blast_resolver::{project_package, MarkerEnvironment};
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, spec, write_wheel};
+
+fn constraints(pairs: &[(&str, &str)]) -> HashMap<String, VersionConstraint> {
+    pairs.iter()
+        .map(|(name, constraint)| (name.to_string(), VersionConstraint::parse(constraint).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_split_requirements() {
+    let requires_dist = [
+        "Typing_Extensions>=4.0",
+        "typing-extensions<5",
+        "requests[SOCKS,security]>=2.0",
+        "pytest>=7; extra == 'test'",
+        "pytest!=7.1; extra == 'test'",
+        "legacy; python_version < '3'",
+    ].map(String::from);
+    let environments = [MarkerEnvironment::current(&PythonVersion::new(3, 11, None))];
+
+    let (base, extras) = split_requirements(&requires_dist, &environments);
+    // Every requirement on a package holds, whatever it is spelled like
+    assert_eq!(base, constraints(&[
+        ("typing-extensions", ">=4.0,<5"),
+        ("requests", ">=2.0"),
+        ("requests[socks]", ">=2.0"),
+        ("requests[security]", ">=2.0"),
+    ]));
+    assert_eq!(extras["test"], constraints(&[("pytest", ">=7,!=7.1")]));
+
+    assert_eq!(split_extra("requests[socks]"), ("requests", Some("socks")));
+    assert_eq!(split_extra("requests"), ("requests", None));
+}
+
+#[tokio::test]
+async fn test_resolve_extras() {
+    // app wants http with its socks extra, and http 2.0 only exists for
+    // socks below 1.5
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["HTTP[Socks]>=1.0", "http!=1.1"]);
+    write_wheel(wheelhouse.path(), "http", "1.0", &["socks>=1.0; extra == 'socks'"]);
+    write_wheel(wheelhouse.path(), "http", "2.0", &["socks<1.5; extra == 'socks'", "tests; extra == 'test'"]);
+    write_wheel(wheelhouse.path(), "socks", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "socks", "1.6", &[]);
+    write_wheel(wheelhouse.path(), "tests", "1.0", &[]);
+    let cache_dir = TempDir::new().unwrap();
+
+    let config = project_config(wheelhouse.path(), &[("app", "*")]);
+    let result = resolver(wheelhouse.path(), cache_dir.path())
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap();
+
+    // The extra's dependencies are resolved, and only the requested extra's
+    let mut resolved: Vec<String> = result.packages.iter()
+        .map(|package| format!("{}=={}", package.name(), package.version()))
+        .collect();
+    resolved.sort();
+    assert_eq!(resolved, vec!["app==1.0", "http==2.0", "socks==1.0"]);
+
+    // and they are dependencies of the package with the extra
+    let graph = result.graph.unwrap();
+    assert!(graph.relationships.iter().any(|edge| edge.from.name() == "http" && edge.to.name() == "socks"));
+}
+
+#[tokio::test]
+async fn test_project_extras_and_markers() {
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "http", "1.0", &["socks>=1.0; extra == 'socks'"]);
+    write_wheel(wheelhouse.path(), "socks", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "pytest", "8.0", &[]);
+    let cache_dir = TempDir::new().unwrap();
+
+    // `legacy` does not exist, but its marker never holds on Python 3.11
+    let mut config = project_config(wheelhouse.path(), &[]);
+    let mut http = spec("http", ">=1.0");
+    http.extras = Some(vec!["Socks".to_string()]);
+    config.dependencies.packages.push(http);
+    config.dependency_groups.insert("test".to_string(), vec![
+        DependencyGroupEntry::Requirement("pytest>=8 ; python_version >= '3.11'".to_string()),
+        DependencyGroupEntry::Requirement("legacy ; python_version < '3'".to_string()),
+    ]);
+
+    let project = project_package(&config).unwrap();
+    let dependencies = &project.metadata().dependencies;
+    assert!(dependencies.contains_key("http[socks]"));
+    assert!(!dependencies.contains_key("legacy"));
+
+    let result = resolver(wheelhouse.path(), cache_dir.path()).resolve_project(&project).await.unwrap();
+    let mut resolved: Vec<String> = result.packages.iter()
+        .map(|package| format!("{}=={}", package.name(), package.version()))
+        .collect();
+    resolved.sort();
+    assert_eq!(resolved, vec!["http==1.0", "pytest==8.0", "socks==1.0"]);
+
+    config.dependency_groups.insert("broken".to_string(), vec![
+        DependencyGroupEntry::Requirement("lib ; python_version <".to_string()),
+    ]);
+    let error = project_package(&config).unwrap_err().to_string();
+    assert!(error.contains("Invalid marker for dependency lib"), "{}", error);
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use blast_cache::ArtifactCache;
+use blast_core::config::{BlastConfig, DependencySource};
+use blast_resolver::source::PackageSourceChain;
+use blast_resolver::{
+    create_resolver_with_config, project_package, Cache, Config, DependencyResolver, Lockfile, PyPIClient,
+};
+use tempfile::TempDir;
+
+use super::common::{project_config, write_wheel};
+
+/// An in-tree backend reading the `[project]` table, with PEP 660 support
+const BACKEND: &str = r#"
+import os, tomllib, zipfile
+
+def _project():
+    with open("pyproject.toml", "rb") as f:
+        return tomllib.load(f)["project"]
+
+def _wheel(wheel_directory, files):
+    project = _project()
+    name, version = project["name"], project["version"]
+    lines = ["Metadata-Version: 2.1", f"Name: {name}", f"Version: {version}"]
+    lines += [f"Requires-Dist: {r}" for r in project.get("dependencies", [])]
+    filename = f"{name}-{version}-py3-none-any.whl"
+    with zipfile.ZipFile(os.path.join(wheel_directory, filename), "w") as wheel:
+        for path, content in files.items():
+            wheel.writestr(path, content)
+        wheel.writestr(f"{name}-{version}.dist-info/METADATA", "\n".join(lines) + "\n")
+        wheel.writestr(f"{name}-{version}.dist-info/WHEEL", "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n")
+        wheel.writestr(f"{name}-{version}.dist-info/RECORD", "")
+    return filename
+
+def build_wheel(wheel_directory, config_settings=None, metadata_directory=None):
+    name = _project()["name"]
+    return _wheel(wheel_directory, {f"{name}/__init__.py": open(f"src/{name}/__init__.py").read()})
+
+def build_editable(wheel_directory, config_settings=None, metadata_directory=None):
+    return _wheel(wheel_directory, {f"_{_project()['name']}.pth": os.path.abspath("src") + "\n"})
+"#;
+
+/// Write a project built by the in-tree backend
+fn write_project(dir: &Path, name: &str, version: &str, dependencies: &[&str]) {
+    let dependencies: Vec<String> = dependencies.iter().map(|d| format!("\"{}\"", d)).collect();
+    fs::create_dir_all(dir.join("_build")).unwrap();
+    fs::create_dir_all(dir.join("src").join(name)).unwrap();
+    fs::write(dir.join("pyproject.toml"), format!(
+        "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\"_build\"]\n\n\
+         [project]\nname = \"{}\"\nversion = \"{}\"\ndependencies = [{}]\n",
+        name,
+        version,
+        dependencies.join(", ")
+    ))
+    .unwrap();
+    fs::write(dir.join("_build/backend.py"), BACKEND).unwrap();
+    fs::write(dir.join("src").join(name).join("__init__.py"), format!("VERSION = '{}'\n", version)).unwrap();
+}
+
+/// Configure a project requiring any version of a package from a direct source
+fn source_config(root: &Path, name: &str, source: DependencySource) -> BlastConfig {
+    let mut config = project_config(root, &[(name, "*")]);
+    config.dependencies.packages[0].source = Some(source);
+    config
+}
+
+/// Run git in a directory, returning its trimmed output
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=Blast", "-c", "user.email=blast@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Commit a new version of the project and push it to the bare repository
+fn commit_version(work: &Path, version: &str) -> String {
+    write_project(work, "demo", version, &[]);
+    git(work, &["add", "-A"]);
+    git(work, &["commit", "--quiet", "-m", version]);
+    git(work, &["push", "--quiet", "origin", "main", "--tags"]);
+    git(work, &["rev-parse", "HEAD"])
+}
+
+fn git_source(repository: &Path, rev: Option<&str>, tag: Option<&str>, branch: Option<&str>) -> DependencySource {
+    DependencySource::Git {
+        git: repository.display().to_string(),
+        rev: rev.map(str::to_string),
+        tag: tag.map(str::to_string),
+        branch: branch.map(str::to_string),
+    }
+}
+
+/// Lock a project whose only dependency is `demo` from a git source
+async fn lock_git(config: &Config, source: DependencySource) -> Lockfile {
+    let resolver = DependencyResolver::with_sources(
+        PyPIClient::new(10, 30, true).unwrap(),
+        PackageSourceChain::new(),
+        Cache::new(config.cache_dir.clone()),
+    )
+    .with_package_index("demo", Arc::new(config.direct_source("demo", source.clone())));
+    let project = project_package(&source_config(&config.project_root, "demo", source)).unwrap();
+    resolver.lock(&project).await.unwrap()
+}
+
+fn wheel_files(path: &Path) -> Vec<String> {
+    let archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    let mut files: Vec<String> = archive.file_names().map(str::to_string).collect();
+    files.sort();
+    files
+}
+
+#[tokio::test]
+async fn test_path_dependency() {
+    let root = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let wheelhouse = root.path().join("wheels");
+    fs::create_dir_all(&wheelhouse).unwrap();
+    write_wheel(&wheelhouse, "lib", "1.2", &[]);
+    write_project(&root.path().join("libs/demo"), "demo", "0.3.0", &["lib>=1"]);
+
+    let source = DependencySource::Path { path: PathBuf::from("libs/demo"), editable: true };
+    let config = source_config(root.path(), "demo", source.clone());
+    let resolver_config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        find_links: vec![wheelhouse],
+        offline: true,
+        ..Config::from_blast_config(&config).unwrap()
+    };
+    assert_eq!(resolver_config.direct_sources.get("demo"), Some(&source));
+
+    // The path's dependencies are resolved from the other sources
+    let resolver = create_resolver_with_config(resolver_config.clone()).await.unwrap();
+    let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+    let demo = lockfile.package("demo").unwrap();
+    assert_eq!(demo.version, "0.3.0");
+    assert_eq!(demo.direct, Some(source.clone()));
+    assert!(demo.commit.is_none());
+    assert!(demo.artifacts.is_empty());
+    assert!(demo.is_built_locally());
+    assert_eq!(lockfile.package("lib").unwrap().version, "1.2");
+
+    let path = root.path().join("blast.lock");
+    lockfile.write(&path).unwrap();
+    let lockfile = Lockfile::read(&path).unwrap();
+    assert_eq!(lockfile.package("demo").unwrap().direct, Some(source.clone()));
+
+    // Only indexed packages have files to fetch
+    let artifacts = lockfile.fetch_artifacts(&ArtifactCache::new(cache_dir.path()), &resolver_config.fetch_options()).await.unwrap();
+    assert_eq!(artifacts.len(), 1);
+
+    // An editable install points back at the project
+    let wheel = resolver_config.direct_source("demo", source).wheel().await.unwrap();
+    assert!(wheel_files(&wheel).contains(&"_demo.pth".to_string()));
+    let mut pth = String::new();
+    zip::ZipArchive::new(fs::File::open(&wheel).unwrap()).unwrap()
+        .by_name("_demo.pth").unwrap()
+        .read_to_string(&mut pth).unwrap();
+    assert!(pth.trim().ends_with("libs/demo/src"), "{}", pth);
+
+    let source = DependencySource::Path { path: PathBuf::from("libs/demo"), editable: false };
+    let wheel = resolver_config.direct_source("demo", source).wheel().await.unwrap();
+    assert!(wheel_files(&wheel).contains(&"demo/__init__.py".to_string()));
+
+    // The name declared for a path must match its metadata
+    let source = DependencySource::Path { path: PathBuf::from("libs/demo"), editable: false };
+    let resolver = create_resolver_with_config(Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        offline: true,
+        ..Config::from_blast_config(&source_config(root.path(), "other", source.clone())).unwrap()
+    })
+    .await
+    .unwrap();
+    let error = resolver.lock(&project_package(&source_config(root.path(), "other", source)).unwrap()).await;
+    assert!(error.is_err());
+}
+
+#[tokio::test]
+async fn test_url_dependency() {
+    let root = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let wheel = write_wheel(root.path(), "remote", "2.0", &[]);
+    let url = format!("file://{}", wheel.display());
+
+    let source = DependencySource::Url { url: url.clone() };
+    let config = source_config(root.path(), "remote", source.clone());
+    let resolver = create_resolver_with_config(Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        offline: true,
+        ..Config::from_blast_config(&config).unwrap()
+    })
+    .await
+    .unwrap();
+    let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+
+    // Archives are pinned by hash like any indexed file
+    let remote = lockfile.package("remote").unwrap();
+    assert_eq!(remote.version, "2.0");
+    assert_eq!(remote.direct, Some(source));
+    assert!(!remote.is_built_locally());
+    assert_eq!(remote.artifacts[0].url, url);
+    assert_eq!(remote.artifacts[0].sha256.as_ref().map(String::len), Some(64));
+}
+
+#[tokio::test]
+async fn test_git_dependency() {
+    let root = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let repository = root.path().join("demo.git");
+    let work = root.path().join("work");
+    fs::create_dir_all(&work).unwrap();
+    git(root.path(), &["init", "--quiet", "--bare", "demo.git"]);
+    git(&work, &["init", "--quiet", "-b", "main"]);
+    git(&work, &["remote", "add", "origin", repository.to_str().unwrap()]);
+    let first = commit_version(&work, "1.0");
+    git(&work, &["tag", "v1.0"]);
+    let second = commit_version(&work, "2.0");
+
+    let config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        project_root: root.path().to_path_buf(),
+        ..Config::default()
+    };
+
+    // Revisions, tags and branches resolve to the commit they point at
+    let lockfile = lock_git(&config, git_source(&repository, Some(&first), None, None)).await;
+    assert_eq!(lockfile.package("demo").unwrap().version, "1.0");
+    assert_eq!(lockfile.package("demo").unwrap().commit.as_deref(), Some(first.as_str()));
+    let lockfile = lock_git(&config, git_source(&repository, None, Some("v1.0"), None)).await;
+    assert_eq!(lockfile.package("demo").unwrap().commit.as_deref(), Some(first.as_str()));
+
+    let branch = git_source(&repository, None, None, Some("main"));
+    let lockfile = lock_git(&config, branch.clone()).await;
+    let demo = lockfile.package("demo").unwrap();
+    assert_eq!(demo.version, "2.0");
+    assert_eq!(demo.commit.as_deref(), Some(second.as_str()));
+    assert_eq!(demo.direct, Some(branch.clone()));
+    assert!(demo.is_built_locally());
+
+    // Relocking keeps the pinned commit after the branch moves on
+    let third = commit_version(&work, "3.0");
+    let mut pinned = config.clone();
+    pinned.direct_sources.insert("demo".to_string(), branch.clone());
+    let pinned = pinned.with_locked_commits(&lockfile);
+    let relocked = lock_git(&pinned, branch.clone()).await;
+    assert_eq!(relocked.package("demo").unwrap().commit.as_deref(), Some(second.as_str()));
+    assert_eq!(relocked.package("demo").unwrap().version, "2.0");
+
+    // Installs build the locked commit, whatever the branch is at
+    let wheel = config.direct_source("demo", branch.clone()).with_commit(Some(second.clone())).wheel().await.unwrap();
+    assert!(wheel.ends_with("demo-2.0-py3-none-any.whl"));
+    // but nothing a lockfile says is used unless it is a commit id
+    for commit in ["../../..", "--upload-pack=touch", &second[..12]] {
+        let source = config.direct_source("demo", branch.clone()).with_commit(Some(commit.to_string()));
+        let error = source.wheel().await.unwrap_err().to_string();
+        assert!(error.contains("Invalid commit"), "{}", error);
+    }
+
+    let unpinned = lock_git(&config, branch).await;
+    assert_eq!(unpinned.package("demo").unwrap().commit.as_deref(), Some(third.as_str()));
+    assert_eq!(unpinned.package("demo").unwrap().version, "3.0");
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use blast_core::package::PackageId;
+use blast_core::python::PythonVersion;
+use blast_core::version::Version;
+use blast_image::platform::{Libc, PlatformInfo};
+use blast_resolver::find_links::FindLinksSource;
+use blast_resolver::simple::SimpleIndexSource;
+use blast_resolver::source::{PackageSource, PackageSourceChain};
+use blast_resolver::PythonTarget;
+use tempfile::TempDir;
+
+/// Write a zip archive with a single metadata file
+fn write_archive(path: &Path, member: &str, metadata: &str) {
+    let mut archive = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    archive.start_file(member, Default::default()).unwrap();
+    archive.write_all(metadata.as_bytes()).unwrap();
+    archive.finish().unwrap();
+}
+
+fn create_wheelhouse() -> TempDir {
+    let dir = TempDir::new().unwrap();
+
+    write_archive(
+        &dir.path().join("Demo_Pkg-1.0-py3-none-any.whl"),
+        "Demo_Pkg-1.0.dist-info/METADATA",
+        "Metadata-Version: 2.1\nName: Demo-Pkg\nVersion: 1.0\nRequires-Dist: helper>=2\n",
+    );
+    write_archive(
+        &dir.path().join("demo_pkg-1.1-1-cp311-cp311-manylinux_2_17_x86_64.whl"),
+        "demo_pkg-1.1.dist-info/METADATA",
+        "Metadata-Version: 2.1\nName: demo-pkg\nVersion: 1.1\n",
+    );
+    // Source distribution with static metadata
+    write_archive(
+        &dir.path().join("helper-2.0.zip"),
+        "helper-2.0/PKG-INFO",
+        "Metadata-Version: 2.2\nName: helper\nVersion: 2.0\nRequires-Dist: six\n",
+    );
+    // Source distribution whose dependencies are only known after a build
+    write_archive(
+        &dir.path().join("legacy-0.1.zip"),
+        "legacy-0.1/PKG-INFO",
+        "Metadata-Version: 1.2\nName: legacy\nVersion: 0.1\n",
+    );
+    fs::write(dir.path().join("README.txt"), "not a distribution").unwrap();
+
+    dir
+}
+
+#[tokio::test]
+async fn test_find_links_versions() {
+    let dir = create_wheelhouse();
+    // A host the manylinux wheel of 1.1 is built for
+    let platform = PlatformInfo {
+        os: "linux".to_string(),
+        arch: "x86_64".to_string(),
+        libc: Some(Libc::Glibc(2, 28)),
+        ..PlatformInfo::current()
+    };
+    let target = PythonTarget::version(PythonVersion::new(3, 11, None)).with_platform(platform);
+    let source = FindLinksSource::new(dir.path(), 5).unwrap().with_python_target(target);
+
+    let versions = source.get_versions("demo.pkg").await.unwrap();
+    assert_eq!(versions, vec![Version::parse("1.0").unwrap(), Version::parse("1.1").unwrap()]);
+    assert_eq!(source.distributions("DEMO-PKG").len(), 2);
+    assert!(source.get_versions("missing").await.is_err());
+
+    let id = PackageId::new("demo-pkg", Version::parse("1.1").unwrap());
+    assert!(source.package_exists(&id).await.unwrap());
+    assert!(!source.package_exists(&PackageId::new("demo-pkg", Version::parse("2.0").unwrap())).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_find_links_metadata_and_download() {
+    let dir = create_wheelhouse();
+    let source = FindLinksSource::new(dir.path(), 5).unwrap();
+
+    let wheel = source.get_package(&PackageId::new("demo-pkg", Version::parse("1.0").unwrap())).await.unwrap();
+    assert!(wheel.metadata().dependencies.contains_key("helper"));
+
+    let sdist = source.get_package(&PackageId::new("helper", Version::parse("2.0").unwrap())).await.unwrap();
+    assert!(sdist.metadata().dependencies.contains_key("six"));
+
+    // Legacy sdists need a build before their dependencies are known
+    let legacy = source.get_package(&PackageId::new("legacy", Version::parse("0.1").unwrap())).await;
+    assert!(legacy.is_err());
+
+    let id = PackageId::new("helper", Version::parse("2.0").unwrap());
+    let data = source.download_package(&id).await.unwrap();
+    assert_eq!(data, fs::read(dir.path().join("helper-2.0.zip")).unwrap());
+}
+
+#[tokio::test]
+async fn test_find_links_refresh() {
+    let dir = create_wheelhouse();
+    let mut source = FindLinksSource::new(dir.path(), 5).unwrap();
+    assert!(source.distributions("added").is_empty());
+
+    write_archive(
+        &dir.path().join("added-1.0-py3-none-any.whl"),
+        "added-1.0.dist-info/METADATA",
+        "Metadata-Version: 2.1\nName: added\nVersion: 1.0\n",
+    );
+    source.refresh().unwrap();
+    assert_eq!(source.distributions("added").len(), 1);
+}
+
+#[test]
+fn test_find_links_priority() {
+    let dir = create_wheelhouse();
+    let index = TempDir::new().unwrap();
+
+    let mut ahead = PackageSourceChain::new();
+    ahead.add_source(Box::new(SimpleIndexSource::new(index.path().to_str().unwrap(), 10).unwrap()));
+    ahead.add_source(Box::new(FindLinksSource::new(dir.path(), 5).unwrap()));
+    assert_eq!(ahead.sources()[0].priority(), 5);
+
+    let mut behind = PackageSourceChain::new();
+    behind.add_source(Box::new(FindLinksSource::new(dir.path(), 200).unwrap()));
+    behind.add_source(Box::new(SimpleIndexSource::new(index.path().to_str().unwrap(), 10).unwrap()));
+    assert_eq!(behind.sources()[0].priority(), 10);
+    assert_eq!(behind.sources()[1].name(), dir.path().display().to_string());
+
+    assert!(FindLinksSource::new(dir.path().join("missing"), 5).is_err());
+}
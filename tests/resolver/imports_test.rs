@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use blast_core::hot_reload::{HotReloadConfig, HotReloadManager, HotReloadUpdateStatus, HotReloadUpdateType, ImportResolver};
+use blast_resolver::imports::{bundled, top_level_modules, MappingSource};
+use blast_resolver::source::PackageSourceChain;
+use blast_resolver::{create_resolver_with_config, Cache, Config, DependencyResolver, ImportMap, ImportResolution, PyPIClient};
+use tempfile::TempDir;
+
+/// Write a `.dist-info` directory with the given metadata files
+fn write_dist_info(site_packages: &Path, name: &str, files: &[(&str, &str)]) {
+    let dist_info = site_packages.join(format!("{}.dist-info", name));
+    fs::create_dir_all(&dist_info).unwrap();
+    for (file, content) in files {
+        fs::write(dist_info.join(file), content).unwrap();
+    }
+}
+
+/// A site-packages directory with modules named unlike their distributions
+fn create_site_packages() -> TempDir {
+    let site_packages = TempDir::new().unwrap();
+    write_dist_info(site_packages.path(), "PyYAML-6.0.1", &[("top_level.txt", "_yaml\nyaml\n")]);
+    write_dist_info(site_packages.path(), "scikit_learn-1.4.0", &[(
+        "RECORD",
+        "sklearn/__init__.py,sha256=abc,100\n\
+         sklearn/__pycache__/__init__.cpython-311.pyc,,\n\
+         scikit_learn-1.4.0.dist-info/METADATA,sha256=def,200\n\
+         scikit_learn.libs/libgomp.so,,\n\
+         _sklearn_ext.cpython-311-x86_64-linux-gnu.so,,\n\
+         ../../bin/sklearn-cli,,\n",
+    )]);
+    // Namespace packages share a top-level module
+    write_dist_info(site_packages.path(), "protobuf-4.25.0", &[("top_level.txt", "google\n")]);
+    write_dist_info(site_packages.path(), "google_api_core-2.15.0", &[("top_level.txt", "google\n")]);
+    site_packages
+}
+
+fn found(distribution: &str, source: MappingSource) -> ImportResolution {
+    ImportResolution::Found { distribution: distribution.to_string(), source }
+}
+
+#[test]
+fn test_bundled_mappings() {
+    assert_eq!(bundled("cv2"), Some("opencv-python"));
+    assert_eq!(bundled("PIL"), Some("Pillow"));
+    assert_eq!(bundled("Bio"), Some("biopython"));
+    assert_eq!(bundled("zmq"), Some("pyzmq"));
+    assert_eq!(bundled("requests"), None);
+
+    let imports = ImportMap::new();
+    assert_eq!(imports.resolve("PIL.Image"), found("Pillow", MappingSource::Bundled));
+    assert_eq!(imports.resolve("requests"), ImportResolution::Unknown);
+    assert_eq!(imports.distribution_for("sklearn.linear_model").unwrap(), "scikit-learn");
+    // Modules nothing is known about are assumed to share their name
+    assert_eq!(imports.distribution_for("flask_cors").unwrap(), "flask-cors");
+}
+
+#[test]
+fn test_installed_distributions() {
+    let site_packages = create_site_packages();
+    let modules = top_level_modules(&site_packages.path().join("scikit_learn-1.4.0.dist-info")).unwrap();
+    assert_eq!(modules, vec!["_sklearn_ext", "sklearn"]);
+
+    let mut imports = ImportMap::new();
+    imports.scan_site_packages(site_packages.path()).unwrap();
+    assert_eq!(imports.resolve("yaml"), found("pyyaml", MappingSource::Installed));
+    assert_eq!(imports.resolve("_yaml"), found("pyyaml", MappingSource::Installed));
+    assert_eq!(imports.resolve("sklearn"), found("scikit-learn", MappingSource::Installed));
+
+    // Ambiguous modules are reported, not guessed
+    let ambiguous = ImportResolution::Ambiguous(vec!["google-api-core".to_string(), "protobuf".to_string()]);
+    assert_eq!(imports.resolve("google.protobuf"), ambiguous);
+    let error = imports.distribution_for("google").unwrap_err().to_string();
+    assert!(error.contains("google-api-core, protobuf"), "{}", error);
+}
+
+#[test]
+fn test_learned_mappings() {
+    let cache_dir = TempDir::new().unwrap();
+    let mut imports = ImportMap::load(cache_dir.path()).unwrap();
+    imports.learn("cv2", "opencv-python-headless");
+    imports.learn("internal_tools.cli", "acme-internal-tools");
+    imports.save().unwrap();
+
+    // Learned mappings survive a reload and win over the bundled table
+    let mut imports = ImportMap::load(cache_dir.path()).unwrap();
+    assert_eq!(imports.resolve("cv2"), found("opencv-python-headless", MappingSource::Learned));
+    assert_eq!(imports.resolve("internal_tools"), found("acme-internal-tools", MappingSource::Learned));
+
+    // A successful install teaches every module of the distribution
+    let site_packages = create_site_packages();
+    let modules = imports.learn_installed(&site_packages.path().join("PyYAML-6.0.1.dist-info")).unwrap();
+    assert_eq!(modules, vec!["_yaml", "yaml"]);
+    assert_eq!(imports.resolve("yaml"), found("pyyaml", MappingSource::Learned));
+
+    // What is actually installed comes first
+    imports.learn("sklearn", "sklearn");
+    imports.scan_site_packages(site_packages.path()).unwrap();
+    assert_eq!(imports.resolve("sklearn"), found("scikit-learn", MappingSource::Installed));
+}
+
+#[tokio::test]
+async fn test_resolver_reports_ambiguous_import() {
+    let site_packages = create_site_packages();
+    let cache_dir = TempDir::new().unwrap();
+    let mut imports = ImportMap::new();
+    imports.scan_site_packages(site_packages.path()).unwrap();
+
+    let resolver = DependencyResolver::with_sources(
+        PyPIClient::new(10, 30, true).unwrap(),
+        PackageSourceChain::new(),
+        Cache::new(cache_dir.path().to_path_buf()),
+    )
+    .with_import_map(imports);
+    let error = resolver.resolve_import("google").await.unwrap_err();
+    assert!(error.to_string().contains("several distributions"), "{}", error);
+    assert!(!resolver.is_available("google").await);
+}
+
+#[tokio::test]
+async fn test_hot_reload_installs_mapped_distributions() {
+    let site_packages = create_site_packages();
+    let cache_dir = TempDir::new().unwrap();
+    let resolver = create_resolver_with_config(Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        offline: true,
+        site_packages: Some(site_packages.path().to_path_buf()),
+        ..Config::default()
+    })
+    .await
+    .unwrap();
+    // The environment's own distributions are known from the start
+    assert_eq!(resolver.distribution_for("sklearn.svm").await.unwrap(), "scikit-learn");
+
+    let project = TempDir::new().unwrap();
+    let mut manager = HotReloadManager::new(HotReloadConfig::new(vec![project.path().to_path_buf()]), resolver.clone());
+    manager.start().await.unwrap();
+    fs::write(project.path().join("app.py"), "import cv2\nfrom PIL import Image\nimport google\n").unwrap();
+
+    let mut updates = Vec::new();
+    for _ in 0..50 {
+        updates = manager.get_pending_updates().await;
+        if !updates.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut installs: Vec<(&str, &str)> = updates.iter()
+        .filter_map(|update| match &update.update_type {
+            HotReloadUpdateType::Import { module, distribution } => Some((module.as_str(), distribution.as_str())),
+            _ => None,
+        })
+        .collect();
+    installs.sort();
+    installs.dedup();
+    // Ambiguous imports install nothing rather than a guess
+    assert_eq!(installs, vec![("PIL", "Pillow"), ("cv2", "opencv-python")]);
+
+    // A completed install is remembered for the module
+    let cv2 = updates.iter()
+        .find(|update| matches!(&update.update_type, HotReloadUpdateType::Import { module, .. } if module == "cv2"))
+        .unwrap();
+    manager.update_status(cv2.timestamp, HotReloadUpdateStatus::Completed).await;
+    manager.stop();
+    let imports = ImportMap::load(cache_dir.path()).unwrap();
+    assert_eq!(imports.resolve("cv2"), found("opencv-python", MappingSource::Learned));
+}
@@ -0,0 +1,230 @@
+use std::path::Path;
+
+use blast_cache::ArtifactCache;
+use blast_core::config::{BlastConfig, DependencyGroupEntry, GroupSelection};
+use blast_core::python::PythonVersion;
+use blast_core::version::VersionConstraint;
+use blast_resolver::{project_package, Config, FetchOptions, LockTarget, LockedArtifact, Lockfile, ResolutionMode};
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, spec, write_wheel};
+
+/// Lock a project requiring `app` against a wheelhouse where `app` needs `lib`
+async fn lock_project(wheelhouse: &Path, cache_dir: &Path) -> (BlastConfig, Lockfile) {
+    write_wheel(wheelhouse, "app", "1.0", &["lib>=1"]);
+    write_wheel(wheelhouse, "lib", "1.0", &[]);
+    write_wheel(wheelhouse, "lib", "1.2", &[]);
+
+    let config = project_config(wheelhouse, &[("app", ">=1.0")]);
+    let resolver = resolver(wheelhouse, cache_dir)
+        .with_python_target(Config::from_blast_config(&config).unwrap().python);
+
+    let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+    (config, lockfile)
+}
+
+#[tokio::test]
+async fn test_lock_project() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let (_, lockfile) = lock_project(wheelhouse.path(), cache_dir.path()).await;
+
+    assert_eq!(lockfile.project, "project");
+    assert_eq!(lockfile.requirements.get("app").map(String::as_str), Some(">=1.0"));
+    assert_eq!(lockfile.packages.len(), 2);
+
+    let app = lockfile.package("app").unwrap();
+    assert_eq!(app.version, "1.0");
+    assert_eq!(app.dependencies, vec!["lib"]);
+    assert_eq!(app.source, wheelhouse.path().display().to_string());
+    assert_eq!(app.artifacts[0].filename, "app-1.0-py3-none-any.whl");
+    assert!(app.artifacts[0].url.starts_with("file://"));
+    assert_eq!(app.artifacts[0].sha256.as_ref().map(String::len), Some(64));
+
+    let lib = lockfile.package("lib").unwrap();
+    assert_eq!(lib.version, "1.2");
+    assert!(lib.dependencies.is_empty());
+
+    let order: Vec<&str> = lockfile.install_order().iter().map(|package| package.name.as_str()).collect();
+    assert_eq!(order, vec!["lib", "app"]);
+}
+
+#[tokio::test]
+async fn test_lockfile_roundtrip() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let (config, lockfile) = lock_project(wheelhouse.path(), cache_dir.path()).await;
+
+    let content = lockfile.to_toml().unwrap();
+    assert!(content.contains("[[package]]"));
+    assert!(content.contains("[[package.artifact]]"));
+    assert_eq!(Lockfile::from_toml(&content).unwrap(), lockfile);
+    assert_eq!(Lockfile::from_toml(&content).unwrap().to_toml().unwrap(), content);
+
+    let path = wheelhouse.path().join("blast.lock");
+    lockfile.write(&path).unwrap();
+    assert_eq!(Lockfile::read(&path).unwrap(), lockfile);
+
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    assert_eq!(lockfile.target, Some(LockTarget::new(&resolver_config.python)));
+    assert!(lockfile.is_up_to_date(&project_package(&config).unwrap(), &resolver_config));
+    let changed = project_config(wheelhouse.path(), &[("app", ">=1.0"), ("lib", "<2")]);
+    assert!(!lockfile.is_up_to_date(&project_package(&changed).unwrap(), &resolver_config));
+
+    // So does a change of constraints, overrides or interpreter
+    let mut constrained = config.clone();
+    constrained.constraints.push(spec("lib", "<2"));
+    let constrained_config = Config::from_blast_config(&constrained).unwrap();
+    assert!(!lockfile.is_up_to_date(&project_package(&constrained).unwrap(), &constrained_config));
+    let mut overridden = config.clone();
+    overridden.overrides = constrained.constraints.clone();
+    let overridden_config = Config::from_blast_config(&overridden).unwrap();
+    assert!(!lockfile.is_up_to_date(&project_package(&overridden).unwrap(), &overridden_config));
+    let mut other_python = config.clone();
+    other_python.python_version = PythonVersion::new(3, 12, None);
+    let other_python_config = Config::from_blast_config(&other_python).unwrap();
+    assert!(!lockfile.is_up_to_date(&project_package(&other_python).unwrap(), &other_python_config));
+
+    let future = content.replace("version = 1", "version = 99");
+    assert!(Lockfile::from_toml(&future).is_err());
+}
+
+#[tokio::test]
+async fn test_lockfile_fetch_artifacts() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let (_, lockfile) = lock_project(wheelhouse.path(), cache_dir.path()).await;
+    let artifacts = ArtifactCache::new(cache_dir.path().join("artifacts"));
+
+    let offline = FetchOptions { offline: true, ..FetchOptions::default() };
+    let fetched = lockfile.fetch_artifacts(&artifacts, &offline).await.unwrap();
+    assert_eq!(fetched.len(), 2);
+    assert_eq!(fetched[0].filename, "lib-1.2-py3-none-any.whl");
+    assert_eq!(Some(&fetched[0].sha256), lockfile.package("lib").unwrap().artifacts[0].sha256.as_ref());
+
+    // A file that no longer matches its locked hash is rejected
+    let mut tampered = lockfile.clone();
+    tampered.packages[0].artifacts[0].sha256 = Some("0".repeat(64));
+    let fresh = ArtifactCache::new(cache_dir.path().join("fresh"));
+    assert!(tampered.fetch_artifacts(&fresh, &FetchOptions::default()).await.is_err());
+
+    // Offline, remote files are reported instead of downloaded
+    let mut remote = lockfile.clone();
+    remote.packages[0].artifacts[0].url = "https://example.com/app-1.0-py3-none-any.whl".to_string();
+    let error = remote.fetch_artifacts(&fresh, &offline).await.unwrap_err().to_string();
+    assert!(error.contains("app==1.0"), "{}", error);
+    // but found among the other cached files of their release
+    artifacts.store("app", "1.0", "app-1.0-cp311-abi3-manylinux_2_17_x86_64.whl", b"native").await.unwrap();
+    let fetched = remote.fetch_artifacts(&artifacts, &offline).await.unwrap();
+    assert_eq!(fetched[1].filename, "app-1.0-py3-none-any.whl");
+
+    // Files without a locked hash are only installed when allowed
+    let mut unhashed = lockfile.clone();
+    unhashed.packages[0].artifacts[0].sha256 = None;
+    let empty = ArtifactCache::new(cache_dir.path().join("empty"));
+    let error = unhashed.fetch_artifacts(&empty, &offline).await.unwrap_err().to_string();
+    assert!(error.contains("no hash for: app-1.0-py3-none-any.whl"), "{}", error);
+    assert!(empty.get("lib", "1.2").await.unwrap().is_none());
+    let allowed = FetchOptions { allow_unhashed: true, ..offline.clone() };
+    assert_eq!(unhashed.fetch_artifacts(&empty, &allowed).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_lockfile_fetch_compatible_artifact() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let (_, mut lockfile) = lock_project(wheelhouse.path(), cache_dir.path()).await;
+    let offline = FetchOptions { offline: true, ..FetchOptions::default() };
+
+    // A lock made elsewhere lists files for other platforms first; the
+    // install takes the one its interpreter can use
+    let pure = lockfile.packages[0].artifacts[0].clone();
+    let windows = LockedArtifact {
+        filename: "app-1.0-cp311-cp311-win_amd64.whl".to_string(),
+        url: "https://example.com/app-1.0-cp311-cp311-win_amd64.whl".to_string(),
+        sha256: Some("0".repeat(64)),
+    };
+    lockfile.packages[0].artifacts = vec![windows.clone(), pure.clone()];
+    let artifacts = ArtifactCache::new(cache_dir.path().join("artifacts"));
+    let fetched = lockfile.fetch_artifacts(&artifacts, &offline).await.unwrap();
+    assert_eq!(fetched[1].filename, pure.filename);
+
+    // With nothing it can use, nothing is fetched
+    lockfile.packages[0].artifacts = vec![windows];
+    let fresh = ArtifactCache::new(cache_dir.path().join("fresh"));
+    let error = lockfile.fetch_artifacts(&fresh, &offline).await.unwrap_err().to_string();
+    assert!(error.contains("No locked file can be installed") && error.contains("app==1.0"), "{}", error);
+    assert!(fresh.get("lib", "1.2").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_lock_resolution_modes() {
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["lib>=1"]);
+    write_wheel(wheelhouse.path(), "app", "2.0", &["lib>=1"]);
+    write_wheel(wheelhouse.path(), "lib", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "lib", "1.2", &[]);
+    let config = project_config(wheelhouse.path(), &[("app", ">=1.0")]);
+
+    let cases = [
+        (ResolutionMode::Highest, "2.0", "1.2"),
+        (ResolutionMode::Lowest, "1.0", "1.0"),
+        (ResolutionMode::LowestDirect, "1.0", "1.2"),
+    ];
+    for (mode, app, lib) in cases {
+        let cache_dir = TempDir::new().unwrap();
+        let resolver = resolver(wheelhouse.path(), cache_dir.path()).with_mode(mode);
+
+        let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+        assert_eq!(lockfile.package("app").unwrap().version, app, "{}", mode);
+        assert_eq!(lockfile.package("lib").unwrap().version, lib, "{}", mode);
+
+        // The mode is recorded so the lock can be reproduced
+        let content = lockfile.to_toml().unwrap();
+        assert!(content.contains(&format!("resolution = \"{}\"", mode)), "{}", content);
+        assert_eq!(Lockfile::from_toml(&content).unwrap().resolution, mode);
+        assert_eq!(mode.as_str().parse::<ResolutionMode>().unwrap(), mode);
+    }
+    assert!("newest".parse::<ResolutionMode>().is_err());
+}
+
+#[tokio::test]
+async fn test_lock_dependency_groups() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["lib>=1"]);
+    write_wheel(wheelhouse.path(), "lib", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "lib", "1.2", &[]);
+    write_wheel(wheelhouse.path(), "pytest", "8.0", &["lib<1.1"]);
+
+    let mut config = project_config(wheelhouse.path(), &[("app", ">=1.0")]);
+    config.dependency_groups.insert("test".to_string(), vec![
+        DependencyGroupEntry::Requirement("pytest>=8".to_string()),
+    ]);
+
+    let resolver = resolver(wheelhouse.path(), cache_dir.path());
+    let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+
+    // Groups are resolved together, so `lib` is the same with or without them
+    assert_eq!(lockfile.packages.len(), 3);
+    assert_eq!(lockfile.package("lib").unwrap().version, "1.0");
+
+    let selected = |selection: GroupSelection| -> Vec<String> {
+        let requirements = config.requirements(&selection).unwrap();
+        let subset = lockfile.subset(requirements.iter().map(|spec| spec.name.as_str())).unwrap();
+        subset.install_order().iter().map(|package| package.name.clone()).collect()
+    };
+    assert_eq!(selected(GroupSelection::default()), vec!["lib", "app"]);
+    assert_eq!(selected(GroupSelection { groups: vec!["test".to_string()], dev: false }), vec!["lib", "app", "pytest"]);
+
+    let error = lockfile.subset(["missing"]).unwrap_err().to_string();
+    assert!(error.contains("missing is not in the lockfile"), "{}", error);
+    // Requirements on a package from several groups all hold, however each
+    // spells its name
+    config.dependency_groups.insert("compat".to_string(), vec![
+        DependencyGroupEntry::Requirement("App<2".to_string()),
+    ]);
+    let dependencies = project_package(&config).unwrap().metadata().dependencies.clone();
+    assert_eq!(dependencies.len(), 2);
+    assert_eq!(dependencies["app"], VersionConstraint::parse(">=1.0,<2").unwrap());
+}
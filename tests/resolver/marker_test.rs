@@ -0,0 +1,68 @@
+use blast_core::python::PythonVersion;
+use blast_image::platform::PlatformInfo;
+use blast_resolver::marker::{MarkerEnvironment, MarkerTree};
+
+fn environment(os: &str) -> MarkerEnvironment {
+    let mut platform = PlatformInfo::current();
+    platform.os = os.to_string();
+    platform.arch = "x86_64".to_string();
+    MarkerEnvironment::new(&PythonVersion::new(3, 11, Some(4)), &platform)
+}
+
+fn evaluate(marker: &str, env: &MarkerEnvironment, extra: Option<&str>) -> bool {
+    MarkerTree::parse(marker).unwrap().evaluate(env, extra)
+}
+
+#[test]
+fn test_platform_markers() {
+    let linux = environment("linux");
+    let windows = environment("windows");
+
+    assert!(!evaluate("sys_platform == \"win32\"", &linux, None));
+    assert!(evaluate("sys_platform == 'win32'", &windows, None));
+    assert!(evaluate("platform_machine == 'AMD64'", &windows, None));
+    assert!(evaluate("platform_system not in 'Windows Darwin'", &linux, None));
+}
+
+#[test]
+fn test_version_markers() {
+    let linux = environment("linux");
+
+    assert!(evaluate("python_version >= \"3.8\"", &linux, None));
+    assert!(!evaluate("python_version < \"3.8\"", &linux, None));
+    assert!(evaluate("python_full_version >= '3.11.4'", &linux, None));
+    assert!(evaluate("'3.9' <= python_version", &linux, None));
+}
+
+#[test]
+fn test_boolean_markers() {
+    let linux = environment("linux");
+    let windows = environment("windows");
+    let marker = "python_version < '3.8' or (os_name == 'nt' and implementation_name == 'cpython')";
+
+    assert!(!evaluate(marker, &linux, None));
+    assert!(evaluate(marker, &windows, None));
+
+    let tree = MarkerTree::parse("(python_version<'3.8' or sys_platform=='win32') and extra=='test'").unwrap();
+    assert_eq!(
+        tree.to_string(),
+        "(python_version < \"3.8\" or sys_platform == \"win32\") and extra == \"test\""
+    );
+    assert_eq!(MarkerTree::parse(&tree.to_string()).unwrap(), tree);
+}
+
+#[test]
+fn test_extra_markers() {
+    let linux = environment("linux");
+
+    assert!(!evaluate("extra == 'dev'", &linux, None));
+    assert!(evaluate("extra == 'Dev_Tools'", &linux, Some("dev-tools")));
+    assert_eq!(MarkerTree::parse("extra == 'test' or extra == 'docs'").unwrap().extras(), vec!["test", "docs"]);
+}
+
+#[test]
+fn test_invalid_markers() {
+    for marker in ["python_version", "unknown_var == 'x'", "(python_version > '3'", "python_version > '3' and"] {
+        assert!(MarkerTree::parse(marker).is_err(), "{}", marker);
+    }
+}
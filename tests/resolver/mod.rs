@@ -1,3 +1,22 @@
+mod build_test;
 mod cache_test;
+mod common;
+mod constraint_test;
+mod core_metadata_test;
+mod direct_test;
+mod find_links_test;
+mod imports_test;
+mod lockfile_test;
+mod marker_test;
+mod offline_test;
+mod prefetch_test;
+mod prerelease_test;
+mod pubgrub_test;
+mod pypi_test;
+mod remote_wheel_test;
+mod report_test;
 mod resolver_test;
-mod source_test; 
\ No newline at end of file
+mod simple_test;
+mod source_test;
+mod tags_test;
+mod target_test; 
\ No newline at end of file
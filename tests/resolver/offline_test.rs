@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use blast_core::metadata::PackageMetadata;
+use blast_core::package::Package;
+use blast_core::version::VersionConstraint;
+use blast_resolver::{create_resolver_with_config, Config};
+use tempfile::TempDir;
+
+use super::common::{resolver, write_wheel};
+
+fn root_package(name: &str) -> Package {
+    let metadata = PackageMetadata::new(
+        name.to_string(),
+        "1.0".to_string(),
+        HashMap::new(),
+        VersionConstraint::any(),
+    );
+    Package::new(name.to_string(), "1.0".to_string(), metadata, VersionConstraint::any()).unwrap()
+}
+
+/// Resolve `app` against a wheelhouse, filling the metadata and artifact caches
+async fn warm_caches(cache_dir: &Path) {
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["lib>=1"]);
+    write_wheel(wheelhouse.path(), "lib", "1.2", &[]);
+
+    let resolver = resolver(wheelhouse.path(), cache_dir);
+
+    let packages = resolver.resolve(&root_package("app")).await.unwrap();
+    let config = Config { cache_dir: cache_dir.to_path_buf(), ..Config::default() };
+    let artifacts = resolver.fetch_artifacts(&packages, &config.artifact_cache()).await.unwrap();
+    assert_eq!(artifacts.len(), 1);
+}
+
+#[tokio::test]
+async fn test_offline_resolution_from_caches() {
+    let cache_dir = TempDir::new().unwrap();
+    warm_caches(cache_dir.path()).await;
+
+    // The wheelhouse is gone; only the caches remain
+    let config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        offline: true,
+        ..Config::default()
+    };
+    let artifact_cache = config.artifact_cache();
+    let resolver = create_resolver_with_config(config).await.unwrap();
+    assert!(resolver.is_offline());
+
+    let packages = resolver.resolve(&root_package("app")).await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name(), "lib");
+    assert_eq!(packages[0].version().to_string(), "1.2");
+
+    let artifacts = resolver.fetch_artifacts(&packages, &artifact_cache).await.unwrap();
+    assert_eq!(artifacts[0].filename, "lib-1.2-py3-none-any.whl");
+}
+
+#[tokio::test]
+async fn test_offline_reports_missing_metadata() {
+    let cache_dir = TempDir::new().unwrap();
+    warm_caches(cache_dir.path()).await;
+
+    // A local source can add packages, but not their uncached dependencies
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "tool", "1.0", &["lib>=1", "ghost", "phantom>=2"]);
+
+    let config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        find_links: vec![wheelhouse.path().to_path_buf()],
+        offline: true,
+        ..Config::default()
+    };
+    let resolver = create_resolver_with_config(config).await.unwrap();
+
+    let error = resolver.resolve(&root_package("tool")).await.unwrap_err().to_string();
+    assert!(error.contains("offline"), "{}", error);
+    assert!(error.contains("ghost"), "{}", error);
+    assert!(error.contains("phantom"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_offline_reports_missing_artifacts() {
+    let cache_dir = TempDir::new().unwrap();
+    let config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        offline: true,
+        ..Config::default()
+    };
+    let artifact_cache = config.artifact_cache();
+    let resolver = create_resolver_with_config(config).await.unwrap();
+
+    let packages = vec![root_package("first"), root_package("second")];
+    let error = resolver.fetch_artifacts(&packages, &artifact_cache).await.unwrap_err().to_string();
+    assert!(error.contains("first==1.0"), "{}", error);
+    assert!(error.contains("second==1.0"), "{}", error);
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use blast_core::error::{BlastError, BlastResult};
+use blast_core::metadata::PackageMetadata;
+use blast_core::package::{Package, PackageId};
+use blast_core::version::{Version, VersionConstraint};
+use blast_resolver::source::{PackageSource, PackageSourceChain};
+use blast_resolver::{Cache, DependencyResolver, PyPIClient};
+use tempfile::TempDir;
+
+fn package(name: &str, version: &str, requires: &[(&str, &str)]) -> Package {
+    let dependencies = requires.iter()
+        .map(|(name, constraint)| (name.to_string(), VersionConstraint::parse(constraint).unwrap()))
+        .collect();
+    let metadata = PackageMetadata::new(name.to_string(), version.to_string(), dependencies, VersionConstraint::any());
+    Package::new(name.to_string(), version.to_string(), metadata, VersionConstraint::any()).unwrap()
+}
+
+/// A slow source counting every lookup and how many run at once
+#[derive(Default)]
+struct CountingSource {
+    packages: HashMap<PackageId, Package>,
+    lookups: Mutex<HashMap<String, usize>>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl CountingSource {
+    async fn lookup(&self, key: String) {
+        *self.lookups.lock().unwrap().entry(key).or_default() += 1;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn lookups(&self) -> usize {
+        self.lookups.lock().unwrap().values().sum()
+    }
+}
+
+#[async_trait]
+impl PackageSource for CountingSource {
+    async fn get_package(&self, id: &PackageId) -> BlastResult<Package> {
+        self.lookup(id.to_string()).await;
+        self.packages.get(id).cloned().ok_or_else(|| BlastError::package("Package not found"))
+    }
+
+    async fn get_versions(&self, package_name: &str) -> BlastResult<Vec<Version>> {
+        self.lookup(package_name.to_string()).await;
+        Ok(self.packages.keys().filter(|id| id.name() == package_name).map(|id| id.version().clone()).collect())
+    }
+
+    async fn download_package(&self, _id: &PackageId) -> BlastResult<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    async fn package_exists(&self, id: &PackageId) -> BlastResult<bool> {
+        Ok(self.packages.contains_key(id))
+    }
+
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn priority(&self) -> u32 {
+        1
+    }
+}
+
+/// Four libraries that all depend on `common`
+fn create_source() -> Arc<CountingSource> {
+    let mut source = CountingSource::default();
+    for name in ["a", "b", "c", "d"] {
+        for version in ["1.0", "2.0"] {
+            let package = package(name, version, &[("common", ">=1.0")]);
+            source.packages.insert(package.id().clone(), package);
+        }
+    }
+    for version in ["1.0", "1.1"] {
+        let package = package("common", version, &[]);
+        source.packages.insert(package.id().clone(), package);
+    }
+    Arc::new(source)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_prefetch_dedupes_and_bounds_requests() {
+    let source = create_source();
+    let cache_dir = TempDir::new().unwrap();
+    let mut sources = PackageSourceChain::new();
+    sources.add_shared_source(source.clone());
+    let resolver = DependencyResolver::with_sources(
+        PyPIClient::new(10, 30, true).unwrap(),
+        sources,
+        Cache::new(cache_dir.path().to_path_buf()),
+    )
+    .with_max_concurrent_requests(2);
+
+    let project = package("project", "0.1.0", &[("a", "*"), ("b", "*"), ("c", "*"), ("d", "*")]);
+    let result = resolver.resolve_project(&project).await.unwrap();
+    assert_eq!(result.packages.len(), 5);
+
+    // Every version list and chosen release is looked up exactly once,
+    // although four packages require `common`
+    let lookups = source.lookups.lock().unwrap().clone();
+    assert_eq!(lookups.len(), 10, "{:?}", lookups);
+    assert!(lookups.values().all(|&count| count == 1), "{:?}", lookups);
+    assert_eq!(lookups["common==1.1"], 1);
+    assert_eq!(source.max_in_flight.load(Ordering::SeqCst), 2);
+
+    assert_eq!(result.metrics.network_requests, 10);
+    assert_eq!(result.metrics.cache_hits, 0);
+    assert_eq!(result.metrics.package_count, 5);
+
+    // Release metadata now comes from the cache, only versions are fetched
+    let result = resolver.resolve_project(&project).await.unwrap();
+    assert_eq!(result.metrics.network_requests, 5);
+    assert_eq!(result.metrics.cache_hits, 5);
+    assert_eq!(source.lookups(), 15);
+    assert_eq!(resolver.metrics().cache_hits, 5);
+}
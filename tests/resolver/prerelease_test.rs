@@ -0,0 +1,60 @@
+use blast_resolver::{project_package, Config};
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, write_wheel};
+
+/// A wheelhouse where the newest `lib` is a beta, and `app` requires any
+/// `lib` from 1.0
+fn create_wheelhouse() -> TempDir {
+    let wheelhouse = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["lib>=1.0"]);
+    write_wheel(wheelhouse.path(), "lib", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "lib", "1.1", &[]);
+    write_wheel(wheelhouse.path(), "lib", "2.0b1", &[]);
+    wheelhouse
+}
+
+/// Resolve a project with the given requirements against the wheelhouse
+/// and get the version of `lib` chosen
+async fn resolve_lib(requirements: &[(&str, &str)], allow_prereleases: bool) -> String {
+    let wheelhouse = create_wheelhouse();
+    let cache_dir = TempDir::new().unwrap();
+    let mut config = project_config(wheelhouse.path(), requirements);
+    config.dependencies.allow_prereleases = allow_prereleases;
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+
+    let result = resolver(wheelhouse.path(), cache_dir.path())
+        .with_prereleases(resolver_config.allow_prereleases)
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap();
+
+    let lib = result.packages.iter().find(|package| package.name() == "lib").unwrap();
+    lib.version().to_string()
+}
+
+#[tokio::test]
+async fn test_prereleases_excluded_by_default() {
+    assert_eq!(resolve_lib(&[("lib", ">=1.0")], false).await, "1.1");
+    // Transitive requirements are held to the same rule
+    assert_eq!(resolve_lib(&[("app", "*")], false).await, "1.1");
+}
+
+#[tokio::test]
+async fn test_prereleases_named_by_specifier() {
+    assert_eq!(resolve_lib(&[("lib", ">=1.1b1")], false).await, "2.0b1");
+    assert_eq!(resolve_lib(&[("lib", "==2.0b1")], false).await, "2.0b1");
+    // Another requirement naming one is enough
+    assert_eq!(resolve_lib(&[("app", "*"), ("lib", "!=1.0a1,>=1.0rc1")], false).await, "2.0b1");
+}
+
+#[tokio::test]
+async fn test_prereleases_without_final_release() {
+    assert_eq!(resolve_lib(&[("lib", ">1.1")], false).await, "2.0b1");
+}
+
+#[tokio::test]
+async fn test_prereleases_allowed_by_config() {
+    assert_eq!(resolve_lib(&[("lib", ">=1.0")], true).await, "2.0b1");
+    assert_eq!(resolve_lib(&[("app", "*")], true).await, "2.0b1");
+}
@@ -0,0 +1,54 @@
+use blast_core::version::{Version, VersionConstraint};
+use blast_resolver::resolver::{constraint_to_range, range_to_string, PubgrubVersion};
+
+const VERSIONS: &[&str] = &[
+    "0.9", "1.0.dev0", "1.0a1", "1.0a1.post1", "1.0rc1", "1.0rc1+local", "1.0",
+    "1.0+local", "1.0.post0.dev1", "1.0.post1", "1.0.1", "1.1.dev0", "1.4.2",
+    "1.5", "1.5.3", "2.0rc1", "2.0", "3.0", "1!1.0",
+];
+
+#[test]
+fn test_range_matches_constraint() {
+    let constraints = [
+        "==1.0", "!=1.0", "==1.0+local", "==1.*", "!=1.0.*", "~=1.4.2", "~=1.0rc1",
+        "<1.0", "<=1.0", "<1.0.post1", ">1.0", ">=1.0", ">1.0rc1", ">1.0.post0",
+        "===1.0", ">=1.0, <2.0 || ==3.*", "*",
+    ];
+
+    for constraint in constraints {
+        let parsed = VersionConstraint::parse(constraint).unwrap();
+        let range = constraint_to_range(&parsed);
+        for version in VERSIONS {
+            let version = Version::parse(version).unwrap();
+            assert_eq!(
+                parsed.matches(&version),
+                range.contains(&PubgrubVersion::from(version.clone())),
+                "{} against {}",
+                constraint,
+                version
+            );
+        }
+    }
+}
+
+#[test]
+fn test_range_to_string() {
+    let cases = [
+        ("==1.0", "==1.0"),
+        ("==1.0+local", "==1.0+local"),
+        (">=2", ">=2"),
+        ("<2", "<2"),
+        ("<=1.0", "<=1.0"),
+        (">1.0", ">1.0"),
+        (">=1.0, <3", ">=1.0,<3"),
+        ("~=1.4.2", ">=1.4.2,<1.5"),
+        ("==1.*", "==1.*"),
+        ("!=1.5", "<1.5 || >1.5"),
+        ("*", "*"),
+    ];
+
+    for (constraint, expected) in cases {
+        let range = constraint_to_range(&VersionConstraint::parse(constraint).unwrap());
+        assert_eq!(range_to_string(&range), expected, "{}", constraint);
+    }
+}
@@ -0,0 +1,44 @@
+use blast_core::python::PythonVersion;
+use blast_image::platform::PlatformInfo;
+use blast_resolver::marker::MarkerEnvironment;
+use blast_resolver::pypi::Dependency;
+
+fn linux() -> MarkerEnvironment {
+    let mut platform = PlatformInfo::current();
+    platform.os = "linux".to_string();
+    MarkerEnvironment::new(&PythonVersion::new(3, 11, None), &platform)
+}
+
+#[test]
+fn test_requirement_with_extras_and_markers() {
+    let dep = Dependency::parse("requests[security, socks] (>=2.8.1, ==2.8.*) ; python_version < \"2.7\"").unwrap();
+    assert_eq!(dep.package, "requests");
+    assert_eq!(dep.extras, vec!["security", "socks"]);
+    assert_eq!(dep.version_constraint.to_string(), ">=2.8.1, ==2.8.*");
+    assert!(!dep.applies_to(&linux(), None));
+}
+
+#[test]
+fn test_platform_specific_requirement() {
+    let dep = Dependency::parse("pywin32>=1.0; sys_platform == 'win32'").unwrap();
+    assert!(!dep.applies_to(&linux(), None));
+
+    let dep = Dependency::parse("pytest; extra == 'test'").unwrap();
+    assert!(!dep.applies_to(&linux(), None));
+    assert!(dep.applies_to(&linux(), Some("test")));
+}
+
+#[test]
+fn test_direct_url_requirement() {
+    let dep = Dependency::parse("pip @ https://github.com/pypa/pip/archive/1.3.1.zip#sha1=da9234ee ; extra == 'vcs'").unwrap();
+    assert_eq!(dep.url.as_deref(), Some("https://github.com/pypa/pip/archive/1.3.1.zip#sha1=da9234ee"));
+    assert!(dep.version_constraint.is_any());
+    assert!(dep.applies_to(&linux(), Some("vcs")));
+}
+
+#[test]
+fn test_invalid_requirements() {
+    for requirement in ["", ">=1.0", "foo bar", "foo[bar", "foo @", "foo (>=1.0", "foo; nonsense"] {
+        assert!(Dependency::parse(requirement).is_err(), "{}", requirement);
+    }
+}
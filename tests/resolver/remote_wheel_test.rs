@@ -0,0 +1,168 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use blast_core::package::PackageId;
+use blast_core::python::PythonVersion;
+use blast_core::version::Version;
+use blast_resolver::simple::SimpleIndexSource;
+use blast_resolver::source::PackageSource;
+use blast_resolver::PythonTarget;
+use sha2::{Digest, Sha256};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+use zip::write::FileOptions;
+use zip::CompressionMethod;
+
+const METADATA: &str = "Metadata-Version: 2.1\nName: app\nVersion: 1.0\nRequires-Dist: lib>=1.0\n";
+
+/// Build a wheel whose METADATA is followed by a large, incompressible file
+fn large_wheel() -> Vec<u8> {
+    let mut wheel = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    wheel.start_file("app-1.0.dist-info/METADATA", deflated).unwrap();
+    wheel.write_all(METADATA.as_bytes()).unwrap();
+
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    wheel.start_file("app/data.bin", stored).unwrap();
+    let mut state = 0x2545_f491_u32;
+    let payload: Vec<u8> = (0..1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    wheel.write_all(&payload).unwrap();
+    wheel.finish().unwrap().into_inner()
+}
+
+/// Serves a file, honoring `Range` headers unless told not to, and counts
+/// the bytes sent
+struct FileResponder {
+    data: Vec<u8>,
+    ranges: bool,
+    sent: Arc<AtomicUsize>,
+}
+
+impl Respond for FileResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let len = self.data.len();
+        let range = request.headers.iter()
+            .find(|(name, _)| name.as_str().eq_ignore_ascii_case("range"))
+            .map(|(_, values)| values.last().as_str().to_string())
+            .filter(|_| self.ranges);
+        let Some(spec) = range.as_deref().and_then(|range| range.strip_prefix("bytes=")) else {
+            self.sent.fetch_add(len, Ordering::SeqCst);
+            return ResponseTemplate::new(200).set_body_bytes(self.data.clone());
+        };
+
+        let (start, end) = match spec.split_once('-').unwrap() {
+            ("", suffix) => (len.saturating_sub(suffix.parse().unwrap()), len),
+            (start, "") => (start.parse().unwrap(), len),
+            (start, end) => (start.parse().unwrap(), end.parse::<usize>().unwrap() + 1),
+        };
+        self.sent.fetch_add(end - start, Ordering::SeqCst);
+        let content_range = format!("bytes {}-{}/{}", start, end - 1, len);
+        ResponseTemplate::new(206)
+            .insert_header("Content-Range", content_range.as_str())
+            .set_body_bytes(self.data[start..end].to_vec())
+    }
+}
+
+/// Serve a JSON project page for `app` listing the given files
+async fn serve_project(server: &MockServer, files: serde_json::Value) {
+    let page = serde_json::json!({"meta": {"api-version": "1.1"}, "name": "app", "files": files});
+    Mock::given(method("GET"))
+        .and(path("/simple/app/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page.to_string(), "application/vnd.pypi.simple.v1+json"))
+        .mount(server)
+        .await;
+}
+
+fn source(server: &MockServer) -> SimpleIndexSource {
+    SimpleIndexSource::new(&format!("{}/simple/", server.uri()), 10)
+        .unwrap()
+        .with_python_target(PythonTarget::version(PythonVersion::new(3, 11, None)))
+}
+
+fn app(version: &str) -> PackageId {
+    PackageId::new("app", Version::parse(version).unwrap())
+}
+
+#[tokio::test]
+async fn test_pep658_metadata_file() {
+    let server = MockServer::start().await;
+    let metadata_hash = hex::encode(Sha256::digest(METADATA.as_bytes()));
+    serve_project(&server, serde_json::json!([
+        {
+            "filename": "app-1.0-py3-none-any.whl",
+            "url": "/files/app-1.0-py3-none-any.whl",
+            "hashes": {},
+            "core-metadata": {"sha256": metadata_hash},
+        },
+        {
+            "filename": "app-2.0-py3-none-any.whl",
+            "url": "/files/app-2.0-py3-none-any.whl",
+            "hashes": {},
+            "dist-info-metadata": {"sha256": "0000"},
+        },
+    ]))
+    .await;
+    for version in ["1.0", "2.0"] {
+        Mock::given(method("GET"))
+            .and(path(format!("/files/app-{}-py3-none-any.whl.metadata", version)))
+            .respond_with(ResponseTemplate::new(200).set_body_string(METADATA))
+            .mount(&server)
+            .await;
+    }
+    // The wheels themselves are never downloaded
+    Mock::given(method("GET"))
+        .and(path("/files/app-1.0-py3-none-any.whl"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let source = source(&server);
+    let package = source.get_package(&app("1.0")).await.unwrap();
+    assert!(package.metadata().dependencies.contains_key("lib"));
+
+    // A metadata file that does not match its published hash is rejected
+    let error = source.get_package(&app("2.0")).await.unwrap_err();
+    assert!(error.to_string().contains("Hash mismatch"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_range_request_metadata() {
+    let wheel = large_wheel();
+
+    for ranges in [true, false] {
+        let server = MockServer::start().await;
+        serve_project(&server, serde_json::json!([
+            {"filename": "app-1.0-py3-none-any.whl", "url": "/files/app-1.0-py3-none-any.whl", "hashes": {}},
+        ]))
+        .await;
+        let sent = Arc::new(AtomicUsize::new(0));
+        Mock::given(method("GET"))
+            .and(path("/files/app-1.0-py3-none-any.whl"))
+            .respond_with(FileResponder { data: wheel.clone(), ranges, sent: sent.clone() })
+            .mount(&server)
+            .await;
+
+        let package = source(&server).get_package(&app("1.0")).await.unwrap();
+        assert!(package.metadata().dependencies.contains_key("lib"));
+
+        // Only the central directory and METADATA entry are fetched when the
+        // server supports ranges, the whole wheel otherwise
+        let sent = sent.load(Ordering::SeqCst);
+        if ranges {
+            assert!(sent < wheel.len() / 10, "sent {} of {} bytes", sent, wheel.len());
+            let requests = server.received_requests().await.unwrap();
+            assert_eq!(requests.iter().filter(|request| request.url.path().ends_with(".whl")).count(), 2);
+        } else {
+            assert_eq!(sent, wheel.len());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use blast_core::diagnostics::{DiagnosticCategory, DiagnosticLevel};
+use blast_resolver::project_package;
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, write_wheel};
+
+#[tokio::test]
+async fn test_conflict_report() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "a", "1.0", &["b>=2"]);
+    write_wheel(wheelhouse.path(), "c", "1.0", &["b<2"]);
+    write_wheel(wheelhouse.path(), "b", "1.0", &[]);
+    write_wheel(wheelhouse.path(), "b", "2.0", &[]);
+
+    let config = project_config(wheelhouse.path(), &[("a", ">=1.0"), ("c", ">=1.0")]);
+    let error = resolver(wheelhouse.path(), cache_dir.path())
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap_err();
+
+    let diagnostic = error.diagnostic().expect("conflicts carry a diagnostic");
+    assert_eq!(diagnostic.level, DiagnosticLevel::Error);
+    assert_eq!(diagnostic.category, DiagnosticCategory::Dependency);
+    assert_eq!(diagnostic.message, "No solution found for project");
+
+    let details = diagnostic.details.as_deref().unwrap();
+    assert!(details.starts_with("Because "), "{}", details);
+    assert!(details.contains("depends on b>=2"), "{}", details);
+    assert!(details.contains("depends on b<2"), "{}", details);
+    assert!(details.contains("the requirements of project cannot be satisfied"), "{}", details);
+
+    let suggestions: Vec<&str> = diagnostic.suggestions.iter().map(|s| s.description.as_str()).collect();
+    assert!(suggestions[0].starts_with("Relax the requirement"), "{:?}", suggestions);
+    assert!(suggestions.iter().any(|s| s.contains("requires b")), "{:?}", suggestions);
+
+    // The report is part of the error message too
+    let message = error.to_string();
+    assert!(message.contains(details), "{}", message);
+    assert!(message.contains("help: "), "{}", message);
+}
+
+#[tokio::test]
+async fn test_unavailable_version_report() {
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "a", "1.0", &["b>=5"]);
+    write_wheel(wheelhouse.path(), "b", "1.0", &[]);
+
+    let config = project_config(wheelhouse.path(), &[("a", "*")]);
+    let error = resolver(wheelhouse.path(), cache_dir.path())
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap_err();
+
+    let diagnostic = error.diagnostic().unwrap();
+    let details = diagnostic.details.as_deref().unwrap();
+    assert!(details.contains("no versions of b match >=5"), "{}", details);
+    assert!(diagnostic.suggestions.iter().any(|s| s.description.starts_with("No version of b matches >=5")));
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use blast_core::config::{BlastConfig, DependencySpec};
+use blast_core::metadata::PackageMetadata;
+use blast_core::package::{Package, PackageId};
+use blast_core::python::PythonVersion;
+use blast_core::version::{Version, VersionConstraint};
+use blast_resolver::simple::{parse_html_page, parse_json_page, SimpleIndexSource};
+use blast_resolver::source::{yank_reason, PackageSource, PackageSourceChain, YankedRelease};
+use blast_resolver::{
+    create_resolver_with_config, project_package, Cache, Config, DependencyResolver, Lockfile, PyPIClient, PythonTarget,
+};
+use tempfile::TempDir;
+use url::Url;
+
+/// Write a minimal wheel containing only its METADATA file
+fn write_wheel(dir: &Path, name: &str, version: &str, requires: &[&str]) -> String {
+    let filename = format!("{}-{}-py3-none-any.whl", name, version);
+    let mut metadata = format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n", name, version);
+    for requirement in requires {
+        metadata.push_str(&format!("Requires-Dist: {}\n", requirement));
+    }
+
+    let file = fs::File::create(dir.join(&filename)).unwrap();
+    let mut wheel = zip::ZipWriter::new(file);
+    wheel.start_file(format!("{}-{}.dist-info/METADATA", name, version), Default::default()).unwrap();
+    wheel.write_all(metadata.as_bytes()).unwrap();
+    wheel.finish().unwrap();
+    filename
+}
+
+/// Build an on-disk index with `app` (HTML page) depending on `lib` (JSON page)
+fn create_index() -> TempDir {
+    let root = TempDir::new().unwrap();
+    let files = root.path().join("files");
+    let simple = root.path().join("simple");
+    fs::create_dir_all(&files).unwrap();
+    fs::create_dir_all(simple.join("app")).unwrap();
+    fs::create_dir_all(simple.join("lib")).unwrap();
+
+    let app = write_wheel(&files, "app", "1.0.0", &["lib>=1.0,<3", "extra-only; extra == 'docs'"]);
+    fs::write(
+        simple.join("app").join("index.html"),
+        format!("<!DOCTYPE html>\n<html><body>\n<a href=\"../../files/{0}\">{0}</a>\n</body></html>\n", app),
+    ).unwrap();
+
+    let lib_1 = write_wheel(&files, "lib", "1.5.0", &[]);
+    let lib_2 = write_wheel(&files, "lib", "2.0.0", &[]);
+    let lib_3 = write_wheel(&files, "lib", "2.1.0", &[]);
+    let json = serde_json::json!({
+        "meta": {"api-version": "1.0"},
+        "name": "lib",
+        "files": [
+            {"filename": lib_1, "url": format!("../../files/{}", lib_1), "hashes": {}},
+            {"filename": lib_2, "url": format!("../../files/{}", lib_2), "hashes": {}, "yanked": "broken build"},
+            {"filename": lib_3, "url": format!("../../files/{}", lib_3), "hashes": {}, "requires-python": ">=4"},
+        ],
+    });
+    fs::write(simple.join("lib").join("index.json"), json.to_string()).unwrap();
+
+    root
+}
+
+fn app_package() -> Package {
+    let metadata = PackageMetadata::new(
+        "app".to_string(),
+        "1.0.0".to_string(),
+        HashMap::new(),
+        VersionConstraint::any(),
+    );
+    Package::new("app".to_string(), "1.0.0".to_string(), metadata, VersionConstraint::any()).unwrap()
+}
+
+#[test]
+fn test_parse_html_page() {
+    let page = r#"<html><body>
+        <a href="/files/demo-1.0-py3-none-any.whl#sha256=abc123" data-requires-python="&gt;=3.8" data-core-metadata="sha256=def456">demo-1.0-py3-none-any.whl</a><br/>
+        <A HREF='https://cdn.example.com/demo-0.9.tar.gz' data-yanked="">demo-0.9.tar.gz</A>
+        <a href="demo-0.8.tar.gz" data-yanked="CVE &amp; crash">demo-0.8.tar.gz</a>
+        <abbr>not a link</abbr>
+    </body></html>"#;
+    let base = Url::parse("https://example.com/simple/demo/").unwrap();
+    let files = parse_html_page(page, &base);
+
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[0].filename, "demo-1.0-py3-none-any.whl");
+    assert_eq!(files[0].url.as_str(), "https://example.com/files/demo-1.0-py3-none-any.whl");
+    assert_eq!(files[0].hashes.get("sha256").map(String::as_str), Some("abc123"));
+    assert_eq!(files[0].requires_python.as_deref(), Some(">=3.8"));
+    assert!(!files[0].yanked);
+    let core_metadata = files[0].core_metadata.as_ref().unwrap();
+    assert_eq!(core_metadata.get("sha256").map(String::as_str), Some("def456"));
+    assert_eq!(files[1].core_metadata, None);
+
+    assert_eq!(files[1].url.as_str(), "https://cdn.example.com/demo-0.9.tar.gz");
+    assert!(files[1].yanked);
+    assert_eq!(files[1].yanked_reason, None);
+
+    assert_eq!(files[2].url.as_str(), "https://example.com/simple/demo/demo-0.8.tar.gz");
+    assert_eq!(files[2].yanked_reason.as_deref(), Some("CVE & crash"));
+}
+
+#[test]
+fn test_parse_json_page() {
+    let page = r#"{
+        "meta": {"api-version": "1.1"},
+        "name": "demo",
+        "files": [
+            {"filename": "demo-1.0.tar.gz", "url": "../../files/demo-1.0.tar.gz", "hashes": {"sha256": "abc"}, "requires-python": ">=3.9", "yanked": false, "upload-time": "2024-01-02T03:04:05.678901Z"},
+            {"filename": "demo-0.9.tar.gz", "url": "https://cdn.example.com/demo-0.9.tar.gz", "hashes": {}, "yanked": true, "dist-info-metadata": true}
+        ]
+    }"#;
+    let base = Url::parse("https://example.com/simple/demo/").unwrap();
+    let files = parse_json_page(page, &base).unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].url.as_str(), "https://example.com/files/demo-1.0.tar.gz");
+    assert_eq!(files[0].hashes.get("sha256").map(String::as_str), Some("abc"));
+    assert_eq!(files[0].requires_python.as_deref(), Some(">=3.9"));
+    assert!(files[1].yanked);
+    assert_eq!(files[0].upload_time, Some("2024-01-02T03:04:05.678901Z".parse().unwrap()));
+    assert_eq!(files[1].upload_time, None);
+    assert_eq!(files[0].core_metadata, None);
+    assert_eq!(files[1].core_metadata, Some(HashMap::new()));
+
+    let unsupported = r#"{"meta": {"api-version": "2.0"}, "name": "demo", "files": []}"#;
+    assert!(parse_json_page(unsupported, &base).is_err());
+}
+
+#[test]
+fn test_yank_reason() {
+    // Indexes flag files, and a release is yanked only when every file of
+    // it that can be used is
+    assert_eq!(yank_reason([(true, None), (true, Some("broken build"))]), Some(Some("broken build".to_string())));
+    assert_eq!(yank_reason([(true, Some(""))]), Some(None));
+    assert_eq!(yank_reason([(true, Some("broken build")), (false, None)]), None);
+    assert_eq!(yank_reason(std::iter::empty()), None);
+}
+
+#[tokio::test]
+async fn test_local_index_source() {
+    let index = create_index();
+    let source = SimpleIndexSource::new(index.path().join("simple").to_str().unwrap(), 10)
+        .unwrap()
+        .with_python_target(PythonTarget::version(PythonVersion::new(3, 11, None)));
+
+    // Yanked files and files requiring another Python are skipped
+    let versions = source.get_versions("LIB").await.unwrap();
+    assert_eq!(versions, vec![Version::parse("1.5.0").unwrap()]);
+    assert_eq!(
+        source.get_yanked_versions("lib").await.unwrap(),
+        vec![YankedRelease { version: Version::parse("2.0.0").unwrap(), reason: Some("broken build".to_string()) }]
+    );
+
+    let id = PackageId::new("app", Version::parse("1.0.0").unwrap());
+    let app = source.get_package(&id).await.unwrap();
+    assert_eq!(app.name(), "app");
+    assert_eq!(app.metadata().dependencies.len(), 1);
+    assert!(app.metadata().dependencies.contains_key("lib"));
+    assert!(app.metadata().extras.contains_key("docs"));
+
+    assert!(source.package_exists(&id).await.unwrap());
+    assert!(!source.package_exists(&PackageId::new("missing", Version::parse("1.0").unwrap())).await.unwrap());
+
+    let wheel = source.download_package(&id).await.unwrap();
+    assert_eq!(&wheel[..2], b"PK");
+}
+
+#[tokio::test]
+async fn test_resolve_against_local_index() {
+    let index = create_index();
+    let cache_dir = TempDir::new().unwrap();
+    let source = SimpleIndexSource::new(index.path().join("simple").to_str().unwrap(), 10)
+        .unwrap()
+        .with_python_target(PythonTarget::version(PythonVersion::new(3, 11, None)));
+
+    let mut sources = PackageSourceChain::new();
+    sources.add_source(Box::new(source));
+    let resolver = DependencyResolver::with_sources(
+        PyPIClient::new(10, 30, true).unwrap(),
+        sources,
+        Cache::new(cache_dir.path().to_path_buf()),
+    );
+
+    let packages = resolver.resolve(&app_package()).await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name(), "lib");
+    assert_eq!(packages[0].version().to_string(), "1.5.0");
+}
+
+#[tokio::test]
+async fn test_yanked_release_needs_exact_pin() {
+    let index = create_index();
+    let cache_dir = TempDir::new().unwrap();
+    let resolver = || {
+        let source = SimpleIndexSource::new(index.path().join("simple").to_str().unwrap(), 10)
+            .unwrap()
+            .with_python_target(PythonTarget::version(PythonVersion::new(3, 11, None)));
+        let mut sources = PackageSourceChain::new();
+        sources.add_source(Box::new(source));
+        DependencyResolver::with_sources(
+            PyPIClient::new(10, 30, true).unwrap(),
+            sources,
+            Cache::new(cache_dir.path().to_path_buf()),
+        )
+    };
+    let project = |version: &str| {
+        let mut config = BlastConfig::new("project", "0.1.0", PythonVersion::new(3, 11, None), index.path().to_path_buf());
+        config.dependencies.packages.push(DependencySpec {
+            name: "lib".to_string(),
+            version: version.to_string(),
+            extras: None,
+            index: None,
+            source: None,
+            marker: None,
+        });
+        project_package(&config).unwrap()
+    };
+
+    // A range never selects the yanked 2.0.0
+    assert!(resolver().resolve_project(&project(">=2.0")).await.is_err());
+    let result = resolver().resolve_project(&project(">=1.0")).await.unwrap();
+    assert_eq!(result.packages[0].version().to_string(), "1.5.0");
+
+    // An exact pin still gets it, flagged with the reason
+    let result = resolver().resolve_project(&project("==2.0.0")).await.unwrap();
+    let lib = &result.packages[0];
+    assert_eq!(lib.version().to_string(), "2.0.0");
+    assert!(lib.metadata().yanked);
+    assert_eq!(lib.metadata().yanked_reason.as_deref(), Some("broken build"));
+
+    let yanked = resolver().yanked_release("lib", &Version::parse("2.0.0").unwrap()).await.unwrap();
+    assert_eq!(yanked.and_then(|release| release.reason).as_deref(), Some("broken build"));
+    assert!(resolver().yanked_release("lib", &Version::parse("1.5.0").unwrap()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_dependency_spec_index() {
+    let index = create_index();
+    let index_path = index.path().join("simple").to_str().unwrap().to_string();
+
+    let mut config = BlastConfig::new("project", "0.1.0", PythonVersion::new(3, 11, None), index.path().to_path_buf());
+    for name in ["app", "lib"] {
+        config.dependencies.packages.push(DependencySpec {
+            name: name.to_string(),
+            version: "*".to_string(),
+            extras: None,
+            index: Some(index_path.clone()),
+            source: None,
+            marker: None,
+        });
+    }
+    config.dependencies.package_index = Some(vec![index_path.clone()]);
+
+    let resolver_config = Config::from_blast_config(&config).unwrap();
+    assert_eq!(resolver_config.additional_sources, vec![index_path.clone()]);
+    assert_eq!(resolver_config.package_indexes.get("lib"), Some(&index_path));
+
+    // Both packages are pinned to the local index, so PyPI is never consulted
+    let resolver = create_resolver_with_config(resolver_config).await.unwrap();
+    let packages = resolver.resolve(&app_package()).await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].version().to_string(), "1.5.0");
+}
+
+/// Build an on-disk index where `lib` releases were uploaded at different times
+fn create_dated_index() -> TempDir {
+    let root = TempDir::new().unwrap();
+    let files = root.path().join("files");
+    fs::create_dir_all(&files).unwrap();
+    fs::create_dir_all(root.path().join("simple").join("lib")).unwrap();
+
+    let releases = [("1.0", Some("2024-01-01T00:00:00Z")), ("1.1", Some("2024-03-01T00:00:00Z")), ("1.2", None)];
+    let entries: Vec<_> = releases.iter()
+        .map(|(version, upload_time)| {
+            let filename = write_wheel(&files, "lib", version, &[]);
+            let mut entry = serde_json::json!({"filename": filename, "url": format!("../../files/{}", filename), "hashes": {}});
+            if let Some(upload_time) = upload_time {
+                entry["upload-time"] = serde_json::json!(upload_time);
+            }
+            entry
+        })
+        .collect();
+    let json = serde_json::json!({"meta": {"api-version": "1.1"}, "name": "lib", "files": entries});
+    fs::write(root.path().join("simple").join("lib").join("index.json"), json.to_string()).unwrap();
+    root
+}
+
+#[tokio::test]
+async fn test_exclude_newer() {
+    let index = create_dated_index();
+    let index_path = index.path().join("simple").to_str().unwrap().to_string();
+    let cutoff = "2024-02-01T00:00:00Z".parse().unwrap();
+
+    let source = SimpleIndexSource::new(&index_path, 10).unwrap();
+    assert_eq!(source.get_versions("lib").await.unwrap().len(), 3);
+
+    // Files without an upload time cannot be shown to predate the cutoff
+    let source = source.with_exclude_newer(Some(cutoff));
+    assert_eq!(source.get_versions("lib").await.unwrap(), vec![Version::parse("1.0").unwrap()]);
+    let newer = PackageId::new("lib", Version::parse("1.1").unwrap());
+    assert!(source.list_artifacts(&newer).await.is_err());
+
+    // The cutoff is recorded in the lockfile
+    let cache_dir = TempDir::new().unwrap();
+    let mut config = BlastConfig::new("project", "0.1.0", PythonVersion::new(3, 11, None), index.path().to_path_buf());
+    config.dependencies.packages.push(DependencySpec {
+        name: "lib".to_string(),
+        version: ">=1.0".to_string(),
+        extras: None,
+        index: Some(index_path),
+        source: None,
+        marker: None,
+    });
+    let resolver_config = Config {
+        cache_dir: cache_dir.path().to_path_buf(),
+        exclude_newer: Some(cutoff),
+        ..Config::from_blast_config(&config).unwrap()
+    };
+    let resolver = create_resolver_with_config(resolver_config).await.unwrap();
+    let lockfile = resolver.lock(&project_package(&config).unwrap()).await.unwrap();
+    assert_eq!(lockfile.package("lib").unwrap().version, "1.0");
+    assert_eq!(lockfile.exclude_newer, Some(cutoff));
+
+    let content = lockfile.to_toml().unwrap();
+    assert!(content.contains("exclude_newer = \"2024-02-01T00:00:00Z\""), "{}", content);
+    assert_eq!(Lockfile::from_toml(&content).unwrap(), lockfile);
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+
+use blast_core::python::PythonVersion;
+use blast_core::version::Version;
+use blast_image::platform::{Libc, PlatformInfo};
+use blast_resolver::source::PackageSource;
+use blast_resolver::tags::{platform_tags, supported_tags};
+use blast_resolver::{DistFilename, FindLinksSource, PythonTarget, Tag, WheelTags};
+use tempfile::TempDir;
+
+fn platform(os: &str, arch: &str, libc: Option<Libc>) -> PlatformInfo {
+    PlatformInfo {
+        os: os.to_string(),
+        arch: arch.to_string(),
+        libc,
+        ..PlatformInfo::current()
+    }
+}
+
+fn linux_target(python: &str) -> PythonTarget {
+    PythonTarget::parse(python).unwrap().with_platform(platform("linux", "x86_64", Some(Libc::Glibc(2, 28))))
+}
+
+fn wheel(filename: &str) -> DistFilename {
+    DistFilename::parse(filename).unwrap()
+}
+
+#[test]
+fn test_platform_tags() {
+    let glibc = platform_tags(&platform("linux", "x86_64", Some(Libc::Glibc(2, 28))));
+    assert_eq!(glibc[0], "manylinux_2_28_x86_64");
+    let position = |tag: &str| glibc.iter().position(|t| t == tag).unwrap();
+    assert_eq!(position("manylinux2014_x86_64"), position("manylinux_2_17_x86_64") + 1);
+    assert_eq!(position("manylinux2010_x86_64"), position("manylinux_2_12_x86_64") + 1);
+    assert_eq!(glibc[glibc.len() - 2], "manylinux1_x86_64");
+    assert_eq!(glibc.last().unwrap(), "linux_x86_64");
+
+    // Other architectures start at glibc 2.17 and only have manylinux2014
+    let aarch64 = platform_tags(&platform("linux", "aarch64", Some(Libc::Glibc(2, 17))));
+    assert_eq!(aarch64, vec!["manylinux_2_17_aarch64", "manylinux2014_aarch64", "linux_aarch64"]);
+
+    let musl = platform_tags(&platform("linux", "x86_64", Some(Libc::Musl(1, 2))));
+    assert_eq!(musl, vec!["musllinux_1_2_x86_64", "musllinux_1_1_x86_64", "musllinux_1_0_x86_64", "linux_x86_64"]);
+
+    assert_eq!(platform_tags(&platform("windows", "x86_64", None)), vec!["win_amd64"]);
+    let macos = platform_tags(&platform("macos", "aarch64", None));
+    assert!(macos.contains(&"macosx_11_0_arm64".to_string()));
+    assert!(!macos.iter().any(|tag| tag.starts_with("macosx_10_")));
+}
+
+#[test]
+fn test_supported_tags_order() {
+    let tags = supported_tags(&PythonVersion::new(3, 11, None), &platform("linux", "x86_64", Some(Libc::Glibc(2, 28))));
+    assert_eq!(tags[0], Tag::new("cp311", "cp311", "manylinux_2_28_x86_64"));
+    assert_eq!(tags.last().unwrap().to_string(), "py30-none-any");
+
+    let position = |tag: &str| tags.iter().position(|t| t.to_string() == tag).unwrap();
+    assert!(position("cp311-cp311-linux_x86_64") < position("cp311-abi3-manylinux_2_28_x86_64"));
+    assert!(position("cp311-abi3-linux_x86_64") < position("cp38-abi3-manylinux_2_17_x86_64"));
+    assert!(position("cp32-abi3-linux_x86_64") < position("py3-none-any"));
+    assert!(!tags.iter().any(|tag| tag.python == "cp312"));
+}
+
+#[test]
+fn test_wheel_ranking() {
+    let tags = WheelTags::new(&linux_target("3.11"));
+    let native = wheel("numpy-2.0.0-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl");
+    let abi3 = wheel("cryptography-43.0.0-cp39-abi3-manylinux_2_28_x86_64.whl");
+    let pure = wheel("six-1.16.0-py2.py3-none-any.whl");
+
+    let rank = |filename: &DistFilename| tags.rank(filename).unwrap();
+    assert!(rank(&native) < rank(&abi3));
+    assert!(rank(&abi3) < rank(&pure));
+
+    for incompatible in [
+        "numpy-2.0.0-cp312-cp312-manylinux_2_17_x86_64.whl",
+        "numpy-2.0.0-cp311-cp311-musllinux_1_1_x86_64.whl",
+        "numpy-2.0.0-cp311-cp311-manylinux_2_34_x86_64.whl",
+        "numpy-2.0.0-cp311-cp311-win_amd64.whl",
+    ] {
+        assert_eq!(tags.rank(&wheel(incompatible)), None, "{}", incompatible);
+    }
+
+    // Source distributions come after every wheel, unless disallowed
+    let sdist = wheel("numpy-2.0.0.tar.gz");
+    assert_eq!(tags.preference(&sdist, false), Some(usize::MAX));
+    assert_eq!(tags.preference(&sdist, true), None);
+    assert!(tags.installable([&sdist], false));
+    assert!(!tags.installable([&sdist], true));
+}
+
+#[test]
+fn test_installable_on_every_interpreter() {
+    let tags = WheelTags::new(&linux_target(">=3.11, <3.13"));
+    let cp311 = wheel("numpy-2.0.0-cp311-cp311-manylinux_2_17_x86_64.whl");
+    let cp312 = wheel("numpy-2.0.0-cp312-cp312-manylinux_2_17_x86_64.whl");
+
+    assert!(tags.rank(&cp311).is_some());
+    assert!(!tags.installable([&cp311], true));
+    assert!(tags.installable([&cp311, &cp312], true));
+    assert!(tags.installable([&wheel("numpy-2.0.0-cp311-abi3-manylinux_2_17_x86_64.whl")], true));
+}
+
+#[tokio::test]
+async fn test_releases_without_installable_files() {
+    let dir = TempDir::new().unwrap();
+    let touch = |name: &str| fs::write(dir.path().join(name), b"").unwrap();
+    touch("lib-1.0-py3-none-any.whl");
+    touch("lib-2.0.tar.gz");
+    touch("lib-3.0-cp311-cp311-win_amd64.whl");
+    touch("lib-3.0-cp311-cp311-manylinux_2_17_x86_64.whl");
+    touch("lib-3.0.tar.gz");
+
+    let versions = |source: FindLinksSource| async move { source.get_versions("lib").await.unwrap() };
+    let source = |dir: &Path| FindLinksSource::new(dir, 5).unwrap().with_python_target(linux_target("3.11"));
+    let parse = |versions: &[&str]| versions.iter().map(|v| Version::parse(v).unwrap()).collect::<Vec<_>>();
+
+    assert_eq!(versions(source(dir.path())).await, parse(&["1.0", "2.0", "3.0"]));
+    assert_eq!(versions(source(dir.path()).with_no_build(true)).await, parse(&["1.0", "3.0"]));
+
+    // The wheel for the target is preferred, the other platform's left out
+    let id = blast_core::package::PackageId::new("lib", Version::parse("3.0").unwrap());
+    let artifacts = source(dir.path()).list_artifacts(&id).await.unwrap();
+    let filenames: Vec<&str> = artifacts.iter().map(|artifact| artifact.filename.as_str()).collect();
+    assert_eq!(filenames, vec!["lib-3.0-cp311-cp311-manylinux_2_17_x86_64.whl", "lib-3.0.tar.gz"]);
+
+    let windows = PythonTarget::parse("3.11").unwrap().with_platform(platform("windows", "x86_64", None));
+    let source = FindLinksSource::new(dir.path(), 5).unwrap().with_python_target(windows).with_no_build(true);
+    assert_eq!(versions(source).await, parse(&["1.0", "3.0"]));
+}
@@ -0,0 +1,61 @@
+use blast_core::python::PythonVersion;
+use blast_core::version::VersionConstraint;
+use blast_image::platform::PlatformInfo;
+use blast_resolver::{project_package, PythonTarget};
+use tempfile::TempDir;
+
+use super::common::{project_config, resolver, write_wheel, write_wheel_requiring_python};
+
+#[test]
+fn test_single_interpreter_target() {
+    let target = PythonTarget::parse("3.11").unwrap();
+    assert_eq!(target.versions(), &[PythonVersion::new(3, 11, None)]);
+    assert!(target.allows(&VersionConstraint::parse(">=3.8").unwrap()));
+    assert!(!target.allows(&VersionConstraint::parse("<3.11").unwrap()));
+}
+
+#[test]
+fn test_interpreter_range_target() {
+    let target = PythonTarget::parse(">=3.9, <3.12").unwrap();
+    assert_eq!(target.versions().len(), 3);
+    assert!(target.allows(&VersionConstraint::parse(">=3.7").unwrap()));
+    // A release must support every interpreter in the range
+    assert!(!target.allows(&VersionConstraint::parse(">=3.10").unwrap()));
+
+    let environments = target.marker_environments(&PlatformInfo::current());
+    assert_eq!(environments.len(), 3);
+    assert_eq!(environments[2].python_version, "3.11");
+
+    assert!(PythonTarget::parse("<3.0").is_err());
+}
+
+#[tokio::test]
+async fn test_requires_python_filters_local_releases() {
+    // Find-links sources offer every wheel, whatever its Requires-Python
+    let wheelhouse = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_wheel(wheelhouse.path(), "app", "1.0", &["lib>=1"]);
+    write_wheel(wheelhouse.path(), "lib", "1.0", &[]);
+    write_wheel_requiring_python(wheelhouse.path(), "lib", "2.0", ">=3.12");
+
+    let config = project_config(wheelhouse.path(), &[("app", "*")]);
+    let target = PythonTarget::parse("3.11").unwrap();
+    let result = resolver(wheelhouse.path(), cache_dir.path())
+        .with_python_target(target.clone())
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap();
+    let lib = result.packages.iter().find(|package| package.name() == "lib").unwrap();
+    assert_eq!(lib.version().to_string(), "1.0");
+
+    let config = project_config(wheelhouse.path(), &[("lib", ">=2")]);
+    let error = resolver(wheelhouse.path(), cache_dir.path())
+        .with_python_target(target)
+        .resolve_project(&project_package(&config).unwrap())
+        .await
+        .unwrap_err();
+    let diagnostic = error.diagnostic().unwrap();
+    let details = diagnostic.details.as_deref().unwrap();
+    assert!(details.contains("lib==2.0 requires Python >=3.12, which excludes the target Python 3.11"), "{}", details);
+    assert!(diagnostic.suggestions.iter().any(|s| s.description.contains("supports Python 3.11")));
+}